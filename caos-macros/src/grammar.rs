@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Returns the canonical CAOS keyword for a grammar rule, e.g. `NEW: SIMP` for
+/// `command_new_simp`.
+///
+/// Keywords are read from the `.pest` grammar files of the crate being compiled,
+/// found in `src/grammar` in the same way `pest_derive` resolves its grammars.
+/// Only rules made up entirely of case-insensitive string literals (`^"new:" ~ ^"simp"`)
+/// are considered keywords.
+pub fn keyword(rule: &str) -> Option<&'static str> {
+    all_keywords().get(rule).map(String::as_str)
+}
+
+/// Returns every keyword in the grammar as `(rule, keyword)` pairs, sorted by rule.
+pub fn keywords() -> Vec<(&'static str, &'static str)> {
+    let mut keywords: Vec<_> = all_keywords()
+        .iter()
+        .map(|(rule, keyword)| (rule.as_str(), keyword.as_str()))
+        .collect();
+    keywords.sort();
+    keywords
+}
+
+fn all_keywords() -> &'static HashMap<String, String> {
+    static KEYWORDS: OnceLock<HashMap<String, String>> = OnceLock::new();
+    KEYWORDS.get_or_init(load_keywords)
+}
+
+fn load_keywords() -> HashMap<String, String> {
+    let dir = std::env::var("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_default()
+        .join("src")
+        .join("grammar");
+
+    let mut keywords = HashMap::new();
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return keywords;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().map(|e| e == "pest").unwrap_or(false) {
+            if let Ok(content) = std::fs::read_to_string(&path) {
+                keywords.extend(content.lines().filter_map(parse_keyword_rule));
+            }
+        }
+    }
+    keywords
+}
+
+/// Parses a single grammar line of the form `name = { ^"a" ~ ^"b" }`.
+fn parse_keyword_rule(line: &str) -> Option<(String, String)> {
+    let (name, body) = line.split_once('=')?;
+    let name = name.trim();
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return None;
+    }
+
+    let body = body.trim().trim_start_matches(['@', '$', '_']);
+    let body = body.strip_prefix('{')?.strip_suffix('}')?;
+
+    let parts = body
+        .split('~')
+        .map(|part| {
+            part.trim()
+                .strip_prefix("^\"")?
+                .strip_suffix('"')
+                .filter(|s| !s.contains('"'))
+                .map(str::to_uppercase)
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some((name.to_owned(), parts.join(" ")))
+}
@@ -1,7 +1,9 @@
+mod grammar;
+
 use darling::{ast, FromDeriveInput, FromVariant};
 use proc_macro::TokenStream;
-use quote::{format_ident, quote_spanned};
-use syn::parse_macro_input;
+use quote::{format_ident, quote, quote_spanned};
+use syn::{ext::IdentExt, parse_macro_input};
 
 #[derive(Debug, FromDeriveInput)]
 #[darling(supports(enum_any))]
@@ -11,9 +13,10 @@ struct Expression {
 }
 
 #[derive(Debug, FromVariant)]
-#[darling(attributes(parse))]
+#[darling(attributes(parse), forward_attrs(category))]
 struct ExpressionVariant {
     ident: syn::Ident,
+    attrs: Vec<syn::Attribute>,
     ignore: Option<()>,
     rule: Option<syn::Path>,
     fields: darling::ast::Fields<syn::Field>,
@@ -53,26 +56,35 @@ fn trait_parser(input: TokenStream, trait_name: syn::Ident, thunk_name: syn::Ide
                 (None, Some(rule)) => Some({
                     if field_count == 0 {
                         quote_spanned!(name.span()=>
-                            #rule => Some(crate::parser::#thunk_name::Completed(pair, Self::#name.into()))
+                            #rule => Some(crate::parser::#thunk_name::Completed(crate::SpanNode::leaf(pair.as_span().into()), Self::#name.into()))
                         )
                     } else {
                         let fields = v.fields.iter().map(|field| {
                             let fname = &field.ident;
                             quote_spanned!(name.span()=>
-                                #fname: Box::new(arg_it.next().unwrap().try_into()?)
+                                #fname: {
+                                    let (arg, span) = arg_it.next().unwrap();
+                                    Box::new(crate::parser::cast_arg(arg, span)?)
+                                }
                             )
                         });
                         quote_spanned!(name.span()=>
                             #rule => Some(crate::parser::#thunk_name::Partial(crate::parser::Partial {
                                 origin: pair,
                                 arg_parts: Vec::<crate::ast::Anything>::with_capacity(#field_count),
+                                arg_spans: Vec::<crate::SpanNode>::with_capacity(#field_count),
                                 target_args: #field_count,
-                                complete_method: Box::new(|pair, args| {
+                                complete_method: Box::new(|pair, args, spans| {
                                     if args.len() == #field_count {
-                                        let mut arg_it = args.into_iter();
+                                        let mut arg_it = args.into_iter().zip(spans);
                                         Ok(Self::#name { #(#fields),* }.into())
                                     } else {
-                                        Err(crate::CaosError::new_arg_count_error(#field_count, args.len(), pair.line_col()))
+                                        Err(crate::CaosError::new_arg_count_error(
+                                            &crate::parser::keyword_name(&pair),
+                                            #field_count,
+                                            args.len(),
+                                            pair.as_span().into(),
+                                        ))
                                     }
                                 })
                         })))
@@ -84,6 +96,7 @@ fn trait_parser(input: TokenStream, trait_name: syn::Ident, thunk_name: syn::Ide
         let name = &expression.ident;
         quote_spanned!(name.span()=>
             impl crate::parser::#trait_name for #name {
+            #[allow(clippy::useless_conversion)]
             fn parse_thunk<'i>(pair : pest::iterators::Pair<'i, crate::Rule>) -> Option<crate::parser::#thunk_name<'i>> {
                 match pair.as_rule() {
                     #(#marked_variants),*,
@@ -95,3 +108,284 @@ fn trait_parser(input: TokenStream, trait_name: syn::Ident, thunk_name: syn::Ide
         TokenStream::default()
     }
 }
+
+/// Expands to a `&[(&str, &str)]` of every keyword in the grammar, as
+/// `(rule, keyword)` pairs sorted by rule name, e.g. `("command_new_simp", "NEW: SIMP")`.
+#[proc_macro]
+pub fn grammar_keywords(_input: TokenStream) -> TokenStream {
+    let entries = grammar::keywords()
+        .into_iter()
+        .map(|(rule, keyword)| quote!((#rule, #keyword)));
+    quote!(&[#(#entries),*]).into()
+}
+
+/// Derives `WriteCaos`, printing each `#[parse(rule=...)]` variant as the rule's
+/// keyword followed by its fields in order.
+///
+/// Variants marked `#[parse(ignore)]` are delegated to `WriteCaosIgnored`.
+#[proc_macro_derive(WriteCaos, attributes(parse))]
+pub fn write_caos(input: TokenStream) -> TokenStream {
+    let derive_input = parse_macro_input!(input as syn::DeriveInput);
+    let expression = Expression::from_derive_input(&derive_input).unwrap();
+    let name = &expression.ident;
+
+    if let Some(v) = expression.data.take_enum() {
+        let mut has_ignored = false;
+        let marked_variants: Vec<_> = v
+            .iter()
+            .filter_map(|v| {
+                let vname = &v.ident;
+                match (v.ignore, &v.rule) {
+                    (None, None) => panic!("Expected ignore or rule"),
+                    (Some(_), Some(_)) => panic!("Cannot have ignore and rule"),
+                    (Some(_), None) => {
+                        has_ignored = true;
+                        None
+                    }
+                    (None, Some(rule)) => Some({
+                        let rule_name = rule
+                            .segments
+                            .last()
+                            .map(|s| s.ident.to_string())
+                            .unwrap_or_default();
+                        let keyword = grammar::keyword(&rule_name).unwrap_or_else(|| {
+                            panic!("No keyword found in grammar for rule {}", rule_name)
+                        });
+                        let fnames: Vec<_> = v.fields.iter().map(|f| &f.ident).collect();
+                        quote_spanned!(vname.span()=>
+                            Self::#vname { #(#fnames),* } => {
+                                w.keyword(#keyword)?;
+                                #(crate::printer::WriteCaos::write_caos(#fnames, w)?;)*
+                                Ok(())
+                            }
+                        )
+                    }),
+                }
+            })
+            .collect();
+
+        let fallback = if has_ignored {
+            quote_spanned!(name.span()=>
+                _ => crate::printer::WriteCaosIgnored::write_caos_ignored(self, w)
+            )
+        } else {
+            quote_spanned!(name.span()=>)
+        };
+
+        quote_spanned!(name.span()=>
+            impl crate::printer::WriteCaos for #name {
+                fn write_caos(&self, w: &mut crate::printer::CaosWriter<'_>) -> std::fmt::Result {
+                    match self {
+                        #(#marked_variants)*
+                        #fallback
+                    }
+                }
+            }
+        )
+        .into()
+    } else {
+        TokenStream::default()
+    }
+}
+
+/// Derives `Signatures`, describing each `#[parse(rule=...)]` variant by its
+/// keyword, fields and category.
+///
+/// A variant takes the category of the nearest `#[category = "..."]` at or
+/// above it. Variants marked `#[parse(ignore)]` are described by hand in
+/// `crate::signature`.
+#[proc_macro_derive(Signatures, attributes(parse, category))]
+pub fn signatures(input: TokenStream) -> TokenStream {
+    let derive_input = parse_macro_input!(input as syn::DeriveInput);
+    let expression = Expression::from_derive_input(&derive_input).unwrap();
+    let name = &expression.ident;
+    let Some(variants) = expression.data.take_enum() else {
+        return TokenStream::default();
+    };
+
+    let mut category = None;
+    let mut signatures = Vec::new();
+    for v in &variants {
+        let vname = &v.ident;
+        if let Some(c) = v.attrs.iter().find_map(category_of) {
+            category = Some(c);
+        }
+        let Some(rule) = &v.rule else {
+            continue;
+        };
+        let rule_name = rule
+            .segments
+            .last()
+            .map(|s| s.ident.to_string())
+            .unwrap_or_default();
+        let keyword = grammar::keyword(&rule_name)
+            .unwrap_or_else(|| panic!("No keyword found in grammar for rule {}", rule_name));
+        let category = category
+            .clone()
+            .unwrap_or_else(|| panic!("No category given for variant {}", vname));
+        let args = v.fields.iter().map(|f| {
+            let fname = f
+                .ident
+                .as_ref()
+                .map(|i| i.unraw().to_string())
+                .unwrap_or_default();
+            let kind = arg_kind(&f.ty);
+            quote!(crate::signature::Argument {
+                name: #fname,
+                kind: crate::signature::ArgKind::#kind,
+            })
+        });
+        let vname = vname.to_string();
+        signatures.push(quote_spanned!(name.span()=>
+            crate::signature::Signature {
+                keyword: #keyword,
+                rule: #rule_name,
+                variant: #vname,
+                returns: crate::signature::ReturnType::#name,
+                category: #category,
+                args: &[#(#args),*],
+            }
+        ));
+    }
+
+    let names = variants.iter().map(|v| {
+        let vname = &v.ident;
+        let vname_str = vname.to_string();
+        let pattern = match v.fields.style {
+            ast::Style::Struct => quote!(Self::#vname { .. }),
+            ast::Style::Tuple => quote!(Self::#vname(..)),
+            ast::Style::Unit => quote!(Self::#vname),
+        };
+        quote!(#pattern => #vname_str)
+    });
+
+    quote_spanned!(name.span()=>
+        impl crate::signature::Signatures for #name {
+            const RETURNS: crate::signature::ReturnType = crate::signature::ReturnType::#name;
+            const SIGNATURES: &'static [crate::signature::Signature] = &[#(#signatures),*];
+
+            fn variant_name(&self) -> &'static str {
+                match self {
+                    #(#names),*
+                }
+            }
+        }
+    )
+    .into()
+}
+
+/// Reads the category from a `#[category = "..."]` attribute.
+fn category_of(attr: &syn::Attribute) -> Option<String> {
+    match &attr.meta.require_name_value().ok()?.value {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(s),
+            ..
+        }) => Some(s.value()),
+        _ => None,
+    }
+}
+
+/// The argument kind of a field, named after its type with any `Box` removed.
+fn arg_kind(ty: &syn::Type) -> syn::Ident {
+    let syn::Type::Path(path) = ty else {
+        panic!("Unsupported argument type");
+    };
+    let segment = path.path.segments.last().expect("Argument type has a name");
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) if segment.ident == "Box" => {
+            match args.args.first() {
+                Some(syn::GenericArgument::Type(inner)) => arg_kind(inner),
+                _ => panic!("Unsupported argument type"),
+            }
+        }
+        _ => segment.ident.clone(),
+    }
+}
+
+/// Derives `Walk`, visiting each field of every variant in order through
+/// `crate::visit::Node`, so that new variants are walked without further work.
+#[proc_macro_derive(Walk)]
+pub fn walk(input: TokenStream) -> TokenStream {
+    let derive_input = parse_macro_input!(input as syn::DeriveInput);
+    let name = &derive_input.ident;
+
+    let arms: Vec<_> = match &derive_input.data {
+        syn::Data::Enum(e) => e
+            .variants
+            .iter()
+            .map(|v| {
+                let vname = &v.ident;
+                walk_arm(quote!(Self::#vname), &v.fields)
+            })
+            .collect(),
+        syn::Data::Struct(s) => vec![walk_arm(quote!(Self), &s.fields)],
+        syn::Data::Union(_) => panic!("Walk cannot be derived for unions"),
+    };
+    let walks = arms.iter().map(|(pattern, bindings, _)| {
+        quote!(#pattern => { #(crate::visit::Node::accept(#bindings, visitor);)* })
+    });
+    let walks_mut = arms.iter().map(|(pattern, bindings, _)| {
+        quote!(#pattern => { #(crate::visit::Node::accept_mut(#bindings, visitor);)* })
+    });
+    let folds = arms
+        .iter()
+        .map(|(pattern, _, rebuilt)| quote!(#pattern => #rebuilt));
+
+    quote_spanned!(name.span()=>
+        #[allow(unused_variables)]
+        impl crate::visit::Walk for #name {
+            fn walk<V: crate::visit::Visit + ?Sized>(&self, visitor: &mut V) {
+                match self {
+                    #(#walks)*
+                }
+            }
+
+            fn walk_mut<V: crate::visit::VisitMut + ?Sized>(&mut self, visitor: &mut V) {
+                match self {
+                    #(#walks_mut)*
+                }
+            }
+
+            fn walk_fold<F: crate::visit::Fold + ?Sized>(self, folder: &mut F) -> Self {
+                match self {
+                    #(#folds,)*
+                }
+            }
+        }
+    )
+    .into()
+}
+
+/// Builds the pattern binding every field of `path`, the names bound, and the
+/// expression rebuilding `path` from its folded fields.
+fn walk_arm(
+    path: proc_macro2::TokenStream,
+    fields: &syn::Fields,
+) -> (
+    proc_macro2::TokenStream,
+    Vec<syn::Ident>,
+    proc_macro2::TokenStream,
+) {
+    let bindings: Vec<_> = (0..fields.len())
+        .map(|i| format_ident!("field_{}", i))
+        .collect();
+    let folded = bindings
+        .iter()
+        .map(|b| quote!(crate::visit::Node::fold_with(#b, folder)));
+    match fields {
+        syn::Fields::Named(named) => {
+            let names: Vec<_> = named.named.iter().map(|f| &f.ident).collect();
+            (
+                quote!(#path { #(#names: #bindings),* }),
+                bindings.clone(),
+                quote!(#path { #(#names: #folded),* }),
+            )
+        }
+        syn::Fields::Unnamed(_) => (
+            quote!(#path(#(#bindings),*)),
+            bindings.clone(),
+            quote!(#path(#(#folded),*)),
+        ),
+        syn::Fields::Unit => (path.clone(), bindings, path),
+    }
+}
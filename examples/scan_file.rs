@@ -13,7 +13,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         let p = entry.path();
         if p.extension().map(|p| p == "cos").unwrap_or(false) {
             println!("{}", p.display());
-            let file_content = std::fs::read_to_string(&p)?;
+            let file_content = std::fs::read_to_string(p)?;
             scan_file(&file_content).expect("Successful");
         }
     }
@@ -1,11 +1,13 @@
 use super::{AgentArg, IntArg, SStringArg};
+use crate::visit::Walk;
 use crate::Rule;
-use caos_macros::ExpressionParser;
+use caos_macros::{ExpressionParser, Signatures, WriteCaos};
 
 /// Agent types represents a reference to an in-game CAOS
 /// Agent.
-#[derive(Eq, PartialEq, Debug, Clone, ExpressionParser)]
+#[derive(Eq, PartialEq, Debug, Clone, ExpressionParser, Signatures, WriteCaos, Walk)]
 pub enum Agent {
+    #[category = "Agents"]
     #[parse(rule=Rule::agent_carr)]
     Carr,
     #[parse(rule=Rule::agent_from)]
@@ -43,23 +45,28 @@ pub enum Agent {
     },
     #[parse(rule=Rule::agent_it)]
     It,
+    #[category = "Camera"]
     #[parse(rule=Rule::overloaded_trck)]
     Trck,
+    #[category = "Creatures"]
     #[parse(rule=Rule::agent_hhld)]
     Hhld,
     #[parse(rule=Rule::overloaded_norn)]
     Norn,
+    #[category = "Debug"]
     #[parse(rule=Rule::agent_agnt)]
     Agnt { unique_id: Box<IntArg> },
     #[parse(rule=Rule::agent_tack)]
     Tack,
+    #[category = "History"]
     #[parse(rule=Rule::agent_mtoa)]
     Mtoa { moniker: Box<SStringArg> },
     #[parse(rule=Rule::agent_mtoc)]
     Mtoc { moniker: Box<SStringArg> },
+    #[category = "Input"]
     #[parse(rule=Rule::agent_hots)]
     Hots,
-    // Ports
+    #[category = "Ports"]
     #[parse(rule=Rule::agent_prt_frma)]
     PrtFrma { input_port: Box<IntArg> },
 }
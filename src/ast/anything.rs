@@ -1,11 +1,12 @@
-use crate::{CaosError, ErrorType};
+use crate::CaosError;
 
 use super::{
     Agent, AgentArg, ByteString, Decimal, DecimalArg, Float, FloatArg, IntArg, Integer, SString,
     SStringArg, Variable,
 };
+use crate::visit::Walk;
 
-#[derive(Eq, PartialEq, Debug, Clone)]
+#[derive(Eq, PartialEq, Debug, Clone, Walk)]
 
 pub enum Anything {
     Variable(Variable),
@@ -16,6 +17,19 @@ pub enum Anything {
 }
 
 impl Anything {
+    /// The name of the AST type held, as used in
+    /// [ErrorType::WrongArgumentType](crate::ErrorType::WrongArgumentType).
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Anything::Variable(_) => "Variable",
+            Anything::String(_) => "SString",
+            Anything::Decimal(Decimal::Integer(_)) => "Integer",
+            Anything::Decimal(Decimal::Float(_)) => "Float",
+            Anything::ByteString(_) => "ByteString",
+            Anything::Agent(_) => "Agent",
+        }
+    }
+
     pub fn variable_or_else<E, F>(self, err: F) -> Result<Variable, E>
     where
         F: FnOnce() -> E,
@@ -139,12 +153,8 @@ impl From<Variable> for Anything {
 impl TryFrom<Anything> for Variable {
     type Error = CaosError;
     fn try_from(value: Anything) -> Result<Self, Self::Error> {
-        value.variable_or_else(|| {
-            CaosError::new(
-                ErrorType::CastError { line_col: None },
-                String::from("Could not cast to Variable"),
-            )
-        })
+        let found = value.type_name();
+        value.variable_or_else(|| CaosError::new_wrong_argument_type("Variable", found))
     }
 }
 
@@ -163,12 +173,8 @@ impl From<String> for Anything {
 impl TryFrom<Anything> for SStringArg {
     type Error = CaosError;
     fn try_from(value: Anything) -> Result<Self, Self::Error> {
-        value.string_arg_or_else(|| {
-            CaosError::new(
-                ErrorType::CastError { line_col: None },
-                String::from("Could not cast to string"),
-            )
-        })
+        let found = value.type_name();
+        value.string_arg_or_else(|| CaosError::new_wrong_argument_type("SStringArg", found))
     }
 }
 
@@ -181,12 +187,8 @@ impl From<Decimal> for Anything {
 impl TryFrom<Anything> for DecimalArg {
     type Error = CaosError;
     fn try_from(value: Anything) -> Result<Self, Self::Error> {
-        value.decimal_arg_or_else(|| {
-            CaosError::new(
-                ErrorType::CastError { line_col: None },
-                String::from("Could not cast to decimal"),
-            )
-        })
+        let found = value.type_name();
+        value.decimal_arg_or_else(|| CaosError::new_wrong_argument_type("DecimalArg", found))
     }
 }
 
@@ -199,12 +201,8 @@ impl From<ByteString> for Anything {
 impl TryFrom<Anything> for ByteString {
     type Error = CaosError;
     fn try_from(value: Anything) -> Result<Self, Self::Error> {
-        value.bytestring_or_else(|| {
-            CaosError::new(
-                ErrorType::CastError { line_col: None },
-                String::from("Could not cast to decimal"),
-            )
-        })
+        let found = value.type_name();
+        value.bytestring_or_else(|| CaosError::new_wrong_argument_type("ByteString", found))
     }
 }
 
@@ -217,12 +215,8 @@ impl From<Agent> for Anything {
 impl TryFrom<Anything> for AgentArg {
     type Error = CaosError;
     fn try_from(value: Anything) -> Result<Self, Self::Error> {
-        value.agent_arg_or_else(|| {
-            CaosError::new(
-                ErrorType::CastError { line_col: None },
-                String::from("Could not cast to agent"),
-            )
-        })
+        let found = value.type_name();
+        value.agent_arg_or_else(|| CaosError::new_wrong_argument_type("AgentArg", found))
     }
 }
 
@@ -241,12 +235,8 @@ impl From<i32> for Anything {
 impl TryFrom<Anything> for IntArg {
     type Error = CaosError;
     fn try_from(value: Anything) -> Result<Self, Self::Error> {
-        value.int_arg_or_else(|| {
-            CaosError::new(
-                ErrorType::CastError { line_col: None },
-                String::from("Could not cast to int"),
-            )
-        })
+        let found = value.type_name();
+        value.int_arg_or_else(|| CaosError::new_wrong_argument_type("IntArg", found))
     }
 }
 
@@ -265,12 +255,8 @@ impl From<f32> for Anything {
 impl TryFrom<Anything> for FloatArg {
     type Error = CaosError;
     fn try_from(value: Anything) -> Result<Self, Self::Error> {
-        value.float_arg_or_else(|| {
-            CaosError::new(
-                ErrorType::CastError { line_col: None },
-                String::from("Could not cast to float"),
-            )
-        })
+        let found = value.type_name();
+        value.float_arg_or_else(|| CaosError::new_wrong_argument_type("FloatArg", found))
     }
 }
 
@@ -343,4 +329,20 @@ mod tests {
         let a: AgentArg = Anything::from(Variable::P1).try_into().unwrap();
         assert_eq!(a, Variable::P1.into());
     }
+
+    #[test]
+    fn test_anything_wrong_type() {
+        let e = IntArg::try_from(Anything::from(SString::Hand)).unwrap_err();
+        assert_eq!(
+            *e.error_type(),
+            crate::ErrorType::WrongArgumentType {
+                expected: "IntArg",
+                found: "SString"
+            }
+        );
+        assert_eq!(
+            e.message(),
+            "Wrong argument type: expected `IntArg`, got `SString`"
+        );
+    }
 }
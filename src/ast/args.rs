@@ -1,6 +1,7 @@
 use super::{Agent, Decimal, Float, Integer, SString, Variable};
+use crate::visit::Walk;
 
-#[derive(Eq, PartialEq, Debug, Clone)]
+#[derive(Eq, PartialEq, Debug, Clone, Walk)]
 pub enum AgentArg {
     Agent(Agent),
     Variable(Variable),
@@ -18,7 +19,7 @@ impl From<Variable> for AgentArg {
     }
 }
 
-#[derive(Eq, PartialEq, Debug, Clone)]
+#[derive(Eq, PartialEq, Debug, Clone, Walk)]
 pub enum SStringArg {
     String(SString),
     Variable(Variable),
@@ -42,7 +43,7 @@ impl From<Variable> for SStringArg {
     }
 }
 
-#[derive(Eq, PartialEq, Debug, Clone)]
+#[derive(Eq, PartialEq, Debug, Clone, Walk)]
 pub enum DecimalArg {
     Decimal(Decimal),
     Variable(Variable),
@@ -84,7 +85,7 @@ impl From<f32> for DecimalArg {
     }
 }
 
-#[derive(Eq, PartialEq, Debug, Clone)]
+#[derive(Eq, PartialEq, Debug, Clone, Walk)]
 pub enum IntArg {
     Primary(Integer),
     Castable(Float),
@@ -121,7 +122,7 @@ impl From<Variable> for IntArg {
     }
 }
 
-#[derive(Eq, PartialEq, Debug, Clone)]
+#[derive(Eq, PartialEq, Debug, Clone, Walk)]
 pub enum FloatArg {
     Primary(Float),
     Castable(Integer),
@@ -1,4 +1,6 @@
-#[derive(Debug, PartialEq, Eq, Clone, Default)]
+use crate::visit::Walk;
+
+#[derive(Debug, PartialEq, Eq, Clone, Default, Walk)]
 pub struct ByteString(Vec<u8>);
 
 impl From<Vec<u8>> for ByteString {
@@ -6,3 +8,9 @@ impl From<Vec<u8>> for ByteString {
         ByteString(v)
     }
 }
+
+impl AsRef<[u8]> for ByteString {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
@@ -1,6 +1,7 @@
 use crate::ast::{IntArg, ScriptDefinition};
+use crate::visit::Walk;
 
-#[derive(Eq, PartialEq, Clone, Debug)]
+#[derive(Eq, PartialEq, Clone, Debug, Walk)]
 pub struct ClassifierEnum {
     pub family: Box<IntArg>,
     pub genus: Box<IntArg>,
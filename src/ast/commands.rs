@@ -1,12 +1,13 @@
-use caos_macros::CommandParser;
+use caos_macros::{CommandParser, Signatures, WriteCaos};
 
 use super::{
     AgentArg, Anything, ByteString, ClassifierEnum, Condition, DecimalArg, DoIf, FloatArg, IntArg,
     Label, SStringArg, ScriptDefinition, Variable,
 };
+use crate::visit::Walk;
 use crate::Rule;
 
-#[derive(Eq, PartialEq, Clone, Debug, CommandParser)]
+#[derive(Eq, PartialEq, Clone, Debug, CommandParser, Signatures, WriteCaos, Walk)]
 pub enum Command {
     #[parse(ignore)]
     Gsub { destination: Label },
@@ -46,7 +47,17 @@ pub enum Command {
     Esee(ClassifierEnum),
     #[parse(ignore)]
     Epas(ClassifierEnum),
-    // Box<AgentArg>s
+    /// Source that could not be parsed as a command, kept by
+    /// [parse_cos_recovering](crate::parse_cos_recovering) in place of the
+    /// commands it spans.
+    #[parse(ignore)]
+    Error { text: String },
+    #[category = "Agents"]
+    #[parse(rule=Rule::command_alph)]
+    Alph {
+        alpha_value: Box<IntArg>,
+        yes_or_no: Box<IntArg>,
+    },
     #[parse(rule=Rule::command_anim)]
     Anim { pose_list: Box<ByteString> },
     #[parse(rule=Rule::command_anms)]
@@ -57,6 +68,17 @@ pub enum Command {
     Base { index: Box<IntArg> },
     #[parse(rule=Rule::overloaded_bhvr)]
     Bhvr { permissions: Box<IntArg> },
+    #[parse(rule=Rule::command_cato)]
+    Cato { category_id: Box<IntArg> },
+    #[parse(rule=Rule::command_core)]
+    Core {
+        top_y: Box<FloatArg>,
+        bottom_y: Box<FloatArg>,
+        left_x: Box<FloatArg>,
+        right_x: Box<FloatArg>,
+    },
+    #[parse(rule=Rule::command_drop)]
+    Drop,
     #[parse(rule=Rule::command_frat)]
     Frat { framerate: Box<IntArg> },
     #[parse(rule=Rule::command_gait)]
@@ -143,13 +165,30 @@ pub enum Command {
         rotation: Box<IntArg>,
         swap: Box<IntArg>,
     },
+    #[parse(rule=Rule::command_tino)]
+    Tino {
+        red_tint: Box<IntArg>,
+        green_tint: Box<IntArg>,
+        blue_tint: Box<IntArg>,
+        rotation: Box<IntArg>,
+        swap: Box<IntArg>,
+    },
     #[parse(rule=Rule::command_ttar)]
     Ttar {
         family: Box<IntArg>,
         genus: Box<IntArg>,
         species: Box<IntArg>,
     },
-    // Brain
+    #[parse(rule=Rule::command_ucln)]
+    Ucln,
+    #[category = "Brain"]
+    #[parse(rule=Rule::command_adin)]
+    Adin {
+        verb: Box<IntArg>,
+        noun: Box<IntArg>,
+        qualifier: Box<FloatArg>,
+        cert: Box<IntArg>,
+    },
     #[parse(rule=Rule::command_brn_dmpb)]
     BrnDmpb,
     #[parse(rule=Rule::command_brn_dmpd)]
@@ -192,7 +231,11 @@ pub enum Command {
         line_number: Box<IntArg>,
         new_value: Box<FloatArg>,
     },
-    // Camera
+    #[parse(rule=Rule::command_doin)]
+    Doin {
+        no_of_instincts_to_process: Box<IntArg>,
+    },
+    #[category = "Camera"]
     #[parse(rule=Rule::overloaded_bkgd)]
     Bkgd {
         metaroom_id: Box<IntArg>,
@@ -269,7 +312,7 @@ pub enum Command {
         x: Box<IntArg>,
         y: Box<IntArg>,
     },
-    // Compounds
+    #[category = "Compounds"]
     #[parse(rule=Rule::command_fcus)]
     Fcus,
     #[parse(rule=Rule::command_frmt)]
@@ -366,6 +409,12 @@ pub enum Command {
     },
     #[parse(rule=Rule::command_pat_kill)]
     PatKill { part_id: Box<IntArg> },
+    #[parse(rule=Rule::command_pat_move)]
+    PatMove {
+        part_id: Box<IntArg>,
+        x: Box<DecimalArg>,
+        y: Box<DecimalArg>,
+    },
     #[parse(rule=Rule::command_pat_text)]
     PatText {
         part_id: Box<IntArg>,
@@ -379,7 +428,7 @@ pub enum Command {
     },
     #[parse(rule=Rule::overloaded_ptxt)]
     Ptxt { text: Box<SStringArg> },
-    // Creates
+    #[category = "Creatures"]
     #[parse(rule=Rule::command_ages)]
     Ages { times: Box<IntArg> },
     #[parse(rule=Rule::command_appr)]
@@ -417,6 +466,8 @@ pub enum Command {
     Forf {
         creature_to_learn_about: Box<AgentArg>,
     },
+    #[parse(rule=Rule::overloaded_froz)]
+    Froz { frozen: Box<IntArg> },
     #[parse(rule=Rule::command_hair)]
     Hair { stage: Box<IntArg> },
     #[parse(rule=Rule::command_injr)]
@@ -446,6 +497,18 @@ pub enum Command {
     Mate,
     #[parse(rule=Rule::command_mvft)]
     Mvft { x: Box<FloatArg>, y: Box<FloatArg> },
+    #[parse(rule=Rule::command_new_crag)]
+    NewCrag {
+        family: Box<IntArg>,
+        gene_agent: Box<AgentArg>,
+        gene_slot: Box<IntArg>,
+        sex: Box<IntArg>,
+        variant: Box<IntArg>,
+        sprite_file: Box<SStringArg>,
+        image_count: Box<IntArg>,
+        first_image: Box<IntArg>,
+        plane: Box<IntArg>,
+    },
     #[parse(rule=Rule::command_new_crea)]
     NewCrea {
         family: Box<IntArg>,
@@ -477,12 +540,19 @@ pub enum Command {
     },
     #[parse(rule=Rule::command_sayn)]
     Sayn,
+    #[parse(rule=Rule::overloaded_soul)]
+    Soul {
+        faculty_id: Box<IntArg>,
+        on: Box<IntArg>,
+    },
     #[parse(rule=Rule::command_spnl)]
     Spnl {
         lobe_monkier: Box<SStringArg>,
         neuron_id: Box<IntArg>,
         value: Box<FloatArg>,
     },
+    #[parse(rule=Rule::command_step)]
+    Step { faculty_id: Box<IntArg> },
     #[parse(rule=Rule::command_stim_shou)]
     StimShou {
         stimulus: Box<IntArg>,
@@ -592,7 +662,7 @@ pub enum Command {
     #[parse(rule=Rule::overloaded_zomb)]
     Zomb { zombie: Box<IntArg> },
     #[parse(rule=Rule::command_apro)]
-    // Debug
+    #[category = "Debug"]
     Apro { search_text: Box<SStringArg> },
     #[parse(rule=Rule::command_dbg_cpro)]
     DbgCpro,
@@ -624,7 +694,7 @@ pub enum Command {
     Mann { command: Box<SStringArg> },
     #[parse(rule=Rule::command_memx)]
     Memx,
-    // Files
+    #[category = "Files"]
     #[parse(rule=Rule::command_file_glob)]
     FileGlob {
         directory: Box<IntArg>,
@@ -658,7 +728,9 @@ pub enum Command {
     Outv { value: Box<DecimalArg> },
     #[parse(rule=Rule::command_outx)]
     Outx { text: Box<SStringArg> },
-    // Genetics
+    #[parse(rule=Rule::command_webb)]
+    Webb { http_url: Box<SStringArg> },
+    #[category = "Genetics"]
     #[parse(rule=Rule::command_gene_clon)]
     GeneClon {
         dest_agent: Box<AgentArg>,
@@ -697,7 +769,7 @@ pub enum Command {
         source_agent: Box<AgentArg>,
         source_slot: Box<IntArg>,
     },
-    // History
+    #[category = "History"]
     #[parse(rule=Rule::command_hist_evnt)]
     HistEvnt {
         moniker: Box<SStringArg>,
@@ -743,7 +815,7 @@ pub enum Command {
         transparency: Box<IntArg>,
         part_no: Box<IntArg>,
     },
-    // Map
+    #[category = "Map"]
     #[parse(rule=Rule::command_addb)]
     Addb {
         metaroom_id: Box<IntArg>,
@@ -817,7 +889,7 @@ pub enum Command {
         room_id: Box<IntArg>,
         room_type: Box<IntArg>,
     },
-    // Motion
+    #[category = "Motion"]
     #[parse(rule=Rule::overloaded_accg)]
     Accg { acceleration: Box<FloatArg> },
     #[parse(rule=Rule::overloaded_aero)]
@@ -847,7 +919,49 @@ pub enum Command {
         x_velocity: Box<FloatArg>,
         y_velocity: Box<FloatArg>,
     },
-    // Ports
+    #[category = "Net"]
+    #[parse(rule=Rule::command_net_head)]
+    NetHead,
+    #[parse(rule=Rule::command_net_hear)]
+    NetHear { channel: Box<SStringArg> },
+    #[parse(rule=Rule::overloaded_net_line)]
+    NetLine { state: Box<IntArg> },
+    #[parse(rule=Rule::overloaded_net_pass)]
+    NetPass {
+        nick_name: Box<SStringArg>,
+        password: Box<SStringArg>,
+    },
+    #[parse(rule=Rule::command_net_ruso)]
+    NetRuso { store_result: Box<Variable> },
+    #[parse(rule=Rule::command_net_stat)]
+    NetStat {
+        time_online: Box<Variable>,
+        users_online: Box<Variable>,
+        bytes_received: Box<Variable>,
+        bytes_sent: Box<Variable>,
+    },
+    #[parse(rule=Rule::command_net_unik)]
+    NetUnik {
+        user_id: Box<SStringArg>,
+        store_result: Box<Variable>,
+    },
+    #[parse(rule=Rule::command_net_whod)]
+    NetWhod,
+    #[parse(rule=Rule::command_net_whof)]
+    NetWhof { user_id: Box<SStringArg> },
+    #[parse(rule=Rule::command_net_whon)]
+    NetWhon { user_id: Box<SStringArg> },
+    #[parse(rule=Rule::command_net_whoz)]
+    NetWhoz,
+    #[parse(rule=Rule::command_net_writ)]
+    NetWrit {
+        user_id: Box<SStringArg>,
+        channel: Box<SStringArg>,
+        message_id: Box<IntArg>,
+        param_1: Box<Anything>,
+        param_2: Box<Anything>,
+    },
+    #[category = "Ports"]
     #[parse(rule=Rule::command_prt_bang)]
     PrtBang { bang_strength: Box<IntArg> },
     #[parse(rule=Rule::command_prt_inew)]
@@ -889,12 +1003,12 @@ pub enum Command {
         id: Box<IntArg>,
         data: Box<Anything>,
     },
-    // Resources
+    #[category = "Resources"]
     #[parse(rule=Rule::command_pray_garb)]
     PrayGarb { force: Box<IntArg> },
     #[parse(rule=Rule::command_pray_refr)]
     PrayRefr,
-    // Scripts
+    #[category = "Scripts"]
     #[parse(rule=Rule::command_gids_fmly)]
     GidsFmly { family: Box<IntArg> },
     #[parse(rule=Rule::command_gids_gnus)]
@@ -910,8 +1024,19 @@ pub enum Command {
         genus: Box<IntArg>,
         species: Box<IntArg>,
     },
+    #[parse(rule=Rule::command_call)]
+    Call {
+        script_no: Box<IntArg>,
+        p1: Box<Anything>,
+        p2: Box<Anything>,
+    },
     #[parse(rule=Rule::command_inst)]
     Inst,
+    #[parse(rule=Rule::command_ject)]
+    Ject {
+        macro_file: Box<SStringArg>,
+        flags: Box<IntArg>,
+    },
     #[parse(rule=Rule::command_lock)]
     Lock,
     #[parse(rule=Rule::command_scrx)]
@@ -938,7 +1063,7 @@ pub enum Command {
     Unlk,
     #[parse(rule=Rule::command_wait)]
     Wait { ticks: Box<IntArg> },
-    // Sounds
+    #[category = "Sounds"]
     #[parse(rule=Rule::command_fade)]
     Fade,
     #[parse(rule=Rule::command_mclr)]
@@ -989,10 +1114,12 @@ pub enum Command {
     Vois { voice_name: Box<SStringArg> },
     #[parse(rule=Rule::command_volm)]
     Volm { volume: Box<IntArg> },
-    // Date
+    #[category = "Time"]
+    #[parse(rule=Rule::overloaded_buzz)]
+    Buzz { interval: Box<IntArg> },
     #[parse(rule=Rule::overloaded_wpau)]
     Wpau { paused: Box<IntArg> },
-    // Box<Variable>s
+    #[category = "Variables"]
     #[parse(rule=Rule::command_absv)]
     Absv { var: Box<Variable> },
     #[parse(rule=Rule::command_adds)]
@@ -1016,8 +1143,12 @@ pub enum Command {
         index: Box<IntArg>,
         character: Box<IntArg>,
     },
+    #[parse(rule=Rule::command_dele)]
+    Dele { name: Box<Anything> },
     #[parse(rule=Rule::command_delg)]
     Delg { variable_name: Box<SStringArg> },
+    #[parse(rule=Rule::command_deln)]
+    Deln { name: Box<Anything> },
     #[parse(rule=Rule::command_divv)]
     Divv {
         var: Box<Variable>,
@@ -1033,8 +1164,12 @@ pub enum Command {
         var: Box<Variable>,
         mul: Box<DecimalArg>,
     },
+    #[parse(rule=Rule::command_namn)]
+    Namn { previous: Box<Variable> },
     #[parse(rule=Rule::command_negv)]
     Negv { var: Box<Variable> },
+    #[parse(rule=Rule::command_notv)]
+    Notv { var: Box<Variable> },
     #[parse(rule=Rule::command_orrv)]
     Orrv {
         var: Box<Variable>,
@@ -1064,7 +1199,7 @@ pub enum Command {
     },
     #[parse(rule=Rule::overloaded_targ)]
     Targ { agent: Box<AgentArg> },
-    // Vehicles
+    #[category = "Vehicles"]
     #[parse(rule=Rule::command_cabn)]
     Cabn {
         left: Box<IntArg>,
@@ -1111,7 +1246,7 @@ pub enum Command {
         vehicle: Box<AgentArg>,
         new_passenger: Box<AgentArg>,
     },
-    // World
+    #[category = "World"]
     #[parse(rule=Rule::command_delw)]
     Delw { world_name: Box<SStringArg> },
     #[parse(rule=Rule::command_load)]
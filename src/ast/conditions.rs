@@ -1,6 +1,7 @@
 use super::Anything;
+use crate::visit::Walk;
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Walk)]
 pub enum Condition {
     Simple {
         cond_type: ConditionType,
@@ -1,6 +1,7 @@
 use crate::ast::Script;
+use crate::visit::Walk;
 
-#[derive(Debug, Eq, PartialEq, Default)]
+#[derive(Debug, Eq, PartialEq, Default, Clone, Walk)]
 pub struct CosFile {
     pub scripts: Vec<Script>,
 }
@@ -1,6 +1,7 @@
 use super::{Float, Integer};
+use crate::visit::Walk;
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Walk)]
 pub enum Decimal {
     Integer(Integer),
     Float(Float),
@@ -1,6 +1,7 @@
 use crate::ast::{Command, Condition, ScriptDefinition};
+use crate::visit::Walk;
 
-#[derive(Eq, PartialEq, Clone, Debug)]
+#[derive(Eq, PartialEq, Clone, Debug, Walk)]
 pub struct DoIf {
     pub condition: Condition,
     pub definition: ScriptDefinition,
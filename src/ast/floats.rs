@@ -1,6 +1,7 @@
 use super::{AgentArg, FloatArg, IntArg, SStringArg};
+use crate::visit::Walk;
 use crate::Rule;
-use caos_macros::ExpressionParser;
+use caos_macros::{ExpressionParser, Signatures, WriteCaos};
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct LitF32(f32);
@@ -19,10 +20,11 @@ impl From<f32> for LitF32 {
     }
 }
 
-#[derive(Eq, PartialEq, Debug, Clone, ExpressionParser)]
+#[derive(Eq, PartialEq, Debug, Clone, ExpressionParser, Signatures, WriteCaos, Walk)]
 pub enum Float {
     #[parse(ignore)]
     Literal(LitF32),
+    #[category = "Agents"]
     #[parse(rule=Rule::float_disq)]
     Disq { other: Box<AgentArg> },
     #[parse(rule=Rule::float_fltx)]
@@ -47,6 +49,7 @@ pub enum Float {
     Posy,
     #[parse(rule=Rule::overloaded_rnge)]
     Rnge,
+    #[category = "Creatures"]
     #[parse(rule=Rule::overloaded_chem)]
     Chem { chemical: Box<IntArg> },
     #[parse(rule=Rule::float_dftx)]
@@ -71,8 +74,10 @@ pub enum Float {
     Uftx,
     #[parse(rule=Rule::float_ufty)]
     Ufty,
+    #[category = "Files"]
     #[parse(rule=Rule::float_innf)]
     Innf,
+    #[category = "Map"]
     #[parse(rule=Rule::float_movx)]
     Movx,
     #[parse(rule=Rule::float_movy)]
@@ -86,6 +91,7 @@ pub enum Float {
     Torx { room_id: Box<IntArg> },
     #[parse(rule=Rule::float_tory)]
     Tory { room_id: Box<IntArg> },
+    #[category = "Motion"]
     #[parse(rule=Rule::overloaded_accg)]
     Accg,
     #[parse(rule=Rule::float_obst)]
@@ -100,8 +106,10 @@ pub enum Float {
         first: Box<AgentArg>,
         second: Box<AgentArg>,
     },
+    #[category = "Time"]
     #[parse(rule=Rule::float_pace)]
     Pace,
+    #[category = "Variables"]
     #[parse(rule=Rule::float_acos)]
     Acos { x: Box<FloatArg> },
     #[parse(rule=Rule::float_asin)]
@@ -1,17 +1,23 @@
 use super::{AgentArg, Anything, ByteString, FloatArg, IntArg, SStringArg, Variable};
+use crate::visit::Walk;
 use crate::Rule;
-use caos_macros::ExpressionParser;
+use caos_macros::{ExpressionParser, Signatures, WriteCaos};
 
-#[derive(Eq, PartialEq, Debug, Clone, ExpressionParser)]
+#[derive(Eq, PartialEq, Debug, Clone, ExpressionParser, Signatures, WriteCaos, Walk)]
 pub enum Integer {
     #[parse(ignore)]
     Literal(i32),
+    #[category = "Agents"]
+    #[parse(rule=Rule::int_abba)]
+    Abba,
     #[parse(rule=Rule::overloaded_attr)]
     Attr,
     #[parse(rule=Rule::overloaded_base)]
     Base,
     #[parse(rule=Rule::overloaded_bhvr)]
     Bhvr,
+    #[parse(rule=Rule::int_cata)]
+    Cata,
     #[parse(rule=Rule::int_cati)]
     Cati {
         family: Box<IntArg>,
@@ -59,6 +65,13 @@ pub enum Integer {
     Spcs,
     #[parse(rule=Rule::overloaded_tick)]
     Tick,
+    #[parse(rule=Rule::int_tcor)]
+    Tcor {
+        top_y: Box<FloatArg>,
+        bottom_y: Box<FloatArg>,
+        left_x: Box<FloatArg>,
+        right_x: Box<FloatArg>,
+    },
     #[parse(rule=Rule::int_totl)]
     Totl {
         family: Box<IntArg>,
@@ -74,6 +87,7 @@ pub enum Integer {
     Visi { check_all_cameras: Box<IntArg> },
     #[parse(rule=Rule::int_wdth)]
     Wdth,
+    #[category = "Camera"]
     #[parse(rule=Rule::int_cmrx)]
     Cmrx,
     #[parse(rule=Rule::int_cmry)]
@@ -98,10 +112,12 @@ pub enum Integer {
     Wndt,
     #[parse(rule=Rule::int_wndw)]
     Wndw,
+    #[category = "Compounds"]
     #[parse(rule=Rule::int_npgs)]
     Npgs,
     #[parse(rule=Rule::overloaded_page)]
     Page,
+    #[category = "Creatures"]
     #[parse(rule=Rule::overloaded_aslp)]
     Aslp,
     #[parse(rule=Rule::int_attn)]
@@ -130,8 +146,14 @@ pub enum Integer {
     Expr,
     #[parse(rule=Rule::overloaded_face)]
     Face,
+    #[parse(rule=Rule::overloaded_froz)]
+    Froz,
     #[parse(rule=Rule::int_ins)]
     Ins,
+    #[parse(rule=Rule::int_mind)]
+    Mind,
+    #[parse(rule=Rule::int_motr)]
+    Motr,
     #[parse(rule=Rule::int_orgi)]
     Orgi {
         organ_number: Box<IntArg>,
@@ -139,12 +161,15 @@ pub enum Integer {
     },
     #[parse(rule=Rule::int_orgn)]
     Orgn,
+    #[parse(rule=Rule::overloaded_soul)]
+    Soul { faculty_id: Box<IntArg> },
     #[parse(rule=Rule::int_tage)]
     Tage,
     #[parse(rule=Rule::overloaded_uncs)]
     Uncs,
     #[parse(rule=Rule::overloaded_zomb)]
     Zomb,
+    #[category = "Debug"]
     #[parse(rule=Rule::int_code)]
     Code,
     #[parse(rule=Rule::int_codf)]
@@ -161,10 +186,12 @@ pub enum Integer {
     Paws,
     #[parse(rule=Rule::int_unid)]
     Unid,
+    #[category = "Files"]
     #[parse(rule=Rule::int_inni)]
     Inni,
     #[parse(rule=Rule::int_inok)]
     Inok,
+    #[category = "History"]
     #[parse(rule=Rule::int_hist_cage)]
     HistCage {
         moniker: Box<SStringArg>,
@@ -221,6 +248,9 @@ pub enum Integer {
     },
     #[parse(rule=Rule::int_ooww)]
     Ooww { moniker: Box<SStringArg> },
+    #[category = "Input"]
+    #[parse(rule=Rule::int_hotp)]
+    Hotp,
     #[parse(rule=Rule::int_keyd)]
     Keyd { key_code: Box<IntArg> },
     #[parse(rule=Rule::int_mopx)]
@@ -229,6 +259,7 @@ pub enum Integer {
     Mopy,
     #[parse(rule=Rule::overloaded_pure)]
     Pure,
+    #[category = "Map"]
     #[parse(rule=Rule::int_addm)]
     Addm {
         x: Box<IntArg>,
@@ -298,7 +329,7 @@ pub enum Integer {
     Rtyp { room_id: Box<IntArg> },
     #[parse(rule=Rule::int_up)]
     Up,
-    //Motion
+    #[category = "Motion"]
     #[parse(rule=Rule::overloaded_aero)]
     Aero,
     #[parse(rule=Rule::overloaded_elas)]
@@ -320,7 +351,28 @@ pub enum Integer {
     Tmvt { x: Box<FloatArg>, y: Box<FloatArg> },
     #[parse(rule=Rule::int_wall)]
     Wall,
-    // Resources
+    #[category = "Net"]
+    #[parse(rule=Rule::int_net_erra)]
+    NetErra,
+    #[parse(rule=Rule::int_net_expo)]
+    NetExpo {
+        chunk_type: Box<SStringArg>,
+        dest_user_id: Box<SStringArg>,
+    },
+    #[parse(rule=Rule::overloaded_net_line)]
+    NetLine,
+    #[parse(rule=Rule::int_net_make)]
+    NetMake {
+        which_journal_spool: Box<IntArg>,
+        journal_name: Box<SStringArg>,
+        user_id: Box<SStringArg>,
+        report_destination: Box<Variable>,
+    },
+    #[parse(rule=Rule::int_net_rawe)]
+    NetRawe,
+    #[parse(rule=Rule::int_net_ulin)]
+    NetUlin { user_id: Box<SStringArg> },
+    #[category = "Resources"]
     #[parse(rule=Rule::int_pray_agti)]
     PrayAgti {
         resource_name: Box<SStringArg>,
@@ -354,6 +406,8 @@ pub enum Integer {
         do_install: Box<IntArg>,
         report_var: Box<Variable>,
     },
+    #[parse(rule=Rule::int_pray_kill)]
+    PrayKill { resource_name: Box<SStringArg> },
     #[parse(rule=Rule::int_pray_make)]
     PrayMake {
         which_journal_spot: Box<IntArg>,
@@ -366,7 +420,7 @@ pub enum Integer {
     PraySize { resource_name: Box<SStringArg> },
     #[parse(rule=Rule::int_pray_test)]
     PrayTest { resource_name: Box<SStringArg> },
-    // Scripts
+    #[category = "Scripts"]
     #[parse(rule=Rule::int_sorq)]
     Sorq {
         family: Box<IntArg>,
@@ -374,13 +428,15 @@ pub enum Integer {
         species: Box<IntArg>,
         event: Box<IntArg>,
     },
-    // Sounds
+    #[category = "Sounds"]
     #[parse(rule=Rule::int_mute)]
     Mute {
         and_mask: Box<IntArg>,
         eor_mask: Box<IntArg>,
     },
-    // Time
+    #[category = "Time"]
+    #[parse(rule=Rule::overloaded_buzz)]
+    Buzz,
     #[parse(rule=Rule::int_date)]
     Date,
     #[parse(rule=Rule::int_dayt)]
@@ -425,7 +481,7 @@ pub enum Integer {
     Wtik,
     #[parse(rule=Rule::int_year)]
     Year,
-    // Variables
+    #[category = "Variables"]
     #[parse(rule=Rule::overloaded_char)]
     Char {
         string: Box<SStringArg>,
@@ -442,6 +498,12 @@ pub enum Integer {
     Rean { catalogue_tag: Box<SStringArg> },
     #[parse(rule=Rule::int_reaq)]
     Reaq { catalogue_tag: Box<SStringArg> },
+    #[parse(rule=Rule::int_sins)]
+    Sins {
+        value: Box<SStringArg>,
+        start: Box<IntArg>,
+        search: Box<SStringArg>,
+    },
     #[parse(rule=Rule::int_stoi)]
     Stoi { value: Box<SStringArg> },
     #[parse(rule=Rule::int_strl)]
@@ -452,7 +514,7 @@ pub enum Integer {
     Vmjr,
     #[parse(rule=Rule::int_vmnr)]
     Vmnr,
-    // Vehicles
+    #[category = "Vehicles"]
     #[parse(rule=Rule::int_cabb)]
     Cabb,
     #[parse(rule=Rule::int_cabl)]
@@ -465,12 +527,12 @@ pub enum Integer {
     Cabt,
     #[parse(rule=Rule::overloaded_cabv)]
     Cabv,
-    // World
+    #[category = "World"]
     #[parse(rule=Rule::int_nwld)]
     Nwld,
     #[parse(rule=Rule::int_wnti)]
     Wnti { world: Box<SStringArg> },
-    // Ports
+    #[category = "Ports"]
     #[parse(rule=Rule::int_prt_itot)]
     PrtItot,
     #[parse(rule=Rule::int_prt_from)]
@@ -481,6 +543,6 @@ pub enum Integer {
 
 impl From<i32> for Integer {
     fn from(i: i32) -> Self {
-        Integer::Literal(i.into())
+        Integer::Literal(i)
     }
 }
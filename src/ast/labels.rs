@@ -1,4 +1,6 @@
-#[derive(Eq, PartialEq, Debug, Clone)]
+use crate::visit::Walk;
+
+#[derive(Eq, PartialEq, Debug, Clone, Walk)]
 pub struct Label(String);
 
 impl From<String> for Label {
@@ -12,3 +14,9 @@ impl From<Label> for String {
         l.0
     }
 }
+
+impl AsRef<str> for Label {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
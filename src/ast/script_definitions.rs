@@ -1,6 +1,7 @@
 use crate::ast::Command;
+use crate::visit::Walk;
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Walk)]
 pub struct EventScriptDefinition {
     pub definition: ScriptDefinition,
     pub family: i32,
@@ -9,7 +10,7 @@ pub struct EventScriptDefinition {
     pub script_number: i32,
 }
 
-#[derive(Debug, Eq, PartialEq, Default, Clone)]
+#[derive(Debug, Eq, PartialEq, Default, Clone, Walk)]
 pub struct ScriptDefinition {
     pub commands: Vec<Command>,
 }
@@ -24,7 +25,7 @@ impl ScriptDefinition {
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Walk)]
 pub enum Script {
     Install(ScriptDefinition),
     Removal(ScriptDefinition),
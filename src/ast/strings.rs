@@ -1,11 +1,13 @@
 use super::{AgentArg, Anything, DecimalArg, IntArg, SStringArg, Variable};
+use crate::visit::Walk;
 use crate::Rule;
-use caos_macros::ExpressionParser;
+use caos_macros::{ExpressionParser, Signatures, WriteCaos};
 
-#[derive(Eq, PartialEq, Debug, Clone, ExpressionParser)]
+#[derive(Eq, PartialEq, Debug, Clone, ExpressionParser, Signatures, WriteCaos, Walk)]
 pub enum SString {
     #[parse(ignore)]
     Literal(String),
+    #[category = "Agents"]
     #[parse(rule=Rule::string_catx)]
     Catx { category_id: Box<IntArg> },
     #[parse(rule=Rule::overloaded_hand)]
@@ -18,22 +20,37 @@ pub enum SString {
         tag_stub: Box<SStringArg>,
         offset: Box<IntArg>,
     },
+    #[category = "Camera"]
     #[parse(rule=Rule::overloaded_bkgd)]
     Bkgd { metaroom_id: Box<IntArg> },
+    #[category = "Compounds"]
     #[parse(rule=Rule::overloaded_ptxt)]
     Ptxt,
+    #[category = "Creatures"]
     #[parse(rule=Rule::overloaded_face)]
     Face,
+    #[parse(rule=Rule::string_limb)]
+    Limb {
+        body_part: Box<IntArg>,
+        genus: Box<IntArg>,
+        gender: Box<IntArg>,
+        age: Box<IntArg>,
+        variant: Box<IntArg>,
+    },
+    #[category = "Debug"]
     #[parse(rule=Rule::string_dbg)]
     Dbg { variable: Box<IntArg> },
     #[parse(rule=Rule::string_dbga)]
     Dbga { variable: Box<IntArg> },
+    #[category = "Files"]
     #[parse(rule=Rule::string_fvwm)]
     Fvwm { name: Box<SStringArg> },
     #[parse(rule=Rule::string_innl)]
     Innl,
+    #[category = "Genetics"]
     #[parse(rule=Rule::string_gtos)]
     Gtos { slot: Box<IntArg> },
+    #[category = "History"]
     #[parse(rule=Rule::overloaded_hist_foto)]
     HistFoto {
         moniker: Box<SStringArg>,
@@ -65,6 +82,7 @@ pub enum SString {
         moniker: Box<SStringArg>,
         event_no: Box<IntArg>,
     },
+    #[category = "Map"]
     #[parse(rule=Rule::string_bkds)]
     Bkds { metaroom_id: Box<IntArg> },
     #[parse(rule=Rule::string_emid)]
@@ -80,7 +98,18 @@ pub enum SString {
     },
     #[parse(rule=Rule::string_rloc)]
     Rloc { room_id: Box<IntArg> },
-    // Resource
+    #[category = "Net"]
+    #[parse(rule=Rule::string_net_from)]
+    NetFrom { resource_name: Box<SStringArg> },
+    #[parse(rule=Rule::string_net_host)]
+    NetHost,
+    #[parse(rule=Rule::overloaded_net_pass)]
+    NetPass,
+    #[parse(rule=Rule::string_net_user)]
+    NetUser,
+    #[parse(rule=Rule::string_net_what)]
+    NetWhat,
+    #[category = "Resources"]
     #[parse(rule=Rule::string_pray_agts)]
     PrayAgts {
         resource_name: Box<SStringArg>,
@@ -97,6 +126,7 @@ pub enum SString {
         resource_type: Box<SStringArg>,
         last_known: Box<SStringArg>,
     },
+    #[category = "Scripts"]
     #[parse(rule=Rule::string_caos)]
     Caos {
         inline: Box<IntArg>,
@@ -108,20 +138,28 @@ pub enum SString {
         catches: Box<IntArg>,
         report: Box<Variable>,
     },
+    #[category = "Sounds"]
     #[parse(rule=Rule::overloaded_rmsc)]
     Rmsc { x: Box<IntArg>, y: Box<IntArg> },
     #[parse(rule=Rule::overloaded_vois)]
     Vois,
-    // Date
+    #[category = "Time"]
     #[parse(rule=Rule::string_rtif)]
     Rtif {
         real_time: Box<IntArg>,
         format: Box<SStringArg>,
     },
+    #[category = "Variables"]
+    #[parse(rule=Rule::string_eamn)]
+    Eamn { previous: Box<SStringArg> },
     #[parse(rule=Rule::string_gamn)]
     Gamn { previous: Box<SStringArg> },
     #[parse(rule=Rule::string_gnam)]
     Gnam,
+    #[parse(rule=Rule::string_lowa)]
+    Lowa { value: Box<SStringArg> },
+    #[parse(rule=Rule::string_modu)]
+    Modu,
     #[parse(rule=Rule::string_read)]
     Read {
         catalogue_tag: Box<SStringArg>,
@@ -133,9 +171,13 @@ pub enum SString {
         start: Box<IntArg>,
         count: Box<IntArg>,
     },
+    #[parse(rule=Rule::string_ufos)]
+    Ufos,
+    #[parse(rule=Rule::string_uppa)]
+    Uppa { value: Box<SStringArg> },
     #[parse(rule=Rule::string_vtos)]
     Vtos { value: Box<DecimalArg> },
-    // World
+    #[category = "World"]
     #[parse(rule=Rule::overloaded_pswd)]
     Pswd { world_index: Box<IntArg> },
     #[parse(rule=Rule::string_wnam)]
@@ -144,6 +186,7 @@ pub enum SString {
     Wrld { world_index: Box<IntArg> },
     #[parse(rule=Rule::string_wuid)]
     Wuid,
+    #[category = "Ports"]
     #[parse(rule=Rule::string_prt_name)]
     PrtName {
         agent: Box<AgentArg>,
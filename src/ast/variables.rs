@@ -1,26 +1,37 @@
-use super::{AgentArg, IntArg, SStringArg};
+use super::{AgentArg, Anything, IntArg, SStringArg};
+use crate::visit::Walk;
 use crate::Rule;
-use caos_macros::ExpressionParser;
+use caos_macros::{ExpressionParser, Signatures, WriteCaos};
 
-#[derive(Eq, PartialEq, Debug, Clone, ExpressionParser)]
+#[derive(Eq, PartialEq, Debug, Clone, ExpressionParser, Signatures, WriteCaos, Walk)]
 pub enum Variable {
+    #[category = "Motion"]
     #[parse(rule=Rule::variable_velx)]
     Velx,
     #[parse(rule=Rule::variable_vely)]
     Vely,
+    #[category = "Agents"]
     #[parse(rule=Rule::variable_avar)]
     Avar {
         agent: Box<AgentArg>,
         index: Box<IntArg>,
     },
+    #[category = "Variables"]
     #[parse(rule=Rule::variable_game)]
     Game { variable_name: Box<SStringArg> },
+    #[parse(rule=Rule::variable_name)]
+    Name { name: Box<Anything> },
+    #[parse(rule=Rule::variable_mame)]
+    Mame { name: Box<Anything> },
+    #[parse(rule=Rule::variable_eame)]
+    Eame { name: Box<Anything> },
     #[parse(ignore)]
     Mvxx(u8),
     #[parse(ignore)]
     Ovxx(u8),
     #[parse(ignore)]
     Vaxx(u8),
+    #[category = "Scripts"]
     #[parse(rule=Rule::variable_p1)]
     P1,
     #[parse(rule=Rule::variable_p2)]
@@ -0,0 +1,434 @@
+use caos2::testing::{self, TestSuite};
+use caos2::{
+    c2, check_dialect, dap, docs, format_cos, parse_cos_recovering, suggest_keyword, CaosError,
+    Diagnostic, Dialect, Encoding, FormatOptions, KeywordCase, RenderStyle,
+};
+use std::io::{self, IsTerminal, Read, Write};
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage: caos fmt [OPTIONS] [FILES...]
+       caos check [--format <plain|ansi|json>] [--dialect <c3|ds>]
+                  [--encoding <cp1252|latin1>] [FILES...]
+       caos migrate [--encoding <cp1252|latin1>] [FILES...]
+       caos test [--format <tap|junit>] [--encoding <cp1252|latin1>] [FILES...]
+       caos dap
+       caos help [KEYWORD]
+
+With no files, source is read from stdin. Source is read as UTF-8 unless
+--encoding is given, as for files written for the engine in cp1252
+(Windows-1252).
+
+fmt: Formats CAOS source files, writing them to stdout.
+    --indent <N>                Columns per indentation level (default 4)
+    --tabs                      Indent with tabs
+    --keyword-case <upper|lower>
+                                Case to write keywords in (default upper)
+    --max-width <N>             Wrap commands longer than N columns
+    --check                     Exit with an error if any file is not formatted
+    --write                     Rewrite files in place instead of printing them
+    --encoding <cp1252|latin1>  Read and write files in the given encoding
+
+check: Reports every error in CAOS source files. JSON output has one
+diagnostic per line. Defaults to ansi when writing to a terminal. With
+--dialect, keywords from other engines are also errors, such as NET: commands
+in c3 (Creatures 3) scripts; ds (Docking Station) accepts every keyword.
+
+migrate: Rewrites Creatures 2 CAOS files as Creatures 3 CAOS, writing them to
+stdout. Commands with no Creatures 3 equivalent are kept as they were and
+reported on stderr. With --encoding, the migrated files are written in the
+same encoding they were read in.
+
+test: Runs the tests in CAOS source files, reporting the results on stdout
+in TAP (the default) or as JUnit XML. A test is a script with a '* test: <name>'
+comment above it, which fails if a DBG: ASRT condition is false. Lines it must
+write with DBG: OUTS and DBG: OUTV are given by '* expect: <line>' comments.
+
+dap: Serves the Debug Adapter Protocol on stdin and stdout, so that editors
+can debug CAOS files against a model of the game's world. A launch request
+takes the file to debug as 'program', and 'stopOnEntry', 'event' (the family,
+genus, species and number of an event script to run after the install
+scripts) and 'encoding' as options.
+
+help: Describes a CAOS keyword, such as 'NEW: SIMP', or shows this message.";
+
+#[derive(Default)]
+struct FmtArgs {
+    options: FormatOptions,
+    encoding: Option<Encoding>,
+    check: bool,
+    write: bool,
+    files: Vec<String>,
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let result = match args.next().as_deref() {
+        Some("fmt") => parse_fmt_args(args).and_then(|a| run_fmt(&a)),
+        Some("check") => parse_check_args(args).and_then(|a| run_check(&a)),
+        Some("migrate") => parse_migrate_args(args).and_then(|a| run_migrate(&a)),
+        Some("test") => parse_test_args(args).and_then(|a| run_test(&a)),
+        Some("dap") => run_dap(&args.collect::<Vec<_>>()),
+        Some("help" | "--help" | "-h") => run_help(&args.collect::<Vec<_>>().join(" ")),
+        Some(other) => Err(format!("Unknown command '{}'\n\n{}", other, USAGE)),
+        None => Err(USAGE.to_owned()),
+    };
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(message) => {
+            eprintln!("{}", message);
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn parse_fmt_args(mut args: impl Iterator<Item = String>) -> Result<FmtArgs, String> {
+    fn number(value: Option<String>, flag: &str) -> Result<usize, String> {
+        value
+            .and_then(|v| v.parse().ok())
+            .ok_or_else(|| format!("{} expects a number", flag))
+    }
+
+    let mut fmt_args = FmtArgs::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--indent" => fmt_args.options.indent_width = number(args.next(), &arg)?,
+            "--tabs" => fmt_args.options.use_tabs = true,
+            "--keyword-case" => {
+                fmt_args.options.keyword_case = match args.next().as_deref() {
+                    Some("upper") => KeywordCase::Upper,
+                    Some("lower") => KeywordCase::Lower,
+                    _ => return Err("--keyword-case expects 'upper' or 'lower'".to_owned()),
+                }
+            }
+            "--max-width" => fmt_args.options.max_line_length = Some(number(args.next(), &arg)?),
+            "--check" => fmt_args.check = true,
+            "--write" => fmt_args.write = true,
+            "--encoding" => fmt_args.encoding = Some(args.next().unwrap_or_default().parse()?),
+            flag if flag.starts_with("--") => {
+                return Err(format!("Unknown option '{}'\n\n{}", flag, USAGE))
+            }
+            _ => fmt_args.files.push(arg),
+        }
+    }
+    Ok(fmt_args)
+}
+
+/// Formats each file, returning `false` if any could not be formatted or, with
+/// `--check`, if any were not already formatted.
+fn run_fmt(args: &FmtArgs) -> Result<bool, String> {
+    if args.files.is_empty() {
+        let source = read_stdin(args.encoding)?;
+        let formatted = format_cos(&source, &args.options).map_err(|e| e.to_string())?;
+        if args.check {
+            return Ok(formatted == source);
+        }
+        io::stdout()
+            .write_all(&encode(&formatted, args.encoding).map_err(|e| e.to_string())?)
+            .map_err(|e| e.to_string())?;
+        return Ok(true);
+    }
+
+    let mut success = true;
+    for path in &args.files {
+        let source = read_file(path, args.encoding)?;
+        let formatted = match format_cos(&source, &args.options)
+            .and_then(|f| encode(&f, args.encoding).map(|bytes| (f, bytes)))
+        {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                success = false;
+                continue;
+            }
+        };
+        let (formatted, bytes) = formatted;
+        if args.check {
+            if formatted != source {
+                println!("{}", path);
+                success = false;
+            }
+        } else if args.write {
+            if formatted != source {
+                std::fs::write(path, bytes).map_err(|e| format!("{}: {}", path, e))?;
+            }
+        } else {
+            io::stdout().write_all(&bytes).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(success)
+}
+
+enum CheckFormat {
+    Text(RenderStyle),
+    Json,
+}
+
+struct CheckArgs {
+    format: CheckFormat,
+    dialect: Option<Dialect>,
+    encoding: Option<Encoding>,
+    files: Vec<String>,
+}
+
+fn parse_check_args(mut args: impl Iterator<Item = String>) -> Result<CheckArgs, String> {
+    let default_style = if io::stdout().is_terminal() {
+        RenderStyle::Ansi
+    } else {
+        RenderStyle::Plain
+    };
+    let mut check_args = CheckArgs {
+        format: CheckFormat::Text(default_style),
+        dialect: None,
+        encoding: None,
+        files: Vec::new(),
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                check_args.format = match args.next().as_deref() {
+                    Some("plain") => CheckFormat::Text(RenderStyle::Plain),
+                    Some("ansi") => CheckFormat::Text(RenderStyle::Ansi),
+                    Some("json") => CheckFormat::Json,
+                    _ => return Err("--format expects 'plain', 'ansi' or 'json'".to_owned()),
+                }
+            }
+            "--dialect" => {
+                let dialect = args.next().unwrap_or_default().parse()?;
+                check_args.dialect = Some(dialect);
+            }
+            "--encoding" => {
+                let encoding = args.next().unwrap_or_default().parse()?;
+                check_args.encoding = Some(encoding);
+            }
+            flag if flag.starts_with("--") => {
+                return Err(format!("Unknown option '{}'\n\n{}", flag, USAGE))
+            }
+            _ => check_args.files.push(arg),
+        }
+    }
+    Ok(check_args)
+}
+
+/// Reports the errors in each file, returning `false` if there were any.
+fn run_check(args: &CheckArgs) -> Result<bool, String> {
+    let mut sources = Vec::new();
+    if args.files.is_empty() {
+        sources.push(("<stdin>".to_owned(), read_stdin(args.encoding)?));
+    }
+    for path in &args.files {
+        sources.push((path.clone(), read_file(path, args.encoding)?));
+    }
+
+    let mut success = true;
+    for (name, source) in &sources {
+        let parse = parse_cos_recovering(source);
+        let dialect_errors = args.dialect.map(|dialect| check_dialect(&parse, dialect));
+        let mut errors = parse.errors;
+        if let Some(dialect_errors) = dialect_errors {
+            errors.extend(dialect_errors);
+            errors.sort_by_key(|e| e.span().map_or(usize::MAX, |s| s.start));
+        }
+        for error in &errors {
+            success = false;
+            let diagnostic = Diagnostic::from(error);
+            match args.format {
+                CheckFormat::Text(style) => println!("{}", diagnostic.render(source, name, style)),
+                CheckFormat::Json => println!("{}", diagnostic.to_json(name)),
+            }
+        }
+    }
+    Ok(success)
+}
+
+#[derive(Default)]
+struct MigrateArgs {
+    encoding: Option<Encoding>,
+    files: Vec<String>,
+}
+
+fn parse_migrate_args(mut args: impl Iterator<Item = String>) -> Result<MigrateArgs, String> {
+    let mut migrate_args = MigrateArgs::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--encoding" => migrate_args.encoding = Some(args.next().unwrap_or_default().parse()?),
+            flag if flag.starts_with("--") => {
+                return Err(format!("Unknown option '{}'\n\n{}", flag, USAGE))
+            }
+            _ => migrate_args.files.push(arg),
+        }
+    }
+    Ok(migrate_args)
+}
+
+/// Migrates each file, returning `false` if anything could not be migrated.
+fn run_migrate(args: &MigrateArgs) -> Result<bool, String> {
+    let mut sources = Vec::new();
+    if args.files.is_empty() {
+        sources.push(("<stdin>".to_owned(), read_stdin(args.encoding)?));
+    }
+    for path in &args.files {
+        sources.push((path.clone(), read_file(path, args.encoding)?));
+    }
+
+    let style = if io::stderr().is_terminal() {
+        RenderStyle::Ansi
+    } else {
+        RenderStyle::Plain
+    };
+    let mut success = true;
+    for (name, source) in &sources {
+        let migration = match c2::migrate(source) {
+            Ok(migration) => migration,
+            Err(e) => {
+                eprintln!("{}", Diagnostic::from(&e).render(source, name, style));
+                success = false;
+                continue;
+            }
+        };
+        for issue in &migration.issues {
+            success = false;
+            eprintln!("{}", Diagnostic::from(issue).render(source, name, style));
+        }
+        match encode(&migration.cos_file.to_string(), args.encoding) {
+            Ok(bytes) => io::stdout().write_all(&bytes).map_err(|e| e.to_string())?,
+            Err(e) => {
+                eprintln!("{}: {}", name, e);
+                success = false;
+            }
+        }
+    }
+    Ok(success)
+}
+
+#[derive(Default)]
+enum TestFormat {
+    #[default]
+    Tap,
+    Junit,
+}
+
+#[derive(Default)]
+struct TestArgs {
+    format: TestFormat,
+    encoding: Option<Encoding>,
+    files: Vec<String>,
+}
+
+fn parse_test_args(mut args: impl Iterator<Item = String>) -> Result<TestArgs, String> {
+    let mut test_args = TestArgs::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                test_args.format = match args.next().as_deref() {
+                    Some("tap") => TestFormat::Tap,
+                    Some("junit") => TestFormat::Junit,
+                    _ => return Err("--format expects 'tap' or 'junit'".to_owned()),
+                }
+            }
+            "--encoding" => test_args.encoding = Some(args.next().unwrap_or_default().parse()?),
+            flag if flag.starts_with("--") => {
+                return Err(format!("Unknown option '{}'\n\n{}", flag, USAGE))
+            }
+            _ => test_args.files.push(arg),
+        }
+    }
+    Ok(test_args)
+}
+
+/// Runs the tests in each file, returning `false` if any failed or a file
+/// could not be parsed.
+fn run_test(args: &TestArgs) -> Result<bool, String> {
+    let mut sources = Vec::new();
+    if args.files.is_empty() {
+        sources.push(("<stdin>".to_owned(), read_stdin(args.encoding)?));
+    }
+    for path in &args.files {
+        sources.push((path.clone(), read_file(path, args.encoding)?));
+    }
+
+    let style = if io::stderr().is_terminal() {
+        RenderStyle::Ansi
+    } else {
+        RenderStyle::Plain
+    };
+    let mut success = true;
+    let mut suites: Vec<TestSuite> = Vec::new();
+    for (name, source) in &sources {
+        match testing::run_tests(name, source) {
+            Ok(suite) => {
+                success &= suite.passed();
+                suites.push(suite);
+            }
+            Err(e) => {
+                eprintln!("{}", Diagnostic::from(&e).render(source, name, style));
+                success = false;
+                suites.push(TestSuite::unparsed(name, e));
+            }
+        }
+    }
+    match args.format {
+        TestFormat::Tap => print!("{}", testing::tap(&suites)),
+        TestFormat::Junit => print!("{}", testing::junit(&suites)),
+    }
+    Ok(success)
+}
+
+/// Serves a debugging session on stdin and stdout.
+fn run_dap(args: &[String]) -> Result<bool, String> {
+    if let Some(arg) = args.first() {
+        return Err(format!("Unknown option '{}'\n\n{}", arg, USAGE));
+    }
+    dap::serve(io::stdin().lock(), io::stdout().lock()).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// Reads stdin as UTF-8, or in `encoding` if given.
+fn read_stdin(encoding: Option<Encoding>) -> Result<String, String> {
+    let mut bytes = Vec::new();
+    io::stdin()
+        .read_to_end(&mut bytes)
+        .map_err(|e| e.to_string())?;
+    decode(bytes, encoding).map_err(|e| format!("<stdin>: {}", e))
+}
+
+/// Reads a file as UTF-8, or in `encoding` if given.
+fn read_file(path: &str, encoding: Option<Encoding>) -> Result<String, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+    decode(bytes, encoding).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn decode(bytes: Vec<u8>, encoding: Option<Encoding>) -> Result<String, String> {
+    match encoding {
+        Some(encoding) => Ok(encoding.decode(&bytes)),
+        None => String::from_utf8(bytes).map_err(|e| e.to_string()),
+    }
+}
+
+fn encode(text: &str, encoding: Option<Encoding>) -> Result<Vec<u8>, CaosError> {
+    match encoding {
+        Some(encoding) => encoding.encode(text),
+        None => Ok(text.as_bytes().to_vec()),
+    }
+}
+
+/// Prints the documentation for `keyword`, or the usage if it is empty.
+fn run_help(keyword: &str) -> Result<bool, String> {
+    if keyword.is_empty() {
+        println!("{}", USAGE);
+        return Ok(true);
+    }
+    let docs = docs::lookup(keyword);
+    if docs.is_empty() {
+        return Err(match suggest_keyword(keyword) {
+            Some(suggestion) => format!(
+                "Unknown keyword '{}', did you mean '{}'?",
+                keyword, suggestion
+            ),
+            None => format!("Unknown keyword '{}'", keyword),
+        });
+    }
+    let docs: Vec<_> = docs.iter().map(|d| d.to_string()).collect();
+    println!("{}", docs.join("\n\n"));
+    Ok(true)
+}
@@ -0,0 +1,18 @@
+//! Creatures 2 CAOS, and its migration to Creatures 3.
+//!
+//! Creatures 2 writes strings in square brackets, has ten script variables
+//! `VAR0` to `VAR9` and ten agent variables `OBV0` to `OBV9`, and lacks many
+//! of the keywords of Creatures 3, so it has its own grammar and AST.
+//! [migrate] rewrites a file into the [CosFile](crate::ast::CosFile) of
+//! Creatures 3, reporting anything which has no equivalent.
+#[cfg(test)]
+mod tests;
+
+mod ast;
+mod keywords;
+mod migrate;
+mod parser;
+
+pub use ast::*;
+pub use migrate::{migrate, Migration};
+pub use parser::parse_c2;
@@ -0,0 +1,126 @@
+use crate::ast::{ConditionType, JoinType};
+use crate::Span;
+
+/// A Creatures 2 CAOS file.
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct C2File {
+    pub scripts: Vec<C2Script>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum C2Script {
+    /// The commands outside of any `SCRP` or `RSCR`.
+    Install(Vec<C2Command>),
+    /// The commands following `RSCR`.
+    Removal(Vec<C2Command>),
+    Event {
+        family: i32,
+        genus: i32,
+        species: i32,
+        script_number: i32,
+        commands: Vec<C2Command>,
+    },
+}
+
+/// A Creatures 2 command, along with the source it was parsed from.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum C2Command {
+    /// A command without a body, such as `SETV VAR0 1`.
+    Simple {
+        keyword: &'static str,
+        args: Vec<C2Value>,
+        span: Span,
+    },
+    Doif {
+        condition: C2Condition,
+        definition: Vec<C2Command>,
+        else_definition: Option<Vec<C2Command>>,
+        span: Span,
+    },
+    Reps {
+        count: C2Value,
+        definition: Vec<C2Command>,
+        span: Span,
+    },
+    LoopEver {
+        definition: Vec<C2Command>,
+        span: Span,
+    },
+    LoopUntl {
+        definition: Vec<C2Command>,
+        condition: C2Condition,
+        span: Span,
+    },
+    /// `ENUM`, `ESEE` or `ETCH`, which run their body for each matching agent.
+    Enum {
+        keyword: &'static str,
+        family: C2Value,
+        genus: C2Value,
+        species: C2Value,
+        definition: Vec<C2Command>,
+        span: Span,
+    },
+    Subr {
+        label: String,
+        definition: Vec<C2Command>,
+        span: Span,
+    },
+    /// A command starting with, or taking, `word`, which is not a known
+    /// keyword. It runs up to the next keyword which starts a command.
+    Unknown { word: String, span: Span },
+}
+
+impl C2Command {
+    /// The source of the command, including the body of a block.
+    pub fn span(&self) -> &Span {
+        match self {
+            C2Command::Simple { span, .. }
+            | C2Command::Doif { span, .. }
+            | C2Command::Reps { span, .. }
+            | C2Command::LoopEver { span, .. }
+            | C2Command::LoopUntl { span, .. }
+            | C2Command::Enum { span, .. }
+            | C2Command::Subr { span, .. }
+            | C2Command::Unknown { span, .. } => span,
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum C2Value {
+    Integer(i32),
+    String(String),
+    Variable(C2Variable),
+    /// A label, as taken by `GSUB`.
+    Label(String),
+    /// An expression keyword and its arguments, such as `POSL` or
+    /// `TOUC TARG OWNR`.
+    Expression {
+        keyword: &'static str,
+        args: Vec<C2Value>,
+    },
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum C2Variable {
+    /// `VAR0` to `VAR9`, local to the script.
+    Var(u8),
+    /// `OBV0` to `OBV9`, belonging to the target.
+    Obv(u8),
+}
+
+/// A condition, as taken by `DOIF` and `UNTL`, such as `var0 eq 1 and obv1 lt 2`.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct C2Condition {
+    pub first: C2Comparison,
+    /// The comparisons following the first, each with the keyword joining it
+    /// to those before. They are evaluated left to right.
+    pub rest: Vec<(JoinType, C2Comparison)>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct C2Comparison {
+    pub lhs: C2Value,
+    pub cond_type: ConditionType,
+    pub rhs: C2Value,
+}
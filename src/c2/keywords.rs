@@ -0,0 +1,233 @@
+/// A Creatures 2 keyword which is not part of a block, and how it is migrated.
+#[derive(Debug)]
+pub(super) struct Keyword {
+    pub name: &'static str,
+    pub role: Role,
+    pub args: &'static [Arg],
+    pub c3: C3,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub(super) enum Role {
+    Command,
+    Expression,
+}
+
+/// The kind of token an argument is read from.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub(super) enum Arg {
+    /// A number, variable or expression.
+    Value,
+    /// A string in square brackets.
+    String,
+    /// A file name, such as the sprite file of `NEW: SIMP`, written without
+    /// its extension either bare, as in `eggs`, or in square brackets.
+    File,
+    Variable,
+    Label,
+}
+
+/// How a keyword is written in Creatures 3.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub(super) enum C3 {
+    /// The same keyword, taking the same arguments.
+    Same,
+    /// A keyword of another name, taking the same arguments.
+    Renamed(&'static str),
+    /// Rewritten by the migration, as it takes different arguments.
+    Rewritten,
+    /// Nothing, for the given reason.
+    Missing(&'static str),
+}
+
+use Arg::*;
+use Role::*;
+
+const fn command(name: &'static str, args: &'static [Arg], c3: C3) -> Keyword {
+    Keyword {
+        name,
+        role: Command,
+        args,
+        c3,
+    }
+}
+
+const fn expression(name: &'static str, args: &'static [Arg], c3: C3) -> Keyword {
+    Keyword {
+        name,
+        role: Expression,
+        args,
+        c3,
+    }
+}
+
+const SET: &[Arg] = &[Variable, Value];
+
+/// The sprite file, image count, first image, plane and clone flag of the
+/// `NEW:` commands.
+const NEW: &[Arg] = &[File, Value, Value, Value, Value];
+
+/// The significance, input, intensity and features of a stimulus, followed by
+/// four chemicals and their amounts.
+const STIM: &[Arg] = &[
+    Value, Value, Value, Value, Value, Value, Value, Value, Value, Value, Value, Value,
+];
+
+/// The stimulus arguments of `STIM WRIT`, after the creature to stimulate.
+const STIM_WRIT: &[Arg] = &[
+    Value, Value, Value, Value, Value, Value, Value, Value, Value, Value, Value, Value, Value,
+];
+
+const NEW_CLASSIFIER: &str =
+    "Creatures 3 NEW: commands take the classifier, which Creatures 2 sets with a SETV CLAS straight afterwards";
+const STIM_CHEMICALS: &str =
+    "Creatures 3 stimuli are numbered in the stimulus catalogue rather than listing chemicals";
+
+/// The keywords of Creatures 2 which are not part of a block. `DOIF`, `REPS`,
+/// `LOOP`, `ENUM`, `ESEE`, `ETCH`, `SUBR` and the keywords closing them are
+/// handled by the parser.
+pub(super) const KEYWORDS: &[Keyword] = &[
+    // Variables
+    command("SETV", SET, C3::Same),
+    command("ADDV", SET, C3::Same),
+    command("SUBV", SET, C3::Same),
+    command("MULV", SET, C3::Same),
+    command("DIVV", SET, C3::Same),
+    command("MODV", SET, C3::Same),
+    command("ANDV", SET, C3::Same),
+    command("ORRV", SET, C3::Same),
+    command("NEGV", &[Variable], C3::Same),
+    command("RNDV", &[Variable, Value, Value], C3::Rewritten),
+    command(
+        "SETV CLAS",
+        &[Value],
+        C3::Missing(
+            "Creatures 3 agents are given their classifier when they are created, so it must follow a NEW: command",
+        ),
+    ),
+    command(
+        "SETV ACTV",
+        &[Value],
+        C3::Missing("Creatures 3 agents have no activation state"),
+    ),
+    // Flow
+    command("GSUB", &[Label], C3::Same),
+    command("STOP", &[], C3::Same),
+    command("INST", &[], C3::Same),
+    command("SLOW", &[], C3::Same),
+    command("WAIT", &[Value], C3::Same),
+    command("DONE", &[], C3::Same),
+    // Agents
+    command("NEW: SIMP", NEW, C3::Missing(NEW_CLASSIFIER)),
+    command("NEW: COMP", NEW, C3::Missing(NEW_CLASSIFIER)),
+    command("NEW: VHCL", NEW, C3::Missing(NEW_CLASSIFIER)),
+    command("TARG", &[Value], C3::Same),
+    command("KILL", &[Value], C3::Same),
+    command("ANIM", &[String], C3::Rewritten),
+    command("POSE", &[Value], C3::Same),
+    command("BASE", &[Value], C3::Same),
+    command("PART", &[Value], C3::Same),
+    command("OVER", &[], C3::Same),
+    command("TICK", &[Value], C3::Same),
+    command("ATTR", &[Value], C3::Same),
+    command(
+        "BHVR",
+        &[Value, Value],
+        C3::Missing("Creatures 3 BHVR takes a single set of permission flags"),
+    ),
+    command("MVTO", &[Value, Value], C3::Same),
+    command("MVBY", &[Value, Value], C3::Same),
+    command("MESG WRIT", &[Value, Value], C3::Same),
+    command("MESG WRT+", &[Value, Value, Value, Value, Value], C3::Same),
+    command("DROP", &[], C3::Same),
+    command(
+        "EDIT",
+        &[],
+        C3::Missing("Creatures 3 has no hand editing mode"),
+    ),
+    // Vehicles
+    command("CABN", &[Value, Value, Value, Value], C3::Same),
+    command("SPAS", &[Value, Value], C3::Same),
+    // Stimuli
+    command("STIM SHOU", STIM, C3::Missing(STIM_CHEMICALS)),
+    command("STIM SIGN", STIM, C3::Missing(STIM_CHEMICALS)),
+    command("STIM TACT", STIM, C3::Missing(STIM_CHEMICALS)),
+    command("STIM WRIT", STIM_WRIT, C3::Missing(STIM_CHEMICALS)),
+    // Sounds
+    command("SNDE", &[File], C3::Same),
+    command("SNDL", &[File], C3::Same),
+    command("SNDQ", &[File, Value], C3::Same),
+    command("SNDC", &[File], C3::Same),
+    command(
+        "SNDV",
+        &[File],
+        C3::Missing("Creatures 3 has no SNDV; use SNDE or SNDC instead"),
+    ),
+    command("STPC", &[], C3::Same),
+    // Camera
+    command("SYS: CMRA", &[Value, Value], C3::Rewritten),
+    command("SYS: CMRP", &[Value, Value], C3::Rewritten),
+    command(
+        "SYS: CAMT",
+        &[],
+        C3::Missing("Creatures 3 cameras do not track agents with a command"),
+    ),
+    // Compounds
+    command(
+        "BBD: SHOW",
+        &[Value],
+        C3::Missing("Creatures 3 has no vocabulary board"),
+    ),
+    command(
+        "BBD: WORD",
+        &[Value, Value, String],
+        C3::Missing("Creatures 3 has no vocabulary board"),
+    ),
+    // Agent expressions
+    expression("TARG", &[], C3::Same),
+    expression("OWNR", &[], C3::Same),
+    expression("FROM", &[], C3::Same),
+    expression("_IT_", &[], C3::Same),
+    expression("NORN", &[], C3::Same),
+    expression("PNTR", &[], C3::Same),
+    expression("NULL", &[], C3::Same),
+    expression("CARR", &[], C3::Same),
+    // Integer expressions
+    expression("POSL", &[], C3::Same),
+    expression("POSR", &[], C3::Same),
+    expression("POST", &[], C3::Same),
+    expression("POSB", &[], C3::Same),
+    expression("WDTH", &[], C3::Same),
+    expression("HGHT", &[], C3::Same),
+    expression("POSE", &[], C3::Same),
+    expression("ATTR", &[], C3::Same),
+    expression("FMLY", &[], C3::Same),
+    expression("GNUS", &[], C3::Same),
+    expression("SPCS", &[], C3::Same),
+    expression("TOUC", &[Value, Value], C3::Same),
+    expression("RAND", &[Value, Value], C3::Same),
+    expression("TOTL", &[Value, Value, Value], C3::Same),
+    expression("MOVS", &[], C3::Same),
+    expression("XVEC", &[], C3::Renamed("VELX")),
+    expression("YVEC", &[], C3::Renamed("VELY")),
+    expression(
+        "CLAS",
+        &[],
+        C3::Missing("use FMLY, GNUS and SPCS for the classifier instead"),
+    ),
+    expression(
+        "ACTV",
+        &[],
+        C3::Missing("Creatures 3 agents have no activation state"),
+    ),
+];
+
+/// The classifier arguments of `ENUM`, `ESEE` and `ETCH`.
+pub(super) const ENUM_ARGS: &[Arg] = &[Value, Value, Value];
+
+/// The keyword named `name`, ignoring case, in the given role.
+pub(super) fn lookup(name: &str, role: Role) -> Option<&'static Keyword> {
+    KEYWORDS
+        .iter()
+        .find(|k| k.role == role && k.name.eq_ignore_ascii_case(name))
+}
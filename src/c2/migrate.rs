@@ -0,0 +1,356 @@
+use super::ast::{C2Command, C2Comparison, C2Condition, C2File, C2Script, C2Value, C2Variable};
+use super::keywords::{self, Role, C3};
+use super::parse_c2;
+use crate::ast::{
+    Command, ConditionType, CosFile, EventScriptDefinition, JoinType, Script, ScriptDefinition,
+};
+use crate::{parse_command_str, CaosError, ErrorType, Span};
+
+/// The result of [migrate].
+#[derive(Debug)]
+pub struct Migration {
+    /// The migrated scripts, with a [Command::Error] holding the Creatures 2
+    /// source of each command which could not be migrated.
+    pub cos_file: CosFile,
+    /// Every command which could not be migrated, and why.
+    pub issues: Vec<CaosError>,
+}
+
+impl Migration {
+    /// Returns `true` if every command was migrated.
+    pub fn is_complete(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Parses a Creatures 2 CAOS file and rewrites it as Creatures 3 CAOS.
+///
+/// Variables are renamed, `VAR0` becoming `VA00` and `OBV0` becoming `OV00`,
+/// and commands which take different arguments in Creatures 3 are rewritten,
+/// such as `RNDV VAR0 1 6` to `SETV VA00 RAND 1 6`. A `NEW:` command followed
+/// by `SETV CLAS` becomes a single `NEW:` taking the family, genus and species
+/// held in the classifier, and dropping the Creatures 2 clone flag. Commands with no
+/// equivalent are reported in [Migration::issues], as errors of type
+/// [ErrorType::NoEquivalent] pointing at the Creatures 2 source, and commands
+/// using a word which is not a known keyword as [ErrorType::UnknownToken].
+pub fn migrate(c2_content: &str) -> Result<Migration, CaosError> {
+    let file = parse_c2(c2_content)?;
+    Ok(Migrator {
+        source: c2_content,
+        issues: Vec::new(),
+    }
+    .file(&file))
+}
+
+struct Migrator<'s> {
+    source: &'s str,
+    issues: Vec<CaosError>,
+}
+
+impl Migrator<'_> {
+    fn file(mut self, file: &C2File) -> Migration {
+        let scripts = file
+            .scripts
+            .iter()
+            .map(|script| match script {
+                C2Script::Install(commands) => Script::Install(self.definition(commands)),
+                C2Script::Removal(commands) => Script::Removal(self.definition(commands)),
+                C2Script::Event {
+                    family,
+                    genus,
+                    species,
+                    script_number,
+                    commands,
+                } => Script::Event(EventScriptDefinition {
+                    definition: self.definition(commands),
+                    family: *family,
+                    genus: *genus,
+                    species: *species,
+                    script_number: *script_number,
+                }),
+            })
+            .collect();
+        Migration {
+            cos_file: CosFile { scripts },
+            issues: self.issues,
+        }
+    }
+
+    fn definition(&mut self, commands: &[C2Command]) -> ScriptDefinition {
+        let mut migrated = Vec::new();
+        let mut commands = commands.iter().peekable();
+        while let Some(command) = commands.next() {
+            let classifier = match command {
+                C2Command::Simple { keyword, .. } if keyword.starts_with("NEW: ") => {
+                    commands.next_if(|next| {
+                        matches!(next, C2Command::Simple { keyword, .. } if *keyword == "SETV CLAS")
+                    })
+                }
+                _ => None,
+            };
+            migrated.push(match (command, classifier) {
+                (
+                    C2Command::Simple { keyword, args, .. },
+                    Some(C2Command::Simple {
+                        args: classifier_args,
+                        span,
+                        ..
+                    }),
+                ) => {
+                    let result = new_command(keyword, args, classifier_args)
+                        .and_then(|text| parse_command_str(&text));
+                    self.migrated(command.span().join(span), result)
+                }
+                _ => {
+                    let result = self.try_command(command);
+                    self.migrated(*command.span(), result)
+                }
+            });
+        }
+        ScriptDefinition { commands: migrated }
+    }
+
+    /// Returns a migrated command, or records why the source at `span` could
+    /// not be migrated and keeps it.
+    fn migrated(&mut self, span: Span, result: Result<Command, CaosError>) -> Command {
+        match result {
+            Ok(migrated) => migrated,
+            Err(e) => {
+                self.issues.push(e.with_span(span));
+                Command::Error {
+                    text: span.as_str(self.source).to_owned(),
+                }
+            }
+        }
+    }
+
+    /// Writes the command as Creatures 3 source and parses it. Blocks are
+    /// parsed without their bodies, which are migrated separately.
+    fn try_command(&mut self, command: &C2Command) -> Result<Command, CaosError> {
+        let (header, body) = match command {
+            C2Command::Simple { keyword, args, .. } => {
+                return parse_command_str(&simple_command(keyword, args)?)
+            }
+            C2Command::Unknown { word, .. } => {
+                let text = word.clone();
+                return Err(CaosError::new(ErrorType::UnknownToken { text }, None));
+            }
+            C2Command::Doif {
+                condition,
+                definition,
+                else_definition,
+                ..
+            } => {
+                let mut migrated =
+                    parse_command_str(&format!("DOIF {} ENDI", condition_text(condition)?))?;
+                if let (Command::Doif(do_if), Some(else_definition)) =
+                    (&mut migrated, else_definition)
+                {
+                    do_if.else_definition = Some(self.definition(else_definition));
+                }
+                (migrated, definition)
+            }
+            C2Command::Reps {
+                count, definition, ..
+            } => (
+                parse_command_str(&format!("REPS {} REPE", value_text(count)?))?,
+                definition,
+            ),
+            C2Command::LoopEver { definition, .. } => (
+                Command::LoopEver {
+                    definition: ScriptDefinition::default(),
+                },
+                definition,
+            ),
+            C2Command::LoopUntl {
+                definition,
+                condition,
+                ..
+            } => (
+                parse_command_str(&format!("LOOP UNTL {}", condition_text(condition)?))?,
+                definition,
+            ),
+            C2Command::Enum {
+                keyword,
+                family,
+                genus,
+                species,
+                definition,
+                ..
+            } => (
+                parse_command_str(&format!(
+                    "{} {} {} {} NEXT",
+                    keyword,
+                    value_text(family)?,
+                    value_text(genus)?,
+                    value_text(species)?
+                ))?,
+                definition,
+            ),
+            C2Command::Subr {
+                label, definition, ..
+            } => (
+                Command::Subr {
+                    label: label.clone().into(),
+                    definition: ScriptDefinition::default(),
+                },
+                definition,
+            ),
+        };
+        let mut migrated = header;
+        let body = self.definition(body);
+        match &mut migrated {
+            Command::Doif(do_if) => do_if.definition = body,
+            Command::Reps { definition, .. }
+            | Command::LoopEver { definition }
+            | Command::LoopUntl { definition, .. }
+            | Command::Subr { definition, .. } => *definition = body,
+            Command::Enum(e) | Command::Esee(e) | Command::Etch(e) => e.definition = body,
+            _ => unreachable!("Only blocks have a body"),
+        }
+        Ok(migrated)
+    }
+}
+
+/// Writes a command without a body as Creatures 3 source.
+fn simple_command(keyword: &str, args: &[C2Value]) -> Result<String, CaosError> {
+    let definition = keywords::lookup(keyword, Role::Command).expect("Parsed keywords exist");
+    let args = args.iter().map(value_text).collect::<Result<Vec<_>, _>>()?;
+    Ok(match (definition.c3, keyword, args.as_slice()) {
+        (C3::Same, ..) => join(keyword, &args),
+        (C3::Renamed(c3_keyword), ..) => join(c3_keyword, &args),
+        (C3::Missing(reason), ..) => return Err(no_equivalent(keyword, reason)),
+        (C3::Rewritten, "RNDV", [var, min, max]) => format!("SETV {} RAND {} {}", var, min, max),
+        (C3::Rewritten, "ANIM", _) => format!("ANIM {}", pose_list(keyword, &args[0])?),
+        (C3::Rewritten, "SYS: CMRA", [x, y]) => format!("CMRA {} {} 0", x, y),
+        (C3::Rewritten, "SYS: CMRP", [x, y]) => format!("CMRP {} {} 0", x, y),
+        (C3::Rewritten, ..) => return Err(no_rewrite(keyword)),
+    })
+}
+
+/// Writes a `NEW:` command as Creatures 3 source, with the classifier set by
+/// the `SETV CLAS` after it. A Creatures 2 classifier holds the family, genus
+/// and species in its top three bytes.
+fn new_command(
+    keyword: &str,
+    args: &[C2Value],
+    classifier: &[C2Value],
+) -> Result<String, CaosError> {
+    let [C2Value::Integer(classifier)] = classifier else {
+        return Err(no_equivalent(
+            "SETV CLAS",
+            "the classifier is not a number, so cannot be split into a family, genus and species",
+        ));
+    };
+    // The clone flag, last, has no counterpart in Creatures 3.
+    let [sprite_file, image_count, first_image, plane, _] = args else {
+        return Err(no_rewrite(keyword));
+    };
+    let classifier = *classifier as u32;
+    let (family, genus, species) = (
+        classifier >> 24,
+        (classifier >> 16) & 0xFF,
+        (classifier >> 8) & 0xFF,
+    );
+    Ok(format!(
+        "{} {} {} {} {} {} {} {}",
+        keyword,
+        family,
+        genus,
+        species,
+        value_text(sprite_file)?,
+        value_text(image_count)?,
+        value_text(first_image)?,
+        value_text(plane)?
+    ))
+}
+
+/// Rewrites an animation string such as `[0123R]`, with a digit for each pose
+/// and `R` to repeat, as a byte string.
+fn pose_list(keyword: &str, text: &str) -> Result<String, CaosError> {
+    // `text` is a Creatures 3 string literal, as written by [value_text].
+    let poses = text
+        .trim_matches('"')
+        .chars()
+        .map(|c| match c {
+            '0'..='9' => Ok(c.to_string()),
+            'R' | 'r' => Ok(String::from("255")),
+            _ => Err(no_equivalent(
+                keyword,
+                "the animation string holds something other than poses and R",
+            )),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(format!("[{}]", poses.join(" ")))
+}
+
+fn value_text(value: &C2Value) -> Result<String, CaosError> {
+    Ok(match value {
+        C2Value::Integer(i) => i.to_string(),
+        C2Value::String(s) => format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"")),
+        C2Value::Variable(C2Variable::Var(i)) => format!("VA{:02}", i),
+        C2Value::Variable(C2Variable::Obv(i)) => format!("OV{:02}", i),
+        C2Value::Label(label) => label.clone(),
+        C2Value::Expression { keyword, args } => {
+            let definition =
+                keywords::lookup(keyword, Role::Expression).expect("Parsed keywords exist");
+            let args = args.iter().map(value_text).collect::<Result<Vec<_>, _>>()?;
+            match definition.c3 {
+                C3::Same => join(keyword, &args),
+                C3::Renamed(c3_keyword) => join(c3_keyword, &args),
+                C3::Missing(reason) => return Err(no_equivalent(keyword, reason)),
+                C3::Rewritten => return Err(no_rewrite(keyword)),
+            }
+        }
+    })
+}
+
+fn condition_text(condition: &C2Condition) -> Result<String, CaosError> {
+    let mut text = comparison_text(&condition.first)?;
+    for (join_type, comparison) in &condition.rest {
+        let join_type = match join_type {
+            JoinType::And => "AND",
+            JoinType::Or => "OR",
+        };
+        text = format!("{} {} {}", text, join_type, comparison_text(comparison)?);
+    }
+    Ok(text)
+}
+
+fn comparison_text(comparison: &C2Comparison) -> Result<String, CaosError> {
+    let cond_type = match comparison.cond_type {
+        ConditionType::Eq => "EQ",
+        ConditionType::Ne => "NE",
+        ConditionType::Ge => "GE",
+        ConditionType::Gt => "GT",
+        ConditionType::Le => "LE",
+        ConditionType::Lt => "LT",
+    };
+    Ok(format!(
+        "{} {} {}",
+        value_text(&comparison.lhs)?,
+        cond_type,
+        value_text(&comparison.rhs)?
+    ))
+}
+
+fn join(keyword: &str, args: &[String]) -> String {
+    std::iter::once(keyword.to_owned())
+        .chain(args.iter().cloned())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The error for a keyword the table marks as rewritten, which the migration
+/// has no rewrite of for the arguments it was given.
+fn no_rewrite(keyword: &str) -> CaosError {
+    no_equivalent(
+        keyword,
+        "the migration cannot rewrite it with these arguments",
+    )
+}
+
+fn no_equivalent(keyword: &str, reason: &'static str) -> CaosError {
+    let keyword = keyword.to_owned();
+    CaosError::new(ErrorType::NoEquivalent { keyword, reason }, None::<Span>)
+}
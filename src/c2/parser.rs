@@ -0,0 +1,446 @@
+use super::ast::{C2Command, C2Comparison, C2Condition, C2File, C2Script, C2Value, C2Variable};
+use super::keywords::{self, Arg, Role, ENUM_ARGS};
+use crate::ast::{ConditionType, JoinType};
+use crate::{CaosError, ErrorType, Span};
+use pest::Parser;
+use pest_derive::Parser;
+
+#[derive(Parser)]
+#[grammar = "grammar/c2.pest"]
+struct C2Parser;
+
+/// Parses a Creatures 2 CAOS file.
+///
+/// A command starting with, or taking, a word which is not a known keyword is
+/// kept as a [C2Command::Unknown], running up to the next keyword which starts
+/// a command, and parsing carries on from there.
+pub fn parse_c2(c2_content: &str) -> Result<C2File, CaosError> {
+    let program = C2Parser::parse(Rule::program, c2_content)
+        .map_err(|e| syntax_error(e, c2_content))?
+        .next()
+        .expect("A program is always produced");
+    let tokens = program
+        .into_inner()
+        .filter(|p| p.as_rule() != Rule::EOI)
+        .map(|p| Token {
+            kind: p.as_rule(),
+            text: match p.as_rule() {
+                Rule::c2_string => p.clone().into_inner().as_str(),
+                _ => p.as_str(),
+            },
+            span: p.as_span().into(),
+        })
+        .collect();
+    Tokens { tokens, next: 0 }.file()
+}
+
+fn syntax_error(e: pest::error::Error<Rule>, source: &str) -> CaosError {
+    let (start, end) = match e.location {
+        pest::error::InputLocation::Pos(pos) => (pos, source.len()),
+        pest::error::InputLocation::Span(span) => span,
+    };
+    let span = pest::Span::new(source, start, end).expect("Error is within the source");
+    let error_type = match source[start..].starts_with('[') {
+        true => ErrorType::UnterminatedString,
+        false => ErrorType::Syntax {
+            message: e.variant.message().into_owned(),
+        },
+    };
+    CaosError::new(error_type, Some(span.into())).with_source(e)
+}
+
+struct Token<'i> {
+    kind: Rule,
+    /// The text of the token, without the brackets around a string.
+    text: &'i str,
+    span: Span,
+}
+
+struct Tokens<'i> {
+    tokens: Vec<Token<'i>>,
+    next: usize,
+}
+
+/// The keywords which end a block or script, and so end a list of commands.
+const CLOSERS: &[&str] = &[
+    "ELSE", "ENDI", "REPE", "EVER", "UNTL", "NEXT", "RETN", "ENDM",
+];
+
+/// The keywords which start a block or script, handled by the parser rather
+/// than the keyword table.
+const OPENERS: &[&str] = &[
+    "DOIF", "REPS", "LOOP", "ENUM", "ESEE", "ETCH", "SUBR", "SCRP", "RSCR",
+];
+
+impl<'i> Tokens<'i> {
+    fn peek(&self) -> Option<&Token<'i>> {
+        self.tokens.get(self.next)
+    }
+
+    /// Returns `true` if the next token is the word `keyword`, ignoring case.
+    fn at(&self, keyword: &str) -> bool {
+        self.peek()
+            .is_some_and(|t| t.kind == Rule::c2_word && t.text.eq_ignore_ascii_case(keyword))
+    }
+
+    /// Returns `true` if the next token is a keyword which starts a command,
+    /// or closes a block or script.
+    fn at_command(&self) -> bool {
+        let Some(first) = self.peek().filter(|t| t.kind == Rule::c2_word) else {
+            return false;
+        };
+        if CLOSERS.iter().chain(OPENERS).any(|k| self.at(k))
+            || keywords::lookup(first.text, Role::Command).is_some()
+        {
+            return true;
+        }
+        self.tokens
+            .get(self.next + 1)
+            .filter(|t| t.kind == Rule::c2_word)
+            .is_some_and(|second| {
+                let name = format!("{} {}", first.text, second.text);
+                keywords::lookup(&name, Role::Command).is_some()
+            })
+    }
+
+    fn take(&mut self, expected: &str) -> Result<&Token<'i>, CaosError> {
+        let token = self.tokens.get(self.next).ok_or_else(|| {
+            CaosError::new(
+                ErrorType::UnexpectedEnd {
+                    expected: Some(expected.to_owned()),
+                },
+                self.tokens.last().map(|t| t.span),
+            )
+        })?;
+        self.next += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, keyword: &str) -> Result<Span, CaosError> {
+        let expected = format!("`{}`", keyword);
+        let token = self.take(&expected)?;
+        match token.kind == Rule::c2_word && token.text.eq_ignore_ascii_case(keyword) {
+            true => Ok(token.span),
+            false => Err(unexpected(token, &expected)),
+        }
+    }
+
+    /// The span from the token at `start` to the last one taken.
+    fn span_from(&self, start: usize) -> Span {
+        self.tokens[start]
+            .span
+            .join(&self.tokens[self.next - 1].span)
+    }
+
+    fn file(mut self) -> Result<C2File, CaosError> {
+        let mut scripts = Vec::new();
+        let install = self.commands()?;
+        if !install.is_empty() {
+            scripts.push(C2Script::Install(install));
+        }
+        while let Some(token) = self.peek() {
+            if self.at("SCRP") {
+                self.next += 1;
+                let family = self.integer()?;
+                let genus = self.integer()?;
+                let species = self.integer()?;
+                let script_number = self.integer()?;
+                let commands = self.commands()?;
+                self.expect("ENDM")?;
+                scripts.push(C2Script::Event {
+                    family,
+                    genus,
+                    species,
+                    script_number,
+                    commands,
+                });
+            } else if self.at("RSCR") {
+                self.next += 1;
+                scripts.push(C2Script::Removal(self.commands()?));
+            } else if self.at("ENDM") {
+                self.next += 1;
+            } else {
+                return Err(unexpected(token, "`SCRP` or `RSCR`"));
+            }
+        }
+        Ok(C2File { scripts })
+    }
+
+    /// Reads commands up to the end of the source, a keyword closing a block
+    /// or the start of another script.
+    fn commands(&mut self) -> Result<Vec<C2Command>, CaosError> {
+        let mut commands = Vec::new();
+        while self.peek().is_some() {
+            if CLOSERS.iter().any(|c| self.at(c)) || self.at("SCRP") || self.at("RSCR") {
+                break;
+            }
+            commands.push(self.command()?);
+        }
+        Ok(commands)
+    }
+
+    fn command(&mut self) -> Result<C2Command, CaosError> {
+        let start = self.next;
+        if self.at("DOIF") {
+            self.next += 1;
+            let condition = self.condition()?;
+            let definition = self.commands()?;
+            let else_definition = match self.at("ELSE") {
+                true => {
+                    self.next += 1;
+                    Some(self.commands()?)
+                }
+                false => None,
+            };
+            self.expect("ENDI")?;
+            return Ok(C2Command::Doif {
+                condition,
+                definition,
+                else_definition,
+                span: self.span_from(start),
+            });
+        }
+        if self.at("REPS") {
+            self.next += 1;
+            let count = self.value()?;
+            let definition = self.commands()?;
+            self.expect("REPE")?;
+            return Ok(C2Command::Reps {
+                count,
+                definition,
+                span: self.span_from(start),
+            });
+        }
+        if self.at("LOOP") {
+            self.next += 1;
+            let definition = self.commands()?;
+            if self.at("EVER") {
+                self.next += 1;
+                return Ok(C2Command::LoopEver {
+                    definition,
+                    span: self.span_from(start),
+                });
+            }
+            self.expect("UNTL")?;
+            let condition = self.condition()?;
+            return Ok(C2Command::LoopUntl {
+                definition,
+                condition,
+                span: self.span_from(start),
+            });
+        }
+        if let Some(keyword) = ["ENUM", "ESEE", "ETCH"].into_iter().find(|k| self.at(k)) {
+            self.next += 1;
+            let mut classifier = self.args(ENUM_ARGS)?.into_iter();
+            let definition = self.commands()?;
+            self.expect("NEXT")?;
+            let mut next = || classifier.next().expect("Classifier has three values");
+            return Ok(C2Command::Enum {
+                keyword,
+                family: next(),
+                genus: next(),
+                species: next(),
+                definition,
+                span: self.span_from(start),
+            });
+        }
+        if self.at("SUBR") {
+            self.next += 1;
+            let label = self.take("a label")?.text.to_owned();
+            let definition = self.commands()?;
+            self.expect("RETN")?;
+            return Ok(C2Command::Subr {
+                label,
+                definition,
+                span: self.span_from(start),
+            });
+        }
+
+        let simple = self
+            .keyword(Role::Command)
+            .and_then(|keyword| Ok((keyword, self.args(keyword.args)?)));
+        match simple {
+            Ok((keyword, args)) => Ok(C2Command::Simple {
+                keyword: keyword.name,
+                args,
+                span: self.span_from(start),
+            }),
+            Err(e) => match e.error_type() {
+                ErrorType::UnknownToken { text } => {
+                    let word = text.clone();
+                    while self.peek().is_some() && !self.at_command() {
+                        self.next += 1;
+                    }
+                    Ok(C2Command::Unknown {
+                        word,
+                        span: self.span_from(start),
+                    })
+                }
+                _ => Err(e),
+            },
+        }
+    }
+
+    /// Reads a keyword in the given role, which may be one word or two.
+    fn keyword(&mut self, role: Role) -> Result<&'static keywords::Keyword, CaosError> {
+        let expected = match role {
+            Role::Command => "a command",
+            Role::Expression => "an expression",
+        };
+        let token = self.take(expected)?;
+        if token.kind != Rule::c2_word {
+            return Err(unexpected(token, expected));
+        }
+        let (first, first_span) = (token.text, token.span);
+        if let Some(second) = self.peek().filter(|t| t.kind == Rule::c2_word) {
+            if let Some(keyword) = keywords::lookup(&format!("{} {}", first, second.text), role) {
+                self.next += 1;
+                return Ok(keyword);
+            }
+        }
+        keywords::lookup(first, role).ok_or_else(|| {
+            let text = first.to_owned();
+            CaosError::new(ErrorType::UnknownToken { text }, Some(first_span))
+        })
+    }
+
+    fn args(&mut self, args: &[Arg]) -> Result<Vec<C2Value>, CaosError> {
+        args.iter()
+            .map(|arg| match arg {
+                Arg::Value => self.value(),
+                Arg::String => {
+                    let token = self.take("a string")?;
+                    match token.kind {
+                        Rule::c2_string => Ok(C2Value::String(token.text.to_owned())),
+                        _ => Err(unexpected(token, "a string")),
+                    }
+                }
+                Arg::File => {
+                    let token = self.take("a file name")?;
+                    match token.kind {
+                        Rule::c2_string | Rule::c2_word | Rule::c2_integer => {
+                            Ok(C2Value::String(token.text.to_owned()))
+                        }
+                        _ => Err(unexpected(token, "a file name")),
+                    }
+                }
+                Arg::Variable => {
+                    let token = self.take("a variable")?;
+                    match variable(token.text) {
+                        Some(v) if token.kind == Rule::c2_word => Ok(C2Value::Variable(v)),
+                        _ => Err(unexpected(token, "a variable")),
+                    }
+                }
+                Arg::Label => {
+                    let token = self.take("a label")?;
+                    match token.kind {
+                        Rule::c2_word => Ok(C2Value::Label(token.text.to_owned())),
+                        _ => Err(unexpected(token, "a label")),
+                    }
+                }
+            })
+            .collect()
+    }
+
+    fn value(&mut self) -> Result<C2Value, CaosError> {
+        let token = self.peek().ok_or_else(|| {
+            CaosError::new(
+                ErrorType::UnexpectedEnd {
+                    expected: Some("a value".to_owned()),
+                },
+                self.tokens.last().map(|t| t.span),
+            )
+        })?;
+        match token.kind {
+            Rule::c2_integer => Ok(C2Value::Integer(self.integer()?)),
+            Rule::c2_string => {
+                self.next += 1;
+                Ok(C2Value::String(self.tokens[self.next - 1].text.to_owned()))
+            }
+            _ => match variable(token.text) {
+                Some(v) => {
+                    self.next += 1;
+                    Ok(C2Value::Variable(v))
+                }
+                None => {
+                    let keyword = self.keyword(Role::Expression)?;
+                    Ok(C2Value::Expression {
+                        keyword: keyword.name,
+                        args: self.args(keyword.args)?,
+                    })
+                }
+            },
+        }
+    }
+
+    fn integer(&mut self) -> Result<i32, CaosError> {
+        let token = self.take("an integer")?;
+        if token.kind != Rule::c2_integer {
+            return Err(unexpected(token, "an integer"));
+        }
+        token.text.parse().map_err(|e| {
+            let text = token.text.to_owned();
+            CaosError::new(ErrorType::IntegerOverflow { text }, Some(token.span)).with_source(e)
+        })
+    }
+
+    fn condition(&mut self) -> Result<C2Condition, CaosError> {
+        let first = self.comparison()?;
+        let mut rest = Vec::new();
+        loop {
+            let join_type = if self.at("AND") {
+                JoinType::And
+            } else if self.at("OR") {
+                JoinType::Or
+            } else {
+                return Ok(C2Condition { first, rest });
+            };
+            self.next += 1;
+            rest.push((join_type, self.comparison()?));
+        }
+    }
+
+    fn comparison(&mut self) -> Result<C2Comparison, CaosError> {
+        let lhs = self.value()?;
+        let token = self.take("a comparison")?;
+        let cond_type = match token.text.to_ascii_uppercase().as_str() {
+            "EQ" => ConditionType::Eq,
+            "NE" => ConditionType::Ne,
+            "GE" => ConditionType::Ge,
+            "GT" => ConditionType::Gt,
+            "LE" => ConditionType::Le,
+            "LT" => ConditionType::Lt,
+            _ => return Err(unexpected(token, "a comparison")),
+        };
+        let rhs = self.value()?;
+        Ok(C2Comparison {
+            lhs,
+            cond_type,
+            rhs,
+        })
+    }
+}
+
+/// Reads `VAR0` to `VAR9` or `OBV0` to `OBV9`.
+fn variable(text: &str) -> Option<C2Variable> {
+    let (prefix, digit) = text.split_at_checked(3)?;
+    let digit = match digit.as_bytes() {
+        [d @ b'0'..=b'9'] => d - b'0',
+        _ => return None,
+    };
+    match prefix.to_ascii_uppercase().as_str() {
+        "VAR" => Some(C2Variable::Var(digit)),
+        "OBV" => Some(C2Variable::Obv(digit)),
+        _ => None,
+    }
+}
+
+fn unexpected(token: &Token<'_>, expected: &str) -> CaosError {
+    CaosError::new(
+        ErrorType::UnexpectedToken {
+            text: token.text.to_owned(),
+            expected: Some(expected.to_owned()),
+        },
+        Some(token.span),
+    )
+}
@@ -0,0 +1,247 @@
+use super::*;
+use crate::ast::{Command, Script};
+use crate::{parse_cos, ErrorType};
+
+#[test]
+fn test_parse_c2() {
+    let source = "inst\nsetv obv0 7 * start\nsnde [hello there]\nscrp 2 5 3 9\n\
+                  doif var1 lt 3 and posl gt 10\n  mvby 1,0\nelse\n  stop\nendi\nendm\n\
+                  rscr\nkill targ";
+    let file = parse_c2(source).unwrap();
+    assert_eq!(file.scripts.len(), 3);
+
+    let C2Script::Install(install) = &file.scripts[0] else {
+        panic!("Expected an install script, got {:?}", file.scripts[0]);
+    };
+    assert_eq!(install.len(), 3);
+    assert_eq!(install[1].span().as_str(source), "setv obv0 7");
+    assert_eq!(
+        install[2],
+        C2Command::Simple {
+            keyword: "SNDE",
+            args: vec![C2Value::String("hello there".to_owned())],
+            span: *install[2].span(),
+        }
+    );
+
+    let C2Script::Event {
+        script_number,
+        commands,
+        ..
+    } = &file.scripts[1]
+    else {
+        panic!("Expected an event script, got {:?}", file.scripts[1]);
+    };
+    assert_eq!(*script_number, 9);
+    let C2Command::Doif {
+        condition,
+        else_definition,
+        ..
+    } = &commands[0]
+    else {
+        panic!("Expected a DOIF, got {:?}", commands[0]);
+    };
+    assert_eq!(condition.first.lhs, C2Value::Variable(C2Variable::Var(1)));
+    assert_eq!(condition.rest.len(), 1);
+    assert_eq!(else_definition.as_ref().map(Vec::len), Some(1));
+
+    assert!(matches!(&file.scripts[2], C2Script::Removal(c) if c.len() == 1));
+}
+
+#[test]
+fn test_parse_c2_errors() {
+    let error = parse_c2("setv var0").unwrap_err();
+    assert_eq!(error.code(), "E0004");
+
+    let error = parse_c2("doif var0 eq 1\nstop").unwrap_err();
+    assert_eq!(error.code(), "E0004");
+
+    let error = parse_c2("snde [unterminated").unwrap_err();
+    assert_eq!(*error.error_type(), ErrorType::UnterminatedString);
+}
+
+#[test]
+fn test_parse_c2_unknown_words() {
+    let source = "inst\nfrob 1 [two] var0\nsetv var0 blah 3\nstop";
+    let file = parse_c2(source).unwrap();
+    let C2Script::Install(install) = &file.scripts[0] else {
+        panic!("Expected an install script, got {:?}", file.scripts[0]);
+    };
+    let found: Vec<_> = install
+        .iter()
+        .map(|c| match c {
+            C2Command::Unknown { word, span } => (Some(word.as_str()), span.as_str(source)),
+            _ => (None, c.span().as_str(source)),
+        })
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (None, "inst"),
+            (Some("frob"), "frob 1 [two] var0"),
+            (Some("blah"), "setv var0 blah 3"),
+            (None, "stop"),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_c2_file_names() {
+    let file = parse_c2("new: simp eggs 8 0 2000 0\nnew: simp 2 5 100 1000 0").unwrap();
+    let C2Script::Install(install) = &file.scripts[0] else {
+        panic!("Expected an install script, got {:?}", file.scripts[0]);
+    };
+    let files: Vec<_> = install
+        .iter()
+        .map(|c| match c {
+            C2Command::Simple { keyword, args, .. } => (*keyword, args[0].clone()),
+            _ => panic!("Expected NEW: SIMP, got {:?}", c),
+        })
+        .collect();
+    assert_eq!(
+        files,
+        [
+            ("NEW: SIMP", C2Value::String("eggs".to_owned())),
+            ("NEW: SIMP", C2Value::String("2".to_owned())),
+        ]
+    );
+}
+
+#[test]
+fn test_migrate_variables() {
+    let migration = migrate("setv var3 obv9\naddv obv0 xvec").unwrap();
+    assert!(migration.is_complete());
+    let expected = parse_cos("setv va03 ov09\naddv ov00 velx").unwrap();
+    assert_eq!(migration.cos_file, expected);
+}
+
+#[test]
+fn test_migrate_rewrites() {
+    let migration = migrate("rndv var0 1 6\nanim [012R]\nsys: cmra 10 20").unwrap();
+    assert!(migration.is_complete());
+    let expected = parse_cos("setv va00 rand 1 6\nanim [0 1 2 255]\ncmra 10 20 0").unwrap();
+    assert_eq!(migration.cos_file, expected);
+}
+
+#[test]
+fn test_migrate_new_with_classifier() {
+    // 2 5 100, with 7 in the unused low byte.
+    let source = "new: simp eggs 8 0 2000 0\nsetv clas 33907719\nnew: comp 2 1 0 500 1\n\
+                  setv clas var0\nsetv clas 33907719";
+    let migration = migrate(source).unwrap();
+    let Script::Install(install) = &migration.cos_file.scripts[0] else {
+        panic!("Expected an install script, got {:?}", migration.cos_file);
+    };
+    let expected = crate::parse_script_body_str("new: simp 2 5 100 \"eggs\" 8 0 2000").unwrap();
+    assert_eq!(install.commands[0], expected.commands[0]);
+
+    let found: Vec<_> = migration
+        .issues
+        .iter()
+        .map(|e| e.span().unwrap().as_str(source))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            "new: comp 2 1 0 500 1\nsetv clas var0",
+            "setv clas 33907719"
+        ]
+    );
+    assert_eq!(
+        migration.issues[0].message(),
+        "`SETV CLAS` has no Creatures 3 equivalent: the classifier is not a number, so cannot be \
+         split into a family, genus and species"
+    );
+}
+
+#[test]
+fn test_migrate_blocks() {
+    let source = "scrp 2 5 3 9\n\
+                  enum 2 5 0\n  doif obv0 eq 1 or actv ne 0\n    kill targ\n  endi\nnext\n\
+                  reps 3\n  subv var0 1\nrepe\nloop\n  wait 1\nuntl var0 le 0\n\
+                  loop\n  gsub move\never\nsubr move\n  mvby 1 0\nretn\nendm";
+    let migration = migrate(source).unwrap();
+
+    // The DOIF is kept as Creatures 2 source, as ACTV has no equivalent.
+    let Script::Event(event) = &migration.cos_file.scripts[0] else {
+        panic!("Expected an event script, got {:?}", migration.cos_file);
+    };
+    let Command::Enum(classifier) = &event.definition.commands[0] else {
+        panic!("Expected ENUM, got {:?}", event.definition.commands[0]);
+    };
+    assert_eq!(
+        classifier.definition.commands,
+        [Command::Error {
+            text: "doif obv0 eq 1 or actv ne 0\n    kill targ\n  endi".to_owned()
+        }]
+    );
+
+    let rest = crate::parse_script_body_str(
+        "reps 3\n  subv va00 1\nrepe\nloop\n  wait 1\nuntl va00 le 0\n\
+         loop\n  gsub move\never\nsubr move\n  mvby 1 0\nretn",
+    )
+    .unwrap();
+    assert_eq!(event.definition.commands[1..], rest.commands);
+}
+
+#[test]
+fn test_migrate_reports_missing() {
+    let source = "inst\nedit\nsetv var0 clas\nbbd: show 1\nstop";
+    let migration = migrate(source).unwrap();
+    assert!(!migration.is_complete());
+
+    let found: Vec<_> = migration
+        .issues
+        .iter()
+        .map(|e| (e.span().unwrap().as_str(source), e.line_col().unwrap()))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("edit", (2, 1)),
+            ("setv var0 clas", (3, 1)),
+            ("bbd: show 1", (4, 1))
+        ]
+    );
+    assert!(migration.issues.iter().all(|e| e.code() == "E0303"));
+    assert_eq!(
+        migration.issues[1].message(),
+        "`CLAS` has no Creatures 3 equivalent: use FMLY, GNUS and SPCS for the classifier instead"
+    );
+
+    let Script::Install(install) = &migration.cos_file.scripts[0] else {
+        panic!("Expected an install script, got {:?}", migration.cos_file);
+    };
+    assert_eq!(
+        install.commands[2],
+        Command::Error {
+            text: "setv var0 clas".to_owned()
+        }
+    );
+    assert_eq!(install.commands[4], Command::Stop);
+}
+
+#[test]
+fn test_migrate_reports_unknown_words() {
+    let source = "new: simp 2 5 100 1000 0\nsetv var0 rand 1 6\nfrob 1 2\nsnde drip";
+    let migration = migrate(source).unwrap();
+
+    let found: Vec<_> = migration
+        .issues
+        .iter()
+        .map(|e| (e.code(), e.span().unwrap().as_str(source)))
+        .collect();
+    assert_eq!(
+        found,
+        [("E0303", "new: simp 2 5 100 1000 0"), ("E0002", "frob 1 2")]
+    );
+    assert_eq!(migration.issues[1].message(), "Unknown token `frob`");
+
+    let Script::Install(install) = &migration.cos_file.scripts[0] else {
+        panic!("Expected an install script, got {:?}", migration.cos_file);
+    };
+    let expected = crate::parse_script_body_str("setv va00 rand 1 6").unwrap();
+    assert_eq!(install.commands[1], expected.commands[0]);
+    let expected = crate::parse_script_body_str("snde \"drip\"").unwrap();
+    assert_eq!(install.commands[3], expected.commands[0]);
+}
@@ -1,82 +1,474 @@
-use std::{convert::Infallible, error::Error};
+#[cfg(test)]
+mod tests;
+
+use crate::{Dialect, Encoding, Rule, Span};
+use pest::error::{InputLocation, LineColLocation};
+use std::{convert::Infallible, error::Error, fmt};
 
 pub type Result<T> = std::result::Result<T, CaosError>;
 
-type LineCol = (usize, usize);
+pub type LineCol = (usize, usize);
 
-#[derive(Debug)]
+/// The kind of problem a [CaosError] describes.
+///
+/// Each kind has a stable code, returned by [ErrorType::code], which does not
+/// change between releases and can be used to look up or filter errors. The
+/// code tells where the error comes from:
+///
+/// * `E00xx`: the source could not be parsed.
+/// * `E01xx`: a command or expression was given the wrong arguments.
+/// * `E02xx`: blocks such as `DOIF` and `ENDI` do not match up.
+/// * `E03xx`: a keyword is not part of a dialect, or a Creatures 2 keyword
+///   cannot be migrated.
+/// * `E04xx`: the [interpreter](crate::interpreter) failed while running a
+///   script, which parsed without errors.
+#[derive(Debug, Eq, PartialEq, Clone)]
 pub enum ErrorType {
-    ParseError {
-        line_col: LineCol,
+    /// The source does not match the CAOS grammar. `E0001`
+    Syntax { message: String },
+    /// A word which is not a keyword, literal, variable or label. `E0002`
+    UnknownToken { text: String },
+    /// A valid token in a place it is not allowed, such as a command where an
+    /// argument was expected. `E0003`
+    UnexpectedToken {
+        text: String,
+        expected: Option<String>,
+    },
+    /// The script ended part way through a command or expression. `E0004`
+    UnexpectedEnd { expected: Option<String> },
+    /// A string literal with no closing quote. `E0005`
+    UnterminatedString,
+    /// An integer literal which does not fit in 32 bits. `E0006`
+    IntegerOverflow { text: String },
+    /// A float literal which could not be read. `E0007`
+    InvalidFloat { text: String },
+    /// A keyword used in the wrong role, such as a command where an
+    /// expression was expected. `E0008`
+    MisplacedKeyword {
+        keyword: String,
+        role: KeywordRole,
+        expected: KeywordRole,
     },
-    CastError {
-        line_col: Option<LineCol>,
+    /// A character which the encoding being read or written does not have,
+    /// such as `€` in Latin-1. `E0009`
+    Unencodable { character: char, encoding: Encoding },
+    /// A `SCRP` not followed by the four integers of an event script's
+    /// classifier and event number. `E0010`
+    InvalidScriptHeader,
+    /// Commands after the end of a script which are not part of another,
+    /// such as commands after `ENDM` with no `ISCR` before them. `E0011`
+    OutsideScript,
+    /// An argument of the wrong type, such as a string given where an integer
+    /// is expected. The types are named after the AST types. `E0101`
+    WrongArgumentType {
+        expected: &'static str,
+        found: &'static str,
     },
-    ArgErr {
+    /// A command or expression given fewer arguments than it takes. `E0102`
+    ArityMismatch {
+        command: String,
         expected: usize,
-        actual: usize,
-        line_col: LineCol,
+        found: usize,
+    },
+    /// A keyword which closes or continues a block, such as `NEXT` or `ELSE`,
+    /// which does not match the innermost open block, if any. `E0201`
+    UnbalancedBlock {
+        keyword: String,
+        open_block: Option<OpenBlock>,
+    },
+    /// Blocks which are still open at the end of the script, innermost
+    /// first. `E0202`
+    UnclosedBlock { open_blocks: Vec<OpenBlock> },
+    /// A keyword which is not part of the dialect being checked, such as a
+    /// Docking Station keyword in a Creatures 3 script. `E0301`
+    UnavailableKeyword { keyword: String, dialect: Dialect },
+    /// A dialect the grammar does not cover. `E0302`
+    UnsupportedDialect { dialect: Dialect },
+    /// A Creatures 2 keyword which cannot be migrated to Creatures 3, and
+    /// why. `E0303`
+    NoEquivalent {
+        keyword: String,
+        reason: &'static str,
     },
-    EndOfStream,
-    SubError(Box<dyn Error>),
+    /// A command or expression which the
+    /// [interpreter](crate::interpreter) cannot run, such as one which needs
+    /// the game. `E0401`
+    Unsupported { keyword: String },
+    /// A value of the wrong kind for where it was used, such as a string
+    /// added to a number. Both are described with an article, as in
+    /// `an integer`. `E0402`
+    WrongValueType {
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// `DIVV` or `MODV` by zero. `E0403`
+    DivisionByZero,
+    /// A position outside of a string, counting from 1, or outside of an
+    /// agent's variables. `E0404`
+    IndexOutOfRange { index: i32, length: usize },
+    /// An agent which is `NULL` or has been killed, such as `TARG` when
+    /// setting an `OVxx` variable. `E0405`
+    InvalidAgent,
+    /// A `GSUB` or `GOTO` to a label no `SUBR` defines. `E0406`
+    UnknownLabel { label: String },
+    /// The end of a subroutine reached without a `GSUB` to return to.
+    /// `E0407`
+    ReturnWithoutCall,
+    /// A script which ran for more instructions than allowed, and so
+    /// likely never finishes. `E0408`
+    InstructionLimit { limit: usize },
+    /// A `DBG: ASRT` whose condition was false. `E0409`
+    AssertionFailed,
+}
+
+/// The part a keyword plays in a script.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum KeywordRole {
+    Command,
+    Expression,
+}
+
+impl fmt::Display for KeywordRole {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeywordRole::Command => write!(f, "a command"),
+            KeywordRole::Expression => write!(f, "an expression"),
+        }
+    }
+}
+
+/// A block open at the point of a [ErrorType::UnbalancedBlock] or
+/// [ErrorType::UnclosedBlock] error.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct OpenBlock {
+    /// The keyword which opened the block, such as `ENUM`.
+    pub keyword: String,
+    /// The command which opened the block, including its arguments.
+    pub span: Span,
+    /// The keywords which may close the block, such as `NEXT`.
+    pub closers: &'static [&'static str],
+}
+
+impl OpenBlock {
+    /// The keywords which may close the block, as `` `EVER` or `UNTL` ``.
+    pub fn closers_text(&self) -> String {
+        self.closers
+            .iter()
+            .map(|c| format!("`{}`", c))
+            .collect::<Vec<_>>()
+            .join(" or ")
+    }
 }
 
+impl fmt::Display for OpenBlock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (line, col) = self.span.start_line_col;
+        write!(
+            f,
+            "`{}` at {}:{} expects {}",
+            self.keyword,
+            line,
+            col,
+            self.closers_text()
+        )
+    }
+}
+
+impl ErrorType {
+    /// The stable code identifying this kind of error, such as `E0101`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorType::Syntax { .. } => "E0001",
+            ErrorType::UnknownToken { .. } => "E0002",
+            ErrorType::UnexpectedToken { .. } => "E0003",
+            ErrorType::UnexpectedEnd { .. } => "E0004",
+            ErrorType::UnterminatedString => "E0005",
+            ErrorType::IntegerOverflow { .. } => "E0006",
+            ErrorType::InvalidFloat { .. } => "E0007",
+            ErrorType::MisplacedKeyword { .. } => "E0008",
+            ErrorType::Unencodable { .. } => "E0009",
+            ErrorType::InvalidScriptHeader => "E0010",
+            ErrorType::OutsideScript => "E0011",
+            ErrorType::WrongArgumentType { .. } => "E0101",
+            ErrorType::ArityMismatch { .. } => "E0102",
+            ErrorType::UnbalancedBlock { .. } => "E0201",
+            ErrorType::UnclosedBlock { .. } => "E0202",
+            ErrorType::UnavailableKeyword { .. } => "E0301",
+            ErrorType::UnsupportedDialect { .. } => "E0302",
+            ErrorType::NoEquivalent { .. } => "E0303",
+            ErrorType::Unsupported { .. } => "E0401",
+            ErrorType::WrongValueType { .. } => "E0402",
+            ErrorType::DivisionByZero => "E0403",
+            ErrorType::IndexOutOfRange { .. } => "E0404",
+            ErrorType::InvalidAgent => "E0405",
+            ErrorType::UnknownLabel { .. } => "E0406",
+            ErrorType::ReturnWithoutCall => "E0407",
+            ErrorType::InstructionLimit { .. } => "E0408",
+            ErrorType::AssertionFailed => "E0409",
+        }
+    }
+}
+
+impl fmt::Display for ErrorType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorType::Syntax { message } => write!(f, "{}", message),
+            ErrorType::UnknownToken { text } => write!(f, "Unknown token `{}`", text),
+            ErrorType::UnexpectedToken {
+                text,
+                expected: Some(expected),
+            } => write!(f, "Expected {}, found `{}`", expected, text),
+            ErrorType::UnexpectedToken {
+                text,
+                expected: None,
+            } => write!(f, "Unexpected `{}`", text),
+            ErrorType::UnexpectedEnd {
+                expected: Some(expected),
+            } => write!(f, "Expected {}, found the end of the script", expected),
+            ErrorType::UnexpectedEnd { expected: None } => write!(f, "Stream ended unexpectedly"),
+            ErrorType::UnterminatedString => write!(f, "Unterminated string literal"),
+            ErrorType::IntegerOverflow { text } => {
+                write!(f, "Integer literal `{}` does not fit in 32 bits", text)
+            }
+            ErrorType::InvalidFloat { text } => write!(f, "Invalid float literal `{}`", text),
+            ErrorType::MisplacedKeyword {
+                keyword,
+                role,
+                expected,
+            } => write!(
+                f,
+                "`{}` is {}, but {} was expected",
+                keyword, role, expected
+            ),
+            ErrorType::Unencodable {
+                character,
+                encoding,
+            } => write!(f, "`{}` cannot be written in {}", character, encoding),
+            ErrorType::InvalidScriptHeader => write!(
+                f,
+                "`SCRP` must be followed by a family, genus, species and event number"
+            ),
+            ErrorType::OutsideScript => write!(
+                f,
+                "Commands outside of a script: start one with `ISCR`, `RSCR` or `SCRP`"
+            ),
+            ErrorType::WrongArgumentType { expected, found } => {
+                write!(
+                    f,
+                    "Wrong argument type: expected `{}`, got `{}`",
+                    expected, found
+                )
+            }
+            ErrorType::ArityMismatch {
+                command,
+                expected,
+                found,
+            } => write!(
+                f,
+                "`{}` expects {} argument{}, found {}",
+                command,
+                expected,
+                if *expected == 1 { "" } else { "s" },
+                found
+            ),
+            ErrorType::UnbalancedBlock {
+                keyword,
+                open_block: Some(open_block),
+            } => write!(f, "`{}` found but {}", keyword, open_block),
+            ErrorType::UnbalancedBlock {
+                keyword,
+                open_block: None,
+            } => write!(f, "`{}` found outside of any block", keyword),
+            ErrorType::UnclosedBlock { open_blocks } => match open_blocks.as_slice() {
+                [open_block] => write!(f, "Script ended but {}", open_block),
+                _ => {
+                    let blocks: Vec<_> = open_blocks.iter().map(|b| b.to_string()).collect();
+                    write!(
+                        f,
+                        "Script ended with {} unclosed blocks: {}",
+                        open_blocks.len(),
+                        blocks.join(", ")
+                    )
+                }
+            },
+            ErrorType::UnavailableKeyword { keyword, dialect } => {
+                write!(f, "`{}` is not available in {}", keyword, dialect)
+            }
+            ErrorType::UnsupportedDialect {
+                dialect: Dialect::Creatures2,
+            } => write!(
+                f,
+                "Creatures 2 CAOS must be migrated to Creatures 3 before it can be parsed"
+            ),
+            ErrorType::UnsupportedDialect { dialect } => {
+                write!(f, "{} CAOS cannot be parsed", dialect)
+            }
+            ErrorType::NoEquivalent { keyword, reason } => {
+                write!(f, "`{}` has no Creatures 3 equivalent: {}", keyword, reason)
+            }
+            ErrorType::Unsupported { keyword } => {
+                write!(f, "`{}` is not supported by the interpreter", keyword)
+            }
+            ErrorType::WrongValueType { expected, found } => {
+                write!(f, "Expected {}, found {}", expected, found)
+            }
+            ErrorType::DivisionByZero => write!(f, "Division by zero"),
+            ErrorType::IndexOutOfRange { index, length } => {
+                write!(f, "Index {} is out of range for length {}", index, length)
+            }
+            ErrorType::InvalidAgent => write!(f, "Agent is NULL or has been killed"),
+            ErrorType::UnknownLabel { label } => {
+                write!(f, "No subroutine is labelled `{}`", label)
+            }
+            ErrorType::ReturnWithoutCall => write!(f, "Reached RETN without a GSUB"),
+            ErrorType::InstructionLimit { limit } => {
+                write!(f, "Script ran for more than {} instructions", limit)
+            }
+            ErrorType::AssertionFailed => write!(f, "Assertion failed"),
+        }
+    }
+}
+
+/// An error found while parsing, checking, migrating or running CAOS, as
+/// told apart by its [code](CaosError::code).
+///
+/// Errors are `Send` and `Sync`, so they can be collected from parsers running
+/// on other threads. Where the error was caused by another, such as an integer
+/// literal failing to parse, that error is available from [Error::source].
+#[derive(Debug)]
+pub struct CaosError(Box<ErrorInner>);
+
 #[derive(Debug)]
-pub struct CaosError {
-    pub error_type: ErrorType,
-    message: String,
+struct ErrorInner {
+    error_type: ErrorType,
+    span: Option<Span>,
+    source: Option<Box<dyn Error + Send + Sync>>,
+    suggestion: Option<String>,
 }
 
 impl CaosError {
-    pub fn new(error_type: ErrorType, message: String) -> Self {
-        Self {
+    pub fn new(error_type: ErrorType, span: Option<Span>) -> Self {
+        Self(Box::new(ErrorInner {
             error_type,
-            message,
-        }
+            span,
+            source: None,
+            suggestion: None,
+        }))
     }
 
-    pub fn new_end_of_stream() -> Self {
-        CaosError::new(
-            ErrorType::EndOfStream,
-            String::from("Stream ended unexpectedly"),
-        )
+    /// Sets the source the error was found at.
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.0.span = Some(span);
+        self
     }
 
-    pub fn new_from_error(e: Box<dyn Error>) -> Self {
-        CaosError::new(ErrorType::SubError(e), String::new())
+    /// Sets the underlying error which caused this one.
+    pub fn with_source(mut self, source: impl Error + Send + Sync + 'static) -> Self {
+        self.0.source = Some(Box::new(source));
+        self
     }
 
-    pub fn new_arg_count_error(expected: usize, actual: usize, line_col: LineCol) -> Self {
+    /// Sets what the source at the error was likely meant to be, such as
+    /// `ENUM` for `ENMU`.
+    pub fn with_suggestion(mut self, suggestion: impl Into<String>) -> Self {
+        self.0.suggestion = Some(suggestion.into());
+        self
+    }
+
+    pub fn new_end_of_stream() -> Self {
+        CaosError::new(ErrorType::UnexpectedEnd { expected: None }, None)
+    }
+
+    pub fn new_arg_count_error(command: &str, expected: usize, found: usize, span: Span) -> Self {
         CaosError::new(
-            ErrorType::ArgErr {
+            ErrorType::ArityMismatch {
+                command: command.to_owned(),
                 expected,
-                actual,
-                line_col,
+                found,
             },
-            format!(
-                "Arg count error at line {} col: {}. Expected: {}, actual: {}.",
-                line_col.0, line_col.1, expected, actual
-            ),
+            Some(span),
         )
     }
 
-    pub(crate) fn new_parse_error(p: pest::iterators::Pair<crate::Rule>) -> Self {
-        let line_col @ (line, col) = p.line_col();
-        let error_type = ErrorType::ParseError { line_col };
-        let message = format!(
-            "Parse error at line: {} col: {}, reading {}.",
-            line,
-            col,
-            p.as_str()
-        );
-        CaosError {
-            error_type,
-            message,
-        }
+    pub fn new_wrong_argument_type(expected: &'static str, found: &'static str) -> Self {
+        CaosError::new(ErrorType::WrongArgumentType { expected, found }, None)
+    }
+
+    pub(crate) fn new_parse_error(p: pest::iterators::Pair<Rule>) -> Self {
+        let error_type = ErrorType::UnexpectedToken {
+            text: p.as_str().to_owned(),
+            expected: None,
+        };
+        CaosError::new(error_type, Some(p.as_span().into()))
+    }
+
+    /// An error for `p` being found where `expected` should have been.
+    pub(crate) fn new_unexpected(p: pest::iterators::Pair<Rule>, expected: &str) -> Self {
+        let error_type = ErrorType::UnexpectedToken {
+            text: p.as_str().to_owned(),
+            expected: Some(expected.to_owned()),
+        };
+        CaosError::new(error_type, Some(p.as_span().into()))
+    }
+
+    pub(crate) fn from_pest(e: pest::error::Error<Rule>, source: &str) -> Self {
+        let (start, end) = match e.location {
+            InputLocation::Pos(pos) => (pos, pos),
+            InputLocation::Span(span) => span,
+        };
+        let (start_line_col, end_line_col) = match e.line_col {
+            LineColLocation::Pos(pos) => (pos, pos),
+            LineColLocation::Span(start, end) => (start, end),
+        };
+        let span = Span {
+            start,
+            end: end.min(source.len()),
+            start_line_col,
+            end_line_col,
+        };
+        let message = e.variant.message().into_owned();
+        CaosError::new(ErrorType::Syntax { message }, Some(span)).with_source(e)
+    }
+
+    /// The kind of problem found.
+    pub fn error_type(&self) -> &ErrorType {
+        &self.0.error_type
+    }
+
+    /// The stable code identifying the kind of error. See [ErrorType::code].
+    pub fn code(&self) -> &'static str {
+        self.0.error_type.code()
+    }
+
+    /// The source the error was found at, if known.
+    pub fn span(&self) -> Option<&Span> {
+        self.0.span.as_ref()
+    }
+
+    /// What the source at the error was likely meant to be, if known.
+    pub fn suggestion(&self) -> Option<&str> {
+        self.0.suggestion.as_deref()
+    }
+
+    /// The line and column the error was found at, if known.
+    pub fn line_col(&self) -> Option<LineCol> {
+        self.0.span.map(|s| s.start_line_col)
+    }
+
+    /// A description of the error, without its location.
+    pub fn message(&self) -> String {
+        self.0.error_type.to_string()
     }
 }
 
-impl Error for CaosError {}
+impl Error for CaosError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.0
+            .source
+            .as_deref()
+            .map(|e| e as &(dyn Error + 'static))
+    }
+}
 
 impl From<Infallible> for CaosError {
     fn from(x: Infallible) -> Self {
@@ -84,8 +476,11 @@ impl From<Infallible> for CaosError {
     }
 }
 
-impl std::fmt::Display for CaosError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{:?}: {}", self.error_type, self.message)
+impl fmt::Display for CaosError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line_col() {
+            Some((line, col)) => write!(f, "{} at line {}, column {}", self.message(), line, col),
+            None => write!(f, "{}", self.message()),
+        }
     }
 }
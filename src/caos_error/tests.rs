@@ -0,0 +1,377 @@
+use super::*;
+use crate::{parse_cos, parse_cos_recovering};
+use std::num::ParseIntError;
+
+fn parse_error(content: &str) -> CaosError {
+    parse_cos(content).expect_err("Source should not parse")
+}
+
+fn span_text<'a>(content: &'a str, e: &CaosError) -> &'a str {
+    let span = e.span().expect("Error has a span");
+    &content[span.start..span.end]
+}
+
+#[test]
+fn test_error_is_send_sync() {
+    fn assert_send_sync<T: Send + Sync + 'static>() {}
+    assert_send_sync::<CaosError>();
+}
+
+#[test]
+fn test_unknown_token() {
+    let e = &parse_cos_recovering("nohh\nblah over").errors[0];
+    assert_eq!(
+        *e.error_type(),
+        ErrorType::UnknownToken {
+            text: String::from("blah")
+        }
+    );
+    assert_eq!(e.code(), "E0002");
+    assert_eq!(e.line_col(), Some((2, 1)));
+}
+
+#[test]
+fn test_unterminated_string() {
+    let errors = parse_cos_recovering("sets va00 \"no end").errors;
+    // The command left without its argument is reported first, as it starts first.
+    assert_eq!(errors[0].code(), "E0102");
+    let e = &errors[1];
+    assert_eq!(*e.error_type(), ErrorType::UnterminatedString);
+    assert_eq!(e.code(), "E0005");
+}
+
+#[test]
+fn test_wrong_argument_type() {
+    let content = "setv va00 \"text\"";
+    let e = parse_error(content);
+    assert_eq!(
+        *e.error_type(),
+        ErrorType::WrongArgumentType {
+            expected: "DecimalArg",
+            found: "SString"
+        }
+    );
+    assert_eq!(span_text(content, &e), "\"text\"");
+    assert_eq!(
+        e.to_string(),
+        "Wrong argument type: expected `DecimalArg`, got `SString` at line 1, column 11"
+    );
+}
+
+#[test]
+fn test_wrong_argument_type_for_block() {
+    let content = "reps \"three\" nohh repe";
+    let e = parse_error(content);
+    assert_eq!(e.code(), "E0101");
+    assert_eq!(span_text(content, &e), "\"three\"");
+}
+
+#[test]
+fn test_arity_mismatch() {
+    let content = "setv va00\nnohh";
+    let e = parse_error(content);
+    assert_eq!(
+        *e.error_type(),
+        ErrorType::ArityMismatch {
+            command: String::from("SETV"),
+            expected: 2,
+            found: 1
+        }
+    );
+    assert_eq!(span_text(content, &e), "setv");
+    assert_eq!(e.message(), "`SETV` expects 2 arguments, found 1");
+}
+
+#[test]
+fn test_arity_mismatch_in_expression() {
+    let content = "setv va00 rand 1\nkill targ";
+    let e = parse_error(content);
+    assert_eq!(
+        *e.error_type(),
+        ErrorType::ArityMismatch {
+            command: String::from("RAND"),
+            expected: 2,
+            found: 1
+        }
+    );
+}
+
+#[test]
+fn test_arity_mismatch_at_end() {
+    let e = parse_error("new: simp 1 2 3");
+    assert_eq!(
+        *e.error_type(),
+        ErrorType::ArityMismatch {
+            command: String::from("NEW: SIMP"),
+            expected: 7,
+            found: 3
+        }
+    );
+}
+
+#[test]
+fn test_unbalanced_block() {
+    let content = "reps 3\nnohh\nnext";
+    let e = parse_error(content);
+    assert_eq!(e.message(), "`NEXT` found but `REPS` at 1:1 expects `REPE`");
+    assert_eq!(e.line_col(), Some((3, 1)));
+    assert_eq!(e.code(), "E0201");
+    match e.error_type() {
+        ErrorType::UnbalancedBlock {
+            open_block: Some(open_block),
+            ..
+        } => {
+            assert_eq!(open_block.keyword, "REPS");
+            assert_eq!(
+                &content[open_block.span.start..open_block.span.end],
+                "reps 3"
+            );
+            assert_eq!(open_block.closers, &["REPE"]);
+        }
+        t => panic!("Unexpected error {:?}", t),
+    }
+}
+
+#[test]
+fn test_unbalanced_block_outside_block() {
+    let e = parse_error("nohh endi");
+    assert_eq!(
+        *e.error_type(),
+        ErrorType::UnbalancedBlock {
+            keyword: String::from("ENDI"),
+            open_block: None
+        }
+    );
+}
+
+#[test]
+fn test_unclosed_block() {
+    let content = "nohh\nenum 1 2 3\nkill targ";
+    let e = parse_error(content);
+    assert_eq!(e.message(), "Script ended but `ENUM` at 2:1 expects `NEXT`");
+    assert_eq!(span_text(content, &e), "enum 1 2 3");
+}
+
+#[test]
+fn test_unclosed_blocks_innermost_first() {
+    let content = "doif 1 eq 1
+  subr lbl
+    loop
+endi";
+    let e = parse_error(content);
+    assert_eq!(
+        e.message(),
+        "`ENDI` found but `LOOP` at 3:5 expects `EVER` or `UNTL`"
+    );
+
+    let content = "doif 1 eq 1
+  reps 2
+    loop";
+    let e = parse_error(content);
+    match e.error_type() {
+        ErrorType::UnclosedBlock { open_blocks } => {
+            let keywords: Vec<_> = open_blocks.iter().map(|b| b.keyword.as_str()).collect();
+            assert_eq!(keywords, ["LOOP", "REPS", "DOIF"]);
+        }
+        t => panic!("Unexpected error {:?}", t),
+    }
+    assert_eq!(span_text(content, &e), "loop");
+}
+
+#[test]
+fn test_else_after_else() {
+    let e = parse_error(
+        "doif 1 eq 1
+else
+else
+endi",
+    );
+    assert_eq!(e.message(), "`ELSE` found but `DOIF` at 1:1 expects `ENDI`");
+}
+
+#[test]
+fn test_integer_overflow() {
+    let content = "setv va00 99999999999";
+    let e = parse_error(content);
+    assert_eq!(
+        *e.error_type(),
+        ErrorType::IntegerOverflow {
+            text: String::from("99999999999")
+        }
+    );
+    assert_eq!(span_text(content, &e), "99999999999");
+    let source = e.source().expect("Error has a source");
+    assert!(source.downcast_ref::<ParseIntError>().is_some());
+}
+
+#[test]
+fn test_syntax_error_source() {
+    let e = parse_error("scrp 1 2 3");
+    assert_eq!(e.code(), "E0001");
+    assert!(e.line_col().is_some());
+    let source = e.source().expect("Error has a source");
+    assert!(source
+        .downcast_ref::<pest::error::Error<crate::Rule>>()
+        .is_some());
+}
+
+#[test]
+fn test_codes_are_unique() {
+    let types = [
+        ErrorType::Syntax {
+            message: String::new(),
+        },
+        ErrorType::UnknownToken {
+            text: String::new(),
+        },
+        ErrorType::UnexpectedToken {
+            text: String::new(),
+            expected: None,
+        },
+        ErrorType::UnexpectedEnd { expected: None },
+        ErrorType::UnterminatedString,
+        ErrorType::IntegerOverflow {
+            text: String::new(),
+        },
+        ErrorType::InvalidFloat {
+            text: String::new(),
+        },
+        ErrorType::MisplacedKeyword {
+            keyword: String::new(),
+            role: KeywordRole::Command,
+            expected: KeywordRole::Expression,
+        },
+        ErrorType::WrongArgumentType {
+            expected: "",
+            found: "",
+        },
+        ErrorType::ArityMismatch {
+            command: String::new(),
+            expected: 0,
+            found: 0,
+        },
+        ErrorType::UnbalancedBlock {
+            keyword: String::new(),
+            open_block: None,
+        },
+        ErrorType::UnclosedBlock {
+            open_blocks: Vec::new(),
+        },
+        ErrorType::UnavailableKeyword {
+            keyword: String::new(),
+            dialect: Dialect::Creatures3,
+        },
+        ErrorType::UnsupportedDialect {
+            dialect: Dialect::Creatures2,
+        },
+        ErrorType::InvalidScriptHeader,
+        ErrorType::OutsideScript,
+        ErrorType::Unencodable {
+            character: '€',
+            encoding: Encoding::Latin1,
+        },
+        ErrorType::NoEquivalent {
+            keyword: "EDIT".to_owned(),
+            reason: "Creatures 3 has no hand editing mode",
+        },
+        ErrorType::Unsupported {
+            keyword: "NEW: SIMP".to_owned(),
+        },
+        ErrorType::WrongValueType {
+            expected: "an integer",
+            found: "a string",
+        },
+        ErrorType::DivisionByZero,
+        ErrorType::IndexOutOfRange {
+            index: 5,
+            length: 4,
+        },
+        ErrorType::InvalidAgent,
+        ErrorType::UnknownLabel {
+            label: "foo".to_owned(),
+        },
+        ErrorType::ReturnWithoutCall,
+        ErrorType::InstructionLimit { limit: 10 },
+        ErrorType::AssertionFailed,
+    ];
+    let mut codes: Vec<_> = types.iter().map(|t| t.code()).collect();
+    codes.sort();
+    codes.dedup();
+    assert_eq!(codes.len(), types.len());
+}
+
+#[test]
+fn test_misplaced_keyword() {
+    let e = parse_error("nohh\nownr");
+    assert_eq!(
+        *e.error_type(),
+        ErrorType::MisplacedKeyword {
+            keyword: String::from("OWNR"),
+            role: KeywordRole::Expression,
+            expected: KeywordRole::Command
+        }
+    );
+    assert_eq!(
+        e.message(),
+        "`OWNR` is an expression, but a command was expected"
+    );
+
+    let e = parse_error("doif kill ownr eq 1 endi");
+    assert_eq!(
+        e.message(),
+        "`KILL` is a command, but an expression was expected"
+    );
+}
+
+#[test]
+fn test_suggestion_for_unknown_word() {
+    let e = parse_error("enmu 1 2 3 next");
+    assert_eq!(e.code(), "E0001");
+    assert_eq!(e.suggestion(), Some("ENUM"));
+
+    let parse = parse_cos_recovering("setv va5 1");
+    assert_eq!(parse.errors[1].suggestion(), Some("VA05"));
+}
+
+#[test]
+fn test_suggestion_spans_keyword_words() {
+    let content = "new: smip \"a\" 1 1000 0 0\nnohh";
+    let parse = parse_cos_recovering(content);
+    assert_eq!(parse.errors.len(), 2);
+    let e = &parse.errors[0];
+    assert_eq!(
+        *e.error_type(),
+        ErrorType::UnknownToken {
+            text: String::from("new: smip")
+        }
+    );
+    assert_eq!(e.suggestion(), Some("NEW: SIMP"));
+}
+
+#[test]
+fn test_truncated_input_errors_have_spans() {
+    use crate::{parse_command_str, parse_condition_str, parse_expression_str};
+
+    let content = "inst doif va00 eq 1 and rand 1 2 ne 3 setv va01 sqrt 4.0 gsub lab endi";
+    let ends = content.match_indices(' ').map(|(i, _)| i);
+    for end in ends.chain([content.len()]) {
+        let truncated = &content[..end];
+        let mut errors = parse_cos_recovering(truncated).errors;
+        errors.extend(parse_cos(truncated).err());
+        errors.extend(parse_command_str(truncated.get(5..).unwrap_or("")).err());
+        for e in &errors {
+            assert!(e.span().is_some(), "No span for {:?} in {:?}", e, truncated);
+        }
+    }
+
+    let e = parse_error("inst doif 1 eq");
+    assert_eq!(e.line_col(), Some((1, 15)));
+    assert_eq!(
+        parse_expression_str("").unwrap_err().line_col(),
+        Some((1, 1))
+    );
+    let e = parse_condition_str("va00 eq").unwrap_err();
+    assert_eq!(e.line_col(), Some((1, 8)));
+    let e = parse_condition_str("va00 ").unwrap_err();
+    assert_eq!(e.line_col(), Some((1, 5)));
+}
@@ -0,0 +1,233 @@
+#[cfg(test)]
+mod tests;
+
+use crate::{ast::CosFile, parse_cst, CaosError, Rule, Span, SpanNode};
+use pest::iterators::Pair;
+use std::fmt;
+
+/// The kind of a [CstToken].
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum TokenKind {
+    /// Text matched by a grammar rule, such as a keyword, literal or label.
+    Rule(Rule),
+    /// Text matched by the grammar without a rule of its own, such as the
+    /// `SCRP` and `ENDM` script tags or the brackets of a byte string.
+    Unnamed,
+    /// Spaces, tabs and newlines.
+    Whitespace,
+    /// A `*` comment, up to but not including the end of its line.
+    Comment,
+}
+
+/// A leaf of the concrete syntax tree.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct CstToken {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+impl CstToken {
+    /// Returns `true` for whitespace and comments, which carry no meaning to the parser.
+    pub fn is_trivia(&self) -> bool {
+        matches!(self.kind, TokenKind::Whitespace | TokenKind::Comment)
+    }
+}
+
+/// A child of a [CstNode].
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum CstElement {
+    Node(CstNode),
+    Token(CstToken),
+}
+
+impl CstElement {
+    pub fn span(&self) -> Span {
+        match self {
+            CstElement::Node(n) => n.span,
+            CstElement::Token(t) => t.span,
+        }
+    }
+}
+
+/// An interior node of the concrete syntax tree, matched by the grammar rule `rule`.
+///
+/// The children of a node are contiguous and cover its span exactly, so
+/// concatenating the text of its tokens reproduces the source it was parsed from.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct CstNode {
+    pub rule: Rule,
+    pub span: Span,
+    pub children: Vec<CstElement>,
+}
+
+impl CstNode {
+    /// Iterates over every token under this node, trivia included, in source order.
+    pub fn tokens(&self) -> impl Iterator<Item = &CstToken> + '_ {
+        let mut stack = vec![self.children.iter()];
+        std::iter::from_fn(move || loop {
+            match stack.last_mut()?.next() {
+                Some(CstElement::Token(t)) => return Some(t),
+                Some(CstElement::Node(n)) => stack.push(n.children.iter()),
+                None => {
+                    stack.pop();
+                }
+            }
+        })
+    }
+}
+
+/// A lossless concrete syntax tree of a CAOS file.
+///
+/// Unlike the AST, every byte of the source is accounted for: whitespace and
+/// comments are kept as trivia tokens alongside the tokens of the grammar. The
+/// [CosFile] AST parsed from the same source, with its spans, is available
+/// through [Cst::ast] and [Cst::spans].
+///
+/// The [fmt::Display] implementation writes back the original source exactly.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Cst {
+    source: String,
+    root: CstNode,
+    ast: CosFile,
+    spans: SpanNode,
+}
+
+impl Cst {
+    pub(crate) fn new(
+        source: &str,
+        program: Pair<'_, Rule>,
+        ast: CosFile,
+        spans: SpanNode,
+    ) -> Self {
+        Self {
+            source: source.to_owned(),
+            root: build_node(source, program),
+            ast,
+            spans,
+        }
+    }
+
+    /// The source text the tree was parsed from.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The root of the tree, a `program` node covering the whole source.
+    pub fn root(&self) -> &CstNode {
+        &self.root
+    }
+
+    /// The AST of the source.
+    pub fn ast(&self) -> &CosFile {
+        &self.ast
+    }
+
+    /// The spans of the nodes of [Cst::ast].
+    pub fn spans(&self) -> &SpanNode {
+        &self.spans
+    }
+
+    /// Iterates over every token in the tree, trivia included, in source order.
+    pub fn tokens(&self) -> impl Iterator<Item = &CstToken> + '_ {
+        self.root.tokens()
+    }
+
+    /// The source text of a token.
+    pub fn text(&self, token: &CstToken) -> &str {
+        token.span.as_str(&self.source)
+    }
+
+    /// Replaces the source text covered by `span` with `replacement`, and parses
+    /// the result.
+    ///
+    /// All text outside of `span`, including comments and layout, is kept as it
+    /// was. This allows a single command, found through [Cst::spans], to be
+    /// rewritten without disturbing the rest of the file.
+    pub fn replace(&self, span: Span, replacement: &str) -> Result<Cst, CaosError> {
+        let mut source = self.source.clone();
+        source.replace_range(span.start..span.end, replacement);
+        parse_cst(&source)
+    }
+}
+
+impl fmt::Display for Cst {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.tokens()
+            .try_for_each(|t| f.write_str(t.span.as_str(&self.source)))
+    }
+}
+
+/// Rules matched atomically by the grammar. Their text is kept as a single token.
+fn is_atomic(rule: Rule) -> bool {
+    matches!(
+        rule,
+        Rule::literal_int
+            | Rule::literal_string
+            | Rule::literal_float
+            | Rule::label
+            | Rule::variable_mvxx
+            | Rule::variable_ovxx
+            | Rule::variable_vaxx
+    )
+}
+
+fn build_node(source: &str, pair: Pair<'_, Rule>) -> CstNode {
+    let rule = pair.as_rule();
+    let span = pair.as_span();
+    let mut children = Vec::new();
+    let mut pos = span.start();
+    for inner in pair.into_inner().filter(|p| p.as_rule() != Rule::EOI) {
+        let inner_span = inner.as_span();
+        lex_trivia(source, pos, inner_span.start(), &mut children);
+        let inner_rule = inner.as_rule();
+        if is_atomic(inner_rule) || inner.clone().into_inner().peek().is_none() {
+            children.push(CstElement::Token(CstToken {
+                kind: TokenKind::Rule(inner_rule),
+                span: inner_span.into(),
+            }));
+        } else {
+            children.push(CstElement::Node(build_node(source, inner)));
+        }
+        pos = inner_span.end();
+    }
+    lex_trivia(source, pos, span.end(), &mut children);
+    CstNode {
+        rule,
+        span: span.into(),
+        children,
+    }
+}
+
+/// Splits the text between two grammar tokens into whitespace, comments and
+/// unnamed tokens.
+fn lex_trivia(source: &str, start: usize, end: usize, out: &mut Vec<CstElement>) {
+    let bytes = source.as_bytes();
+    let mut i = start;
+    while i < end {
+        let token_start = i;
+        let kind = match bytes[i] {
+            b' ' | b'\t' | b'\r' | b'\n' => {
+                while i < end && matches!(bytes[i], b' ' | b'\t' | b'\r' | b'\n') {
+                    i += 1;
+                }
+                TokenKind::Whitespace
+            }
+            b'*' => {
+                while i < end && !matches!(bytes[i], b'\r' | b'\n') {
+                    i += 1;
+                }
+                TokenKind::Comment
+            }
+            _ => {
+                while i < end && !matches!(bytes[i], b' ' | b'\t' | b'\r' | b'\n' | b'*') {
+                    i += 1;
+                }
+                TokenKind::Unnamed
+            }
+        };
+        let span = pest::Span::new(source, token_start, i)
+            .expect("Token range is within the source")
+            .into();
+        out.push(CstElement::Token(CstToken { kind, span }));
+    }
+}
@@ -0,0 +1,85 @@
+use super::*;
+use crate::{parse_cos, parse_cst};
+
+const SOURCE: &str = "* Install\r\ninst\n  new: simp 1 2 3 \"a * b\" 1 0 '*'  * trailing\n\
+    anim [0 1  2]\nsetv mv00 2.5\nendm\n\n  scrp 1 2 3 4 * event\n\tgoto lbl\nendm\n* footer";
+
+fn token_texts(cst: &Cst, kind: TokenKind) -> Vec<&str> {
+    cst.tokens()
+        .filter(|t| t.kind == kind)
+        .map(|t| cst.text(t))
+        .collect()
+}
+
+#[test]
+fn test_cst_is_lossless() {
+    let cst = parse_cst(SOURCE).expect("Parsed");
+    assert_eq!(cst.to_string(), SOURCE);
+    assert_eq!(cst.root().span.start, 0);
+    assert_eq!(cst.root().span.end, SOURCE.len());
+}
+
+#[test]
+fn test_cst_tokens_are_contiguous() {
+    let cst = parse_cst(SOURCE).expect("Parsed");
+    let mut pos = 0;
+    for token in cst.tokens() {
+        assert_eq!(token.span.start, pos, "Gap before {:?}", token);
+        assert!(token.span.end > token.span.start, "Empty token {:?}", token);
+        pos = token.span.end;
+    }
+    assert_eq!(pos, SOURCE.len());
+}
+
+#[test]
+fn test_cst_comments() {
+    let cst = parse_cst(SOURCE).expect("Parsed");
+    assert_eq!(
+        token_texts(&cst, TokenKind::Comment),
+        vec!["* Install", "* trailing", "* event", "* footer"]
+    );
+}
+
+#[test]
+fn test_cst_tokens() {
+    let cst = parse_cst(SOURCE).expect("Parsed");
+    assert_eq!(
+        token_texts(&cst, TokenKind::Unnamed),
+        vec!["[", "]", "endm", "scrp", "endm"]
+    );
+    let rules: Vec<_> = cst
+        .tokens()
+        .filter_map(|t| match t.kind {
+            TokenKind::Rule(r) => Some((r, cst.text(t))),
+            _ => None,
+        })
+        .collect();
+    assert!(rules.contains(&(Rule::literal_string, "\"a * b\"")));
+    assert!(rules.contains(&(Rule::literal_int, "'*'")));
+    assert!(rules.contains(&(Rule::variable_mvxx, "mv00")));
+    assert!(rules.contains(&(Rule::label, "lbl")));
+}
+
+#[test]
+fn test_cst_ast() {
+    let cst = parse_cst(SOURCE).expect("Parsed");
+    assert_eq!(cst.ast(), &parse_cos(SOURCE).expect("Parsed"));
+    assert_eq!(cst.spans().children.len(), 2);
+}
+
+#[test]
+fn test_cst_replace_command() {
+    let cst = parse_cst(SOURCE).expect("Parsed");
+    let setv = cst.spans().children[0].children[3].span;
+    assert_eq!(setv.as_str(cst.source()), "setv mv00 2.5");
+
+    let replaced = cst.replace(setv, "SETV MV00 3.5").expect("Parsed");
+    assert_eq!(
+        replaced.to_string(),
+        SOURCE.replace("setv mv00 2.5", "SETV MV00 3.5")
+    );
+    assert_eq!(
+        token_texts(&replaced, TokenKind::Comment),
+        token_texts(&cst, TokenKind::Comment)
+    );
+}
@@ -0,0 +1,643 @@
+//! A [Debug Adapter Protocol](https://microsoft.github.io/debug-adapter-protocol/)
+//! server, so that editors can debug CAOS files with a [Debugger] against the
+//! interpreter's world.
+//!
+//! The server reads requests from its input and writes responses and events
+//! to its output, as an editor does over the adapter's stdin and stdout. A
+//! `launch` request loads the file given as `program` and runs its install
+//! scripts one after another under the debugger. It also takes:
+//!
+//! - `stopOnEntry`: stop before the first command.
+//! - `event`: the family, genus, species and event number of an event
+//!   script to run once the install scripts have finished, owned by the first
+//!   agent with that classifier or a new one if there is none.
+//! - `encoding`: `cp1252` or `latin1` to read the file in, rather than UTF-8.
+//!
+//! Line breakpoints stop at the first command on a line, function
+//! breakpoints at every command with a keyword, such as `NEW: SIMP`, and
+//! data breakpoints whenever a variable changes. Scripts run as a single
+//! thread, and each subroutine called by `GSUB` is a frame of its own.
+
+mod json;
+#[cfg(test)]
+mod tests;
+
+use crate::interpreter::{
+    AgentId, AgentState, Breakpoint, Debugger, Interpreter, StopReason, Value,
+};
+use crate::signature::lookup;
+use crate::{parse_expression_str, Encoding};
+use json::{object, Json};
+use std::io::{self, BufRead, Read, Write};
+
+/// The id of the only thread.
+const THREAD_ID: i64 = 1;
+
+/// The longest message read. Longer ones are skipped without being read into
+/// memory, so that a bad `Content-Length` cannot exhaust it.
+const MAX_CONTENT_LENGTH: u64 = 16 * 1024 * 1024;
+
+/// The variables references of the scopes of each frame.
+const VARIABLES: i64 = 1;
+const AGENTS: i64 = 2;
+const OWNER: i64 = 3;
+const TARGET: i64 = 4;
+
+/// Serves a debugging session, returning once the editor disconnects or
+/// closes the input.
+pub fn serve(mut input: impl BufRead, output: impl Write) -> io::Result<()> {
+    let mut server = Server::new(output);
+    loop {
+        match read_message(&mut input) {
+            Ok(Some(message)) => {
+                if !server.handle(&message)? {
+                    break;
+                }
+            }
+            Ok(None) => break,
+            // The message has been skipped, so the next can still be read.
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                server.output("console", &format!("{}\n", e))?;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Reads a message with its `Content-Length` header, or returns `None` at the
+/// end of the input. A message which is too long or is not JSON is skipped,
+/// giving an error of kind [io::ErrorKind::InvalidData].
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<Json>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() && length.is_some() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let length = length.unwrap_or_default() as u64;
+    if length > MAX_CONTENT_LENGTH {
+        io::copy(&mut input.take(length), &mut io::sink())?;
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Skipped a message of {} bytes, which is too long", length),
+        ));
+    }
+    let mut body = vec![0; length as usize];
+    input.read_exact(&mut body)?;
+    String::from_utf8(body)
+        .ok()
+        .and_then(|text| Json::parse(&text))
+        .map(Some)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "The message is not JSON"))
+}
+
+/// How to run after a request.
+#[derive(Debug, Clone, Copy)]
+enum Run {
+    Continue,
+    StepIn,
+    StepOver,
+    StepOut,
+}
+
+/// What to do once a request has been answered.
+enum Then {
+    Nothing,
+    /// Tell the editor it can configure breakpoints.
+    Initialized,
+    /// Start running if the program is launched and configured.
+    Start,
+    Run(Run),
+    Disconnect,
+}
+
+struct Server<W> {
+    output: W,
+    seq: i64,
+    debugger: Option<Debugger>,
+    program: String,
+    stop_on_entry: bool,
+    event: Option<[i32; 4]>,
+    /// Whether the editor has finished setting breakpoints.
+    configured: bool,
+    started: bool,
+    /// Whether any script has failed.
+    failed: bool,
+    lines: Vec<usize>,
+    commands: Vec<String>,
+    /// The expressions of the data breakpoints.
+    data: Vec<String>,
+    /// How much of the output and debug log has been sent.
+    output_sent: usize,
+    log_sent: usize,
+}
+
+impl<W: Write> Server<W> {
+    fn new(output: W) -> Self {
+        Self {
+            output,
+            seq: 0,
+            debugger: None,
+            program: String::new(),
+            stop_on_entry: false,
+            event: None,
+            configured: false,
+            started: false,
+            failed: false,
+            lines: Vec::new(),
+            commands: Vec::new(),
+            data: Vec::new(),
+            output_sent: 0,
+            log_sent: 0,
+        }
+    }
+
+    /// Handles a message, returning `false` once the editor disconnects.
+    fn handle(&mut self, message: &Json) -> io::Result<bool> {
+        if message.get("type").as_str() != Some("request") {
+            return Ok(true);
+        }
+        let command = message.get("command").as_str().unwrap_or_default();
+        let (body, then) = match self.request(command, message.get("arguments")) {
+            Ok((body, then)) => (Ok(body), then),
+            Err(error) => (Err(error), Then::Nothing),
+        };
+        let mut response = vec![
+            ("type", "response".into()),
+            ("request_seq", message.get("seq").clone()),
+            ("success", body.is_ok().into()),
+            ("command", command.into()),
+        ];
+        match body {
+            Ok(body) => response.push(("body", body)),
+            Err(error) => response.push(("message", error.into())),
+        }
+        self.send(response)?;
+
+        match then {
+            Then::Nothing => {}
+            Then::Initialized => self.event("initialized", object([]))?,
+            Then::Start => self.start()?,
+            Then::Run(run) => self.run(run)?,
+            Then::Disconnect => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn request(&mut self, command: &str, arguments: &Json) -> Result<(Json, Then), String> {
+        let body = match command {
+            "initialize" => {
+                let capabilities = object([
+                    ("supportsConfigurationDoneRequest", true.into()),
+                    ("supportsFunctionBreakpoints", true.into()),
+                    ("supportsDataBreakpoints", true.into()),
+                    ("supportsEvaluateForHovers", true.into()),
+                ]);
+                return Ok((capabilities, Then::Initialized));
+            }
+            "launch" => {
+                self.launch(arguments)?;
+                return Ok((object([]), Then::Start));
+            }
+            "configurationDone" => {
+                self.configured = true;
+                return Ok((object([]), Then::Start));
+            }
+            "setBreakpoints" => {
+                self.lines = arguments
+                    .get("breakpoints")
+                    .elements()
+                    .iter()
+                    .filter_map(|b| b.get("line").as_i64())
+                    .map(|line| line as usize)
+                    .collect();
+                self.apply_breakpoints();
+                let breakpoints = self
+                    .lines
+                    .iter()
+                    .map(|&line| object([("verified", true.into()), ("line", line.into())]))
+                    .collect::<Vec<_>>();
+                object([("breakpoints", breakpoints.into())])
+            }
+            "setFunctionBreakpoints" => {
+                self.commands = arguments
+                    .get("breakpoints")
+                    .elements()
+                    .iter()
+                    .filter_map(|b| b.get("name").as_str())
+                    .map(str::to_owned)
+                    .collect();
+                self.apply_breakpoints();
+                let breakpoints = self
+                    .commands
+                    .iter()
+                    .map(|name| {
+                        let known = !lookup(name).is_empty();
+                        let mut breakpoint = vec![("verified", known.into())];
+                        if !known {
+                            breakpoint
+                                .push(("message", format!("Unknown keyword '{}'", name).into()));
+                        }
+                        object(breakpoint)
+                    })
+                    .collect::<Vec<_>>();
+                object([("breakpoints", breakpoints.into())])
+            }
+            "setExceptionBreakpoints" => object([("breakpoints", Json::Array(Vec::new()))]),
+            "dataBreakpointInfo" => {
+                let name = arguments.get("name").as_str().unwrap_or_default();
+                match parse_expression_str(name) {
+                    Ok(_) => object([
+                        ("dataId", name.into()),
+                        ("description", name.into()),
+                        ("accessTypes", vec!["write".into()].into()),
+                    ]),
+                    Err(_) => object([
+                        ("dataId", Json::Null),
+                        (
+                            "description",
+                            format!("'{}' cannot be watched", name).into(),
+                        ),
+                    ]),
+                }
+            }
+            "setDataBreakpoints" => {
+                self.data = arguments
+                    .get("breakpoints")
+                    .elements()
+                    .iter()
+                    .filter_map(|b| b.get("dataId").as_str())
+                    .map(str::to_owned)
+                    .collect();
+                let verified = self.apply_watches();
+                let breakpoints = verified
+                    .into_iter()
+                    .map(|verified| object([("verified", verified.into())]))
+                    .collect::<Vec<_>>();
+                object([("breakpoints", breakpoints.into())])
+            }
+            "threads" => {
+                let name = self.debugger.as_ref().and_then(Debugger::name);
+                let thread = object([
+                    ("id", THREAD_ID.into()),
+                    ("name", name.unwrap_or(&self.program).into()),
+                ]);
+                object([("threads", vec![thread].into())])
+            }
+            "stackTrace" => {
+                let frames = self.debugger()?.frames();
+                let source = object([("path", self.program.as_str().into())]);
+                let frames = frames
+                    .into_iter()
+                    .enumerate()
+                    .map(|(id, frame)| {
+                        let (line, column) = frame.span.map_or((0, 0), |s| s.start_line_col);
+                        object([
+                            ("id", id.into()),
+                            ("name", frame.name.into()),
+                            ("source", source.clone()),
+                            ("line", line.into()),
+                            ("column", column.into()),
+                        ])
+                    })
+                    .collect::<Vec<_>>();
+                let total = frames.len();
+                object([
+                    ("stackFrames", frames.into()),
+                    ("totalFrames", total.into()),
+                ])
+            }
+            "scopes" => {
+                let scopes = [
+                    ("Variables", VARIABLES),
+                    ("Agents", AGENTS),
+                    ("OWNR", OWNER),
+                    ("TARG", TARGET),
+                ]
+                .map(|(name, reference)| {
+                    object([
+                        ("name", name.into()),
+                        ("variablesReference", reference.into()),
+                        ("expensive", false.into()),
+                    ])
+                });
+                object([("scopes", Json::Array(scopes.to_vec()))])
+            }
+            "variables" => {
+                let reference = arguments.get("variablesReference").as_i64();
+                let variables = self
+                    .variables(reference.unwrap_or_default())?
+                    .into_iter()
+                    .map(|(name, value)| {
+                        object([
+                            ("name", name.into()),
+                            ("value", value.into()),
+                            ("variablesReference", 0i64.into()),
+                        ])
+                    })
+                    .collect::<Vec<_>>();
+                object([("variables", variables.into())])
+            }
+            "evaluate" => {
+                let expression = arguments.get("expression").as_str().unwrap_or_default();
+                let debugger = self.debugger_mut()?;
+                let value = debugger.evaluate(expression).map_err(|e| e.to_string())?;
+                object([
+                    ("result", describe(debugger, &value).into()),
+                    ("variablesReference", 0i64.into()),
+                ])
+            }
+            "continue" => {
+                self.debugger()?;
+                let body = object([("allThreadsContinued", true.into())]);
+                return Ok((body, Then::Run(Run::Continue)));
+            }
+            "next" | "stepIn" | "stepOut" => {
+                self.debugger()?;
+                let run = match command {
+                    "next" => Run::StepOver,
+                    "stepIn" => Run::StepIn,
+                    _ => Run::StepOut,
+                };
+                return Ok((object([]), Then::Run(run)));
+            }
+            // Scripts only run between requests, so are always paused.
+            "pause" => object([]),
+            "disconnect" | "terminate" => return Ok((object([]), Then::Disconnect)),
+            _ => return Err(format!("Unsupported request '{}'", command)),
+        };
+        Ok((body, Then::Nothing))
+    }
+
+    fn launch(&mut self, arguments: &Json) -> Result<(), String> {
+        let program = arguments.get("program").as_str();
+        let program = program.ok_or("launch expects a program to debug")?;
+        let bytes = std::fs::read(program).map_err(|e| format!("{}: {}", program, e))?;
+        let source = match arguments.get("encoding").as_str() {
+            Some(encoding) => encoding.parse::<Encoding>()?.decode(&bytes),
+            None => String::from_utf8(bytes).map_err(|e| format!("{}: {}", program, e))?,
+        };
+        let mut debugger = Debugger::new(Interpreter::new());
+        debugger
+            .load(&source)
+            .map_err(|e| format!("{}: {}", program, e))?;
+
+        let event: Vec<_> = arguments
+            .get("event")
+            .elements()
+            .iter()
+            .filter_map(Json::as_i64)
+            .map(|n| n as i32)
+            .collect();
+        self.event = match event[..] {
+            [] => None,
+            [family, genus, species, event] => Some([family, genus, species, event]),
+            _ => return Err("event expects a family, genus, species and event".to_owned()),
+        };
+        self.program = program.to_owned();
+        self.stop_on_entry = arguments.get("stopOnEntry").as_bool().unwrap_or(false);
+        self.debugger = Some(debugger);
+        self.apply_breakpoints();
+        self.apply_watches();
+        Ok(())
+    }
+
+    fn debugger(&self) -> Result<&Debugger, String> {
+        self.debugger
+            .as_ref()
+            .ok_or_else(|| "No program has been launched".to_owned())
+    }
+
+    fn debugger_mut(&mut self) -> Result<&mut Debugger, String> {
+        self.debugger
+            .as_mut()
+            .ok_or_else(|| "No program has been launched".to_owned())
+    }
+
+    fn apply_breakpoints(&mut self) {
+        let lines = self.lines.iter().map(|&line| Breakpoint::Line(line));
+        let commands = self.commands.iter().cloned().map(Breakpoint::Command);
+        let breakpoints = lines.chain(commands).collect();
+        if let Some(debugger) = &mut self.debugger {
+            debugger.set_breakpoints(breakpoints);
+        }
+    }
+
+    /// Watches the expressions of the data breakpoints, returning whether
+    /// each could be watched.
+    fn apply_watches(&mut self) -> Vec<bool> {
+        match &mut self.debugger {
+            Some(debugger) => {
+                debugger.clear_watches();
+                self.data
+                    .iter()
+                    .map(|e| debugger.watch(e).is_ok())
+                    .collect()
+            }
+            None => self
+                .data
+                .iter()
+                .map(|e| parse_expression_str(e).is_ok())
+                .collect(),
+        }
+    }
+
+    /// The variables in a scope, by name.
+    fn variables(&self, reference: i64) -> Result<Vec<(String, String)>, String> {
+        let debugger = self.debugger()?;
+        let Some(thread) = debugger.thread() else {
+            return Ok(Vec::new());
+        };
+        let agent_variables = |agent: Option<AgentId>, prefix: &str| {
+            let state = agent.and_then(|id| debugger.interpreter.world.agent(id));
+            state.map_or_else(Vec::new, |state| {
+                let values = state.ovxx.iter().enumerate();
+                values
+                    .map(|(i, v)| (format!("{}{:02}", prefix, i), describe(debugger, v)))
+                    .collect()
+            })
+        };
+        Ok(match reference {
+            VARIABLES => (0..100)
+                .map(|i| (format!("VA{:02}", i), describe(debugger, thread.vaxx(i))))
+                .collect(),
+            AGENTS => [
+                ("OWNR", Value::Agent(thread.owner)),
+                ("TARG", Value::Agent(thread.targ)),
+                ("FROM", Value::Agent(thread.from)),
+                ("_IT_", Value::Agent(thread.it)),
+                ("_P1_", thread.p1.clone()),
+                ("_P2_", thread.p2.clone()),
+            ]
+            .iter()
+            .map(|(name, value)| (name.to_string(), describe(debugger, value)))
+            .collect(),
+            // OWNR's variables are MVxx, and TARG's are OVxx.
+            OWNER => agent_variables(thread.owner, "MV"),
+            TARGET => agent_variables(thread.targ, "OV"),
+            _ => return Err(format!("Unknown variables reference {}", reference)),
+        })
+    }
+
+    /// Starts running once the program has been launched and the editor has
+    /// set its breakpoints.
+    fn start(&mut self) -> io::Result<()> {
+        if self.started || !self.configured || self.debugger.is_none() {
+            return Ok(());
+        }
+        self.started = true;
+        if self.debugger.as_ref().and_then(Debugger::thread).is_none() && !self.queue_event()? {
+            return self.terminate();
+        }
+        if self.stop_on_entry {
+            self.stopped("entry", None)
+        } else {
+            self.run(Run::Continue)
+        }
+    }
+
+    /// Queues the event script given to `launch`, returning whether there
+    /// was one to queue.
+    fn queue_event(&mut self) -> io::Result<bool> {
+        let (Some([family, genus, species, event]), Some(debugger)) =
+            (self.event.take(), &mut self.debugger)
+        else {
+            return Ok(false);
+        };
+        let world = &mut debugger.interpreter.world;
+        let existing = world
+            .agents()
+            .find(|(_, a)| (a.family, a.genus, a.species) == (family, genus, species))
+            .map(|(id, _)| id);
+        let owner =
+            existing.unwrap_or_else(|| world.add_agent(AgentState::new(family, genus, species)));
+        let name = format!("SCRP {} {} {} {}", family, genus, species, event);
+        match debugger.interpreter.event_thread(owner, event) {
+            Some(thread) => {
+                debugger.queue(name, thread);
+                Ok(true)
+            }
+            None => {
+                self.output("console", &format!("There is no {} script\n", name))?;
+                Ok(false)
+            }
+        }
+    }
+
+    fn run(&mut self, run: Run) -> io::Result<()> {
+        let Some(debugger) = &mut self.debugger else {
+            return Ok(());
+        };
+        let mut reason = match run {
+            Run::Continue => debugger.resume(),
+            Run::StepIn => debugger.step_in(),
+            Run::StepOver => debugger.step_over(),
+            Run::StepOut => debugger.step_out(),
+        };
+        if matches!(reason, StopReason::Finished) && self.queue_event()? {
+            reason = match run {
+                Run::Continue => self.debugger.as_mut().expect("Launched").resume(),
+                _ => StopReason::Step,
+            };
+        }
+        self.send_output()?;
+        match reason {
+            StopReason::Step => self.stopped("step", None),
+            StopReason::Breakpoint(_) => self.stopped("breakpoint", None),
+            StopReason::Watch(_) => self.stopped("data breakpoint", None),
+            StopReason::Error(error) => {
+                self.failed = true;
+                self.stopped("exception", Some(error.to_string()))
+            }
+            StopReason::Finished => self.terminate(),
+        }
+    }
+
+    /// Sends what the scripts have written since it was last sent.
+    fn send_output(&mut self) -> io::Result<()> {
+        let Some(debugger) = &self.debugger else {
+            return Ok(());
+        };
+        let output = debugger.interpreter.output()[self.output_sent..].to_owned();
+        let log: Vec<_> = debugger.interpreter.debug_log()[self.log_sent..].to_vec();
+        self.output_sent += output.len();
+        self.log_sent += log.len();
+        if !output.is_empty() {
+            self.output("stdout", &output)?;
+        }
+        for line in log {
+            self.output("console", &format!("{}\n", line))?;
+        }
+        Ok(())
+    }
+
+    fn output(&mut self, category: &str, output: &str) -> io::Result<()> {
+        let body = object([("category", category.into()), ("output", output.into())]);
+        self.event("output", body)
+    }
+
+    fn stopped(&mut self, reason: &str, text: Option<String>) -> io::Result<()> {
+        let mut body = vec![
+            ("reason", reason.into()),
+            ("threadId", THREAD_ID.into()),
+            ("allThreadsStopped", true.into()),
+        ];
+        if let Some(text) = text {
+            body.push(("text", text.into()));
+        }
+        self.event("stopped", object(body))
+    }
+
+    fn terminate(&mut self) -> io::Result<()> {
+        let exit_code = i64::from(self.failed);
+        self.event("exited", object([("exitCode", exit_code.into())]))?;
+        self.event("terminated", object([]))
+    }
+
+    fn event(&mut self, event: &str, body: Json) -> io::Result<()> {
+        self.send(vec![
+            ("type", "event".into()),
+            ("event", event.into()),
+            ("body", body),
+        ])
+    }
+
+    fn send(&mut self, members: Vec<(&str, Json)>) -> io::Result<()> {
+        self.seq += 1;
+        let mut message = vec![("seq", self.seq.into())];
+        message.extend(members);
+        let message = object(message).to_string();
+        write!(
+            self.output,
+            "Content-Length: {}\r\n\r\n{}",
+            message.len(),
+            message
+        )?;
+        self.output.flush()
+    }
+}
+
+/// Describes a value, with the classifier of an agent.
+fn describe(debugger: &Debugger, value: &Value) -> String {
+    match value {
+        Value::Agent(Some(id)) => match debugger.interpreter.world.agent(*id) {
+            Some(agent) => format!(
+                "agent {} ({} {} {})",
+                id, agent.family, agent.genus, agent.species
+            ),
+            None => format!("agent {} (killed)", id),
+        },
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,292 @@
+//! Just enough JSON for the Debug Adapter Protocol.
+
+use crate::diagnostic::json_string;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// Members in the order they were written.
+    Object(Vec<(String, Json)>),
+}
+
+/// An object with the given members.
+pub fn object<'a>(members: impl IntoIterator<Item = (&'a str, Json)>) -> Json {
+    Json::Object(
+        members
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), value))
+            .collect(),
+    )
+}
+
+impl Json {
+    /// Parses a JSON document, returning `None` if it is not valid.
+    pub fn parse(text: &str) -> Option<Json> {
+        let mut parser = Parser {
+            chars: text.chars().collect(),
+            at: 0,
+        };
+        let value = parser.value()?;
+        parser.whitespace();
+        (parser.at == parser.chars.len()).then_some(value)
+    }
+
+    /// The member of an object, or [Json::Null] if there is none.
+    pub fn get(&self, key: &str) -> &Json {
+        match self {
+            Json::Object(members) => members
+                .iter()
+                .find(|(k, _)| k == key)
+                .map_or(&Json::Null, |(_, value)| value),
+            _ => &Json::Null,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Json::Number(n) if n.fract() == 0.0 => Some(*n as i64),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// The elements of an array, or none if it is not one.
+    pub fn elements(&self) -> &[Json] {
+        match self {
+            Json::Array(elements) => elements,
+            _ => &[],
+        }
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Self {
+        Json::Bool(b)
+    }
+}
+
+impl From<i64> for Json {
+    fn from(n: i64) -> Self {
+        Json::Number(n as f64)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(n: usize) -> Self {
+        Json::Number(n as f64)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Self {
+        Json::String(s.to_owned())
+    }
+}
+
+impl From<String> for Json {
+    fn from(s: String) -> Self {
+        Json::String(s)
+    }
+}
+
+impl From<Vec<Json>> for Json {
+    fn from(elements: Vec<Json>) -> Self {
+        Json::Array(elements)
+    }
+}
+
+impl fmt::Display for Json {
+    /// Writes the value on a single line.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(s) => write_string(f, s),
+            Json::Array(elements) => {
+                write!(f, "[")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(members) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    let mut out = String::new();
+    json_string(&mut out, s);
+    f.write_str(&out)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    at: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.at).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.at += 1;
+        Some(c)
+    }
+
+    fn whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+            self.at += 1;
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: Json) -> Option<Json> {
+        for expected in word.chars() {
+            if self.next()? != expected {
+                return None;
+            }
+        }
+        Some(value)
+    }
+
+    fn value(&mut self) -> Option<Json> {
+        self.whitespace();
+        match self.peek()? {
+            'n' => self.literal("null", Json::Null),
+            't' => self.literal("true", Json::Bool(true)),
+            'f' => self.literal("false", Json::Bool(false)),
+            '"' => self.string().map(Json::String),
+            '[' => {
+                self.at += 1;
+                let mut elements = Vec::new();
+                self.whitespace();
+                if self.peek() == Some(']') {
+                    self.at += 1;
+                    return Some(Json::Array(elements));
+                }
+                loop {
+                    elements.push(self.value()?);
+                    self.whitespace();
+                    match self.next()? {
+                        ',' => continue,
+                        ']' => return Some(Json::Array(elements)),
+                        _ => return None,
+                    }
+                }
+            }
+            '{' => {
+                self.at += 1;
+                let mut members = Vec::new();
+                self.whitespace();
+                if self.peek() == Some('}') {
+                    self.at += 1;
+                    return Some(Json::Object(members));
+                }
+                loop {
+                    self.whitespace();
+                    let key = self.string()?;
+                    self.whitespace();
+                    if self.next()? != ':' {
+                        return None;
+                    }
+                    members.push((key, self.value()?));
+                    self.whitespace();
+                    match self.next()? {
+                        ',' => continue,
+                        '}' => return Some(Json::Object(members)),
+                        _ => return None,
+                    }
+                }
+            }
+            _ => self.number(),
+        }
+    }
+
+    fn number(&mut self) -> Option<Json> {
+        let start = self.at;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || "+-.eE".contains(c))
+        {
+            self.at += 1;
+        }
+        let text: String = self.chars[start..self.at].iter().collect();
+        text.parse().ok().map(Json::Number)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        if self.next()? != '"' {
+            return None;
+        }
+        let mut s = String::new();
+        loop {
+            match self.next()? {
+                '"' => return Some(s),
+                '\\' => match self.next()? {
+                    'n' => s.push('\n'),
+                    'r' => s.push('\r'),
+                    't' => s.push('\t'),
+                    'b' => s.push('\u{8}'),
+                    'f' => s.push('\u{c}'),
+                    'u' => {
+                        let mut code = self.hex()?;
+                        // Characters outside the first plane are written as a
+                        // pair of surrogates.
+                        if (0xD800..0xDC00).contains(&code) {
+                            if self.next()? != '\\' || self.next()? != 'u' {
+                                return None;
+                            }
+                            let low = self.hex()?;
+                            code = 0x10000 + ((code - 0xD800) << 10) + (low.checked_sub(0xDC00)?);
+                        }
+                        s.push(char::from_u32(code)?);
+                    }
+                    c => s.push(c),
+                },
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn hex(&mut self) -> Option<u32> {
+        let mut code = 0;
+        for _ in 0..4 {
+            code = code * 16 + self.next()?.to_digit(16)?;
+        }
+        Some(code)
+    }
+}
@@ -0,0 +1,310 @@
+use super::*;
+
+const SOURCE: &str = "\
+iscr
+    setv va00 1
+    gsub add
+    outv va00
+    subr add
+        addv va00 2
+        retn
+endm
+scrp 2 11 100 1
+    dbg: outs \"clicked\"
+    kill ownr
+endm
+";
+
+/// Runs a session debugging `source`, with requests written as JSON with
+/// `PROGRAM` standing for the path of the file, and returns the messages
+/// sent back.
+fn session(name: &str, source: &str, requests: &[&str]) -> Vec<Json> {
+    let path = std::env::temp_dir().join(format!("caos2-dap-{}-{}.cos", std::process::id(), name));
+    std::fs::write(&path, source).unwrap();
+    let path_json = Json::from(path.to_str().unwrap()).to_string();
+
+    let mut input = String::new();
+    for (seq, request) in requests.iter().enumerate() {
+        let request = request.replace("PROGRAM", &path_json);
+        let message = format!(r#"{{"seq":{},"type":"request",{}}}"#, seq + 1, request);
+        input += &format!("Content-Length: {}\r\n\r\n{}", message.len(), message);
+    }
+    let mut output = Vec::new();
+    serve(input.as_bytes(), &mut output).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut reader = output.as_slice();
+    std::iter::from_fn(|| read_message(&mut reader).unwrap()).collect()
+}
+
+/// Describes each message by its command or event, with the reason of a
+/// `stopped` event and the text of an `output` event.
+fn summary(messages: &[Json]) -> Vec<String> {
+    messages
+        .iter()
+        .map(|m| {
+            let body = m.get("body");
+            match (m.get("event").as_str(), m.get("command").as_str()) {
+                (Some("stopped"), _) => format!("stopped {}", body.get("reason").as_str().unwrap()),
+                (Some("output"), _) => format!("output {:?}", body.get("output").as_str().unwrap()),
+                (Some(event), _) => event.to_owned(),
+                (None, Some(command)) if m.get("success") == &Json::Bool(true) => {
+                    command.to_owned()
+                }
+                (None, Some(command)) => format!("{} failed", command),
+                _ => panic!("Unexpected message {}", m),
+            }
+        })
+        .collect()
+}
+
+fn response<'a>(messages: &'a [Json], command: &str) -> &'a Json {
+    messages
+        .iter()
+        .rfind(|m| m.get("command").as_str() == Some(command))
+        .unwrap()
+        .get("body")
+}
+
+#[test]
+fn test_json() {
+    let text = r#" {"a": [1, -2.5e1, true, null], "b\n": "é😀\"", "c": {}} "#;
+    let json = Json::parse(text).unwrap();
+    assert_eq!(json.get("a").elements()[1], Json::Number(-25.0));
+    assert_eq!(json.get("b\n").as_str(), Some("é😀\""));
+    assert_eq!(
+        json.to_string(),
+        r#"{"a":[1,-25,true,null],"b\n":"é😀\"","c":{}}"#
+    );
+    assert_eq!(Json::parse("[1,]"), None);
+    assert_eq!(Json::parse("{} {}"), None);
+}
+
+#[test]
+fn test_breakpoints_and_variables() {
+    let messages = session(
+        "breakpoints",
+        SOURCE,
+        &[
+            r#""command":"initialize","arguments":{}"#,
+            r#""command":"launch","arguments":{"program":PROGRAM}"#,
+            r#""command":"setBreakpoints","arguments":{"breakpoints":[{"line":6}]}"#,
+            r#""command":"configurationDone""#,
+            r#""command":"stackTrace","arguments":{"threadId":1}"#,
+            r#""command":"variables","arguments":{"variablesReference":1}"#,
+            r#""command":"evaluate","arguments":{"expression":"va00"}"#,
+            r#""command":"continue","arguments":{"threadId":1}"#,
+            r#""command":"disconnect""#,
+        ],
+    );
+    assert_eq!(
+        summary(&messages),
+        [
+            "initialize",
+            "initialized",
+            "launch",
+            "setBreakpoints",
+            "configurationDone",
+            "stopped breakpoint",
+            "stackTrace",
+            "variables",
+            "evaluate",
+            "continue",
+            "output \"3\"",
+            "exited",
+            "terminated",
+            "disconnect",
+        ]
+    );
+
+    let frames = response(&messages, "stackTrace")
+        .get("stackFrames")
+        .elements();
+    let frames: Vec<_> = frames
+        .iter()
+        .map(|f| {
+            (
+                f.get("name").as_str().unwrap(),
+                f.get("line").as_i64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(frames, [("add", 6), ("install script 1", 3)]);
+
+    let variables = response(&messages, "variables").get("variables").elements();
+    assert_eq!(variables.len(), 100);
+    assert_eq!(variables[0].get("name").as_str(), Some("VA00"));
+    assert_eq!(variables[0].get("value").as_str(), Some("1"));
+    assert_eq!(
+        response(&messages, "evaluate").get("result").as_str(),
+        Some("1")
+    );
+}
+
+#[test]
+fn test_stepping_and_events() {
+    let messages = session(
+        "stepping",
+        SOURCE,
+        &[
+            r#""command":"initialize","arguments":{}"#,
+            r#""command":"launch","arguments":{"program":PROGRAM,"stopOnEntry":true,"event":[2,11,100,1]}"#,
+            r#""command":"configurationDone""#,
+            r#""command":"next","arguments":{"threadId":1}"#,
+            r#""command":"next","arguments":{"threadId":1}"#,
+            r#""command":"next","arguments":{"threadId":1}"#,
+            r#""command":"threads""#,
+            r#""command":"setFunctionBreakpoints","arguments":{"breakpoints":[{"name":"kill"},{"name":"nope"}]}"#,
+            r#""command":"stepIn","arguments":{"threadId":1}"#,
+            r#""command":"variables","arguments":{"variablesReference":2}"#,
+            r#""command":"continue","arguments":{"threadId":1}"#,
+        ],
+    );
+    assert_eq!(
+        summary(&messages),
+        [
+            "initialize",
+            "initialized",
+            "launch",
+            "configurationDone",
+            "stopped entry",
+            "next",
+            "stopped step",
+            "next",
+            "stopped step",
+            "next",
+            "output \"3\"",
+            "stopped step",
+            "threads",
+            "setFunctionBreakpoints",
+            "stepIn",
+            "output \"clicked\\n\"",
+            "stopped breakpoint",
+            "variables",
+            "continue",
+            "exited",
+            "terminated",
+        ]
+    );
+
+    let threads = response(&messages, "threads").get("threads").elements();
+    assert_eq!(threads[0].get("name").as_str(), Some("SCRP 2 11 100 1"));
+    let agents = response(&messages, "variables").get("variables").elements();
+    assert_eq!(agents[0].get("name").as_str(), Some("OWNR"));
+    assert_eq!(agents[0].get("value").as_str(), Some("agent 1 (2 11 100)"));
+    let breakpoints = response(&messages, "setFunctionBreakpoints").get("breakpoints");
+    let verified: Vec<_> = breakpoints
+        .elements()
+        .iter()
+        .map(|b| b.get("verified").as_bool().unwrap())
+        .collect();
+    assert_eq!(verified, [true, false]);
+}
+
+#[test]
+fn test_data_breakpoints() {
+    let messages = session(
+        "data",
+        SOURCE,
+        &[
+            r#""command":"launch","arguments":{"program":PROGRAM}"#,
+            r#""command":"dataBreakpointInfo","arguments":{"variablesReference":1,"name":"VA00"}"#,
+            r#""command":"setDataBreakpoints","arguments":{"breakpoints":[{"dataId":"VA00"}]}"#,
+            r#""command":"configurationDone""#,
+            r#""command":"evaluate","arguments":{"expression":"va00"}"#,
+            r#""command":"continue","arguments":{"threadId":1}"#,
+            r#""command":"evaluate","arguments":{"expression":"va00"}"#,
+            r#""command":"evaluate","arguments":{"expression":"nonsense ("}"#,
+        ],
+    );
+    assert_eq!(
+        summary(&messages),
+        [
+            "launch",
+            "dataBreakpointInfo",
+            "setDataBreakpoints",
+            "configurationDone",
+            "stopped data breakpoint",
+            "evaluate",
+            "continue",
+            "stopped data breakpoint",
+            "evaluate",
+            "evaluate failed",
+        ]
+    );
+    let info = response(&messages, "dataBreakpointInfo");
+    assert_eq!(info.get("dataId").as_str(), Some("VA00"));
+    let values: Vec<_> = messages
+        .iter()
+        .filter(|m| m.get("command").as_str() == Some("evaluate"))
+        .map(|m| m.get("body").get("result").as_str())
+        .collect();
+    assert_eq!(values, [Some("1"), Some("3"), None]);
+}
+
+#[test]
+fn test_errors() {
+    let messages = session(
+        "errors",
+        "iscr setv va00 1 kill null endm",
+        &[
+            r#""command":"launch","arguments":{}"#,
+            r#""command":"launch","arguments":{"program":PROGRAM}"#,
+            r#""command":"configurationDone""#,
+            r#""command":"continue","arguments":{"threadId":1}"#,
+            r#""command":"goto""#,
+        ],
+    );
+    assert_eq!(
+        summary(&messages),
+        [
+            "launch failed",
+            "launch",
+            "configurationDone",
+            "stopped exception",
+            "continue",
+            "exited",
+            "terminated",
+            "goto failed",
+        ]
+    );
+    let stopped = messages
+        .iter()
+        .find(|m| m.get("event").as_str() == Some("stopped"))
+        .unwrap();
+    assert_eq!(
+        stopped.get("body").get("text").as_str(),
+        Some("Agent is NULL or has been killed at line 1, column 18")
+    );
+    let exited = messages
+        .iter()
+        .find(|m| m.get("event").as_str() == Some("exited"))
+        .unwrap();
+    assert_eq!(exited.get("body").get("exitCode").as_i64(), Some(1));
+}
+
+#[test]
+fn test_invalid_messages() {
+    let initialize = r#"{"seq":2,"type":"request","command":"initialize"}"#;
+    let input = format!(
+        "Content-Length: 8\r\n\r\nnot json\
+         Content-Length: {}\r\n\r\n{}\
+         Content-Length: 99999999999\r\n\r\n{{}}",
+        initialize.len(),
+        initialize
+    );
+    let mut output = Vec::new();
+    serve(input.as_bytes(), &mut output).unwrap();
+
+    let mut reader = output.as_slice();
+    let messages: Vec<_> = std::iter::from_fn(|| read_message(&mut reader).unwrap()).collect();
+    assert_eq!(
+        summary(&messages),
+        [
+            "output \"The message is not JSON\\n\"",
+            "initialize",
+            "initialized",
+            "output \"Skipped a message of 99999999999 bytes, which is too long\\n\"",
+        ]
+    );
+}
@@ -0,0 +1,452 @@
+#[cfg(test)]
+mod tests;
+
+use crate::{CaosError, ErrorType, LineCol, Span};
+use std::fmt::Write;
+
+/// How serious a [Diagnostic] is.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        }
+    }
+}
+
+/// A region of source pointed at by a [Diagnostic], with a message explaining
+/// its part in the problem.
+///
+/// Positions are 1-based lines and columns, as in [Span].
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Label {
+    pub start: LineCol,
+    pub end: LineCol,
+    pub message: String,
+    /// Primary labels mark where the problem is. Secondary labels give context,
+    /// such as where an unclosed block was opened.
+    pub primary: bool,
+}
+
+impl Label {
+    /// A label marking where the problem is.
+    pub fn primary(span: &Span, message: impl Into<String>) -> Self {
+        Self {
+            start: span.start_line_col,
+            end: span.end_line_col,
+            message: message.into(),
+            primary: true,
+        }
+    }
+
+    /// A label giving context to the problem.
+    pub fn secondary(span: &Span, message: impl Into<String>) -> Self {
+        Self {
+            primary: false,
+            ..Self::primary(span, message)
+        }
+    }
+
+    /// A primary label pointing at a single position.
+    pub fn at(line_col: LineCol, message: impl Into<String>) -> Self {
+        Self {
+            start: line_col,
+            end: line_col,
+            message: message.into(),
+            primary: true,
+        }
+    }
+}
+
+/// The output format of [Diagnostic::render].
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Default)]
+pub enum RenderStyle {
+    #[default]
+    Plain,
+    /// Plain text, coloured with ANSI escape codes for a terminal.
+    Ansi,
+}
+
+/// A problem found in CAOS source, ready to be shown to a user.
+///
+/// A diagnostic is rendered either as human readable text, in the style of
+/// `rustc`, which quotes the source lines its labels point at:
+///
+/// ```text
+/// error[E0002]: Unknown token `blah`
+///  --> example.cos:3:1
+///   |
+/// 3 | blah
+///   | ^^^^
+/// ```
+///
+/// or as a single line of JSON with [Diagnostic::to_json].
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// A stable identifier for the kind of problem.
+    pub code: Option<String>,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            code: None,
+            message: message.into(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: Vec::new(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, message)
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, message)
+    }
+
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    pub fn with_label(mut self, label: Label) -> Self {
+        self.labels.push(label);
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help.push(help.into());
+        self
+    }
+
+    /// The label the diagnostic is reported at: its first primary label, or
+    /// failing that its first label.
+    pub fn location(&self) -> Option<&Label> {
+        self.labels
+            .iter()
+            .find(|l| l.primary)
+            .or_else(|| self.labels.first())
+    }
+
+    /// Renders the diagnostic as text, quoting the lines of `source` its
+    /// labels point at. `file_name` is used in the location line.
+    pub fn render(&self, source: &str, file_name: &str, style: RenderStyle) -> String {
+        let mut out = String::new();
+        Renderer {
+            out: &mut out,
+            style,
+            severity: self.severity,
+        }
+        .render(self, source, file_name)
+        .expect("Writing to a String cannot fail");
+        out
+    }
+
+    /// Renders the diagnostic as a single line JSON object, suitable for
+    /// annotating CI results:
+    ///
+    /// ```json
+    /// {"severity":"error","code":null,"message":"...","file":"a.cos","line":3,"column":1,
+    ///  "end_line":3,"end_column":5,"labels":[...],"notes":[],"help":[]}
+    /// ```
+    ///
+    /// Labels have the same `line`, `column`, `end_line` and `end_column`
+    /// fields, along with `message` and `primary`.
+    pub fn to_json(&self, file_name: &str) -> String {
+        let mut out = String::from("{");
+        out.push_str("\"severity\":");
+        json_string(&mut out, self.severity.as_str());
+        out.push_str(",\"code\":");
+        match &self.code {
+            Some(code) => json_string(&mut out, code),
+            None => out.push_str("null"),
+        }
+        out.push_str(",\"message\":");
+        json_string(&mut out, &self.message);
+        out.push_str(",\"file\":");
+        json_string(&mut out, file_name);
+        match self.location() {
+            Some(label) => json_position(&mut out, label),
+            None => {
+                out.push_str(",\"line\":null,\"column\":null,\"end_line\":null,\"end_column\":null")
+            }
+        }
+        out.push_str(",\"labels\":[");
+        for (i, label) in self.labels.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str("{\"message\":");
+            json_string(&mut out, &label.message);
+            out.push_str(if label.primary {
+                ",\"primary\":true"
+            } else {
+                ",\"primary\":false"
+            });
+            json_position(&mut out, label);
+            out.push('}');
+        }
+        out.push_str("],\"notes\":");
+        json_strings(&mut out, &self.notes);
+        out.push_str(",\"help\":");
+        json_strings(&mut out, &self.help);
+        out.push('}');
+        out
+    }
+}
+
+impl From<&CaosError> for Diagnostic {
+    fn from(e: &CaosError) -> Self {
+        let mut diagnostic = Diagnostic::error(e.message()).with_code(e.code());
+        if let Some(suggestion) = e.suggestion() {
+            diagnostic = diagnostic.with_help(format!("did you mean `{}`?", suggestion));
+        }
+        match (e.error_type(), e.span()) {
+            (
+                ErrorType::UnbalancedBlock {
+                    open_block: Some(open_block),
+                    ..
+                },
+                Some(span),
+            ) => diagnostic
+                .with_label(Label::primary(
+                    span,
+                    format!("expected {}", open_block.closers_text()),
+                ))
+                .with_label(Label::secondary(
+                    &open_block.span,
+                    format!("`{}` opened here", open_block.keyword),
+                )),
+            (ErrorType::UnclosedBlock { open_blocks }, _) => {
+                open_blocks
+                    .iter()
+                    .enumerate()
+                    .fold(diagnostic, |diagnostic, (i, open_block)| {
+                        let message = format!(
+                            "`{}` is never closed by {}",
+                            open_block.keyword,
+                            open_block.closers_text()
+                        );
+                        diagnostic.with_label(match i {
+                            0 => Label::primary(&open_block.span, message),
+                            _ => Label::secondary(&open_block.span, message),
+                        })
+                    })
+            }
+            (_, Some(span)) => diagnostic.with_label(Label::primary(span, "")),
+            (_, None) => diagnostic,
+        }
+    }
+}
+
+impl From<CaosError> for Diagnostic {
+    fn from(e: CaosError) -> Self {
+        Diagnostic::from(&e)
+    }
+}
+
+const TAB_WIDTH: usize = 4;
+
+struct Renderer<'a> {
+    out: &'a mut String,
+    style: RenderStyle,
+    severity: Severity,
+}
+
+impl Renderer<'_> {
+    fn paint(&mut self, code: &str, text: &str) -> std::fmt::Result {
+        match self.style {
+            RenderStyle::Plain => self.out.write_str(text),
+            RenderStyle::Ansi => write!(self.out, "\x1b[{}m{}\x1b[0m", code, text),
+        }
+    }
+
+    fn severity_color(&self, severity: Severity) -> &'static str {
+        match severity {
+            Severity::Error => "1;31",
+            Severity::Warning => "1;33",
+            Severity::Note => "1;32",
+        }
+    }
+
+    fn gutter(&mut self, width: usize, text: &str) -> std::fmt::Result {
+        self.paint("1;34", &format!("{:>width$} |", text, width = width))
+    }
+
+    fn render(&mut self, d: &Diagnostic, source: &str, file_name: &str) -> std::fmt::Result {
+        let color = self.severity_color(d.severity);
+        let title = match &d.code {
+            Some(code) => format!("{}[{}]", d.severity.as_str(), code),
+            None => d.severity.as_str().to_owned(),
+        };
+        self.paint(color, &title)?;
+        self.paint("1", &format!(": {}", d.message))?;
+        self.out.push('\n');
+
+        let mut labels: Vec<&Label> = d.labels.iter().collect();
+        labels.sort_by_key(|l| (l.start, !l.primary));
+        let width = labels
+            .iter()
+            .map(|l| l.start.0.to_string().len())
+            .max()
+            .unwrap_or(0);
+
+        if let Some(location) = d.location() {
+            self.out.push_str(&" ".repeat(width));
+            self.paint("1;34", "--> ")?;
+            writeln!(
+                self.out,
+                "{}:{}:{}",
+                file_name, location.start.0, location.start.1
+            )?;
+        }
+
+        let lines: Vec<&str> = source.lines().collect();
+        if !labels.is_empty() {
+            self.gutter(width, "")?;
+            self.out.push('\n');
+        }
+        let mut last_line = None;
+        for label in &labels {
+            let line_number = label.start.0;
+            if last_line != Some(line_number) {
+                if let Some(last) = last_line {
+                    if line_number > last + 1 {
+                        self.paint("1;34", "...")?;
+                        self.out.push('\n');
+                    }
+                }
+                let text = lines
+                    .get(line_number.wrapping_sub(1))
+                    .copied()
+                    .unwrap_or("");
+                self.gutter(width, &line_number.to_string())?;
+                writeln!(self.out, " {}", expand_tabs(text))?;
+                last_line = Some(line_number);
+            }
+            let text = lines
+                .get(line_number.wrapping_sub(1))
+                .copied()
+                .unwrap_or("");
+            self.underline(width, text, label)?;
+        }
+
+        if !d.notes.is_empty() || !d.help.is_empty() {
+            if !labels.is_empty() {
+                self.gutter(width, "")?;
+                self.out.push('\n');
+            }
+            for note in &d.notes {
+                self.out.push_str(&" ".repeat(width + 1));
+                self.paint("1", "= note")?;
+                writeln!(self.out, ": {}", note)?;
+            }
+            for help in &d.help {
+                self.out.push_str(&" ".repeat(width + 1));
+                self.paint("1", "= help")?;
+                writeln!(self.out, ": {}", help)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the carets under the columns of `line` covered by `label`.
+    fn underline(&mut self, width: usize, line: &str, label: &Label) -> std::fmt::Result {
+        let start = display_column(line, label.start.1);
+        let end = if label.end.0 == label.start.0 {
+            display_column(line, label.end.1)
+        } else {
+            display_column(line, line.chars().count() + 1)
+        };
+        let (marker, color) = if label.primary {
+            ('^', self.severity_color(self.severity))
+        } else {
+            ('-', "1;34")
+        };
+        let marks: String = marker.to_string().repeat(end.saturating_sub(start).max(1));
+
+        self.gutter(width, "")?;
+        self.out.push_str(&" ".repeat(start + 1));
+        if label.message.is_empty() {
+            self.paint(color, &marks)?;
+        } else {
+            self.paint(color, &format!("{} {}", marks, label.message))?;
+        }
+        self.out.push('\n');
+        Ok(())
+    }
+}
+
+/// The display offset of the 1-based character `column` of `line`, with tabs expanded.
+fn display_column(line: &str, column: usize) -> usize {
+    let prefix = line.chars().take(column.saturating_sub(1));
+    let width: usize = prefix.map(|c| if c == '\t' { TAB_WIDTH } else { 1 }).sum();
+    width
+        + column
+            .saturating_sub(1)
+            .saturating_sub(line.chars().count())
+}
+
+fn expand_tabs(line: &str) -> String {
+    line.replace('\t', &" ".repeat(TAB_WIDTH))
+}
+
+/// Writes `s` as a JSON string, quoted and escaped. Used for all of the JSON
+/// the crate writes.
+pub(crate) fn json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn json_strings(out: &mut String, strings: &[String]) {
+    out.push('[');
+    for (i, s) in strings.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        json_string(out, s);
+    }
+    out.push(']');
+}
+
+fn json_position(out: &mut String, label: &Label) {
+    let _ = write!(
+        out,
+        ",\"line\":{},\"column\":{},\"end_line\":{},\"end_column\":{}",
+        label.start.0, label.start.1, label.end.0, label.end.1
+    );
+}
@@ -0,0 +1,161 @@
+use super::*;
+use crate::parse_cos_recovering;
+
+const SOURCE: &str = "econ targ\n\tnohh\n\tover\n\tkill targ\nrepe";
+
+fn span(start: LineCol, end: LineCol) -> Span {
+    Span {
+        start: 0,
+        end: 0,
+        start_line_col: start,
+        end_line_col: end,
+    }
+}
+
+fn block_diagnostic() -> Diagnostic {
+    Diagnostic::error("`REPE` does not close `ECON`")
+        .with_code("E0001")
+        .with_label(Label::primary(&span((5, 1), (5, 5)), "expected `NEXT`"))
+        .with_label(Label::secondary(
+            &span((1, 1), (1, 5)),
+            "`ECON` opened here",
+        ))
+        .with_note("`REPE` closes a `REPS` block")
+        .with_help("replace `REPE` with `NEXT`")
+}
+
+#[test]
+fn test_render_plain() {
+    assert_eq!(
+        block_diagnostic().render(SOURCE, "test.cos", RenderStyle::Plain),
+        "error[E0001]: `REPE` does not close `ECON`
+ --> test.cos:5:1
+  |
+1 | econ targ
+  | ---- `ECON` opened here
+...
+5 | repe
+  | ^^^^ expected `NEXT`
+  |
+  = note: `REPE` closes a `REPS` block
+  = help: replace `REPE` with `NEXT`
+"
+    );
+}
+
+#[test]
+fn test_render_expands_tabs() {
+    let d = Diagnostic::warning("Odd")
+        .with_label(Label::primary(&span((4, 7), (4, 11)), ""))
+        .with_label(Label::secondary(&span((4, 2), (4, 6)), "command"));
+    assert_eq!(
+        d.render(SOURCE, "test.cos", RenderStyle::Plain),
+        "warning: Odd
+ --> test.cos:4:7
+  |
+4 |     kill targ
+  |     ---- command
+  |          ^^^^
+"
+    );
+}
+
+#[test]
+fn test_render_without_labels() {
+    let d = Diagnostic::error("Stream ended unexpectedly").with_note("at the end of the file");
+    assert_eq!(
+        d.render(SOURCE, "test.cos", RenderStyle::Plain),
+        "error: Stream ended unexpectedly\n = note: at the end of the file\n"
+    );
+}
+
+#[test]
+fn test_render_ansi() {
+    let rendered = block_diagnostic().render(SOURCE, "test.cos", RenderStyle::Ansi);
+    assert!(rendered.starts_with("\x1b[1;31merror[E0001]\x1b[0m"));
+    assert!(rendered.contains("\x1b[1;31m^^^^ expected `NEXT`\x1b[0m"));
+    assert!(rendered.contains("\x1b[1;34m---- `ECON` opened here\x1b[0m"));
+}
+
+#[test]
+fn test_json() {
+    let d = Diagnostic::error("Unexpected \"x\"")
+        .with_label(Label::primary(&span((2, 3), (2, 4)), ""))
+        .with_help("a\tb");
+    assert_eq!(
+        d.to_json("dir\\a.cos"),
+        r#"{"severity":"error","code":null,"message":"Unexpected \"x\"","file":"dir\\a.cos","line":2,"column":3,"end_line":2,"end_column":4,"labels":[{"message":"","primary":true,"line":2,"column":3,"end_line":2,"end_column":4}],"notes":[],"help":["a\tb"]}"#
+    );
+}
+
+#[test]
+fn test_from_caos_error() {
+    let source = "inst\n  blah\nendm";
+    let parse = parse_cos_recovering(source);
+    let d = Diagnostic::from(&parse.errors[0]);
+    assert_eq!(
+        d.render(source, "test.cos", RenderStyle::Plain),
+        "error[E0002]: Unknown token `blah`
+ --> test.cos:2:3
+  |
+2 |   blah
+  |   ^^^^
+"
+    );
+}
+
+#[test]
+fn test_from_unbalanced_block() {
+    let source = "inst\nenum 1 2 3\n  kill targ\nrepe\nendm";
+    let parse = parse_cos_recovering(source);
+    let error = parse.errors.iter().find(|e| e.code() == "E0201").unwrap();
+    let d = Diagnostic::from(error);
+    assert_eq!(
+        d.render(source, "test.cos", RenderStyle::Plain),
+        "error[E0201]: `REPE` found but `ENUM` at 2:1 expects `NEXT`
+ --> test.cos:4:1
+  |
+2 | enum 1 2 3
+  | ---------- `ENUM` opened here
+...
+4 | repe
+  | ^^^^ expected `NEXT`
+"
+    );
+}
+
+#[test]
+fn test_from_unclosed_blocks() {
+    let source = "inst\nreps 3\n  loop\n    nohh\nendm";
+    let parse = parse_cos_recovering(source);
+    let d = Diagnostic::from(&parse.errors[0]);
+    assert_eq!(
+        d.render(source, "test.cos", RenderStyle::Plain),
+        "error[E0202]: Script ended with 2 unclosed blocks: `LOOP` at 3:3 expects `EVER` or `UNTL`, `REPS` at 2:1 expects `REPE`
+ --> test.cos:3:3
+  |
+2 | reps 3
+  | ------ `REPS` is never closed by `REPE`
+3 |   loop
+  |   ^^^^ `LOOP` is never closed by `EVER` or `UNTL`
+"
+    );
+}
+
+#[test]
+fn test_from_error_with_suggestion() {
+    let source = "inst\nenmu 1 2 3 next\nendm";
+    let parse = parse_cos_recovering(source);
+    let d = Diagnostic::from(&parse.errors[0]);
+    assert_eq!(
+        d.render(source, "test.cos", RenderStyle::Plain),
+        "error[E0002]: Unknown token `enmu`
+ --> test.cos:2:1
+  |
+2 | enmu 1 2 3 next
+  | ^^^^
+  |
+  = help: did you mean `ENUM`?
+"
+    );
+}
@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod tests;
+
+use crate::docs::{self, Engine};
+use crate::signature::Signature;
+use std::fmt;
+use std::str::FromStr;
+
+/// The version of CAOS a script is written in, which depends on the engine
+/// that runs it.
+///
+/// The grammar covers Creatures 3 and Docking Station, which share a syntax.
+/// Docking Station adds keywords to Creatures 3, such as the `NET:` family,
+/// and [Dialect::has] tells which a keyword belongs to. Creatures 1 and 2 have
+/// their own vocabulary, which the grammar does not cover.
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy, Default)]
+pub enum Dialect {
+    Creatures1,
+    /// Parsed with [c2::parse_c2](crate::c2::parse_c2) instead of the grammar,
+    /// and migrated to Creatures 3 with [c2::migrate](crate::c2::migrate).
+    Creatures2,
+    Creatures3,
+    #[default]
+    DockingStation,
+}
+
+impl Dialect {
+    pub const ALL: [Dialect; 4] = [
+        Dialect::Creatures1,
+        Dialect::Creatures2,
+        Dialect::Creatures3,
+        Dialect::DockingStation,
+    ];
+
+    /// The short name of the dialect, such as `c3` or `ds`, as accepted by
+    /// [Dialect::from_str].
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dialect::Creatures1 => "c1",
+            Dialect::Creatures2 => "c2",
+            Dialect::Creatures3 => "c3",
+            Dialect::DockingStation => "ds",
+        }
+    }
+
+    /// The engine documented as running the dialect, if it is one the
+    /// grammar covers.
+    pub fn engine(&self) -> Option<Engine> {
+        match self {
+            Dialect::Creatures1 | Dialect::Creatures2 => None,
+            Dialect::Creatures3 => Some(Engine::C3),
+            Dialect::DockingStation => Some(Engine::DockingStation),
+        }
+    }
+
+    /// Returns `true` if source in the dialect can be parsed with
+    /// [parse_cos](crate::parse_cos).
+    pub fn is_supported(&self) -> bool {
+        self.engine().is_some()
+    }
+
+    /// Returns `true` if the keyword of `signature` is part of the dialect,
+    /// according to the engines given in its [documentation](docs). Every
+    /// keyword in the catalog is documented, so a signature from elsewhere is
+    /// never part of a dialect.
+    pub fn has(&self, signature: &Signature) -> bool {
+        match (self.engine(), docs::by_signature(signature)) {
+            (Some(engine), Some(doc)) => doc.engines.contains(&engine),
+            (_, None) | (None, _) => false,
+        }
+    }
+}
+
+impl fmt::Display for Dialect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Dialect::Creatures1 => write!(f, "Creatures 1"),
+            Dialect::Creatures2 => write!(f, "Creatures 2"),
+            Dialect::Creatures3 => write!(f, "Creatures 3"),
+            Dialect::DockingStation => write!(f, "Docking Station"),
+        }
+    }
+}
+
+impl FromStr for Dialect {
+    type Err = String;
+
+    /// Reads a dialect from its [short name](Dialect::name), ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Dialect::ALL
+            .into_iter()
+            .find(|d| d.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("Unknown dialect '{}', expected c1, c2, c3 or ds", s))
+    }
+}
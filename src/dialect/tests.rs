@@ -0,0 +1,108 @@
+use super::*;
+use crate::{
+    check_dialect, parse_cos, parse_cos_recovering, parse_cos_with_dialect, signature, ErrorType,
+};
+
+#[test]
+fn test_from_str() {
+    assert_eq!("C3".parse(), Ok(Dialect::Creatures3));
+    assert_eq!("ds".parse(), Ok(Dialect::DockingStation));
+    for dialect in Dialect::ALL {
+        assert_eq!(dialect.name().parse(), Ok(dialect));
+    }
+    assert!("c4".parse::<Dialect>().is_err());
+}
+
+#[test]
+fn test_has() {
+    let net_writ = signature::lookup("NET: WRIT")[0];
+    assert!(Dialect::DockingStation.has(net_writ));
+    assert!(!Dialect::Creatures3.has(net_writ));
+
+    let setv = signature::lookup("SETV")[0];
+    assert!(Dialect::Creatures3.has(setv));
+    assert!(Dialect::DockingStation.has(setv));
+    assert!(!Dialect::Creatures2.has(setv));
+
+    for keyword in ["TINO", "CABV"] {
+        let signature = signature::lookup(keyword)[0];
+        assert!(Dialect::DockingStation.has(signature), "{}", keyword);
+        assert!(!Dialect::Creatures3.has(signature), "{}", keyword);
+    }
+}
+
+#[test]
+fn test_check_dialect_outside_the_net_family() {
+    let source = "tino 255 0 0 128 128\ncabv -1\nsetv va00 cabv";
+    assert!(check_dialect(&parse_cos_recovering(source), Dialect::DockingStation).is_empty());
+
+    let found: Vec<_> = check_dialect(&parse_cos_recovering(source), Dialect::Creatures3)
+        .iter()
+        .map(|e| (e.span().unwrap().as_str(source), e.line_col().unwrap()))
+        .collect();
+    assert_eq!(
+        found,
+        [("tino", (1, 1)), ("cabv", (2, 1)), ("cabv", (3, 11))]
+    );
+}
+
+#[test]
+fn test_check_dialect() {
+    let source = "inst\nnet: line 1\ndoif net: line eq 1\n  setv va00 oowwx\nendi";
+    assert!(check_dialect(&parse_cos_recovering(source), Dialect::DockingStation).is_empty());
+
+    let errors = check_dialect(&parse_cos_recovering(source), Dialect::Creatures3);
+    let found: Vec<_> = errors
+        .iter()
+        .map(|e| (e.span().unwrap().as_str(source), e.line_col().unwrap()))
+        .collect();
+    // The unknown word is left to the parser to report.
+    assert_eq!(found, [("net: line", (2, 1)), ("net: line", (3, 6))]);
+    assert_eq!(errors[0].code(), "E0301");
+    assert_eq!(
+        errors[0].message(),
+        "`NET: LINE` is not available in Creatures 3"
+    );
+}
+
+#[test]
+fn test_check_dialect_where_parsing_failed() {
+    let source = "scrp 1 2\nnet: line 1\nendm\nnet: unik 1 va00\nsetv va00";
+    let parse = parse_cos_recovering(source);
+    assert!(!parse.is_ok());
+    let found: Vec<_> = check_dialect(&parse, Dialect::Creatures3)
+        .iter()
+        .map(|e| e.span().unwrap().as_str(source))
+        .collect();
+    assert_eq!(found, ["net: line", "net: unik"]);
+}
+
+#[test]
+fn test_parse_cos_with_dialect() {
+    let source = "scrp 1 2 3 4 net: writ \"user\" \"chat\" 1000 0 0 endm";
+    assert_eq!(
+        parse_cos_with_dialect(source, Dialect::DockingStation).unwrap(),
+        parse_cos(source).unwrap()
+    );
+    let e = parse_cos_with_dialect(source, Dialect::Creatures3).unwrap_err();
+    assert_eq!(
+        e.error_type(),
+        &ErrorType::UnavailableKeyword {
+            keyword: String::from("NET: WRIT"),
+            dialect: Dialect::Creatures3,
+        }
+    );
+}
+
+#[test]
+fn test_unsupported_dialect() {
+    let e = parse_cos_with_dialect("setv va00 1", Dialect::Creatures2).unwrap_err();
+    assert_eq!(e.code(), "E0302");
+    assert_eq!(
+        e.message(),
+        "Creatures 2 CAOS must be migrated to Creatures 3 before it can be parsed"
+    );
+    let errors = check_dialect(&parse_cos_recovering("setv va00 1"), Dialect::Creatures1);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message(), "Creatures 1 CAOS cannot be parsed");
+}
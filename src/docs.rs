@@ -0,0 +1,282 @@
+//! Documentation for CAOS keywords, for hover text and help.
+//!
+//! Every [Signature] in the catalog has a [Doc], adding descriptions of the
+//! keyword and its arguments, what it returns and which engines have it, read
+//! from a data file bundled with the crate. Every keyword is documented in the
+//! data file, which says explicitly which keywords only one engine has.
+//!
+//! ```
+//! let doc = caos2::docs::lookup("NEW: SIMP")[0];
+//! assert_eq!(doc.args[0].name, "family");
+//! assert!(doc.description.as_deref().unwrap().starts_with("Creates"));
+//! ```
+
+#[cfg(test)]
+mod tests;
+
+use crate::signature::{self, ArgKind, ReturnType, Signature};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+
+/// The bundled documentation, in the format described at its top.
+const DATA: &str = include_str!("docs/keywords.txt");
+
+/// A game engine which runs CAOS.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Engine {
+    /// Creatures 3.
+    C3,
+    /// Docking Station.
+    DockingStation,
+}
+
+/// The documentation for a keyword.
+#[derive(Debug)]
+pub struct Doc {
+    pub signature: &'static Signature,
+    pub description: Option<String>,
+    pub args: Vec<ArgDoc>,
+    /// What an expression returns.
+    pub returns: Option<String>,
+    /// The engines with the keyword.
+    pub engines: Vec<Engine>,
+}
+
+/// The documentation for an argument of a keyword.
+#[derive(Debug)]
+pub struct ArgDoc {
+    pub name: &'static str,
+    pub kind: ArgKind,
+    pub description: Option<String>,
+}
+
+/// The documentation for `keyword`, ignoring case and spacing.
+///
+/// Keywords such as `TARG` are both a command and an expression, and so have
+/// more than one [Doc].
+pub fn lookup(keyword: &str) -> Vec<&'static Doc> {
+    signature::lookup(keyword)
+        .into_iter()
+        .filter_map(|s| by_signature(s))
+        .collect()
+}
+
+/// The documentation for a signature from the catalog.
+pub fn by_signature(signature: &Signature) -> Option<&'static Doc> {
+    docs().iter().find(|d| {
+        d.signature.returns == signature.returns && d.signature.variant == signature.variant
+    })
+}
+
+/// The documentation for every keyword, in the order of
+/// [signatures](signature::signatures).
+pub fn docs() -> &'static [Doc] {
+    static DOCS: OnceLock<Vec<Doc>> = OnceLock::new();
+    DOCS.get_or_init(|| {
+        let mut entries = parse_entries(DATA).expect("Bundled documentation is valid");
+        signature::signatures()
+            .map(|signature| {
+                let entry = entries
+                    .remove(&(signature.returns, signature.variant))
+                    .unwrap_or_default();
+                Doc::new(signature, entry)
+            })
+            .collect()
+    })
+}
+
+impl Doc {
+    fn new(signature: &'static Signature, mut entry: Entry) -> Self {
+        let args = signature
+            .args
+            .iter()
+            .map(|arg| ArgDoc {
+                name: arg.name,
+                kind: arg.kind,
+                description: entry.args.remove(arg.name),
+            })
+            .collect();
+        Doc {
+            signature,
+            description: entry.description,
+            args,
+            returns: entry.returns,
+            engines: entry
+                .engines
+                .unwrap_or_else(|| vec![Engine::C3, Engine::DockingStation]),
+        }
+    }
+}
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Engine::C3 => write!(f, "Creatures 3"),
+            Engine::DockingStation => write!(f, "Docking Station"),
+        }
+    }
+}
+
+impl fmt::Display for Doc {
+    /// Writes the documentation as Markdown, for hover text.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "`{}`", self.signature)?;
+        if let Some(description) = &self.description {
+            write!(f, "\n{}\n", description)?;
+        }
+        if !self.args.is_empty() {
+            writeln!(f)?;
+        }
+        for arg in &self.args {
+            write!(f, "* `{}` ({:?})", arg.name, arg.kind)?;
+            match &arg.description {
+                Some(description) => writeln!(f, ": {}", description)?,
+                None => writeln!(f)?,
+            }
+        }
+        if let Some(returns) = &self.returns {
+            write!(f, "\nReturns: {}\n", returns)?;
+        }
+        let engines: Vec<_> = self.engines.iter().map(|e| e.to_string()).collect();
+        write!(
+            f,
+            "\n{}, {}. {}.",
+            self.signature.category,
+            describe(self.signature.returns),
+            engines.join(", ")
+        )
+    }
+}
+
+/// What a keyword of the type is, as `command` or `integer expression`.
+fn describe(returns: ReturnType) -> &'static str {
+    match returns {
+        ReturnType::Command => "command",
+        ReturnType::Integer => "integer expression",
+        ReturnType::Float => "float expression",
+        ReturnType::SString => "string expression",
+        ReturnType::Agent => "agent expression",
+        ReturnType::Variable => "variable",
+    }
+}
+
+/// An entry of the data file.
+#[derive(Debug, Default)]
+struct Entry {
+    description: Option<String>,
+    args: HashMap<String, String>,
+    returns: Option<String>,
+    engines: Option<Vec<Engine>>,
+}
+
+/// The type and variant of the signature an entry documents.
+type EntryKey = (ReturnType, &'static str);
+
+/// Parses the data file, matching each entry to the signature it documents.
+///
+/// An entry matches the signature of the same type and keyword whose arguments
+/// include every argument it describes. Keywords such as `LOOP` have more than
+/// one signature of the same type, told apart by their arguments, so an entry
+/// without arguments documents the first.
+fn parse_entries(data: &str) -> Result<HashMap<EntryKey, Entry>, String> {
+    let mut entries = HashMap::new();
+    let mut current: Option<(usize, ReturnType, String, Entry)> = None;
+    for (i, line) in data.lines().enumerate() {
+        let line_no = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            if let Some(entry) = current.take() {
+                insert_entry(&mut entries, entry)?;
+            }
+            let (returns, keyword) = header
+                .split_once(' ')
+                .ok_or_else(|| format!("Line {}: expected `[<type> <KEYWORD>]`", line_no))?;
+            let returns = parse_return_type(returns)
+                .ok_or_else(|| format!("Line {}: unknown type `{}`", line_no, returns))?;
+            current = Some((
+                line_no,
+                returns,
+                keyword.trim().to_owned(),
+                Entry::default(),
+            ));
+            continue;
+        }
+
+        let Some((_, _, _, entry)) = current.as_mut() else {
+            return Err(format!("Line {}: text outside of an entry", line_no));
+        };
+        if let Some(arg) = line.strip_prefix('@') {
+            let (name, text) = arg.split_once(' ').unwrap_or((arg, ""));
+            entry.args.insert(name.to_owned(), text.trim().to_owned());
+        } else if let Some(returns) = line.strip_prefix("returns:") {
+            entry.returns = Some(returns.trim().to_owned());
+        } else if let Some(engines) = line.strip_prefix("engines:") {
+            let engines = engines
+                .split_whitespace()
+                .map(|e| match e {
+                    "C3" => Ok(Engine::C3),
+                    "DS" => Ok(Engine::DockingStation),
+                    e => Err(format!("Line {}: unknown engine `{}`", line_no, e)),
+                })
+                .collect::<Result<_, _>>()?;
+            entry.engines = Some(engines);
+        } else {
+            match &mut entry.description {
+                Some(description) => {
+                    description.push(' ');
+                    description.push_str(line);
+                }
+                None => entry.description = Some(line.to_owned()),
+            }
+        }
+    }
+    if let Some(entry) = current.take() {
+        insert_entry(&mut entries, entry)?;
+    }
+    Ok(entries)
+}
+
+fn insert_entry(
+    entries: &mut HashMap<EntryKey, Entry>,
+    (line_no, returns, keyword, entry): (usize, ReturnType, String, Entry),
+) -> Result<(), String> {
+    let signature = signature::lookup(&keyword)
+        .into_iter()
+        .filter(|s| s.returns == returns)
+        .find(|s| {
+            entry
+                .args
+                .keys()
+                .all(|name| s.args.iter().any(|a| a.name == name))
+        })
+        .ok_or_else(|| {
+            format!(
+                "Line {}: no {:?} `{}` with the arguments described",
+                line_no, returns, keyword
+            )
+        })?;
+    let key = (returns, signature.variant);
+    if entries.insert(key, entry).is_some() {
+        return Err(format!(
+            "Line {}: {:?} `{}` is documented twice",
+            line_no, returns, keyword
+        ));
+    }
+    Ok(())
+}
+
+fn parse_return_type(text: &str) -> Option<ReturnType> {
+    match text {
+        "Command" => Some(ReturnType::Command),
+        "Integer" => Some(ReturnType::Integer),
+        "Float" => Some(ReturnType::Float),
+        "SString" => Some(ReturnType::SString),
+        "Agent" => Some(ReturnType::Agent),
+        "Variable" => Some(ReturnType::Variable),
+        _ => None,
+    }
+}
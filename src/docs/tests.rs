@@ -0,0 +1,132 @@
+use super::*;
+
+#[test]
+fn test_bundled_docs_are_valid() {
+    parse_entries(DATA).unwrap();
+    assert_eq!(docs().len(), signature::signatures().count());
+}
+
+#[test]
+fn test_lookup() {
+    let docs = lookup("new:  simp");
+    assert_eq!(docs.len(), 1);
+    let doc = docs[0];
+    assert_eq!(doc.signature.keyword, "NEW: SIMP");
+    assert_eq!(
+        doc.description.as_deref(),
+        Some("Creates a simple agent and makes it the target.")
+    );
+    assert_eq!(doc.args[3].name, "sprite_file");
+    assert_eq!(doc.args[3].kind, ArgKind::SStringArg);
+    assert_eq!(
+        doc.args[3].description.as_deref(),
+        Some("The sprite file to draw the agent with, without its extension.")
+    );
+    assert_eq!(doc.engines, [Engine::C3, Engine::DockingStation]);
+}
+
+#[test]
+fn test_lookup_overloaded() {
+    let docs = lookup("TARG");
+    assert_eq!(docs.len(), 2);
+    assert!(docs
+        .iter()
+        .all(|d| d.description.is_some() || d.returns.is_some()));
+}
+
+#[test]
+fn test_variants_with_the_same_keyword() {
+    let docs = lookup("LOOP");
+    assert_eq!(docs.len(), 2);
+    assert!(docs[0].description.as_deref().unwrap().contains("EVER"));
+    assert!(docs[1].description.as_deref().unwrap().contains("UNTL"));
+    assert!(docs[1].args[0].description.is_some());
+}
+
+#[test]
+fn test_engines() {
+    assert_eq!(lookup("OOWW")[0].engines, [Engine::DockingStation]);
+}
+
+#[test]
+fn test_every_keyword_is_documented() {
+    let entries = parse_entries(DATA).unwrap();
+    for signature in signature::signatures() {
+        let key = (signature.returns, signature.variant);
+        assert!(
+            entries.contains_key(&key),
+            "{:?} `{}` has no documentation",
+            signature.returns,
+            signature.keyword
+        );
+    }
+    for doc in docs() {
+        assert!(
+            doc.description.is_some() || doc.returns.is_some(),
+            "`{}` has an empty entry",
+            doc.signature.keyword
+        );
+    }
+}
+
+#[test]
+fn test_docking_station_only_keywords() {
+    for keyword in ["TINO", "MIRA", "CABV", "CABP"] {
+        for doc in lookup(keyword) {
+            assert_eq!(doc.engines, [Engine::DockingStation], "{}", keyword);
+        }
+    }
+    assert_eq!(lookup("NWLD")[0].signature.category, "World");
+}
+
+#[test]
+fn test_display() {
+    let doc = lookup("KILL")[0];
+    assert_eq!(
+        doc.to_string(),
+        "`KILL agent (AgentArg)`
+
+Destroys an agent. Any variables referring to it become NULL.
+
+* `agent` (AgentArg): The agent to destroy.
+
+Agents, command. Creatures 3, Docking Station."
+    );
+
+    let doc = lookup("RAND")[0];
+    assert!(doc
+        .to_string()
+        .contains("Returns: A random integer between the two values, inclusive."));
+}
+
+#[test]
+fn test_parse_errors() {
+    let err = |data: &str| parse_entries(data).unwrap_err();
+    assert_eq!(err("[Thing KILL]"), "Line 1: unknown type `Thing`");
+    assert_eq!(err("Some text"), "Line 1: text outside of an entry");
+    assert_eq!(
+        err("[Command KILL]\n@target The agent."),
+        "Line 1: no Command `KILL` with the arguments described"
+    );
+    assert_eq!(
+        err("[Command KILL]\n[Command KILL]"),
+        "Line 2: Command `KILL` is documented twice"
+    );
+    assert_eq!(
+        err("[Command KILL]\nengines: C2"),
+        "Line 2: unknown engine `C2`"
+    );
+}
+
+#[test]
+fn test_net_family_is_docking_station_only() {
+    let net: Vec<_> = docs()
+        .iter()
+        .filter(|d| d.signature.keyword.starts_with("NET: "))
+        .collect();
+    assert_eq!(net.len(), 23);
+    for doc in net {
+        assert_eq!(doc.engines, [Engine::DockingStation]);
+        assert_eq!(doc.signature.category, "Net");
+    }
+}
@@ -0,0 +1,109 @@
+#[cfg(test)]
+mod tests;
+
+use crate::{CaosError, ErrorType, Span};
+use std::fmt;
+use std::str::FromStr;
+
+/// A single byte text encoding, as used by CAOS files and the engines reading
+/// them.
+///
+/// The engines read and write Windows-1252, which agrees with Latin-1 other
+/// than in the bytes `0x80` to `0x9F`, where Windows-1252 has characters such
+/// as `€` and Latin-1 has control characters.
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy, Default)]
+pub enum Encoding {
+    #[default]
+    Windows1252,
+    Latin1,
+}
+
+/// The characters of Windows-1252 from `0x80` to `0x9F`. The five bytes it
+/// leaves undefined decode to the control characters of the same value, as
+/// in Latin-1, so that every byte survives a round trip.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž', '\u{8F}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}', 'ž', 'Ÿ',
+];
+
+impl Encoding {
+    pub const ALL: [Encoding; 2] = [Encoding::Windows1252, Encoding::Latin1];
+
+    /// The short name of the encoding, `cp1252` or `latin1`, as accepted by
+    /// [Encoding::from_str].
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Windows1252 => "cp1252",
+            Encoding::Latin1 => "latin1",
+        }
+    }
+
+    /// The character a byte stands for. Every byte is a character in both
+    /// encodings, so decoding cannot fail.
+    pub fn char(&self, byte: u8) -> char {
+        match (self, byte) {
+            (Encoding::Windows1252, 0x80..=0x9F) => WINDOWS_1252_HIGH[usize::from(byte - 0x80)],
+            _ => char::from(byte),
+        }
+    }
+
+    /// The byte standing for `c`, if the encoding has it.
+    pub fn byte(&self, c: char) -> Option<u8> {
+        if *self == Encoding::Latin1 {
+            return u8::try_from(c).ok();
+        }
+        match WINDOWS_1252_HIGH.iter().position(|&high| high == c) {
+            Some(i) => Some(0x80 + i as u8),
+            None if ('\u{80}'..='\u{9F}').contains(&c) => None,
+            None => u8::try_from(c).ok(),
+        }
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        bytes.iter().map(|&b| self.char(b)).collect()
+    }
+
+    /// Encodes `text`, failing at the first character the encoding does not
+    /// have. The error's span is within `text`.
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>, CaosError> {
+        text.char_indices()
+            .map(|(i, c)| {
+                self.byte(c).ok_or_else(|| {
+                    let span = pest::Span::new(text, i, i + c.len_utf8())
+                        .expect("Character is within the text");
+                    let error_type = ErrorType::Unencodable {
+                        character: c,
+                        encoding: *self,
+                    };
+                    CaosError::new(error_type, Some(Span::from(span)))
+                })
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Encoding::Windows1252 => write!(f, "Windows-1252"),
+            Encoding::Latin1 => write!(f, "Latin-1"),
+        }
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = String;
+
+    /// Reads an encoding from its [short name](Encoding::name) or its full
+    /// name, such as `windows-1252` or `iso-8859-1`, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "cp1252" | "windows-1252" => Ok(Encoding::Windows1252),
+            "latin1" | "latin-1" | "iso-8859-1" => Ok(Encoding::Latin1),
+            _ => Err(format!(
+                "Unknown encoding '{}', expected cp1252 or latin1",
+                s
+            )),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use super::*;
+use crate::{parse_cos_bytes, write_cos_bytes};
+
+#[test]
+fn test_from_str() {
+    assert_eq!("CP1252".parse(), Ok(Encoding::Windows1252));
+    assert_eq!("iso-8859-1".parse(), Ok(Encoding::Latin1));
+    for encoding in Encoding::ALL {
+        assert_eq!(encoding.name().parse(), Ok(encoding));
+    }
+    assert!("utf-8".parse::<Encoding>().is_err());
+}
+
+#[test]
+fn test_every_byte_round_trips() {
+    let bytes: Vec<u8> = (0..=255).collect();
+    for encoding in Encoding::ALL {
+        let text = encoding.decode(&bytes);
+        assert_eq!(text.chars().count(), 256);
+        assert_eq!(encoding.encode(&text).unwrap(), bytes);
+    }
+}
+
+#[test]
+fn test_decode() {
+    let bytes = b"caf\xE9 \x80\x93 \x9D";
+    assert_eq!(Encoding::Windows1252.decode(bytes), "café €“ \u{9D}");
+    assert_eq!(Encoding::Latin1.decode(bytes), "café \u{80}\u{93} \u{9D}");
+}
+
+#[test]
+fn test_encode_error() {
+    let error = Encoding::Latin1.encode("Zoë’s").unwrap_err();
+    assert_eq!(error.code(), "E0009");
+    assert_eq!(error.message(), "`’` cannot be written in Latin-1");
+    assert_eq!(error.span().unwrap().as_str("Zoë’s"), "’");
+
+    // Windows-1252 has no character at 0x80 to stand for U+0080.
+    assert!(Encoding::Windows1252.encode("\u{80}").is_err());
+    assert_eq!(Encoding::Windows1252.encode("’").unwrap(), [0x92]);
+}
+
+#[test]
+fn test_parse_and_write_cos_bytes() {
+    let source = b"inst\nsetv va00 '\xE9'\nsets va01 \"Zo\xEB\x92s \x80\"\n";
+    let cos_file = parse_cos_bytes(source, Encoding::Windows1252).unwrap();
+    let expected = crate::parse_cos("inst\nsetv va00 233\nsets va01 \"Zoë’s €\"").unwrap();
+    assert_eq!(cos_file, expected);
+
+    let written = write_cos_bytes(&cos_file, Encoding::Windows1252).unwrap();
+    assert!(written.windows(9).any(|w| w == b"\"Zo\xEB\x92s \x80\""));
+    assert_eq!(
+        parse_cos_bytes(&written, Encoding::Windows1252).unwrap(),
+        cos_file
+    );
+
+    let latin1 = parse_cos_bytes(
+        b"inst\nsetv va00 '\x80'\nsetv va01 '\xE9'\n",
+        Encoding::Latin1,
+    );
+    assert_eq!(
+        latin1.unwrap(),
+        crate::parse_cos("inst\nsetv va00 128\nsetv va01 233").unwrap()
+    );
+
+    let error = write_cos_bytes(&cos_file, Encoding::Latin1).unwrap_err();
+    assert_eq!(
+        *error.error_type(),
+        ErrorType::Unencodable {
+            character: '’',
+            encoding: Encoding::Latin1,
+        }
+    );
+}
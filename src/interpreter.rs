@@ -0,0 +1,478 @@
+//! A headless interpreter, for running scripts without the game.
+//!
+//! Scripts are compiled to a [Program] and run by a [Thread] against the
+//! [World] held by an [Interpreter]. The interpreter covers variables,
+//! strings, arithmetic and flow control, converting between integers and
+//! floats as the engine does: a float given for an integer is rounded to the
+//! nearest integer, and arithmetic on an integer and a float gives a float.
+//!
+//! The world is a model of the game's agents, with their classifiers,
+//! positions, sizes and attributes but no physics or sprites, which scripts
+//! can create, find, move and kill. [Interpreter::load] runs the install
+//! scripts of a CAOS file and keeps its event scripts for
+//! [Interpreter::run_event], so tests can check the agents a file makes.
+//!
+//! Time passes only when [Interpreter::tick] is called, which delivers
+//! messages, runs timer scripts and gives each running script its share of
+//! the tick, as the engine does. A [Debugger] instead runs scripts a command
+//! at a time, stopping at breakpoints so they can be inspected.
+//! Anything else, such as a command which needs the game, stops the script
+//! with an [ErrorType::Unsupported] error.
+//!
+//! ```
+//! use caos2::interpreter::{Interpreter, Value};
+//!
+//! let script = caos2::parse_script_body_str("setv va00 7 mulv va00 6 sets va01 vtos va00").unwrap();
+//! let mut interpreter = Interpreter::new();
+//! let thread = interpreter.run(&script).unwrap();
+//! assert_eq!(thread.vaxx(1), &Value::from("42"));
+//!
+//! interpreter
+//!     .load("new: simp 2 11 100 \"ball\" 1 0 500 scrp 2 11 100 1 kill ownr endm")
+//!     .unwrap();
+//! let ball = interpreter.world.matching(2, 11, 100).next().unwrap();
+//! interpreter.run_event(ball, 1).unwrap();
+//! assert_eq!(interpreter.world.agents().count(), 0);
+//! ```
+
+#[cfg(test)]
+mod tests;
+
+mod debugger;
+mod eval;
+mod exec;
+mod program;
+mod scheduler;
+mod value;
+mod world;
+
+pub use debugger::{Breakpoint, Debugger, Frame, StopReason};
+pub use program::Program;
+pub use scheduler::{messages, Message, SLOW_INSTRUCTIONS_PER_TICK, TIMER_EVENT};
+pub use value::{AgentId, Value};
+pub use world::{AgentKind, AgentState, World};
+
+use crate::ast::{CosFile, Script, ScriptDefinition};
+use crate::{parse_cos_with_spans, CaosError, ErrorType, SpanNode};
+use program::Op;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+/// The number of `VAxx` variables each script has.
+const SCRIPT_VARIABLES: usize = 100;
+
+/// The number of instructions [Interpreter::run_thread] runs before deciding
+/// a script will never finish.
+pub const DEFAULT_INSTRUCTION_LIMIT: usize = 1_000_000;
+
+/// Runs scripts against a [World].
+#[derive(Debug, Clone)]
+pub struct Interpreter {
+    pub world: World,
+    /// The number of instructions [Interpreter::run_thread] runs before
+    /// stopping the script with an [ErrorType::InstructionLimit] error.
+    pub instruction_limit: usize,
+    output: String,
+    debug_log: Vec<String>,
+    rng: Rng,
+    /// The event scripts loaded, by family, genus, species and event number.
+    scripts: HashMap<[i32; 4], Arc<Program>>,
+    ticks: u32,
+    /// The messages waiting to be delivered, in the order they were sent.
+    messages: Vec<Message>,
+    /// The script each agent is running.
+    threads: BTreeMap<AgentId, Thread>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self {
+            world: World::default(),
+            instruction_limit: DEFAULT_INSTRUCTION_LIMIT,
+            output: String::new(),
+            debug_log: Vec::new(),
+            rng: Rng::new(0),
+            scripts: HashMap::new(),
+            ticks: 0,
+            messages: Vec::new(),
+            threads: BTreeMap::new(),
+        }
+    }
+}
+
+/// A script being run: its [Program], where it is up to and the variables
+/// it has of its own.
+#[derive(Debug, Clone)]
+pub struct Thread {
+    program: Arc<Program>,
+    pc: usize,
+    vaxx: Vec<Value>,
+    /// The instructions to return to from each `GSUB`, innermost last.
+    call_stack: Vec<usize>,
+    /// The iterations left in each `REPS` loop, innermost last.
+    reps: Vec<i32>,
+    /// The agents left to visit in each enumeration, such as `ENUM`,
+    /// innermost last, with `TARG` from before the loop.
+    enums: Vec<(Vec<AgentId>, Option<AgentId>)>,
+    /// The agent running the script, as given by `OWNR`.
+    pub owner: Option<AgentId>,
+    /// The agent acted on, as given by `TARG`, which starts as the owner.
+    pub targ: Option<AgentId>,
+    /// The agent the script was started by, as given by `FROM`.
+    pub from: Option<AgentId>,
+    /// The agent the owner is looking at, as given by `_IT_`.
+    pub it: Option<AgentId>,
+    /// The parameters given to the script, as `_P1_` and `_P2_`.
+    pub p1: Value,
+    pub p2: Value,
+    /// Set by `INST` and cleared by `SLOW`.
+    pub inst: bool,
+    /// Set by `LOCK` and cleared by `UNLK`.
+    pub lock: bool,
+    /// The ticks left to wait, as set by `WAIT`.
+    waiting: i32,
+    finished: bool,
+}
+
+/// What happened when a [Thread] ran an instruction.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Step {
+    /// There are more instructions to run.
+    Continue,
+    /// The script ran `WAIT`, and has more instructions to run once the
+    /// wait is over.
+    Waiting,
+    /// The script reached its end or `STOP`.
+    Finished,
+}
+
+impl Thread {
+    /// A thread at the start of `program`, run by `owner`.
+    pub fn new(program: Arc<Program>, owner: Option<AgentId>) -> Self {
+        Self {
+            program,
+            pc: 0,
+            vaxx: vec![Value::default(); SCRIPT_VARIABLES],
+            call_stack: Vec::new(),
+            reps: Vec::new(),
+            enums: Vec::new(),
+            owner,
+            targ: owner,
+            from: None,
+            it: None,
+            p1: Value::default(),
+            p2: Value::default(),
+            inst: false,
+            lock: false,
+            waiting: 0,
+            finished: false,
+        }
+    }
+
+    pub fn program(&self) -> &Arc<Program> {
+        &self.program
+    }
+
+    /// The instruction to be run next.
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    /// The `VAxx` variable numbered `index`.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is 100 or more.
+    pub fn vaxx(&self, index: usize) -> &Value {
+        &self.vaxx[index]
+    }
+
+    /// The instructions `GSUB` will return to, innermost last.
+    pub fn call_stack(&self) -> &[usize] {
+        &self.call_stack
+    }
+
+    /// The ticks left before the thread carries on from a `WAIT`.
+    pub fn waiting(&self) -> i32 {
+        self.waiting
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Seeds the generator behind `RAND`, which otherwise starts from the same
+    /// seed for every interpreter, so runs are repeatable.
+    pub fn seed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+
+    /// Everything written by `OUTS`, `OUTV` and `OUTX`.
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+
+    /// Returns everything written so far, clearing the output.
+    pub fn take_output(&mut self) -> String {
+        std::mem::take(&mut self.output)
+    }
+
+    /// The lines written by `DBG: OUTS` and `DBG: OUTV`.
+    pub fn debug_log(&self) -> &[String] {
+        &self.debug_log
+    }
+
+    /// Compiles and runs the commands of a script with no owner, returning
+    /// the finished thread.
+    ///
+    /// The script is compiled without spans, so errors have no location. Use
+    /// [Interpreter::load] for errors which point at the source.
+    pub fn run(&mut self, definition: &ScriptDefinition) -> Result<Thread, CaosError> {
+        let program = Arc::new(Program::compile(definition)?);
+        let mut thread = Thread::new(program, None);
+        self.run_thread(&mut thread)?;
+        Ok(thread)
+    }
+
+    /// Loads a CAOS file, keeping its event scripts for
+    /// [Interpreter::run_event] and then running its install scripts in
+    /// order. Event scripts replace any loaded before for the same agents and
+    /// event, and removal scripts are not run.
+    ///
+    /// Errors point at the source.
+    pub fn load(&mut self, source: &str) -> Result<(), CaosError> {
+        let (cos_file, spans) = parse_cos_with_spans(source)?;
+        self.load_cos_file(&cos_file, &spans)
+    }
+
+    /// Loads a parsed CAOS file as [Interpreter::load] does, given the span
+    /// node of the file from [parse_cos_with_spans].
+    pub fn load_cos_file(&mut self, cos_file: &CosFile, spans: &SpanNode) -> Result<(), CaosError> {
+        for program in self.add_scripts(cos_file, spans)? {
+            self.run_thread(&mut Thread::new(Arc::new(program), None))?;
+        }
+        Ok(())
+    }
+
+    /// Keeps the event scripts of a file, returning its install scripts
+    /// compiled but not run.
+    fn add_scripts(
+        &mut self,
+        cos_file: &CosFile,
+        spans: &SpanNode,
+    ) -> Result<Vec<Program>, CaosError> {
+        let mut install = Vec::new();
+        for (script, node) in cos_file.scripts.iter().zip(&spans.children) {
+            match script {
+                Script::Install(definition) => install.push((definition, node)),
+                Script::Removal(_) => {}
+                Script::Event(event) => {
+                    let program = Program::compile_with_spans(&event.definition, &node.children)?;
+                    let key = [
+                        event.family,
+                        event.genus,
+                        event.species,
+                        event.script_number,
+                    ];
+                    self.scripts.insert(key, Arc::new(program));
+                }
+            }
+        }
+        install
+            .into_iter()
+            .map(|(definition, node)| Program::compile_with_spans(definition, &node.children))
+            .collect()
+    }
+
+    /// A thread for an agent's script for an event, with the agent as
+    /// `OWNR`, or `None` if there is no such agent or script.
+    ///
+    /// As in the engine, a script for the agent's family, genus and species
+    /// is used if there is one, and otherwise one for its family and genus
+    /// with species 0, its family with genus 0 or for every agent.
+    pub fn event_thread(&self, agent: AgentId, event: i32) -> Option<Thread> {
+        let state = self.world.agent(agent)?;
+        let (f, g, s) = (state.family, state.genus, state.species);
+        let program = [[f, g, s], [f, g, 0], [f, 0, 0], [0, 0, 0]]
+            .into_iter()
+            .find_map(|[f, g, s]| self.scripts.get(&[f, g, s, event]))?;
+        Some(Thread::new(Arc::clone(program), Some(agent)))
+    }
+
+    /// Runs an agent's script for an event to the end, returning `false` if
+    /// there is no such script.
+    pub fn run_event(&mut self, agent: AgentId, event: i32) -> Result<bool, CaosError> {
+        let Some(mut thread) = self.event_thread(agent, event) else {
+            return Ok(false);
+        };
+        self.run_thread(&mut thread)?;
+        Ok(true)
+    }
+
+    /// Runs a thread until it finishes, or fails if it runs more than
+    /// [Interpreter::instruction_limit] instructions. No time passes, so
+    /// `WAIT` carries straight on.
+    pub fn run_thread(&mut self, thread: &mut Thread) -> Result<(), CaosError> {
+        for _ in 0..self.instruction_limit {
+            match self.step(thread)? {
+                Step::Finished => return Ok(()),
+                Step::Waiting => thread.waiting = 0,
+                Step::Continue => {}
+            }
+        }
+        Err(self.instruction_limit_error(thread))
+    }
+
+    fn instruction_limit_error(&self, thread: &Thread) -> CaosError {
+        let error_type = ErrorType::InstructionLimit {
+            limit: self.instruction_limit,
+        };
+        let error = CaosError::new(error_type, None);
+        match thread.program.span(thread.pc) {
+            Some(span) => error.with_span(span),
+            None => error,
+        }
+    }
+
+    /// Runs the next instruction of a thread.
+    ///
+    /// Errors point at the instruction's source if the program was compiled
+    /// with spans, and otherwise have no location. Either way they leave the
+    /// thread at the failing instruction.
+    pub fn step(&mut self, thread: &mut Thread) -> Result<Step, CaosError> {
+        if thread.finished {
+            return Ok(Step::Finished);
+        }
+        let program = Arc::clone(&thread.program);
+        let Some(op) = program.ops.get(thread.pc) else {
+            thread.finished = true;
+            return Ok(Step::Finished);
+        };
+        let next = self
+            .op(thread, op)
+            .map_err(|e| match program.span(thread.pc) {
+                Some(span) if e.span().is_none() => e.with_span(span),
+                _ => e,
+            })?;
+        match next {
+            Some(pc) => thread.pc = pc,
+            None => thread.finished = true,
+        }
+        Ok(if thread.finished {
+            Step::Finished
+        } else if thread.waiting > 0 {
+            Step::Waiting
+        } else {
+            Step::Continue
+        })
+    }
+
+    /// Runs an instruction, returning the instruction to run next or `None`
+    /// if the script has finished.
+    fn op(&mut self, thread: &mut Thread, op: &Op) -> Result<Option<usize>, CaosError> {
+        let next = thread.pc + 1;
+        match op {
+            Op::Command(command) => return self.command(thread, command, next),
+            Op::Jump(target) => return Ok(Some(*target)),
+            Op::JumpUnless(condition, target) => {
+                if !self.condition(thread, condition)? {
+                    return Ok(Some(*target));
+                }
+            }
+            Op::RepsStart(count, end) => {
+                let count = self.int(thread, count)?;
+                if count < 1 {
+                    return Ok(Some(*end));
+                }
+                thread.reps.push(count);
+            }
+            Op::RepsEnd(start) => {
+                let left = thread.reps.last_mut().expect("REPS started its loop");
+                *left -= 1;
+                if *left > 0 {
+                    return Ok(Some(*start));
+                }
+                thread.reps.pop();
+            }
+            Op::Gsub(target) => {
+                thread.call_stack.push(next);
+                return Ok(Some(*target));
+            }
+            Op::Retn => {
+                let Some(back) = thread.call_stack.pop() else {
+                    return Err(CaosError::new(ErrorType::ReturnWithoutCall, None));
+                };
+                return Ok(Some(back));
+            }
+            Op::Enum(enumeration, [family, genus, species], next) => {
+                let family = self.int(thread, family)?;
+                let genus = self.int(thread, genus)?;
+                let species = self.int(thread, species)?;
+                let agents = self.enumerate(thread, *enumeration, family, genus, species)?;
+                thread.enums.push((agents, thread.targ));
+                return Ok(Some(*next));
+            }
+            Op::Econ(agent, next) => {
+                let agent = self.agent(thread, agent)?;
+                let agents = self.world.connected(self.valid(agent)?);
+                thread.enums.push((agents, thread.targ));
+                return Ok(Some(*next));
+            }
+            Op::Next(body) => {
+                let (agents, _) = thread.enums.last_mut().expect("The loop was started");
+                // Agents killed since the loop started are skipped.
+                agents.retain(|&id| self.world.agent(id).is_some());
+                if !agents.is_empty() {
+                    thread.targ = Some(agents.remove(0));
+                    return Ok(Some(*body));
+                }
+                let (_, targ) = thread.enums.pop().expect("The loop was started");
+                thread.targ = targ;
+            }
+        }
+        Ok(Some(next))
+    }
+}
+
+pub(crate) fn unsupported(keyword: &str) -> CaosError {
+    let keyword = keyword.to_owned();
+    CaosError::new(ErrorType::Unsupported { keyword }, None)
+}
+
+/// A small, seedable random number generator for `RAND`, so runs can be
+/// repeated.
+#[derive(Debug, Clone)]
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // Xorshift gets stuck at zero.
+        const MIX: u64 = 0x9E37_79B9_7F4A_7C15;
+        Self(match seed ^ MIX {
+            0 => MIX,
+            state => state,
+        })
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    /// A random integer between `a` and `b`, inclusive, in either order.
+    fn between(&mut self, a: i32, b: i32) -> i32 {
+        let (low, high) = (a.min(b) as i64, a.max(b) as i64);
+        let range = (high - low + 1) as u64;
+        (low + (self.next_u64() % range) as i64) as i32
+    }
+}
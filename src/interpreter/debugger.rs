@@ -0,0 +1,372 @@
+//! Runs scripts an instruction at a time, stopping at breakpoints.
+
+#[cfg(test)]
+mod tests;
+
+use super::program::Op;
+use super::{Interpreter, Program, Step, Thread, Value};
+use crate::ast::Anything;
+use crate::{parse_cos_with_spans, parse_expression_str, CaosError, Span};
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// Where a [Debugger] stops before running an instruction.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Breakpoint {
+    /// The first command on a line, counting from 1.
+    Line(usize),
+    /// Every command with a keyword, such as `NEW: SIMP`, ignoring case and
+    /// spacing.
+    Command(String),
+}
+
+/// Why a [Debugger] stopped.
+#[derive(Debug)]
+pub enum StopReason {
+    /// A step finished.
+    Step,
+    /// The script reached the breakpoint with this index.
+    Breakpoint(usize),
+    /// The value of the watch with this index changed while running the
+    /// command before.
+    Watch(usize),
+    /// The script failed at the instruction it is stopped at. Running on
+    /// stops it and moves on to the next script.
+    Error(CaosError),
+    /// Every script has finished.
+    Finished,
+}
+
+/// A script being run, or a subroutine it called with `GSUB`.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Frame {
+    /// The name of the script, or the label of the subroutine.
+    pub name: String,
+    /// The instruction being run, or the `GSUB` waiting to return.
+    pub pc: usize,
+    pub span: Option<Span>,
+}
+
+/// An expression checked after every instruction.
+#[derive(Debug)]
+struct Watch {
+    expression: String,
+    parsed: Anything,
+    /// The value after the last instruction, or `None` if it could not be
+    /// evaluated.
+    value: Option<Value>,
+}
+
+/// How far to run.
+#[derive(Debug, Clone, Copy)]
+enum Until {
+    Breakpoint,
+    /// The next command, including those in a subroutine.
+    StepIn,
+    /// The next command at this call depth or above.
+    StepOver(usize),
+    /// The next command above this call depth.
+    StepOut(usize),
+}
+
+/// Runs scripts on an [Interpreter] under control, one after another, so
+/// that their variables and the world can be inspected between commands.
+///
+/// No time passes, so `WAIT` carries straight on as in
+/// [Interpreter::run_thread].
+///
+/// ```
+/// use caos2::interpreter::{Breakpoint, Debugger, Interpreter, StopReason, Value};
+///
+/// let mut debugger = Debugger::new(Interpreter::new());
+/// debugger.load("iscr\nsetv va00 1\nsetv va00 2\nendm").unwrap();
+/// debugger.set_breakpoints(vec![Breakpoint::Line(3)]);
+/// assert!(matches!(debugger.resume(), StopReason::Breakpoint(0)));
+/// assert_eq!(debugger.thread().unwrap().vaxx(0), &Value::Integer(1));
+/// assert!(matches!(debugger.resume(), StopReason::Finished));
+/// ```
+#[derive(Debug)]
+pub struct Debugger {
+    pub interpreter: Interpreter,
+    /// The script being run and its name.
+    current: Option<(String, Thread)>,
+    queue: VecDeque<(String, Thread)>,
+    /// Whether the current script has just started, and so has not been
+    /// checked for a breakpoint at its first instruction.
+    fresh: bool,
+    /// Whether the current script failed at its instruction.
+    failed: bool,
+    breakpoints: Vec<Breakpoint>,
+    watches: Vec<Watch>,
+}
+
+impl Debugger {
+    pub fn new(interpreter: Interpreter) -> Self {
+        Self {
+            interpreter,
+            current: None,
+            queue: VecDeque::new(),
+            fresh: false,
+            failed: false,
+            breakpoints: Vec::new(),
+            watches: Vec::new(),
+        }
+    }
+
+    /// Loads a CAOS file as [Interpreter::load] does, but queues its install
+    /// scripts to be run under the debugger rather than running them.
+    pub fn load(&mut self, source: &str) -> Result<(), CaosError> {
+        let (cos_file, spans) = parse_cos_with_spans(source)?;
+        let install = self.interpreter.add_scripts(&cos_file, &spans)?;
+        for (i, program) in install.into_iter().enumerate() {
+            let thread = Thread::new(Arc::new(program), None);
+            self.queue(format!("install script {}", i + 1), thread);
+        }
+        Ok(())
+    }
+
+    /// Queues a thread to run once those before it have finished.
+    pub fn queue(&mut self, name: impl Into<String>, thread: Thread) {
+        self.queue.push_back((name.into(), thread));
+        if self.current.is_none() {
+            self.next_thread();
+        }
+    }
+
+    /// The thread being run, stopped before its next instruction.
+    pub fn thread(&self) -> Option<&Thread> {
+        self.current.as_ref().map(|(_, thread)| thread)
+    }
+
+    /// The name of the thread being run.
+    pub fn name(&self) -> Option<&str> {
+        self.current.as_ref().map(|(name, _)| name.as_str())
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Replaces the breakpoints.
+    pub fn set_breakpoints(&mut self, breakpoints: Vec<Breakpoint>) {
+        self.breakpoints = breakpoints;
+    }
+
+    /// Watches an expression, such as `va00` or `posx`, stopping whenever
+    /// its value changes. Returns the index of the watch.
+    pub fn watch(&mut self, expression: &str) -> Result<usize, CaosError> {
+        let parsed = parse_expression_str(expression)?;
+        let value = self.evaluate_parsed(&parsed).ok();
+        self.watches.push(Watch {
+            expression: expression.to_owned(),
+            parsed,
+            value,
+        });
+        Ok(self.watches.len() - 1)
+    }
+
+    pub fn clear_watches(&mut self) {
+        self.watches.clear();
+    }
+
+    /// The watched expressions with their values, which are `None` where
+    /// they could not be evaluated.
+    pub fn watches(&self) -> impl Iterator<Item = (&str, Option<&Value>)> {
+        self.watches
+            .iter()
+            .map(|w| (w.expression.as_str(), w.value.as_ref()))
+    }
+
+    /// Evaluates an expression in the thread being run, without changing it.
+    pub fn evaluate(&mut self, expression: &str) -> Result<Value, CaosError> {
+        self.evaluate_parsed(&parse_expression_str(expression)?)
+    }
+
+    fn evaluate_parsed(&mut self, expression: &Anything) -> Result<Value, CaosError> {
+        match &self.current {
+            Some((_, thread)) => self.interpreter.evaluate(thread, expression),
+            None => {
+                let thread = Thread::new(Arc::new(Program::default()), None);
+                self.interpreter.evaluate(&thread, expression)
+            }
+        }
+    }
+
+    /// The frames of the thread being run, innermost first.
+    pub fn frames(&self) -> Vec<Frame> {
+        let Some((name, thread)) = &self.current else {
+            return Vec::new();
+        };
+        let program = &thread.program;
+        // Each return address follows the GSUB which called the frame above.
+        let calls: Vec<usize> = thread.call_stack.iter().map(|back| back - 1).collect();
+        let mut frames = Vec::new();
+        let mut pc = thread.pc;
+        for &call in calls.iter().rev() {
+            let label = match program.ops[call] {
+                Op::Gsub(start) => program.subroutine(start),
+                _ => None,
+            };
+            frames.push(Frame {
+                name: label.unwrap_or_default().to_owned(),
+                pc,
+                span: program.span(pc),
+            });
+            pc = call;
+        }
+        frames.push(Frame {
+            name: name.clone(),
+            pc,
+            span: program.span(pc),
+        });
+        frames
+    }
+
+    /// Runs until a breakpoint, a watch changes, a script fails or every
+    /// script has finished.
+    pub fn resume(&mut self) -> StopReason {
+        self.run(Until::Breakpoint)
+    }
+
+    /// Runs to the next command, stepping into subroutines.
+    pub fn step_in(&mut self) -> StopReason {
+        self.run(Until::StepIn)
+    }
+
+    /// Runs to the next command, running any subroutine it calls to the end.
+    pub fn step_over(&mut self) -> StopReason {
+        let depth = self.depth();
+        self.run(Until::StepOver(depth))
+    }
+
+    /// Runs until the current subroutine returns, or to the end of the
+    /// script if it is not in one.
+    pub fn step_out(&mut self) -> StopReason {
+        match self.depth() {
+            0 => self.run(Until::Breakpoint),
+            depth => self.run(Until::StepOut(depth)),
+        }
+    }
+
+    fn depth(&self) -> usize {
+        self.thread().map_or(0, |t| t.call_stack.len())
+    }
+
+    fn next_thread(&mut self) {
+        self.current = self.queue.pop_front();
+        self.fresh = true;
+        self.failed = false;
+    }
+
+    fn run(&mut self, until: Until) -> StopReason {
+        let failed = std::mem::take(&mut self.failed);
+        if failed {
+            self.next_thread();
+        }
+        if std::mem::take(&mut self.fresh) {
+            if let Some(index) = self.breakpoint() {
+                return StopReason::Breakpoint(index);
+            }
+            // A step from a failed script is to the start of the next.
+            if failed && !matches!(until, Until::Breakpoint) {
+                return StopReason::Step;
+            }
+        }
+        let mut watch = None;
+        for _ in 0..self.interpreter.instruction_limit {
+            let Some((_, thread)) = &mut self.current else {
+                return StopReason::Finished;
+            };
+            match self.interpreter.step(thread) {
+                Ok(Step::Finished) => {
+                    self.next_thread();
+                    if self.current.is_none() {
+                        return StopReason::Finished;
+                    }
+                }
+                Ok(Step::Waiting) => thread.waiting = 0,
+                Ok(Step::Continue) => {}
+                Err(error) => {
+                    self.failed = true;
+                    return StopReason::Error(error);
+                }
+            }
+            self.fresh = false;
+            watch = watch.or(self.changed_watch());
+            let Some((_, thread)) = &self.current else {
+                continue;
+            };
+            // Stop only before an instruction starting a command.
+            if thread.program.keyword(thread.pc).is_none() {
+                continue;
+            }
+            if let Some(index) = watch {
+                return StopReason::Watch(index);
+            }
+            if let Some(index) = self.breakpoint() {
+                return StopReason::Breakpoint(index);
+            }
+            let depth = thread.call_stack.len();
+            let stepped = match until {
+                Until::Breakpoint => false,
+                Until::StepIn => true,
+                Until::StepOver(from) => depth <= from,
+                Until::StepOut(from) => depth < from,
+            };
+            if stepped {
+                return StopReason::Step;
+            }
+        }
+        let (_, thread) = self.current.as_ref().expect("The thread is running");
+        self.failed = true;
+        StopReason::Error(self.interpreter.instruction_limit_error(thread))
+    }
+
+    /// The first breakpoint at the instruction to be run next.
+    fn breakpoint(&self) -> Option<usize> {
+        let (_, thread) = self.current.as_ref()?;
+        let (program, pc) = (&thread.program, thread.pc);
+        let keyword = program.keyword(pc)?;
+        self.breakpoints
+            .iter()
+            .position(|breakpoint| match breakpoint {
+                Breakpoint::Line(line) => starts_line(program, pc, *line),
+                Breakpoint::Command(command) => {
+                    let words: Vec<_> = command.split_whitespace().collect();
+                    keyword.eq_ignore_ascii_case(&words.join(" "))
+                }
+            })
+    }
+
+    /// Updates the watches, returning the first which changed.
+    fn changed_watch(&mut self) -> Option<usize> {
+        let mut watches = std::mem::take(&mut self.watches);
+        let mut changed = None;
+        for (i, watch) in watches.iter_mut().enumerate() {
+            let value = self.evaluate_parsed(&watch.parsed).ok();
+            if value != watch.value {
+                watch.value = value;
+                changed = changed.or(Some(i));
+            }
+        }
+        self.watches = watches;
+        changed
+    }
+}
+
+/// Whether the instruction at `pc` is the first command on a line.
+fn starts_line(program: &Program, pc: usize, line: usize) -> bool {
+    let line_of = |pc| program.span(pc).map(|span| span.start_line_col.0);
+    line_of(pc) == Some(line) && (0..pc).rev().find_map(line_of) != Some(line)
+}
+
+impl Interpreter {
+    /// Evaluates an expression as a thread would, without changing the
+    /// thread or what `RAND` gives next.
+    pub fn evaluate(&mut self, thread: &Thread, expression: &Anything) -> Result<Value, CaosError> {
+        let rng = self.rng.clone();
+        let value = self.anything(&mut thread.clone(), expression);
+        self.rng = rng;
+        value
+    }
+}
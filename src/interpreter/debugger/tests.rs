@@ -0,0 +1,161 @@
+use super::*;
+use crate::ErrorType;
+
+const SOURCE: &str = "\
+new: simp 2 11 100 \"ball\" 1 0 0
+iscr
+    setv va00 1
+    gsub double
+    gsub double
+    setv game \"result\" va00
+    subr double
+        mulv va00 2
+        retn
+endm
+iscr setv va00 7 endm
+";
+
+fn load(source: &str) -> Debugger {
+    let mut debugger = Debugger::new(Interpreter::new());
+    debugger.load(source).expect("Loaded");
+    debugger
+}
+
+fn line(debugger: &Debugger) -> usize {
+    let span = debugger.frames()[0].span.expect("The command has a span");
+    span.start_line_col.0
+}
+
+#[test]
+fn test_line_breakpoints() {
+    let mut debugger = load(SOURCE);
+    debugger.set_breakpoints(vec![Breakpoint::Line(6), Breakpoint::Line(11)]);
+    assert!(matches!(debugger.resume(), StopReason::Breakpoint(0)));
+    assert_eq!(debugger.name(), Some("install script 2"));
+    assert_eq!(line(&debugger), 6);
+    assert_eq!(debugger.thread().unwrap().vaxx(0), &Value::Integer(4));
+
+    assert!(matches!(debugger.resume(), StopReason::Breakpoint(1)));
+    assert_eq!(debugger.name(), Some("install script 3"));
+    assert!(matches!(debugger.resume(), StopReason::Finished));
+    assert!(debugger.thread().is_none());
+    let result = debugger.interpreter.world.game_variable("result");
+    assert_eq!(result, Value::Integer(4));
+}
+
+#[test]
+fn test_breakpoint_at_first_command() {
+    let mut debugger = load(SOURCE);
+    debugger.set_breakpoints(vec![Breakpoint::Line(1)]);
+    assert!(matches!(debugger.resume(), StopReason::Breakpoint(0)));
+    assert_eq!(debugger.thread().unwrap().pc(), 0);
+    assert!(debugger.interpreter.world.agents().next().is_none());
+    assert!(matches!(debugger.resume(), StopReason::Finished));
+}
+
+#[test]
+fn test_command_breakpoints() {
+    let mut debugger = load(SOURCE);
+    debugger.set_breakpoints(vec![Breakpoint::Command("MULV".to_owned())]);
+    assert!(matches!(debugger.resume(), StopReason::Breakpoint(0)));
+    assert_eq!(line(&debugger), 8);
+    assert_eq!(debugger.thread().unwrap().vaxx(0), &Value::Integer(1));
+    assert!(matches!(debugger.resume(), StopReason::Breakpoint(0)));
+    assert_eq!(debugger.thread().unwrap().vaxx(0), &Value::Integer(2));
+
+    let mut debugger = load("iscr new:   SIMP 1 1 1 \"a\" 1 0 0 endm");
+    debugger.set_breakpoints(vec![Breakpoint::Command("new: simp".to_owned())]);
+    assert!(matches!(debugger.resume(), StopReason::Breakpoint(0)));
+}
+
+#[test]
+fn test_stepping() {
+    let mut debugger = load(SOURCE);
+    assert!(matches!(debugger.step_in(), StopReason::Step));
+    assert_eq!(debugger.name(), Some("install script 2"));
+    assert_eq!(line(&debugger), 3);
+    debugger.step_in();
+    assert_eq!(line(&debugger), 4);
+
+    // Stepping into GSUB stops in the subroutine.
+    debugger.step_in();
+    assert_eq!(line(&debugger), 8);
+    let frames = debugger.frames();
+    let names: Vec<_> = frames.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, ["double", "install script 2"]);
+    assert_eq!(frames[1].span.unwrap().start_line_col, (4, 5));
+
+    debugger.step_out();
+    assert_eq!(line(&debugger), 5);
+    assert_eq!(debugger.frames().len(), 1);
+    assert_eq!(debugger.thread().unwrap().vaxx(0), &Value::Integer(2));
+
+    // Stepping over GSUB runs the whole subroutine.
+    debugger.step_over();
+    assert_eq!(line(&debugger), 6);
+    assert_eq!(debugger.thread().unwrap().vaxx(0), &Value::Integer(4));
+
+    debugger.step_over();
+    assert_eq!(debugger.name(), Some("install script 3"));
+    debugger.step_over();
+    assert!(matches!(debugger.step_over(), StopReason::Finished));
+}
+
+#[test]
+fn test_step_over_stops_at_breakpoints_in_subroutines() {
+    let mut debugger = load(SOURCE);
+    debugger.set_breakpoints(vec![Breakpoint::Line(8)]);
+    debugger.step_in();
+    debugger.step_in();
+    assert_eq!(line(&debugger), 4);
+    assert!(matches!(debugger.step_over(), StopReason::Breakpoint(0)));
+    assert_eq!(line(&debugger), 8);
+}
+
+#[test]
+fn test_watches() {
+    let mut debugger = load(SOURCE);
+    assert_eq!(debugger.watch("va00").unwrap(), 0);
+    debugger.watch("ovxx 0").unwrap_err();
+    assert!(matches!(debugger.resume(), StopReason::Watch(0)));
+    assert_eq!(line(&debugger), 4);
+    assert_eq!(
+        debugger.watches().collect::<Vec<_>>(),
+        [("va00", Some(&Value::Integer(1)))]
+    );
+    // The change is seen after RETN, which closes the subroutine.
+    assert!(matches!(debugger.resume(), StopReason::Watch(0)));
+    assert_eq!(line(&debugger), 5);
+
+    debugger.clear_watches();
+    debugger.watch("targ").unwrap();
+    assert!(matches!(debugger.resume(), StopReason::Finished));
+}
+
+#[test]
+fn test_evaluate() {
+    let mut debugger = load("iscr setv va00 3 targ null endm");
+    debugger.step_in();
+    assert_eq!(debugger.evaluate("va00").unwrap(), Value::Integer(3));
+    assert_eq!(
+        debugger.evaluate("rand 1 1000").unwrap(),
+        debugger.evaluate("rand 1 1000").unwrap()
+    );
+    let error = debugger.evaluate("ov00").unwrap_err();
+    assert_eq!(*error.error_type(), ErrorType::InvalidAgent);
+    assert_eq!(debugger.thread().unwrap().pc(), 1);
+}
+
+#[test]
+fn test_errors_stop_the_script() {
+    let mut debugger = load("iscr setv va00 1 kill null endm iscr setv va00 2 endm");
+    let StopReason::Error(error) = debugger.resume() else {
+        panic!("Expected an error");
+    };
+    assert_eq!(*error.error_type(), ErrorType::InvalidAgent);
+    assert_eq!(debugger.name(), Some("install script 1"));
+    assert_eq!(debugger.thread().unwrap().vaxx(0), &Value::Integer(1));
+
+    assert!(matches!(debugger.step_in(), StopReason::Step));
+    assert_eq!(debugger.name(), Some("install script 2"));
+}
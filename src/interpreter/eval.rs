@@ -0,0 +1,549 @@
+use super::program::Enumeration;
+use super::value::float_to_int;
+use super::world::AGENT_VARIABLES;
+use super::{unsupported, AgentId, AgentState, Interpreter, Thread, Value};
+use crate::ast::{
+    Agent, AgentArg, Anything, Condition, ConditionType, Decimal, DecimalArg, Float, FloatArg,
+    IntArg, Integer, JoinType, SString, SStringArg, Variable,
+};
+use crate::signature::Signatures;
+use crate::{CaosError, Encoding, ErrorType};
+use std::cmp::Ordering;
+
+/// Where a variable's value is kept.
+pub(super) enum Place {
+    Vaxx(usize),
+    Agent(AgentId, usize),
+    Game(String),
+    Eame(String),
+    P1,
+    P2,
+}
+
+impl Interpreter {
+    pub(super) fn place(
+        &mut self,
+        thread: &mut Thread,
+        var: &Variable,
+    ) -> Result<Place, CaosError> {
+        Ok(match var {
+            Variable::Vaxx(i) => Place::Vaxx(usize::from(*i)),
+            Variable::Ovxx(i) => Place::Agent(self.valid(thread.targ)?, usize::from(*i)),
+            Variable::Mvxx(i) => Place::Agent(self.valid(thread.owner)?, usize::from(*i)),
+            Variable::Avar { agent, index } => {
+                let agent = self.agent(thread, agent)?;
+                let agent = self.valid(agent)?;
+                let index = self.int(thread, index)?;
+                match usize::try_from(index) {
+                    Ok(i) if i < AGENT_VARIABLES => Place::Agent(agent, i),
+                    _ => return Err(out_of_range(index, AGENT_VARIABLES)),
+                }
+            }
+            Variable::Game { variable_name } => Place::Game(self.string(thread, variable_name)?),
+            Variable::Eame { name } => {
+                Place::Eame(self.anything(thread, name)?.to_str()?.to_owned())
+            }
+            Variable::P1 => Place::P1,
+            Variable::P2 => Place::P2,
+            _ => return Err(unsupported(keyword(var))),
+        })
+    }
+
+    /// Checks an agent exists, failing if it is `NULL` or has been killed.
+    pub(super) fn valid(&self, agent: Option<AgentId>) -> Result<AgentId, CaosError> {
+        match agent {
+            Some(id) if self.world.agent(id).is_some() => Ok(id),
+            _ => Err(CaosError::new(ErrorType::InvalidAgent, None)),
+        }
+    }
+
+    /// An agent, or `NULL` if it has been killed.
+    fn alive(&self, agent: Option<AgentId>) -> Option<AgentId> {
+        agent.filter(|&id| self.world.agent(id).is_some())
+    }
+
+    /// The state of `TARG`, failing if it is `NULL` or has been killed.
+    pub(super) fn target(&self, thread: &Thread) -> Result<&AgentState, CaosError> {
+        let id = self.valid(thread.targ)?;
+        Ok(self.world.agent(id).expect("The agent is alive"))
+    }
+
+    pub(super) fn target_mut(&mut self, thread: &Thread) -> Result<&mut AgentState, CaosError> {
+        let id = self.valid(thread.targ)?;
+        Ok(self.world.agent_mut(id).expect("The agent is alive"))
+    }
+
+    /// The agents an enumeration visits, in the order they were added. Only
+    /// `ENUM` can run without an owner.
+    pub(super) fn enumerate(
+        &self,
+        thread: &Thread,
+        enumeration: Enumeration,
+        family: i32,
+        genus: i32,
+        species: i32,
+    ) -> Result<Vec<AgentId>, CaosError> {
+        let matching = self
+            .world
+            .agents()
+            .filter(|(_, agent)| agent.matches(family, genus, species));
+        let Enumeration::Enum = enumeration else {
+            let owner = self.valid(thread.owner)?;
+            let owner_state = self.world.agent(owner).expect("The agent is alive");
+            let found = matching.filter(|&(id, agent)| {
+                id != owner
+                    && match enumeration {
+                        Enumeration::Esee => owner_state.can_see(agent),
+                        Enumeration::Etch => owner_state.touches(agent),
+                        _ => agent.vehicle == Some(owner),
+                    }
+            });
+            return Ok(found.map(|(id, _)| id).collect());
+        };
+        Ok(matching.map(|(id, _)| id).collect())
+    }
+
+    /// Reads a variable. Agents which have been killed read as `NULL`.
+    pub(super) fn get(&self, thread: &Thread, place: &Place) -> Value {
+        let value = match place {
+            Place::Vaxx(i) => thread.vaxx[*i].clone(),
+            Place::Agent(id, i) => self
+                .world
+                .agent(*id)
+                .map(|a| a.ovxx[*i].clone())
+                .unwrap_or_default(),
+            Place::Game(name) => self.world.game_variable(name),
+            Place::Eame(name) => self.world.eame.get(name).cloned().unwrap_or_default(),
+            Place::P1 => thread.p1.clone(),
+            Place::P2 => thread.p2.clone(),
+        };
+        match value {
+            Value::Agent(agent) => Value::Agent(self.alive(agent)),
+            value => value,
+        }
+    }
+
+    pub(super) fn set(&mut self, thread: &mut Thread, place: Place, value: Value) {
+        match place {
+            Place::Vaxx(i) => thread.vaxx[i] = value,
+            Place::Agent(id, i) => {
+                if let Some(agent) = self.world.agent_mut(id) {
+                    agent.ovxx[i] = value;
+                }
+            }
+            Place::Game(name) => {
+                self.world.game.insert(name, value);
+            }
+            Place::Eame(name) => {
+                self.world.eame.insert(name, value);
+            }
+            Place::P1 => thread.p1 = value,
+            Place::P2 => thread.p2 = value,
+        }
+    }
+
+    pub(super) fn variable(
+        &mut self,
+        thread: &mut Thread,
+        var: &Variable,
+    ) -> Result<Value, CaosError> {
+        let place = self.place(thread, var)?;
+        Ok(self.get(thread, &place))
+    }
+
+    pub(super) fn int(&mut self, thread: &mut Thread, arg: &IntArg) -> Result<i32, CaosError> {
+        match arg {
+            IntArg::Primary(i) => self.integer(thread, i),
+            IntArg::Castable(f) => Ok(float_to_int(self.float(thread, f)?)),
+            IntArg::Variable(v) => self.variable(thread, v)?.to_int(),
+        }
+    }
+
+    pub(super) fn float_arg(
+        &mut self,
+        thread: &mut Thread,
+        arg: &FloatArg,
+    ) -> Result<f32, CaosError> {
+        match arg {
+            FloatArg::Primary(f) => self.float(thread, f),
+            FloatArg::Castable(i) => Ok(self.integer(thread, i)? as f32),
+            FloatArg::Variable(v) => self.variable(thread, v)?.to_float(),
+        }
+    }
+
+    /// A number, keeping whether it is an integer or a float.
+    pub(super) fn decimal(
+        &mut self,
+        thread: &mut Thread,
+        arg: &DecimalArg,
+    ) -> Result<Value, CaosError> {
+        match arg {
+            DecimalArg::Decimal(Decimal::Integer(i)) => {
+                Ok(Value::Integer(self.integer(thread, i)?))
+            }
+            DecimalArg::Decimal(Decimal::Float(f)) => Ok(Value::Float(self.float(thread, f)?)),
+            DecimalArg::Variable(v) => self.variable(thread, v)?.to_number(),
+        }
+    }
+
+    pub(super) fn string(
+        &mut self,
+        thread: &mut Thread,
+        arg: &SStringArg,
+    ) -> Result<String, CaosError> {
+        match arg {
+            SStringArg::String(s) => self.sstring(thread, s),
+            SStringArg::Variable(v) => Ok(self.variable(thread, v)?.to_str()?.to_owned()),
+        }
+    }
+
+    pub(super) fn agent(
+        &mut self,
+        thread: &mut Thread,
+        arg: &AgentArg,
+    ) -> Result<Option<AgentId>, CaosError> {
+        match arg {
+            AgentArg::Agent(a) => self.agent_expression(thread, a),
+            AgentArg::Variable(v) => self.variable(thread, v)?.to_agent(),
+        }
+    }
+
+    pub(super) fn anything(
+        &mut self,
+        thread: &mut Thread,
+        arg: &Anything,
+    ) -> Result<Value, CaosError> {
+        match arg {
+            Anything::Variable(v) => self.variable(thread, v),
+            Anything::String(s) => Ok(Value::String(self.sstring(thread, s)?)),
+            Anything::Decimal(Decimal::Integer(i)) => Ok(Value::Integer(self.integer(thread, i)?)),
+            Anything::Decimal(Decimal::Float(f)) => Ok(Value::Float(self.float(thread, f)?)),
+            Anything::Agent(a) => Ok(Value::Agent(self.agent_expression(thread, a)?)),
+            Anything::ByteString(_) => {
+                let error_type = ErrorType::WrongValueType {
+                    expected: "a value",
+                    found: "a byte string",
+                };
+                Err(CaosError::new(error_type, None))
+            }
+        }
+    }
+
+    pub(super) fn condition(
+        &mut self,
+        thread: &mut Thread,
+        condition: &Condition,
+    ) -> Result<bool, CaosError> {
+        match condition {
+            Condition::Simple {
+                cond_type,
+                lhs,
+                rhs,
+            } => {
+                let lhs = self.anything(thread, lhs)?;
+                let rhs = self.anything(thread, rhs)?;
+                compare(&lhs, cond_type, &rhs)
+            }
+            // Both sides are evaluated, as the engine does.
+            Condition::Combination {
+                c_lhs,
+                c_rhs,
+                join_type,
+            } => {
+                let lhs = self.condition(thread, c_lhs)?;
+                let rhs = self.condition(thread, c_rhs)?;
+                Ok(match join_type {
+                    JoinType::And => lhs && rhs,
+                    JoinType::Or => lhs || rhs,
+                })
+            }
+        }
+    }
+
+    fn integer(&mut self, thread: &mut Thread, i: &Integer) -> Result<i32, CaosError> {
+        Ok(match i {
+            Integer::Literal(i) => *i,
+            Integer::Char { string, index } => {
+                let string = self.string(thread, string)?;
+                let index = self.int(thread, index)?;
+                let c = nth_char(&string, index)?;
+                let encoding = Encoding::Windows1252;
+                let byte = encoding.byte(c).ok_or_else(|| {
+                    let error_type = ErrorType::Unencodable {
+                        character: c,
+                        encoding,
+                    };
+                    CaosError::new(error_type, None)
+                })?;
+                i32::from(byte)
+            }
+            Integer::Ftoi { number_to_convert } => {
+                float_to_int(self.float_arg(thread, number_to_convert)?)
+            }
+            Integer::Rand { value1, value2 } => {
+                let value1 = self.int(thread, value1)?;
+                let value2 = self.int(thread, value2)?;
+                self.rng.between(value1, value2)
+            }
+            Integer::Sins {
+                value,
+                start,
+                search,
+            } => {
+                let value = self.string(thread, value)?;
+                let start = self.int(thread, start)?;
+                let search = self.string(thread, search)?;
+                let length = value.chars().count();
+                let skip = match usize::try_from(start) {
+                    Ok(s) if (1..=length + 1).contains(&s) => s - 1,
+                    _ => return Err(out_of_range(start, length)),
+                };
+                let chars: Vec<char> = value.chars().collect();
+                let search: Vec<char> = search.chars().collect();
+                (skip..=length.saturating_sub(search.len()))
+                    .find(|&i| chars[i..].starts_with(&search))
+                    .map_or(-1, |i| i as i32 + 1)
+            }
+            Integer::Stoi { value } => {
+                let value = self.string(thread, value)?;
+                let number = leading_number(&value, false);
+                number.parse::<f64>().map_or(0, |n| n as i32)
+            }
+            Integer::Strl { value } => self.string(thread, value)?.chars().count() as i32,
+            Integer::Attr => self.target(thread)?.attributes,
+            Integer::Fmly => self.target(thread)?.family,
+            Integer::Gnus => self.target(thread)?.genus,
+            Integer::Spcs => self.target(thread)?.species,
+            Integer::Hght => self.target(thread)?.height,
+            Integer::Wdth => self.target(thread)?.width,
+            Integer::Plne => self.target(thread)?.plane,
+            Integer::Pose => self.target(thread)?.pose,
+            Integer::Unid => self.valid(thread.targ)?.0 as i32,
+            Integer::Tick => self.target(thread)?.tick_rate,
+            Integer::Wtik => self.ticks as i32,
+            Integer::Totl {
+                family,
+                genus,
+                species,
+            } => {
+                let [family, genus, species] = self.classifier(thread, [family, genus, species])?;
+                self.world.matching(family, genus, species).count() as i32
+            }
+            Integer::Seee { first, second } => {
+                let (first, second) = self.agent_pair(thread, first, second)?;
+                i32::from(first.can_see(second))
+            }
+            Integer::Touc { first, second } => {
+                let (first, second) = self.agent_pair(thread, first, second)?;
+                i32::from(first.touches(second))
+            }
+            Integer::Type { something } => match self.anything(thread, something)? {
+                Value::Integer(_) => 0,
+                Value::Float(_) => 1,
+                Value::String(_) => 2,
+                Value::Agent(Some(_)) => 3,
+                Value::Agent(None) => -1,
+            },
+            _ => return Err(unsupported(keyword(i))),
+        })
+    }
+
+    fn float(&mut self, thread: &mut Thread, f: &Float) -> Result<f32, CaosError> {
+        Ok(match f {
+            Float::Literal(f) => f32::from(f.clone()),
+            Float::Itof { number_to_convert } => self.int(thread, number_to_convert)? as f32,
+            Float::Sin { theta } => self.float_arg(thread, theta)?.to_radians().sin(),
+            Float::Cos { theta } => self.float_arg(thread, theta)?.to_radians().cos(),
+            Float::Tan { theta } => self.float_arg(thread, theta)?.to_radians().tan(),
+            Float::Asin { x } => self.float_arg(thread, x)?.asin().to_degrees(),
+            Float::Acos { x } => self.float_arg(thread, x)?.acos().to_degrees(),
+            Float::Atan { x } => self.float_arg(thread, x)?.atan().to_degrees(),
+            Float::Sqrt { value } => self.float_arg(thread, value)?.sqrt(),
+            Float::Stof { value } => {
+                let value = self.string(thread, value)?;
+                leading_number(&value, true).parse().unwrap_or(0.0)
+            }
+            Float::Posl => self.target(thread)?.x,
+            Float::Post => self.target(thread)?.y,
+            Float::Posr => self.target(thread)?.bottom_right().0,
+            Float::Posb => self.target(thread)?.bottom_right().1,
+            Float::Posx => self.target(thread)?.centre().0,
+            Float::Posy => self.target(thread)?.centre().1,
+            Float::Rnge => self.target(thread)?.range,
+            _ => return Err(unsupported(keyword(f))),
+        })
+    }
+
+    fn sstring(&mut self, thread: &mut Thread, s: &SString) -> Result<String, CaosError> {
+        Ok(match s {
+            SString::Literal(s) => s.clone(),
+            SString::Lowa { value } => self.string(thread, value)?.to_ascii_lowercase(),
+            SString::Uppa { value } => self.string(thread, value)?.to_ascii_uppercase(),
+            SString::Subs {
+                value,
+                start,
+                count,
+            } => {
+                let value = self.string(thread, value)?;
+                let start = self.int(thread, start)?;
+                let count = self.int(thread, count)?;
+                let length = value.chars().count();
+                let skip = match usize::try_from(start) {
+                    Ok(s) if s >= 1 => s - 1,
+                    _ => return Err(out_of_range(start, length)),
+                };
+                match usize::try_from(count) {
+                    Ok(c) if skip + c <= length => value.chars().skip(skip).take(c).collect(),
+                    _ => return Err(out_of_range(start.saturating_add(count) - 1, length)),
+                }
+            }
+            SString::Vtos { value } => self.decimal(thread, value)?.to_string(),
+            _ => return Err(unsupported(keyword(s))),
+        })
+    }
+
+    fn agent_expression(
+        &mut self,
+        thread: &mut Thread,
+        a: &Agent,
+    ) -> Result<Option<AgentId>, CaosError> {
+        Ok(match a {
+            Agent::Null => None,
+            Agent::Ownr => self.alive(thread.owner),
+            Agent::Targ => self.alive(thread.targ),
+            Agent::From => self.alive(thread.from),
+            Agent::It => self.alive(thread.it),
+            Agent::Agnt { unique_id } => {
+                let unique_id = self.int(thread, unique_id)?;
+                self.alive(u32::try_from(unique_id).ok().map(AgentId))
+            }
+            // The next agent after `previous` in the order they were added,
+            // coming round to the first.
+            Agent::Ncls {
+                previous,
+                family,
+                genus,
+                species,
+            } => {
+                let previous = self.agent(thread, previous)?;
+                let [family, genus, species] = self.classifier(thread, [family, genus, species])?;
+                let matching: Vec<_> = self.world.matching(family, genus, species).collect();
+                let next = matching.iter().find(|&&id| Some(id) > previous);
+                next.or(matching.first()).copied()
+            }
+            Agent::Pcls {
+                next,
+                family,
+                genus,
+                species,
+            } => {
+                let next = self.agent(thread, next)?;
+                let [family, genus, species] = self.classifier(thread, [family, genus, species])?;
+                let matching: Vec<_> = self.world.matching(family, genus, species).collect();
+                let previous = matching.iter().rev().find(|&&id| match next {
+                    Some(next) => id < next,
+                    None => true,
+                });
+                previous.or(matching.last()).copied()
+            }
+            _ => return Err(unsupported(keyword(a))),
+        })
+    }
+
+    /// Evaluates a family, genus and species.
+    pub(super) fn classifier(
+        &mut self,
+        thread: &mut Thread,
+        classifier: [&IntArg; 3],
+    ) -> Result<[i32; 3], CaosError> {
+        let [family, genus, species] = classifier;
+        Ok([
+            self.int(thread, family)?,
+            self.int(thread, genus)?,
+            self.int(thread, species)?,
+        ])
+    }
+
+    /// The states of two agents, failing if either is `NULL` or has been
+    /// killed.
+    fn agent_pair(
+        &mut self,
+        thread: &mut Thread,
+        first: &AgentArg,
+        second: &AgentArg,
+    ) -> Result<(&AgentState, &AgentState), CaosError> {
+        let first = self.agent(thread, first)?;
+        let first = self.valid(first)?;
+        let second = self.agent(thread, second)?;
+        let second = self.valid(second)?;
+        let agent = |id| self.world.agent(id).expect("The agent is alive");
+        Ok((agent(first), agent(second)))
+    }
+}
+
+/// Compares two values as a condition does. Numbers compare as floats if
+/// either is a float, strings compare by character and agents can only be
+/// tested for equality.
+pub(super) fn compare(
+    lhs: &Value,
+    cond_type: &ConditionType,
+    rhs: &Value,
+) -> Result<bool, CaosError> {
+    let ordering = match (lhs, rhs) {
+        (Value::Integer(l), Value::Integer(r)) => l.cmp(r),
+        (Value::String(l), Value::String(r)) => l.cmp(r),
+        (Value::Agent(l), Value::Agent(r)) => {
+            return match cond_type {
+                ConditionType::Eq => Ok(l == r),
+                ConditionType::Ne => Ok(l != r),
+                _ => Err(lhs.wrong_type("a number or string")),
+            }
+        }
+        (Value::Integer(_) | Value::Float(_), Value::Integer(_) | Value::Float(_)) => {
+            let (l, r) = (lhs.to_float()?, rhs.to_float()?);
+            l.partial_cmp(&r).unwrap_or(Ordering::Less)
+        }
+        (Value::String(_), _) => return Err(rhs.wrong_type("a string")),
+        (Value::Agent(_), _) => return Err(rhs.wrong_type("an agent")),
+        _ => return Err(rhs.wrong_type("a number")),
+    };
+    Ok(match cond_type {
+        ConditionType::Eq => ordering == Ordering::Equal,
+        ConditionType::Ne => ordering != Ordering::Equal,
+        ConditionType::Ge => ordering != Ordering::Less,
+        ConditionType::Gt => ordering == Ordering::Greater,
+        ConditionType::Le => ordering != Ordering::Greater,
+        ConditionType::Lt => ordering == Ordering::Less,
+    })
+}
+
+/// The character at a position counted from 1.
+pub(super) fn nth_char(s: &str, index: i32) -> Result<char, CaosError> {
+    usize::try_from(index)
+        .ok()
+        .and_then(|i| i.checked_sub(1))
+        .and_then(|i| s.chars().nth(i))
+        .ok_or_else(|| out_of_range(index, s.chars().count()))
+}
+
+pub(super) fn out_of_range(index: i32, length: usize) -> CaosError {
+    CaosError::new(ErrorType::IndexOutOfRange { index, length }, None)
+}
+
+/// The number at the start of a string, after any whitespace, as read by
+/// `STOI` and `STOF`.
+fn leading_number(s: &str, fraction: bool) -> &str {
+    let s = s.trim_start();
+    let sign = usize::from(s.starts_with(['-', '+']));
+    let digits = |from: usize| from + s[from..].bytes().take_while(u8::is_ascii_digit).count();
+    let mut end = digits(sign);
+    if fraction && s[end..].starts_with('.') {
+        end = digits(end + 1);
+    }
+    &s[..end]
+}
+
+/// The keyword of a node, for errors about nodes the interpreter cannot run.
+pub(super) fn keyword(node: &impl Signatures) -> &'static str {
+    node.signature().map_or(node.variant_name(), |s| s.keyword)
+}
@@ -0,0 +1,380 @@
+use super::eval::{keyword, nth_char};
+use super::program::Enumeration;
+use super::{unsupported, AgentId, AgentKind, AgentState, Interpreter, Message, Thread, Value};
+use crate::ast::{Command, Variable};
+use crate::{CaosError, Encoding, ErrorType};
+
+impl Interpreter {
+    /// Runs a command with no body, returning the instruction to run next or
+    /// `None` if the script has finished.
+    pub(super) fn command(
+        &mut self,
+        thread: &mut Thread,
+        command: &Command,
+        next: usize,
+    ) -> Result<Option<usize>, CaosError> {
+        match command {
+            Command::Stop => return Ok(None),
+            Command::Inst => thread.inst = true,
+            Command::Slow => thread.inst = false,
+            Command::Lock => thread.lock = true,
+            Command::Unlk => thread.lock = false,
+            Command::Targ { agent } => thread.targ = self.agent(thread, agent)?,
+            Command::Wait { ticks } => thread.waiting = self.int(thread, ticks)?,
+            Command::Tick { tick_rate } => {
+                let tick_rate = self.int(thread, tick_rate)?;
+                let target = self.target_mut(thread)?;
+                (target.tick_rate, target.timer) = (tick_rate, 0);
+            }
+            Command::DbgWtik { new_world_tick } => {
+                self.ticks = self.int(thread, new_world_tick)? as u32;
+            }
+            Command::MesgWrit { agent, message_id } => {
+                let to = self.agent(thread, agent)?;
+                let to = self.valid(to)?;
+                let message_id = self.int(thread, message_id)?;
+                let from = thread.owner;
+                self.send(Message {
+                    from,
+                    ..Message::new(to, message_id)
+                });
+            }
+            Command::MesgWritPlus {
+                agent,
+                message_id,
+                param_1,
+                param_2,
+                delay,
+            } => {
+                let to = self.agent(thread, agent)?;
+                let to = self.valid(to)?;
+                let message = Message {
+                    to,
+                    message_id: self.int(thread, message_id)?,
+                    from: thread.owner,
+                    p1: self.anything(thread, param_1)?,
+                    p2: self.anything(thread, param_2)?,
+                    delay: self.int(thread, delay)?,
+                };
+                self.send(message);
+            }
+            Command::Setv { var, value } => {
+                let value = self.decimal(thread, value)?;
+                self.assign(thread, var, value)?;
+            }
+            Command::Sets { var, value } => {
+                let value = Value::String(self.string(thread, value)?);
+                self.assign(thread, var, value)?;
+            }
+            Command::Seta { var, value } => {
+                let value = Value::Agent(self.agent(thread, value)?);
+                self.assign(thread, var, value)?;
+            }
+            Command::Addv { var, sum } => {
+                let sum = self.decimal(thread, sum)?;
+                self.arithmetic(thread, var, sum, i32::wrapping_add, |a, b| a + b)?
+            }
+            Command::Subv { var, sub } => {
+                let sub = self.decimal(thread, sub)?;
+                self.arithmetic(thread, var, sub, i32::wrapping_sub, |a, b| a - b)?
+            }
+            Command::Mulv { var, mul } => {
+                let mul = self.decimal(thread, mul)?;
+                self.arithmetic(thread, var, mul, i32::wrapping_mul, |a, b| a * b)?
+            }
+            Command::Divv { var, div } => {
+                let div = self.decimal(thread, div)?;
+                if div.to_float()? == 0.0 {
+                    return Err(CaosError::new(ErrorType::DivisionByZero, None));
+                }
+                self.arithmetic(thread, var, div, i32::wrapping_div, |a, b| a / b)?
+            }
+            Command::Modv { var, r#mod } => {
+                let r#mod = self.int(thread, r#mod)?;
+                if r#mod == 0 {
+                    return Err(CaosError::new(ErrorType::DivisionByZero, None));
+                }
+                self.bitwise(thread, var, r#mod, i32::wrapping_rem)?
+            }
+            Command::Andv { var, value } => {
+                let value = self.int(thread, value)?;
+                self.bitwise(thread, var, value, |a, b| a & b)?
+            }
+            Command::Orrv { var, value } => {
+                let value = self.int(thread, value)?;
+                self.bitwise(thread, var, value, |a, b| a | b)?
+            }
+            Command::Negv { var } => {
+                let value = match self.variable(thread, var)?.to_number()? {
+                    Value::Integer(i) => Value::Integer(i.wrapping_neg()),
+                    Value::Float(f) => Value::Float(-f),
+                    _ => unreachable!("Numbers are integers or floats"),
+                };
+                self.assign(thread, var, value)?;
+            }
+            Command::Absv { var } => {
+                let value = match self.variable(thread, var)?.to_number()? {
+                    Value::Integer(i) => Value::Integer(i.wrapping_abs()),
+                    Value::Float(f) => Value::Float(f.abs()),
+                    _ => unreachable!("Numbers are integers or floats"),
+                };
+                self.assign(thread, var, value)?;
+            }
+            Command::Notv { var } => {
+                let value = Value::Integer(!self.variable(thread, var)?.to_int()?);
+                self.assign(thread, var, value)?;
+            }
+            Command::Adds { var, append } => {
+                let append = self.string(thread, append)?;
+                let mut value = self.variable(thread, var)?.to_str()?.to_owned();
+                value.push_str(&append);
+                self.assign(thread, var, Value::String(value))?;
+            }
+            Command::Char {
+                string,
+                index,
+                character,
+            } => {
+                let index = self.int(thread, index)?;
+                // The engine keeps only the low byte of the character code.
+                let character = Encoding::Windows1252.char(self.int(thread, character)? as u8);
+                let value = self.variable(thread, string)?.to_str()?.to_owned();
+                nth_char(&value, index)?;
+                let value = value
+                    .chars()
+                    .enumerate()
+                    .map(|(i, c)| match i as i32 == index - 1 {
+                        true => character,
+                        false => c,
+                    })
+                    .collect();
+                self.assign(thread, string, Value::String(value))?;
+            }
+            Command::Delg { variable_name } => {
+                let name = self.string(thread, variable_name)?;
+                self.world.game.remove(&name);
+            }
+            Command::DbgAsrt { condition } => {
+                if !self.condition(thread, condition)? {
+                    return Err(CaosError::new(ErrorType::AssertionFailed, None));
+                }
+            }
+            Command::DbgOuts { value } => {
+                let value = self.string(thread, value)?;
+                self.debug_log.push(value);
+            }
+            Command::DbgOutv { value } => {
+                let value = self.decimal(thread, value)?;
+                self.debug_log.push(value.to_string());
+            }
+            Command::Outs { text } => {
+                let text = self.string(thread, text)?;
+                self.output.push_str(&text);
+            }
+            Command::Outv { value } => {
+                let value = self.decimal(thread, value)?;
+                self.output.push_str(&value.to_string());
+            }
+            Command::Outx { text } => {
+                let text = self.string(thread, text)?;
+                let escaped = text
+                    .replace('\\', "\\\\")
+                    .replace('"', "\\\"")
+                    .replace('\n', "\\n");
+                self.output.push_str(&format!("\"{}\"", escaped));
+            }
+            Command::NewSimp {
+                family,
+                genus,
+                species,
+                sprite_file,
+                image_count,
+                first_image,
+                plane,
+            }
+            | Command::NewComp {
+                family,
+                genus,
+                species,
+                sprite_file,
+                image_count,
+                first_image,
+                plane,
+            } => {
+                let [family, genus, species] = self.classifier(thread, [family, genus, species])?;
+                let sprite_file = self.string(thread, sprite_file)?;
+                self.int(thread, image_count)?;
+                self.int(thread, first_image)?;
+                let plane = self.int(thread, plane)?;
+                let kind = match command {
+                    Command::NewSimp { .. } => AgentKind::Simple,
+                    _ => AgentKind::Compound,
+                };
+                let (width, height) = self
+                    .world
+                    .sprites
+                    .get(&sprite_file)
+                    .copied()
+                    .unwrap_or_default();
+                let agent = AgentState {
+                    kind,
+                    sprite_file,
+                    plane,
+                    width,
+                    height,
+                    ..AgentState::new(family, genus, species)
+                };
+                thread.targ = Some(self.world.add_agent(agent));
+            }
+            Command::Kill { agent } => {
+                let agent = self.agent(thread, agent)?;
+                let agent = self.valid(agent)?;
+                self.world.kill(agent);
+            }
+            Command::Attr { attributes } => {
+                let attributes = self.int(thread, attributes)?;
+                self.target_mut(thread)?.attributes = attributes;
+            }
+            Command::Plne { plane } => {
+                let plane = self.int(thread, plane)?;
+                self.target_mut(thread)?.plane = plane;
+            }
+            Command::Pose { pose } => {
+                let pose = self.int(thread, pose)?;
+                self.target_mut(thread)?.pose = pose;
+            }
+            Command::Rnge { distance } => {
+                let distance = self.float_arg(thread, distance)?;
+                self.target_mut(thread)?.range = distance;
+            }
+            // Without walls, moving safely is the same as moving.
+            Command::Mvto { x, y } | Command::Mvsf { x, y } => {
+                let x = self.float_arg(thread, x)?;
+                let y = self.float_arg(thread, y)?;
+                let target = self.target_mut(thread)?;
+                (target.x, target.y) = (x, y);
+            }
+            Command::Mvby { delta_x, delta_y } => {
+                let delta_x = self.float_arg(thread, delta_x)?;
+                let delta_y = self.float_arg(thread, delta_y)?;
+                let target = self.target_mut(thread)?;
+                target.x += delta_x;
+                target.y += delta_y;
+            }
+            Command::Rtar {
+                family,
+                genus,
+                species,
+            } => {
+                let [family, genus, species] = self.classifier(thread, [family, genus, species])?;
+                let agents: Vec<_> = self.world.matching(family, genus, species).collect();
+                thread.targ = self.choose(&agents);
+            }
+            Command::Star {
+                family,
+                genus,
+                species,
+            } => {
+                let [family, genus, species] = self.classifier(thread, [family, genus, species])?;
+                let agents = self.enumerate(thread, Enumeration::Esee, family, genus, species)?;
+                thread.targ = self.choose(&agents);
+            }
+            Command::Spas {
+                vehicle,
+                new_passenger,
+            } => {
+                let vehicle = self.agent(thread, vehicle)?;
+                let vehicle = self.valid(vehicle)?;
+                let passenger = self.agent(thread, new_passenger)?;
+                let passenger = self.valid(passenger)?;
+                let passenger = self.world.agent_mut(passenger).expect("The agent is alive");
+                passenger.vehicle = Some(vehicle);
+            }
+            Command::Dpas {
+                family,
+                genus,
+                species,
+            } => {
+                let [family, genus, species] = self.classifier(thread, [family, genus, species])?;
+                let vehicle = self.valid(thread.targ)?;
+                for id in self
+                    .world
+                    .matching(family, genus, species)
+                    .collect::<Vec<_>>()
+                {
+                    let agent = self.world.agent_mut(id).expect("The agent is alive");
+                    if agent.vehicle == Some(vehicle) {
+                        agent.vehicle = None;
+                    }
+                }
+            }
+            // Ports are not modelled, only which agents are joined.
+            Command::PrtJoin {
+                source_agent,
+                output_id,
+                dest_agent,
+                input_id,
+            } => {
+                let source = self.agent(thread, source_agent)?;
+                let source = self.valid(source)?;
+                self.int(thread, output_id)?;
+                let dest = self.agent(thread, dest_agent)?;
+                let dest = self.valid(dest)?;
+                self.int(thread, input_id)?;
+                self.world.connect(source, dest);
+            }
+            _ => return Err(unsupported(keyword(command))),
+        }
+        Ok(Some(next))
+    }
+
+    /// A random one of some agents, or `NULL` if there are none.
+    fn choose(&mut self, agents: &[AgentId]) -> Option<AgentId> {
+        let last = i32::try_from(agents.len().checked_sub(1)?).ok()?;
+        Some(agents[self.rng.between(0, last) as usize])
+    }
+
+    fn assign(
+        &mut self,
+        thread: &mut Thread,
+        var: &Variable,
+        value: Value,
+    ) -> Result<(), CaosError> {
+        let place = self.place(thread, var)?;
+        self.set(thread, place, value);
+        Ok(())
+    }
+
+    /// Applies an arithmetic command, which gives an integer if both the
+    /// variable and the number are integers and a float otherwise.
+    fn arithmetic(
+        &mut self,
+        thread: &mut Thread,
+        var: &Variable,
+        number: Value,
+        int_op: fn(i32, i32) -> i32,
+        float_op: fn(f32, f32) -> f32,
+    ) -> Result<(), CaosError> {
+        let place = self.place(thread, var)?;
+        let value = match (self.get(thread, &place).to_number()?, number) {
+            (Value::Integer(a), Value::Integer(b)) => Value::Integer(int_op(a, b)),
+            (a, b) => Value::Float(float_op(a.to_float()?, b.to_float()?)),
+        };
+        self.set(thread, place, value);
+        Ok(())
+    }
+
+    /// Applies a command which works only on integers.
+    fn bitwise(
+        &mut self,
+        thread: &mut Thread,
+        var: &Variable,
+        number: i32,
+        op: fn(i32, i32) -> i32,
+    ) -> Result<(), CaosError> {
+        let place = self.place(thread, var)?;
+        let value = op(self.get(thread, &place).to_int()?, number);
+        self.set(thread, place, Value::Integer(value));
+        Ok(())
+    }
+}
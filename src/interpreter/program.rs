@@ -0,0 +1,284 @@
+use super::eval::keyword;
+use crate::ast::{AgentArg, Command, Condition, IntArg, ScriptDefinition};
+use crate::{CaosError, ErrorType, Span, SpanNode};
+use std::collections::HashMap;
+
+/// A script compiled for the [Interpreter](super::Interpreter).
+///
+/// Blocks are flattened into jumps, so that a [Thread](super::Thread) can be
+/// paused between any two instructions and resumed later.
+#[derive(Debug, Clone, Default)]
+pub struct Program {
+    pub(super) ops: Vec<Op>,
+    spans: Vec<Option<Span>>,
+    /// The keyword of the command each instruction starts, if any.
+    keywords: Vec<Option<&'static str>>,
+    /// The first instruction of each subroutine, by label.
+    labels: HashMap<String, usize>,
+}
+
+/// A single instruction of a [Program].
+#[derive(Debug, Clone)]
+pub(super) enum Op {
+    /// A command with no body.
+    Command(Command),
+    Jump(usize),
+    /// Jumps to the target if the condition is false.
+    JumpUnless(Condition, usize),
+    /// Starts a `REPS` loop, or jumps past its end if the count is below 1.
+    RepsStart(IntArg, usize),
+    /// Ends an iteration of the innermost `REPS` loop, jumping back to the
+    /// start of its body if there are iterations left.
+    RepsEnd(usize),
+    Gsub(usize),
+    Retn,
+    /// Starts an `ENUM`, `ESEE`, `ETCH` or `EPAS` loop over the agents
+    /// matching a family, genus and species, jumping to its [Op::Next].
+    Enum(Enumeration, [IntArg; 3], usize),
+    /// Starts an `ECON` loop, jumping to its [Op::Next].
+    Econ(AgentArg, usize),
+    /// Moves the innermost enumeration on to its next agent, jumping back to
+    /// the start of its body if there is one.
+    Next(usize),
+}
+
+/// The agents an enumeration loops over.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Enumeration {
+    /// Every agent.
+    Enum,
+    /// The agents `OWNR` can see.
+    Esee,
+    /// The agents `OWNR` is touching.
+    Etch,
+    /// The passengers of `OWNR`.
+    Epas,
+}
+
+impl Program {
+    /// Compiles the commands of a script, without spans, so errors running
+    /// it have no location.
+    pub fn compile(definition: &ScriptDefinition) -> Result<Program, CaosError> {
+        Compiler::default().finish(definition, None)
+    }
+
+    /// Compiles the commands of a script along with the span node of each
+    /// command, as the children of a script's node from
+    /// [parse_cos_with_spans](crate::parse_cos_with_spans), so that errors and
+    /// [Program::span] point at the source.
+    pub fn compile_with_spans(
+        definition: &ScriptDefinition,
+        spans: &[SpanNode],
+    ) -> Result<Program, CaosError> {
+        Compiler::default().finish(definition, Some(spans))
+    }
+
+    /// The number of instructions.
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// The source of the instruction at `pc`, if the program was compiled
+    /// with spans. Instructions closing a block, such as `ENDI`, have none.
+    pub fn span(&self, pc: usize) -> Option<Span> {
+        self.spans.get(pc).copied().flatten()
+    }
+
+    /// The keyword of the command the instruction at `pc` starts, such as
+    /// `NEW: SIMP` or `DOIF`. Instructions within a command, such as the
+    /// jump back to the start of a loop, have none, as does the jump past a
+    /// `SUBR`.
+    pub fn keyword(&self, pc: usize) -> Option<&'static str> {
+        self.keywords.get(pc).copied().flatten()
+    }
+
+    /// The label of the subroutine whose first instruction is at `pc`.
+    pub fn subroutine(&self, pc: usize) -> Option<&str> {
+        self.labels
+            .iter()
+            .find(|(_, &start)| start == pc)
+            .map(|(label, _)| label.as_str())
+    }
+}
+
+#[derive(Default)]
+struct Compiler {
+    program: Program,
+    /// Instructions jumping to a label, with the label and its source.
+    gsubs: Vec<(usize, String, Option<Span>)>,
+}
+
+impl Compiler {
+    fn finish(
+        mut self,
+        definition: &ScriptDefinition,
+        spans: Option<&[SpanNode]>,
+    ) -> Result<Program, CaosError> {
+        self.definition(definition, spans);
+        for (pc, label, span) in self.gsubs {
+            let Some(&target) = self.program.labels.get(&label) else {
+                let error = CaosError::new(ErrorType::UnknownLabel { label }, span);
+                return Err(error);
+            };
+            match &mut self.program.ops[pc] {
+                Op::Gsub(t) | Op::Jump(t) => *t = target,
+                _ => unreachable!("Only jumps refer to labels"),
+            }
+        }
+        Ok(self.program)
+    }
+
+    fn push(&mut self, op: Op, span: Option<Span>) -> usize {
+        self.program.ops.push(op);
+        self.program.spans.push(span);
+        self.program.keywords.push(None);
+        self.program.ops.len() - 1
+    }
+
+    /// Pushes the first instruction of a command.
+    fn start(&mut self, command: &Command, op: Op, span: Option<Span>) -> usize {
+        let pc = self.push(op, span);
+        self.program.keywords[pc] = Some(keyword(command));
+        pc
+    }
+
+    fn next(&self) -> usize {
+        self.program.ops.len()
+    }
+
+    fn patch(&mut self, pc: usize) {
+        let target = self.next();
+        match &mut self.program.ops[pc] {
+            Op::Jump(t)
+            | Op::JumpUnless(_, t)
+            | Op::RepsStart(_, t)
+            | Op::Enum(_, _, t)
+            | Op::Econ(_, t) => *t = target,
+            _ => unreachable!("Only jumps are patched"),
+        }
+    }
+
+    /// Compiles commands along with their span nodes, if known.
+    fn definition(&mut self, definition: &ScriptDefinition, spans: Option<&[SpanNode]>) {
+        for (i, command) in definition.commands.iter().enumerate() {
+            self.command(command, spans.and_then(|s| s.get(i)));
+        }
+    }
+
+    fn command(&mut self, command: &Command, node: Option<&SpanNode>) {
+        let children = node.map(|n| n.children.as_slice()).unwrap_or_default();
+        let child = |i: usize| node.and(children.get(i));
+        let body = |from: usize| node.map(|_| children.get(from..).unwrap_or_default());
+        // The span from the start of a block to the end of its header.
+        let header = |last: Option<&SpanNode>| {
+            node.map(|n| match last {
+                Some(last) => Span {
+                    end: last.span.end,
+                    end_line_col: last.span.end_line_col,
+                    ..n.span
+                },
+                None => n.span,
+            })
+        };
+
+        match command {
+            Command::Doif(do_if) => {
+                let mut end_jumps = Vec::new();
+                let mut next_child = 0;
+                let branches = std::iter::once((&do_if.condition, &do_if.definition))
+                    .chain(do_if.elif_definitions.iter().map(|(c, d)| (c, d)));
+                for (i, (condition, definition)) in branches.enumerate() {
+                    let condition_node = child(next_child);
+                    let span = match i {
+                        0 => header(condition_node),
+                        _ => condition_node.map(|n| n.span),
+                    };
+                    let op = Op::JumpUnless(condition.clone(), 0);
+                    let jump = match i {
+                        0 => self.start(command, op, span),
+                        _ => self.push(op, span),
+                    };
+                    self.definition(definition, body(next_child + 1));
+                    next_child += 1 + definition.commands.len();
+                    end_jumps.push(self.push(Op::Jump(0), None));
+                    self.patch(jump);
+                }
+                if let Some(else_definition) = &do_if.else_definition {
+                    self.definition(else_definition, body(next_child));
+                }
+                for jump in end_jumps {
+                    self.patch(jump);
+                }
+            }
+            Command::Reps { count, definition } => {
+                let op = Op::RepsStart((**count).clone(), 0);
+                let start = self.start(command, op, header(child(0)));
+                self.definition(definition, body(1));
+                self.push(Op::RepsEnd(start + 1), None);
+                self.patch(start);
+            }
+            Command::LoopEver { definition } => {
+                let start = self.next();
+                self.definition(definition, body(0));
+                self.push(Op::Jump(start), None);
+            }
+            Command::LoopUntl {
+                definition,
+                condition,
+            } => {
+                let start = self.next();
+                self.definition(definition, body(0));
+                let span = child(definition.commands.len()).map(|n| n.span);
+                self.push(Op::JumpUnless(condition.clone(), start), span);
+            }
+            Command::Subr { label, definition } => {
+                // Scripts run straight past a subroutine.
+                let skip = self.push(Op::Jump(0), header(child(0)));
+                self.program
+                    .labels
+                    .insert(label.as_ref().to_owned(), self.next());
+                self.definition(definition, body(1));
+                self.push(Op::Retn, None);
+                self.patch(skip);
+            }
+            Command::Gsub { destination } | Command::Goto { destination } => {
+                let op = match command {
+                    Command::Gsub { .. } => Op::Gsub(0),
+                    _ => Op::Jump(0),
+                };
+                let span = node.map(|n| n.span);
+                let pc = self.start(command, op, span);
+                let label = destination.as_ref().to_owned();
+                self.gsubs.push((pc, label, span));
+            }
+            Command::Econ { agent, definition } => {
+                let op = Op::Econ((**agent).clone(), 0);
+                let start = self.start(command, op, header(child(0)));
+                self.definition(definition, body(1));
+                self.patch(start);
+                self.push(Op::Next(start + 1), None);
+            }
+            Command::Enum(e) | Command::Esee(e) | Command::Etch(e) | Command::Epas(e) => {
+                let enumeration = match command {
+                    Command::Enum(_) => Enumeration::Enum,
+                    Command::Esee(_) => Enumeration::Esee,
+                    Command::Etch(_) => Enumeration::Etch,
+                    _ => Enumeration::Epas,
+                };
+                let classifier = [&e.family, &e.genus, &e.species].map(|arg| (**arg).clone());
+                let op = Op::Enum(enumeration, classifier, 0);
+                let start = self.start(command, op, header(child(2)));
+                self.definition(&e.definition, body(3));
+                self.patch(start);
+                self.push(Op::Next(start + 1), None);
+            }
+            _ => {
+                self.start(command, Op::Command(command.clone()), node.map(|n| n.span));
+            }
+        }
+    }
+}
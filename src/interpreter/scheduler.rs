@@ -0,0 +1,196 @@
+use super::{AgentId, Interpreter, Step, Thread, Value};
+use crate::CaosError;
+
+/// The number of instructions a script runs each tick unless it is `INST`.
+pub const SLOW_INSTRUCTIONS_PER_TICK: usize = 5;
+
+/// The event number of the timer script, run every `TICK` ticks unless the
+/// agent is already running a script.
+pub const TIMER_EVENT: i32 = 9;
+
+/// The `MESG WRIT` message ids the engine gives a meaning to. Any other id
+/// runs the event script with the same number.
+pub mod messages {
+    pub const ACTIVATE_1: i32 = 0;
+    pub const ACTIVATE_2: i32 = 1;
+    pub const DEACTIVATE: i32 = 2;
+    pub const HIT: i32 = 3;
+    pub const PICKUP: i32 = 4;
+    pub const DROP: i32 = 5;
+}
+
+/// A message waiting to be delivered to an agent, as sent by `MESG WRIT` or
+/// `MESG WRT+`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub to: AgentId,
+    /// The message id, such as [messages::ACTIVATE_1].
+    pub message_id: i32,
+    /// The agent which sent the message, given to the script as `FROM`.
+    pub from: Option<AgentId>,
+    pub p1: Value,
+    pub p2: Value,
+    /// The ticks to wait after the next tick before delivering the message.
+    pub delay: i32,
+}
+
+impl Message {
+    /// A message with no sender, parameters or delay.
+    pub fn new(to: AgentId, message_id: i32) -> Self {
+        Self {
+            to,
+            message_id,
+            from: None,
+            p1: Value::default(),
+            p2: Value::default(),
+            delay: 0,
+        }
+    }
+
+    /// The event script a message runs. The first three messages are not in
+    /// the order of the scripts they run.
+    pub fn event(&self) -> i32 {
+        match self.message_id {
+            messages::ACTIVATE_1 => 1,
+            messages::ACTIVATE_2 => 2,
+            messages::DEACTIVATE => 0,
+            id => id,
+        }
+    }
+}
+
+impl Interpreter {
+    /// The number of ticks run, as given by `WTIK`.
+    pub fn ticks(&self) -> u32 {
+        self.ticks
+    }
+
+    /// Sends a message, to be delivered in a later tick.
+    pub fn send(&mut self, message: Message) {
+        self.messages.push(message);
+    }
+
+    /// Clicks an agent, sending it the activate 1 message.
+    pub fn click(&mut self, agent: AgentId) {
+        self.send(Message::new(agent, messages::ACTIVATE_1));
+    }
+
+    /// The script an agent is running, if any.
+    pub fn running(&self, agent: AgentId) -> Option<&Thread> {
+        self.threads.get(&agent)
+    }
+
+    /// Starts an agent's script for an event, stopping any script it was
+    /// running. Returns `false` if there is no such script, or if the
+    /// running script is `LOCK`ed and so cannot be interrupted.
+    pub fn start_event(
+        &mut self,
+        agent: AgentId,
+        event: i32,
+        from: Option<AgentId>,
+        p1: Value,
+        p2: Value,
+    ) -> bool {
+        if self.threads.get(&agent).is_some_and(|t| t.lock) {
+            return false;
+        }
+        let Some(mut thread) = self.event_thread(agent, event) else {
+            return false;
+        };
+        (thread.from, thread.p1, thread.p2) = (from, p1, p2);
+        self.threads.insert(agent, thread);
+        true
+    }
+
+    /// Runs a number of ticks, stopping at the first error.
+    pub fn run_ticks(&mut self, ticks: usize) -> Result<(), CaosError> {
+        for _ in 0..ticks {
+            self.tick()?;
+        }
+        Ok(())
+    }
+
+    /// Runs a tick. Messages due this tick are delivered in the order they
+    /// were sent, each starting its script as [Interpreter::start_event]
+    /// does. Timer scripts due are then started for agents which are not
+    /// already running a script, so a timer never interrupts one. Finally
+    /// every running script runs, in the order of its agent's id: to its end
+    /// or a `WAIT` if it is `INST`, and otherwise for
+    /// [SLOW_INSTRUCTIONS_PER_TICK] instructions.
+    ///
+    /// A script which fails is stopped, and the error is returned without
+    /// running the rest of the tick.
+    pub fn tick(&mut self) -> Result<(), CaosError> {
+        self.ticks += 1;
+
+        let (due, later) = std::mem::take(&mut self.messages)
+            .into_iter()
+            .partition(|m| m.delay <= 0);
+        self.messages = later;
+        for message in &mut self.messages {
+            message.delay -= 1;
+        }
+        for message in due {
+            if self.world.agent(message.to).is_some() {
+                let event = message.event();
+                self.start_event(message.to, event, message.from, message.p1, message.p2);
+            }
+        }
+
+        let mut timers = Vec::new();
+        for id in self.world.agents().map(|(id, _)| id).collect::<Vec<_>>() {
+            let agent = self.world.agent_mut(id).expect("The agent is alive");
+            if agent.tick_rate > 0 {
+                agent.timer += 1;
+                if agent.timer >= agent.tick_rate {
+                    agent.timer = 0;
+                    timers.push(id);
+                }
+            }
+        }
+        for id in timers {
+            if self.threads.contains_key(&id) {
+                continue;
+            }
+            self.start_event(id, TIMER_EVENT, None, Value::default(), Value::default());
+        }
+
+        for id in self.threads.keys().copied().collect::<Vec<_>>() {
+            let mut thread = self.threads.remove(&id).expect("The thread is running");
+            if thread.waiting > 0 {
+                thread.waiting -= 1;
+                self.threads.insert(id, thread);
+                continue;
+            }
+            self.run_slice(&mut thread)?;
+            // The scripts of killed agents stop with them.
+            if !thread.is_finished() && self.world.agent(id).is_some() {
+                self.threads.insert(id, thread);
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs a thread for a tick.
+    fn run_slice(&mut self, thread: &mut Thread) -> Result<(), CaosError> {
+        let mut slow = SLOW_INSTRUCTIONS_PER_TICK;
+        for _ in 0..self.instruction_limit {
+            if thread
+                .owner
+                .is_some_and(|id| self.world.agent(id).is_none())
+            {
+                return Ok(());
+            }
+            if self.step(thread)? != Step::Continue {
+                return Ok(());
+            }
+            if !thread.inst {
+                slow -= 1;
+                if slow == 0 {
+                    return Ok(());
+                }
+            }
+        }
+        Err(self.instruction_limit_error(thread))
+    }
+}
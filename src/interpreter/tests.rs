@@ -0,0 +1,572 @@
+use super::*;
+use crate::{parse_cos_with_spans, parse_script_body_str};
+
+fn run(source: &str) -> (Interpreter, Thread) {
+    let script = parse_script_body_str(source).expect("Parsed");
+    let mut interpreter = Interpreter::new();
+    let thread = interpreter.run(&script).expect("Ran");
+    (interpreter, thread)
+}
+
+fn run_err(source: &str) -> CaosError {
+    let script = parse_script_body_str(source).expect("Parsed");
+    Interpreter::new().run(&script).expect_err("Failed")
+}
+
+#[test]
+fn test_arithmetic() {
+    let (_, thread) = run(
+        "setv va00 7 addv va00 3 mulv va00 4 subv va00 1 divv va00 2 \
+         setv va01 17 modv va01 5 \
+         setv va02 12 andv va02 10 setv va03 12 orrv va03 3 \
+         setv va04 5 negv va04 setv va05 -6 absv va05",
+    );
+    let values: Vec<_> = (0..6).map(|i| thread.vaxx(i).clone()).collect();
+    // Dividing integers rounds towards zero.
+    assert_eq!(
+        values,
+        [
+            19.into(),
+            2.into(),
+            8.into(),
+            15.into(),
+            (-5).into(),
+            6.into()
+        ]
+    );
+}
+
+#[test]
+fn test_int_float_coercion() {
+    let (_, thread) = run("setv va00 1 addv va00 0.5 \
+         setv va01 7 divv va01 2.0 \
+         setv va02 2.6 setv va03 rand va02 va02 \
+         setv va04 ftoi -2.5 \
+         setv va05 itof 3");
+    assert_eq!(thread.vaxx(0), &Value::Float(1.5));
+    assert_eq!(thread.vaxx(1), &Value::Float(3.5));
+    // A float given for an integer is rounded to the nearest.
+    assert_eq!(thread.vaxx(3), &Value::Integer(3));
+    assert_eq!(thread.vaxx(4), &Value::Integer(-3));
+    assert_eq!(thread.vaxx(5), &Value::Float(3.0));
+}
+
+#[test]
+fn test_strings() {
+    let (_, thread) = run("sets va00 \"Hello\" adds va00 \", world\" \
+         sets va01 subs va00 8 5 \
+         setv va02 strl va00 \
+         setv va03 char va00 1 \
+         char va00 1 106 \
+         setv va04 sins va00 1 \"o\" setv va05 sins va00 6 \"o\" setv va06 sins va00 1 \"z\" \
+         setv va07 stoi \" -42abc\" setv va08 stof \"2.5x\" \
+         sets va09 vtos 1.5 sets va10 vtos -3 \
+         sets va11 uppa \"abc\" sets va12 lowa \"DEF\"");
+    let values: Vec<_> = (0..13).map(|i| thread.vaxx(i).clone()).collect();
+    assert_eq!(
+        values,
+        [
+            "jello, world".into(),
+            "world".into(),
+            12.into(),
+            72.into(),
+            5.into(),
+            9.into(),
+            (-1).into(),
+            (-42).into(),
+            2.5f32.into(),
+            "1.500000".into(),
+            "-3".into(),
+            "ABC".into(),
+            "def".into(),
+        ]
+    );
+}
+
+#[test]
+fn test_trigonometry() {
+    let (_, thread) = run("setv va00 sin_ 90 setv va01 atan 1 setv va02 sqrt 16");
+    assert_eq!(thread.vaxx(0), &Value::Float(1.0));
+    assert_eq!(thread.vaxx(1), &Value::Float(45.0));
+    assert_eq!(thread.vaxx(2), &Value::Float(4.0));
+}
+
+#[test]
+fn test_doif() {
+    let source = "doif va00 eq 0 and va01 ne 0 setv va02 1 \
+                  elif va00 eq 0 setv va02 2 \
+                  else setv va02 3 endi \
+                  doif \"b\" gt \"a\" or 1 eq 2 setv va03 1 endi \
+                  doif 2 lt 1.5 setv va04 1 else setv va04 2 endi";
+    let (_, thread) = run(source);
+    assert_eq!(thread.vaxx(2), &Value::Integer(2));
+    assert_eq!(thread.vaxx(3), &Value::Integer(1));
+    assert_eq!(thread.vaxx(4), &Value::Integer(2));
+}
+
+#[test]
+fn test_loops() {
+    let (_, thread) = run("reps 3 reps 2 addv va00 1 repe repe \
+         reps 0 setv va01 1 repe \
+         loop addv va02 1 untl va02 ge 5");
+    assert_eq!(thread.vaxx(0), &Value::Integer(6));
+    assert_eq!(thread.vaxx(1), &Value::Integer(0));
+    assert_eq!(thread.vaxx(2), &Value::Integer(5));
+}
+
+#[test]
+fn test_subroutines() {
+    let (interpreter, thread) = run("gsub twice gsub twice outv va00 stop \
+         subr twice gsub once gsub once retn \
+         subr once addv va00 1 retn");
+    assert_eq!(thread.vaxx(0), &Value::Integer(4));
+    assert_eq!(interpreter.output(), "4");
+    assert!(thread.call_stack().is_empty());
+
+    // Scripts run past subroutines, and GOTO does not return.
+    let (interpreter, _) = run(
+        "outs \"a\" subr skip outs \"b\" retn goto end outs \"c\" subr end outs \"d\" stop retn",
+    );
+    assert_eq!(interpreter.output(), "ad");
+}
+
+#[test]
+fn test_agent_variables() {
+    let script = parse_script_body_str(
+        "setv ov00 1 setv mv01 2 seta va00 ownr setv avar va00 2 3 \
+         targ null doif targ eq null and ownr ne null setv va01 1 endi",
+    )
+    .unwrap();
+    let mut interpreter = Interpreter::new();
+    let owner = interpreter.world.add_agent(AgentState::default());
+    let mut thread = Thread::new(Arc::new(Program::compile(&script).unwrap()), Some(owner));
+    interpreter.run_thread(&mut thread).unwrap();
+
+    let agent = interpreter.world.agent(owner).unwrap();
+    assert_eq!(agent.ovxx[..3], [1.into(), 2.into(), 3.into()]);
+    assert_eq!(thread.vaxx(0), &Value::from(owner));
+    assert_eq!(thread.vaxx(1), &Value::Integer(1));
+    assert_eq!(thread.targ, None);
+
+    let error = run_err("setv ov00 1");
+    assert_eq!(*error.error_type(), ErrorType::InvalidAgent);
+}
+
+#[test]
+fn test_game_variables() {
+    let (interpreter, thread) = run(
+        "setv game \"count\" 3 addv game \"count\" 1 sets eame \"name\" \"x\" \
+         setv va00 game \"unset\" setv game \"gone\" 1 delg \"gone\"",
+    );
+    assert_eq!(interpreter.world.game_variable("count"), Value::Integer(4));
+    assert_eq!(interpreter.world.eame["name"], Value::from("x"));
+    assert!(!interpreter.world.game.contains_key("gone"));
+    assert_eq!(thread.vaxx(0), &Value::Integer(0));
+}
+
+#[test]
+fn test_rand_is_seeded() {
+    let script = parse_script_body_str(
+        "reps 20 setv va00 rand 5 1 doif va00 lt 1 or va00 gt 5 stop endi outv va00 repe",
+    )
+    .unwrap();
+    let mut first = Interpreter::new();
+    first.run(&script).unwrap();
+    assert_eq!(first.output().len(), 20);
+
+    let mut second = Interpreter::new();
+    second.run(&script).unwrap();
+    assert_eq!(first.output(), second.output());
+
+    second.seed(7);
+    second.take_output();
+    second.run(&script).unwrap();
+    assert_ne!(first.output(), second.output());
+}
+
+#[test]
+fn test_stop_and_step() {
+    let script = parse_script_body_str("setv va00 1 stop setv va00 2").unwrap();
+    let mut interpreter = Interpreter::new();
+    let mut thread = Thread::new(Arc::new(Program::compile(&script).unwrap()), None);
+    assert_eq!(interpreter.step(&mut thread).unwrap(), Step::Continue);
+    assert_eq!(thread.pc(), 1);
+    assert_eq!(interpreter.step(&mut thread).unwrap(), Step::Finished);
+    assert!(thread.is_finished());
+    assert_eq!(thread.vaxx(0), &Value::Integer(1));
+}
+
+#[test]
+fn test_runtime_errors() {
+    let cases = [
+        ("setv va00 1 divv va00 0", "E0403"),
+        ("sets va00 \"a\" addv va00 1", "E0402"),
+        ("setv va00 char \"abc\" 4", "E0404"),
+        ("sets va00 subs \"abc\" 2 3", "E0404"),
+        ("cmra 0 0 0", "E0401"),
+        ("esee 0 0 0 next", "E0405"),
+        ("kill null", "E0405"),
+        ("gsub nowhere", "E0406"),
+    ];
+    for (source, code) in cases {
+        assert_eq!(run_err(source).code(), code, "{}", source);
+    }
+
+    let script = parse_script_body_str("loop ever").unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.instruction_limit = 100;
+    let error = interpreter.run(&script).unwrap_err();
+    assert_eq!(
+        *error.error_type(),
+        ErrorType::InstructionLimit { limit: 100 }
+    );
+}
+
+#[test]
+fn test_errors_point_at_source() {
+    let source = "inst\nsetv va00 1\ndoif va00 eq 1\n  setv va01 va00\n  divv va01 0\nendi\n";
+    let (cos_file, spans) = parse_cos_with_spans(source).unwrap();
+    let crate::ast::Script::Install(definition) = &cos_file.scripts[0] else {
+        panic!("Expected an install script");
+    };
+    let program = Program::compile_with_spans(definition, &spans.children[0].children).unwrap();
+    assert_eq!(program.span(2).unwrap().as_str(source), "doif va00 eq 1");
+
+    let mut thread = Thread::new(Arc::new(program), None);
+    let error = Interpreter::new().run_thread(&mut thread).unwrap_err();
+    assert_eq!(error.span().unwrap().as_str(source), "divv va01 0");
+    assert_eq!(error.line_col(), Some((5, 3)));
+
+    // Without spans, the error has no location.
+    let error = run_err("setv va01 1\ndivv va01 0");
+    assert_eq!(*error.error_type(), ErrorType::DivisionByZero);
+    assert!(error.span().is_none());
+}
+
+/// An interpreter with a CAOS file loaded, where sprite files named `A` to
+/// `Z` are 10 pixels square.
+fn load(source: &str) -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    for c in 'a'..='z' {
+        interpreter.world.sprites.insert(c.to_string(), (10, 10));
+    }
+    interpreter.load(source).expect("Loaded");
+    interpreter
+}
+
+fn classifiers(world: &World) -> Vec<[i32; 3]> {
+    world
+        .agents()
+        .map(|(_, a)| [a.family, a.genus, a.species])
+        .collect()
+}
+
+#[test]
+fn test_new_agents() {
+    let interpreter = load(
+        "new: simp 2 11 100 \"a\" 1 0 500 attr 3 mvto 100 50 mvby 5 -10 setv ov00 unid \
+         new: comp 2 11 101 \"z\" 1 0 600 \
+         new: simp 3 8 1 \"unknown\" 1 0 700 setv game \"third\" unid",
+    );
+    let world = &interpreter.world;
+    assert_eq!(classifiers(world), [[2, 11, 100], [2, 11, 101], [3, 8, 1]]);
+    let (id, first) = world.agents().next().unwrap();
+    assert_eq!(first.kind, AgentKind::Simple);
+    assert_eq!((first.attributes, first.plane), (3, 500));
+    assert_eq!(
+        (first.x, first.y, first.width, first.height),
+        (105.0, 40.0, 10, 10)
+    );
+    assert_eq!(first.ovxx[0], Value::Integer(id.0 as i32));
+    assert_eq!(
+        world
+            .matching(2, 11, 101)
+            .map(|id| world.agent(id).unwrap().kind)
+            .next(),
+        Some(AgentKind::Compound)
+    );
+    assert_eq!(world.game_variable("third"), Value::Integer(3));
+    assert_eq!(world.agent(AgentId(3)).unwrap().width, 0);
+}
+
+#[test]
+fn test_positions() {
+    let mut interpreter = load("new: simp 1 1 1 \"a\" 1 0 0 mvto 20 30");
+    let script = parse_script_body_str(
+        "setv va00 posl setv va01 post setv va02 posr setv va03 posb \
+         setv va04 posx setv va05 posy setv va06 wdth setv va07 fmly",
+    )
+    .unwrap();
+    let mut thread = Thread::new(
+        Arc::new(Program::compile(&script).unwrap()),
+        Some(AgentId(1)),
+    );
+    interpreter.run_thread(&mut thread).unwrap();
+    let values: Vec<_> = (0..8).map(|i| thread.vaxx(i).clone()).collect();
+    let expected: [Value; 8] = [
+        20.0f32.into(),
+        30.0f32.into(),
+        30.0f32.into(),
+        40.0f32.into(),
+        25.0f32.into(),
+        35.0f32.into(),
+        10.into(),
+        1.into(),
+    ];
+    assert_eq!(values, expected);
+}
+
+#[test]
+fn test_enum() {
+    let interpreter = load(
+        "reps 3 new: simp 2 11 0 \"a\" 1 0 0 repe new: simp 2 12 0 \"a\" 1 0 0 \
+         new: simp 3 11 0 \"a\" 1 0 0 \
+         enum 2 0 0 setv ov00 unid addv game \"families\" 1 next \
+         enum 0 11 0 addv game \"genera\" 1 next \
+         targ null enum 0 0 0 next doif targ eq null setv game \"restored\" 1 endi \
+         enum 2 11 0 doif unid eq 1 kill agnt 2 endi addv game \"visited\" 1 next",
+    );
+    let world = &interpreter.world;
+    assert_eq!(world.game_variable("families"), Value::Integer(4));
+    assert_eq!(world.game_variable("genera"), Value::Integer(4));
+    assert_eq!(world.game_variable("restored"), Value::Integer(1));
+    // Agents killed during the loop are skipped.
+    assert_eq!(world.game_variable("visited"), Value::Integer(2));
+    assert_eq!(world.agents().count(), 4);
+    assert_eq!(world.agent(AgentId(4)).unwrap().ovxx[0], Value::Integer(4));
+}
+
+#[test]
+fn test_esee_etch_epas() {
+    let mut interpreter = load(
+        "new: simp 1 1 1 \"a\" 1 0 0 mvto 0 0 rnge 100 \
+         new: simp 2 0 1 \"a\" 1 0 0 mvto 5 5 \
+         new: simp 2 0 2 \"a\" 1 0 0 mvto 50 50 \
+         new: simp 2 0 3 \"a\" 1 0 0 mvto 500 500 \
+         spas agnt 1 agnt 4",
+    );
+    let script = parse_script_body_str(
+        "esee 2 0 0 outv spcs next outs \" \" \
+         etch 0 0 0 outv spcs next outs \" \" \
+         epas 0 0 0 outv spcs next outs \" \" \
+         setv va00 seee ownr agnt 3 setv va01 touc ownr agnt 3",
+    )
+    .unwrap();
+    let program = Arc::new(Program::compile(&script).unwrap());
+    let mut thread = Thread::new(program, Some(AgentId(1)));
+    interpreter.run_thread(&mut thread).unwrap();
+    assert_eq!(interpreter.output(), "12 1 3 ");
+    assert_eq!(thread.vaxx(0), &Value::Integer(1));
+    assert_eq!(thread.vaxx(1), &Value::Integer(0));
+    assert_eq!(thread.targ, Some(AgentId(1)));
+}
+
+#[test]
+fn test_econ() {
+    let interpreter = load(
+        "reps 4 new: simp 1 0 0 \"a\" 1 0 0 repe \
+         prt: join agnt 1 0 agnt 2 0 prt: join agnt 3 0 agnt 2 1 \
+         econ agnt 1 outv unid next",
+    );
+    assert_eq!(interpreter.output(), "23");
+}
+
+#[test]
+fn test_kill() {
+    let interpreter = load(
+        "new: simp 1 0 0 \"a\" 1 0 0 seta va00 targ seta game \"agent\" targ \
+         new: simp 1 0 0 \"a\" 1 0 0 \
+         kill va00 \
+         doif va00 eq null and game \"agent\" eq null and agnt 1 eq null setv game \"cleared\" 1 endi \
+         setv game \"left\" totl 1 0 0",
+    );
+    let world = &interpreter.world;
+    assert_eq!(world.game_variable("cleared"), Value::Integer(1));
+    assert_eq!(world.game_variable("left"), Value::Integer(1));
+    assert_eq!(
+        world.agents().map(|(id, _)| id).collect::<Vec<_>>(),
+        [AgentId(2)]
+    );
+}
+
+#[test]
+fn test_classifier_queries() {
+    let interpreter = load(
+        "new: simp 1 1 1 \"a\" 1 0 0 new: simp 1 2 1 \"a\" 1 0 0 \
+         new: simp 1 1 2 \"a\" 1 0 0 new: simp 1 1 3 \"a\" 1 0 0 \
+         setv game \"totl\" totl 1 1 0 \
+         seta va00 ncls null 1 1 0 targ va00 outv unid \
+         seta va00 ncls va00 1 1 0 targ va00 outv unid \
+         seta va00 ncls agnt 2 1 1 0 targ va00 outv unid \
+         seta va00 ncls agnt 4 1 1 0 targ va00 outv unid \
+         seta va00 pcls agnt 1 1 1 0 targ va00 outv unid \
+         seta va00 pcls agnt 3 1 1 0 targ va00 outv unid \
+         doif ncls null 9 9 9 eq null outs \"-\" endi \
+         rtar 1 2 0 outv unid rtar 9 0 0 doif targ eq null outs \"-\" endi",
+    );
+    assert_eq!(interpreter.world.game_variable("totl"), Value::Integer(3));
+    assert_eq!(interpreter.output(), "133141-2-");
+}
+
+#[test]
+fn test_event_scripts() {
+    let mut interpreter = load(
+        "new: simp 2 11 100 \"a\" 1 0 0 new: simp 2 11 200 \"a\" 1 0 0 \
+         scrp 2 11 100 1 setv ov00 1 endm \
+         scrp 2 11 0 1 setv ov00 2 endm \
+         scrp 2 11 100 2 kill ownr stop endm \
+         rscr enum 0 0 0 kill targ next",
+    );
+    assert!(interpreter.run_event(AgentId(1), 1).unwrap());
+    assert!(interpreter.run_event(AgentId(2), 1).unwrap());
+    assert!(!interpreter.run_event(AgentId(2), 2).unwrap());
+    let ovxx: Vec<_> = interpreter
+        .world
+        .agents()
+        .map(|(_, a)| a.ovxx[0].clone())
+        .collect();
+    assert_eq!(ovxx, [1.into(), 2.into()]);
+
+    assert!(interpreter.run_event(AgentId(1), 2).unwrap());
+    assert_eq!(classifiers(&interpreter.world), [[2, 11, 200]]);
+    assert!(!interpreter.run_event(AgentId(1), 1).unwrap());
+
+    let thread = interpreter.event_thread(AgentId(2), 1).unwrap();
+    assert_eq!(
+        (thread.owner, thread.targ),
+        (Some(AgentId(2)), Some(AgentId(2)))
+    );
+}
+
+fn ovxx(interpreter: &Interpreter, agent: u32, index: usize) -> Value {
+    interpreter.world.agent(AgentId(agent)).unwrap().ovxx[index].clone()
+}
+
+#[test]
+fn test_timers() {
+    let mut interpreter = load(
+        "new: simp 2 1 1 \"a\" 1 0 0 tick 3 \
+         scrp 2 1 1 9 addv ov00 1 doif ov00 eq 3 tick 0 endi endm",
+    );
+    interpreter.run_ticks(8).unwrap();
+    assert_eq!(ovxx(&interpreter, 1, 0), Value::Integer(2));
+    interpreter.run_ticks(10).unwrap();
+    assert_eq!(ovxx(&interpreter, 1, 0), Value::Integer(3));
+    assert_eq!(interpreter.ticks(), 18);
+}
+
+#[test]
+fn test_timers_wait_for_running_scripts() {
+    for rate in [1, 2] {
+        let mut interpreter = load(&format!(
+            "new: simp 2 1 1 \"a\" 1 0 0 tick {} mesg writ targ 1000 \
+             scrp 2 1 1 9 addv ov01 1 endm \
+             scrp 2 1 1 1000 setv ov00 1 wait 3 setv ov00 2 endm",
+            rate
+        ));
+        interpreter.run_ticks(6).unwrap();
+        assert_eq!(ovxx(&interpreter, 1, 0), Value::Integer(2), "tick {}", rate);
+        assert!(interpreter.running(AgentId(1)).is_none(), "tick {}", rate);
+        interpreter.run_ticks(2).unwrap();
+        assert!(
+            ovxx(&interpreter, 1, 1) != Value::Integer(0),
+            "tick {}",
+            rate
+        );
+    }
+}
+
+#[test]
+fn test_messages() {
+    let mut interpreter = load(
+        "new: simp 2 1 1 \"a\" 1 0 0 new: simp 2 1 2 \"a\" 1 0 0 \
+         scrp 2 1 1 1 mesg wrt+ agnt 2 1000 7 \"x\" 2 mesg writ agnt 2 2 endm \
+         scrp 2 1 2 0 setv ov04 wtik endm \
+         scrp 2 1 2 1000 seta ov00 from setv ov01 _p1_ sets ov02 _p2_ setv ov03 wtik endm",
+    );
+    interpreter.click(AgentId(1));
+    interpreter.run_ticks(2).unwrap();
+    // Message 2 is deactivate, which runs script 0.
+    assert_eq!(ovxx(&interpreter, 2, 4), Value::Integer(2));
+    assert_eq!(ovxx(&interpreter, 2, 3), Value::Integer(0));
+    interpreter.run_ticks(2).unwrap();
+    let values: Vec<_> = (0..4).map(|i| ovxx(&interpreter, 2, i)).collect();
+    assert_eq!(values, [AgentId(1).into(), 7.into(), "x".into(), 4.into()]);
+
+    let message = Message::new(AgentId(1), messages::ACTIVATE_2);
+    assert_eq!(message.event(), 2);
+    assert_eq!(Message::new(AgentId(1), 1000).event(), 1000);
+}
+
+#[test]
+fn test_inst_and_slow() {
+    let body = "addv ov00 1 ".repeat(12);
+    let mut interpreter = load(&format!(
+        "new: simp 2 1 1 \"a\" 1 0 0 new: simp 2 1 2 \"a\" 1 0 0 \
+         scrp 2 1 1 1 {body} endm scrp 2 1 2 1 inst {body} slow {body} endm"
+    ));
+    interpreter.click(AgentId(1));
+    interpreter.click(AgentId(2));
+    interpreter.tick().unwrap();
+    assert_eq!(ovxx(&interpreter, 1, 0), Value::Integer(5));
+    assert_eq!(ovxx(&interpreter, 2, 0), Value::Integer(16));
+    interpreter.run_ticks(2).unwrap();
+    assert_eq!(ovxx(&interpreter, 1, 0), Value::Integer(12));
+    assert!(interpreter.running(AgentId(1)).is_none());
+    assert_eq!(ovxx(&interpreter, 2, 0), Value::Integer(24));
+}
+
+#[test]
+fn test_wait() {
+    let mut interpreter = load(
+        "new: simp 2 1 1 \"a\" 1 0 0 \
+         scrp 2 1 1 1 inst addv ov00 1 wait 2 addv ov00 1 endm",
+    );
+    interpreter.click(AgentId(1));
+    interpreter.run_ticks(3).unwrap();
+    assert_eq!(ovxx(&interpreter, 1, 0), Value::Integer(1));
+    assert_eq!(interpreter.running(AgentId(1)).unwrap().waiting(), 0);
+    interpreter.tick().unwrap();
+    assert_eq!(ovxx(&interpreter, 1, 0), Value::Integer(2));
+
+    // Outside the scheduler, no time passes.
+    interpreter.run_event(AgentId(1), 1).unwrap();
+    assert_eq!(ovxx(&interpreter, 1, 0), Value::Integer(4));
+}
+
+#[test]
+fn test_lock() {
+    let source = "new: simp 2 1 1 \"a\" 1 0 0 \
+                  scrp 2 1 1 1 LOCKING wait 3 setv ov00 1 endm \
+                  scrp 2 1 1 2 setv ov01 1 endm";
+    for (locking, expected) in [("lock", [1, 0]), ("", [0, 1])] {
+        let mut interpreter = load(&source.replace("LOCKING", locking));
+        interpreter.click(AgentId(1));
+        interpreter.tick().unwrap();
+        interpreter.send(Message::new(AgentId(1), messages::ACTIVATE_2));
+        interpreter.run_ticks(5).unwrap();
+        let values = [ovxx(&interpreter, 1, 0), ovxx(&interpreter, 1, 1)];
+        assert_eq!(values, expected.map(Value::from), "{}", locking);
+    }
+}
+
+#[test]
+fn test_scheduled_errors_and_kills() {
+    let mut interpreter = load(
+        "new: simp 2 1 1 \"a\" 1 0 0 new: simp 2 1 2 \"a\" 1 0 0 \
+         scrp 2 1 1 1 setv va00 0 divv va00 va00 endm \
+         scrp 2 1 2 1 kill ownr setv game \"after\" 1 endm",
+    );
+    interpreter.click(AgentId(1));
+    let error = interpreter.tick().unwrap_err();
+    assert_eq!(*error.error_type(), ErrorType::DivisionByZero);
+    assert!(interpreter.running(AgentId(1)).is_none());
+
+    interpreter.click(AgentId(2));
+    interpreter.tick().unwrap();
+    assert!(interpreter.running(AgentId(2)).is_none());
+    assert_eq!(interpreter.world.game_variable("after"), Value::Integer(0));
+    // Messages to killed agents are dropped.
+    interpreter.click(AgentId(2));
+    interpreter.tick().unwrap();
+}
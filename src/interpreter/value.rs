@@ -0,0 +1,150 @@
+use crate::{CaosError, ErrorType};
+use std::fmt;
+
+/// The unique id of an agent in a [World](super::World), as returned by
+/// `UNID`.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy)]
+pub struct AgentId(pub u32);
+
+impl fmt::Display for AgentId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A value held by a variable, or given by an expression.
+///
+/// Variables start as the integer 0.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    Integer(i32),
+    Float(f32),
+    String(String),
+    /// An agent, or `None` for `NULL`.
+    Agent(Option<AgentId>),
+}
+
+impl Default for Value {
+    fn default() -> Self {
+        Value::Integer(0)
+    }
+}
+
+impl Value {
+    /// The kind of value held, as used in
+    /// [ErrorType::WrongValueType](crate::ErrorType::WrongValueType).
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Integer(_) => "an integer",
+            Value::Float(_) => "a float",
+            Value::String(_) => "a string",
+            Value::Agent(_) => "an agent",
+        }
+    }
+
+    /// The value as an integer, with floats rounded to the nearest integer
+    /// as the engine does wherever a float is given for an integer.
+    pub fn to_int(&self) -> Result<i32, CaosError> {
+        match self {
+            Value::Integer(i) => Ok(*i),
+            Value::Float(f) => Ok(float_to_int(*f)),
+            _ => Err(self.wrong_type("an integer")),
+        }
+    }
+
+    /// The value as a float, with integers converted exactly where they can
+    /// be.
+    pub fn to_float(&self) -> Result<f32, CaosError> {
+        match self {
+            Value::Integer(i) => Ok(*i as f32),
+            Value::Float(f) => Ok(*f),
+            _ => Err(self.wrong_type("a float")),
+        }
+    }
+
+    /// The value as a number, keeping whether it is an integer or a float.
+    pub fn to_number(&self) -> Result<Value, CaosError> {
+        match self {
+            Value::Integer(_) | Value::Float(_) => Ok(self.clone()),
+            _ => Err(self.wrong_type("a number")),
+        }
+    }
+
+    pub fn to_str(&self) -> Result<&str, CaosError> {
+        match self {
+            Value::String(s) => Ok(s),
+            _ => Err(self.wrong_type("a string")),
+        }
+    }
+
+    pub fn to_agent(&self) -> Result<Option<AgentId>, CaosError> {
+        match self {
+            Value::Agent(a) => Ok(*a),
+            _ => Err(self.wrong_type("an agent")),
+        }
+    }
+
+    pub(super) fn wrong_type(&self, expected: &'static str) -> CaosError {
+        let error_type = ErrorType::WrongValueType {
+            expected,
+            found: self.type_name(),
+        };
+        CaosError::new(error_type, None)
+    }
+}
+
+/// Rounds a float to the nearest integer, as `FTOI` does, saturating at the
+/// limits of an integer.
+pub(super) fn float_to_int(f: f32) -> i32 {
+    f.round() as i32
+}
+
+impl fmt::Display for Value {
+    /// Writes the value as `VTOS` and `OUTV` do, with floats to six decimal
+    /// places.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Integer(i) => write!(f, "{}", i),
+            Value::Float(x) => write!(f, "{:.6}", x),
+            Value::String(s) => write!(f, "{}", s),
+            Value::Agent(Some(id)) => write!(f, "agent {}", id),
+            Value::Agent(None) => write!(f, "NULL"),
+        }
+    }
+}
+
+impl From<i32> for Value {
+    fn from(i: i32) -> Self {
+        Value::Integer(i)
+    }
+}
+
+impl From<f32> for Value {
+    fn from(f: f32) -> Self {
+        Value::Float(f)
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_owned())
+    }
+}
+
+impl From<Option<AgentId>> for Value {
+    fn from(a: Option<AgentId>) -> Self {
+        Value::Agent(a)
+    }
+}
+
+impl From<AgentId> for Value {
+    fn from(a: AgentId) -> Self {
+        Value::Agent(Some(a))
+    }
+}
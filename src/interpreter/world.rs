@@ -0,0 +1,224 @@
+use super::{AgentId, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// The number of `OVxx` variables each agent has.
+pub(super) const AGENT_VARIABLES: usize = 100;
+
+/// How far a new agent can see and hear, in pixels.
+const DEFAULT_RANGE: f32 = 500.0;
+
+/// The state scripts act on, beyond their own `VAxx` variables.
+#[derive(Debug, Default, Clone)]
+pub struct World {
+    agents: BTreeMap<AgentId, AgentState>,
+    next_id: u32,
+    /// Pairs of agents joined through their ports, lower id first.
+    connections: BTreeSet<(AgentId, AgentId)>,
+    /// The `GAME` variables, by name.
+    pub game: HashMap<String, Value>,
+    /// The `EAME` variables, by name.
+    pub eame: HashMap<String, Value>,
+    /// The width and height of the images in each sprite file, by name, as
+    /// there are no sprite files to read them from. Agents drawn from any
+    /// other file have no size.
+    pub sprites: HashMap<String, (i32, i32)>,
+}
+
+/// Whether an agent was made by `NEW: SIMP` or `NEW: COMP`.
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Default)]
+pub enum AgentKind {
+    #[default]
+    Simple,
+    Compound,
+}
+
+/// An agent in a [World].
+#[derive(Debug, Clone)]
+pub struct AgentState {
+    pub family: i32,
+    pub genus: i32,
+    pub species: i32,
+    pub kind: AgentKind,
+    pub sprite_file: String,
+    pub plane: i32,
+    pub pose: i32,
+    /// The top left corner.
+    pub x: f32,
+    pub y: f32,
+    pub width: i32,
+    pub height: i32,
+    /// The attribute flags set by `ATTR`.
+    pub attributes: i32,
+    /// How far the agent can see, as set by `RNGE`.
+    pub range: f32,
+    /// The ticks between runs of the timer script, or 0 for none, as set by
+    /// `TICK`.
+    pub tick_rate: i32,
+    /// The ticks since the timer script last ran.
+    pub timer: i32,
+    /// The vehicle the agent is a passenger of.
+    pub vehicle: Option<AgentId>,
+    /// The `OVxx` variables.
+    pub ovxx: Vec<Value>,
+}
+
+impl Default for AgentState {
+    fn default() -> Self {
+        Self {
+            family: 0,
+            genus: 0,
+            species: 0,
+            kind: AgentKind::default(),
+            sprite_file: String::new(),
+            plane: 0,
+            pose: 0,
+            x: 0.0,
+            y: 0.0,
+            width: 0,
+            height: 0,
+            attributes: 0,
+            range: DEFAULT_RANGE,
+            tick_rate: 0,
+            timer: 0,
+            vehicle: None,
+            ovxx: vec![Value::default(); AGENT_VARIABLES],
+        }
+    }
+}
+
+impl AgentState {
+    /// A simple agent with the given classifier.
+    pub fn new(family: i32, genus: i32, species: i32) -> Self {
+        Self {
+            family,
+            genus,
+            species,
+            ..Self::default()
+        }
+    }
+
+    /// Whether the agent matches a classifier, where 0 matches anything.
+    pub fn matches(&self, family: i32, genus: i32, species: i32) -> bool {
+        [
+            (family, self.family),
+            (genus, self.genus),
+            (species, self.species),
+        ]
+        .iter()
+        .all(|&(wanted, actual)| wanted == 0 || wanted == actual)
+    }
+
+    /// The right and bottom edges of the agent.
+    pub fn bottom_right(&self) -> (f32, f32) {
+        (self.x + self.width as f32, self.y + self.height as f32)
+    }
+
+    /// The centre of the agent.
+    pub fn centre(&self) -> (f32, f32) {
+        let (right, bottom) = self.bottom_right();
+        ((self.x + right) / 2.0, (self.y + bottom) / 2.0)
+    }
+
+    /// Whether the agent overlaps another.
+    pub fn touches(&self, other: &AgentState) -> bool {
+        let (right, bottom) = self.bottom_right();
+        let (other_right, other_bottom) = other.bottom_right();
+        self.x < other_right && other.x < right && self.y < other_bottom && other.y < bottom
+    }
+
+    /// Whether another agent is within the agent's range.
+    pub fn can_see(&self, other: &AgentState) -> bool {
+        let ((x1, y1), (x2, y2)) = (self.centre(), other.centre());
+        (x2 - x1).hypot(y2 - y1) <= self.range
+    }
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an agent, returning its id. Ids start at 1 and are not reused.
+    pub fn add_agent(&mut self, agent: AgentState) -> AgentId {
+        self.next_id += 1;
+        let id = AgentId(self.next_id);
+        self.agents.insert(id, agent);
+        id
+    }
+
+    /// Removes an agent, along with its connections and any passengers it
+    /// was carrying. Returns `false` if there was no such agent.
+    pub fn kill(&mut self, id: AgentId) -> bool {
+        if self.agents.remove(&id).is_none() {
+            return false;
+        }
+        self.connections.retain(|&(a, b)| a != id && b != id);
+        for agent in self.agents.values_mut() {
+            if agent.vehicle == Some(id) {
+                agent.vehicle = None;
+            }
+        }
+        true
+    }
+
+    pub fn agent(&self, id: AgentId) -> Option<&AgentState> {
+        self.agents.get(&id)
+    }
+
+    pub fn agent_mut(&mut self, id: AgentId) -> Option<&mut AgentState> {
+        self.agents.get_mut(&id)
+    }
+
+    /// Every agent, in the order they were added.
+    pub fn agents(&self) -> impl Iterator<Item = (AgentId, &AgentState)> {
+        self.agents.iter().map(|(id, agent)| (*id, agent))
+    }
+
+    /// The agents matching a classifier, where 0 matches anything, in the
+    /// order they were added.
+    pub fn matching(
+        &self,
+        family: i32,
+        genus: i32,
+        species: i32,
+    ) -> impl Iterator<Item = AgentId> + '_ {
+        self.agents()
+            .filter(move |(_, agent)| agent.matches(family, genus, species))
+            .map(|(id, _)| id)
+    }
+
+    /// Joins two agents, as `PRT: JOIN` does through their ports.
+    pub fn connect(&mut self, a: AgentId, b: AgentId) {
+        if a != b {
+            self.connections.insert((a.min(b), a.max(b)));
+        }
+    }
+
+    /// The agents joined to an agent, directly or through other agents, in
+    /// the order they were added.
+    pub fn connected(&self, id: AgentId) -> Vec<AgentId> {
+        let mut found = BTreeSet::from([id]);
+        let mut queue = vec![id];
+        while let Some(next) = queue.pop() {
+            for &(a, b) in &self.connections {
+                let other = if a == next {
+                    b
+                } else if b == next {
+                    a
+                } else {
+                    continue;
+                };
+                if found.insert(other) {
+                    queue.push(other);
+                }
+            }
+        }
+        found.remove(&id);
+        found.into_iter().collect()
+    }
+
+    /// The value of a `GAME` variable, which is 0 if it has not been set.
+    pub fn game_variable(&self, name: &str) -> Value {
+        self.game.get(name).cloned().unwrap_or_default()
+    }
+}
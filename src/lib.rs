@@ -1,6 +1,25 @@
 pub mod ast;
+pub mod c2;
 mod caos_error;
+mod cst;
+pub mod dap;
+mod diagnostic;
+mod dialect;
+pub mod docs;
+mod encoding;
+pub mod interpreter;
 mod parser;
+mod printer;
+pub mod signature;
+mod span;
+pub mod testing;
+pub mod visit;
 
 pub use caos_error::*;
+pub use cst::*;
+pub use diagnostic::*;
+pub use dialect::*;
+pub use encoding::*;
 pub use parser::*;
+pub use printer::*;
+pub use span::*;
@@ -3,19 +3,34 @@ mod base;
 mod caos_program;
 mod command_parser;
 mod condition_parser;
+mod dialect;
 mod expression_parser;
+mod fragment;
 mod partial;
+mod recovery;
 mod script;
+mod suggest;
 
 use caos_program::*;
 pub(crate) use command_parser::*;
-pub(self) use condition_parser::*;
+use condition_parser::*;
+pub use dialect::{check_dialect, parse_cos_with_dialect};
 pub(crate) use expression_parser::*;
+pub use fragment::{
+    parse_command_str, parse_condition_str, parse_expression_str, parse_script_body_str,
+};
 pub(crate) use partial::*;
-use pest::Parser;
+use pest::{
+    error::InputLocation,
+    iterators::{Pair, Pairs},
+    Parser,
+};
+use recovery::Recovery;
+pub use recovery::{parse_cos_recovering, RecoveredParse};
 use script::*;
+pub use suggest::suggest_keyword;
 
-use crate::{ast::CosFile, CaosError, ErrorType};
+use crate::{ast::CosFile, CaosError, Cst, Encoding, ErrorType, Span, SpanNode};
 use pest_derive::Parser;
 
 #[derive(Parser)]
@@ -32,13 +47,79 @@ use pest_derive::Parser;
 #[grammar = "grammar/script.pest"]
 struct CaosParser;
 
+/// Parses a CAOS file.
+///
+/// The whole of `cos_content` must be CAOS: anything after the last script
+/// which is not whitespace or a comment, such as a stray word after `ENDM`,
+/// is an error.
 pub fn parse_cos(cos_content: &str) -> Result<CosFile, CaosError> {
-    let res = CaosParser::parse(Rule::program, cos_content)
-        .map_err(|e| CaosError::new_from_error(Box::new(e)))?
+    parse_cos_with_spans(cos_content).map(|(cos_file, _)| cos_file)
+}
+
+/// Parses a CAOS file read as bytes in `encoding`, such as a `.cos` file
+/// written for the engine in [Windows-1252](Encoding::Windows1252).
+///
+/// Spans in errors are within the decoded source, which is
+/// `encoding.decode(bytes)`.
+pub fn parse_cos_bytes(bytes: &[u8], encoding: Encoding) -> Result<CosFile, CaosError> {
+    parse_cos(&encoding.decode(bytes))
+}
+
+/// Parses a CAOS file, additionally returning the source spans of every node in
+/// the resulting AST.
+///
+/// The root [SpanNode] covers the whole file, and has one child per script in
+/// [CosFile::scripts]. See [SpanNode] for how the tree maps onto the AST.
+pub fn parse_cos_with_spans(cos_content: &str) -> Result<(CosFile, SpanNode), CaosError> {
+    parse_program(parse_program_pair(cos_content)?)
+}
+
+/// Parses a CAOS file into a lossless [Cst], which keeps the comments and
+/// whitespace of the source alongside its AST.
+pub fn parse_cst(cos_content: &str) -> Result<Cst, CaosError> {
+    let pair = parse_program_pair(cos_content)?;
+    let (cos_file, spans) = parse_program(pair.clone())?;
+    Ok(Cst::new(cos_content, pair, cos_file, spans))
+}
+
+fn parse_program_pair(cos_content: &str) -> Result<Pair<'_, Rule>, CaosError> {
+    CaosParser::parse(Rule::program, cos_content)
+        .map_err(|e| syntax_error(e, cos_content))?
         .next()
         .ok_or(CaosError::new(
-            ErrorType::ParseError { line_col: (0, 0) },
-            String::from("Unknown parsing error"),
-        ))?;
-    parse_program(res)
+            ErrorType::Syntax {
+                message: String::from("Unknown parsing error"),
+            },
+            None,
+        ))
+}
+
+/// Converts a pest error, suggesting a keyword if it was caused by a misspelt one.
+fn syntax_error(e: pest::error::Error<Rule>, cos_content: &str) -> CaosError {
+    let pos = match e.location {
+        InputLocation::Pos(pos) | InputLocation::Span((pos, _)) => pos,
+    };
+    suggest::with_suggestion_at(CaosError::from_pest(e, cos_content), cos_content, pos)
+}
+
+/// A zero-width span at the end of the last of `pairs`, or of their input if
+/// there are none, where running out of tokens is reported.
+pub(crate) fn end_span(pairs: &Pairs<'_, Rule>) -> Span {
+    let input = pairs.get_input();
+    let end = pairs
+        .clone()
+        .last()
+        .map_or(input.len(), |pair| pair.as_span().end());
+    pest::Span::new(input, end, end)
+        .expect("End is within the input")
+        .into()
+}
+
+/// Places an [ErrorType::UnexpectedEnd] error at `end`. These are found on
+/// running out of tokens, so have no span of their own.
+pub(crate) fn locate_end(e: CaosError, end: Span) -> CaosError {
+    match (e.error_type(), e.span()) {
+        (ErrorType::UnexpectedEnd { .. }, None) => e.with_span(end),
+        _ => e,
+    }
 }
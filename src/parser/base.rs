@@ -3,9 +3,10 @@ mod tests;
 
 use crate::{
     ast::{ByteString, Label},
-    CaosError, Rule,
+    CaosError, Encoding, ErrorType, Rule,
 };
 use pest::iterators::Pair;
+use std::num::ParseIntError;
 
 pub fn parse_label(pair: Pair<Rule>) -> Result<Label, CaosError> {
     match pair.as_rule() {
@@ -40,21 +41,46 @@ pub fn parse_int_literal(pair: Pair<Rule>) -> Result<i32, CaosError> {
     }
 }
 
+/// An error for an integer literal which is out of range.
+fn overflow_error(pair: &Pair<Rule>, e: ParseIntError) -> CaosError {
+    let text = pair.as_str().to_owned();
+    CaosError::new(
+        ErrorType::IntegerOverflow { text },
+        Some(pair.as_span().into()),
+    )
+    .with_source(e)
+}
+
 fn parse_int_inner(pair: Pair<Rule>) -> Result<i32, CaosError> {
     match pair.as_rule() {
         Rule::int_decimal => pair
             .as_str()
             .parse::<i32>()
-            .map_err(|_| CaosError::new_parse_error(pair)),
+            .map_err(|e| overflow_error(&pair, e)),
         Rule::int_char_escape => Ok('\'' as i32),
-        Rule::int_char_ok => pair
-            .as_str()
-            .chars()
-            .next()
-            .map(|i| Ok(i as i32))
-            .unwrap_or(Err(CaosError::new_parse_error(pair))),
+        Rule::int_char_ok => {
+            let Some(c) = pair.as_str().chars().next() else {
+                return Err(CaosError::new_parse_error(pair));
+            };
+            // The engine reads a character as its byte in Windows-1252. Source
+            // decoded as Latin-1 has control characters for the bytes `0x80`
+            // to `0x9F` instead, which are read as those bytes.
+            let byte = Encoding::Windows1252
+                .byte(c)
+                .or_else(|| Encoding::Latin1.byte(c));
+            match byte {
+                Some(b) => Ok(i32::from(b)),
+                None => Err(CaosError::new(
+                    ErrorType::Unencodable {
+                        character: c,
+                        encoding: Encoding::Windows1252,
+                    },
+                    Some(pair.as_span().into()),
+                )),
+            }
+        }
         Rule::int_binary => {
-            i32::from_str_radix(pair.as_str(), 2).map_err(|_| CaosError::new_parse_error(pair))
+            i32::from_str_radix(pair.as_str(), 2).map_err(|e| overflow_error(&pair, e))
         }
         _ => Err(CaosError::new_parse_error(pair)),
     }
@@ -64,10 +90,14 @@ pub fn parse_float_literal(pair: Pair<Rule>) -> Result<f32, CaosError> {
     if pair.as_rule() != Rule::literal_float {
         return Err(CaosError::new_parse_error(pair));
     }
-    return pair
-        .as_str()
-        .parse::<f32>()
-        .map_err(|_| CaosError::new_parse_error(pair));
+    pair.as_str().parse::<f32>().map_err(|e| {
+        let text = pair.as_str().to_owned();
+        CaosError::new(
+            ErrorType::InvalidFloat { text },
+            Some(pair.as_span().into()),
+        )
+        .with_source(e)
+    })
 }
 
 pub fn parse_string_literal(pair: Pair<Rule>) -> Result<String, CaosError> {
@@ -77,7 +107,7 @@ pub fn parse_string_literal(pair: Pair<Rule>) -> Result<String, CaosError> {
     }
 }
 
-fn parse_string_raw(pair: Pair<Rule>) -> Result<&str, CaosError> {
+fn parse_string_raw(pair: Pair<'_, Rule>) -> Result<&str, CaosError> {
     match pair.as_rule() {
         Rule::string_raw => Ok(pair.as_str()),
         Rule::escape_newline => Ok("\n"),
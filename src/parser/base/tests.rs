@@ -132,6 +132,16 @@ fn test_atomic_string() {
     }
 }
 
+#[test]
+fn test_accented_string() {
+    for p in CaosParser::parse(Rule::literal_string, r#""Zoë’s café""#).expect("Parsed") {
+        assert_eq!(
+            parse_string_literal(p).expect("Parsed string"),
+            String::from("Zoë’s café")
+        );
+    }
+}
+
 #[test]
 fn test_decimal_int() {
     for p in CaosParser::parse(Rule::literal_int, r#"0"#).expect("Parsed") {
@@ -162,6 +172,22 @@ fn test_char_int() {
     }
 }
 
+#[test]
+fn test_accented_char_int() {
+    for p in CaosParser::parse(Rule::literal_int, r#"'é'"#).expect("Parsed") {
+        assert_eq!(parse_int_literal(p).expect("Parsed string"), 0xE9);
+    }
+    // Characters are read as their byte in Windows-1252.
+    for p in CaosParser::parse(Rule::literal_int, r#"'€'"#).expect("Parsed") {
+        assert_eq!(parse_int_literal(p).expect("Parsed string"), 0x80);
+    }
+    for p in CaosParser::parse(Rule::literal_int, r#"'日'"#).expect("Parsed") {
+        let error = parse_int_literal(p).expect_err("Not in Windows-1252");
+        assert_eq!(error.code(), "E0009");
+        assert_eq!(error.message(), "`日` cannot be written in Windows-1252");
+    }
+}
+
 #[test]
 fn test_bytestring_empty() {
     for p in CaosParser::parse(Rule::literal_byte_string, r#"[]"#).expect("Parsed") {
@@ -1,12 +1,12 @@
 use crate::{
     ast::{CosFile, Script},
-    CaosError, Rule,
+    CaosError, Rule, SpanNode,
 };
 use pest::iterators::Pair;
 
 use super::{parse_script_contents, script::parse_script};
 
-pub fn parse_program(pair: Pair<Rule>) -> Result<CosFile, CaosError> {
+pub fn parse_program(pair: Pair<Rule>) -> Result<(CosFile, SpanNode), CaosError> {
     if pair.as_rule() != Rule::program {
         return Err(CaosError::new_parse_error(pair));
     }
@@ -18,20 +18,25 @@ pub fn parse_program(pair: Pair<Rule>) -> Result<CosFile, CaosError> {
         .ok_or(CaosError::new_parse_error(pair.clone()))
         .and_then(parse_implict_install_script)?;
 
-    let scripts = it
+    let (scripts, spans): (Vec<Script>, Vec<SpanNode>) = it
         .next()
         .ok_or(CaosError::new_parse_error(pair.clone()))
-        .and_then(|pair| parse_scripts(pair, implicit_script))?;
-
-    Ok(CosFile { scripts })
+        .and_then(|pair| parse_scripts(pair, implicit_script))?
+        .into_iter()
+        .unzip();
+
+    Ok((
+        CosFile { scripts },
+        SpanNode::with_children(pair.as_span().into(), spans),
+    ))
 }
 
-fn parse_implict_install_script(pair: Pair<Rule>) -> Result<Option<Script>, CaosError> {
+fn parse_implict_install_script(pair: Pair<Rule>) -> Result<Option<(Script, SpanNode)>, CaosError> {
     if pair.as_rule() != Rule::implicit_install_script {
         return Err(CaosError::new_parse_error(pair));
     }
 
-    let contents = pair
+    let (contents, spans) = pair
         .clone()
         .into_inner()
         .next()
@@ -41,11 +46,17 @@ fn parse_implict_install_script(pair: Pair<Rule>) -> Result<Option<Script>, Caos
     if contents.commands.is_empty() {
         Ok(None)
     } else {
-        Ok(Some(Script::Install(contents)))
+        Ok(Some((
+            Script::Install(contents),
+            SpanNode::with_children(pair.as_span().into(), spans),
+        )))
     }
 }
 
-fn parse_scripts(pair: Pair<Rule>, implicit: Option<Script>) -> Result<Vec<Script>, CaosError> {
+fn parse_scripts(
+    pair: Pair<Rule>,
+    implicit: Option<(Script, SpanNode)>,
+) -> Result<Vec<(Script, SpanNode)>, CaosError> {
     if pair.as_rule() != Rule::scripts {
         return Err(CaosError::new_parse_error(pair));
     }
@@ -68,7 +79,9 @@ mod tests {
 
     fn parse_program_str(content: &str) -> CosFile {
         let mut ps = CaosParser::parse(Rule::program, content).expect("Parsed");
-        parse_program(ps.next().unwrap()).expect("Successful parse")
+        parse_program(ps.next().unwrap())
+            .expect("Successful parse")
+            .0
     }
 
     #[test]
@@ -173,4 +186,53 @@ mod tests {
             CosFile::default()
         );
     }
+
+    #[test]
+    fn test_program_spans() {
+        let content = "ISCR\nSETV VA05 RAND 1 2\nDOIF VA05 EQ 1\n  NOHH\nENDI\nENDM";
+        let mut ps = CaosParser::parse(Rule::program, content).expect("Parsed");
+        let (_, spans) = parse_program(ps.next().unwrap()).expect("Successful parse");
+
+        assert_eq!(spans.children.len(), 1);
+        let script = &spans.children[0];
+        assert_eq!(script.span.as_str(content), content);
+        assert_eq!(script.children.len(), 2);
+
+        let setv = &script.children[0];
+        assert_eq!(setv.span.as_str(content), "SETV VA05 RAND 1 2");
+        assert_eq!(setv.span.start_line_col, (2, 1));
+        assert_eq!(setv.children.len(), 2);
+        assert_eq!(setv.children[0].span.as_str(content), "VA05");
+        assert_eq!(setv.children[1].span.as_str(content), "RAND 1 2");
+        assert_eq!(setv.children[1].children.len(), 2);
+
+        let doif = &script.children[1];
+        assert_eq!(doif.span.as_str(content), "DOIF VA05 EQ 1\n  NOHH\nENDI");
+        assert_eq!(doif.span.end_line_col, (5, 5));
+        assert_eq!(doif.children.len(), 2);
+        assert_eq!(doif.children[0].span.as_str(content), "VA05 EQ 1");
+        assert_eq!(doif.children[1].span.as_str(content), "NOHH");
+        assert_eq!(doif.children[1].span.start_line_col, (4, 3));
+    }
+
+    #[test]
+    fn test_program_spans_path_to() {
+        let content = "NEW: SIMP 1 2 3 \"x\" 1 0 VA05";
+        let mut ps = CaosParser::parse(Rule::program, content).expect("Parsed");
+        let (_, spans) = parse_program(ps.next().unwrap()).expect("Successful parse");
+
+        let path = spans.path_to(content.find("VA05").unwrap());
+        let innermost = path.last().unwrap();
+        assert_eq!(innermost.span.as_str(content), "VA05");
+        assert_eq!(path[path.len() - 2].span.as_str(content), content);
+    }
+
+    #[test]
+    fn test_program_trailing_tokens() {
+        assert!(CaosParser::parse(Rule::program, "SCRP 1 2 3 4 NOHH ENDM * end\n").is_ok());
+        assert!(CaosParser::parse(Rule::program, "SCRP 1 2 3 4 NOHH ENDM junk").is_err());
+
+        let error = crate::parse_cos("SCRP 1 2 3 4 NOHH ENDM junk").unwrap_err();
+        assert_eq!(error.span().map(|s| s.start), Some(23));
+    }
 }
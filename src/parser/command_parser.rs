@@ -7,9 +7,9 @@ mod command_stack;
 mod command_thunk;
 
 use crate::{
-    ast::{AgentArg, ClassifierEnum, Command, DoIf, IntArg, ScriptDefinition},
+    ast::{AgentArg, ClassifierEnum, Command, DoIf, IntArg, Label, ScriptDefinition},
     parser::parse_condition,
-    CaosError, Rule,
+    CaosError, ErrorType, KeywordRole, Rule, SpanNode,
 };
 use command_control::Control;
 pub(crate) use command_parser_trait::CommandParser;
@@ -18,30 +18,137 @@ use pest::iterators::{Pair, Pairs};
 
 use self::command_stack::CommandStack;
 
-use super::{base::parse_label, parse_expression};
+use super::{
+    base::parse_label, cast_arg, end_span, is_missing_expression, locate_end, parse_expression,
+    suggest::unexpected_token, Recovery,
+};
+
+pub fn parse_commands<'i>(
+    pairs: &mut Pairs<'i, Rule>,
+) -> Result<(Vec<Command>, Vec<SpanNode>), CaosError> {
+    let mut command_stack = CommandStack::new();
+    let end = end_span(pairs);
+
+    while let Some(pair) = pairs.next() {
+        let thunk = parse_command(pair, pairs).map_err(|e| locate_end(e, end))?;
+        command_stack.push(thunk)?;
+    }
+    command_stack.into_commands()
+}
 
-pub fn parse_commands<'i>(pairs: &mut Pairs<'i, Rule>) -> Result<Vec<Command>, CaosError> {
+/// Parses commands as [parse_commands] does, but rather than stopping at the
+/// first error, records it in `errors` and carries on from the next command.
+///
+/// Source which could not be parsed, including any skipped by `recovery` up
+/// to `end`, is replaced by a [Command::Error]. Blocks left open at the end
+/// are closed, as if their closing keyword was present.
+pub fn parse_commands_recovering<'i>(
+    pairs: &mut Pairs<'i, Rule>,
+    end: usize,
+    recovery: &mut Recovery<'_>,
+) -> (Vec<Command>, Vec<SpanNode>) {
     let mut command_stack = CommandStack::new();
+    let tokens_end = end_span(pairs);
 
     while let Some(pair) = pairs.next() {
-        let mut thunk: CommandThunk = find_command_match(pair, pairs)?;
+        while let Some((span, text)) = recovery.skipped_before(pair.as_span().start()) {
+            command_stack.push_error(text, SpanNode::leaf(span));
+        }
+        let remainder = pairs.clone();
+        let result = parse_command(pair.clone(), pairs)
+            .map_err(|e| locate_end(e, tokens_end))
+            .and_then(|t| command_stack.push(t));
+        if let Err(e) = result {
+            recovery.errors.push(e);
+            while pairs.peek().map(|p| !starts_command(&p)).unwrap_or(false) {
+                pairs.next();
+            }
+            let start = pair.as_span().start();
+            let mut error_end = skipped_end(&pair, remainder, pairs.peek());
+            // Source skipped before parsing, up to the next command, is part of the error.
+            let next_start = pairs.peek().map_or(end, |p| p.as_span().start());
+            while let Some((span, _)) = recovery.skipped_before(next_start) {
+                error_end = error_end.max(span.end);
+            }
+            let span = pest::Span::new(pair.get_input(), start, error_end)
+                .expect("Skipped range is within the input");
+            let text = recovery.source[start..error_end].to_owned();
+            command_stack.push_error(text, SpanNode::leaf(span.into()));
+        }
+    }
+    while let Some((span, text)) = recovery.skipped_before(end) {
+        command_stack.push_error(text, SpanNode::leaf(span));
+    }
+    command_stack.into_commands_recovering(&mut recovery.errors)
+}
 
-        loop {
-            if thunk.needs_expression() {
-                match thunk {
-                    CommandThunk::Partial(ref mut p) => {
-                        let arg = parse_expression(pairs)?;
-                        p.arg_parts.push(arg);
+/// Parses a single command, starting at `pair`, along with its arguments.
+fn parse_command<'i>(
+    pair: Pair<'i, Rule>,
+    pairs: &mut Pairs<'i, Rule>,
+) -> Result<CommandThunk<'i>, CaosError> {
+    let mut thunk: CommandThunk = find_command_match(pair, pairs)?;
+    while thunk.needs_expression() {
+        match thunk {
+            CommandThunk::Partial(ref mut p) => {
+                let before = pairs.clone();
+                let (arg, span) = match parse_expression(pairs) {
+                    Ok(parsed) => parsed,
+                    // What was found instead is left to start the next command.
+                    Err(e) if is_missing_expression(&e) => {
+                        *pairs = before;
+                        return Err(p.arity_error());
                     }
-                    _ => unreachable!(),
-                }
-            } else {
-                command_stack.push(thunk)?;
-                break;
+                    Err(e) => return Err(e),
+                };
+                p.push_arg(arg, span);
             }
+            _ => unreachable!(),
         }
     }
-    command_stack.to_commands()
+    Ok(thunk)
+}
+
+/// Returns `true` if `pair` is a keyword which may start a command.
+fn starts_command(pair: &Pair<'_, Rule>) -> bool {
+    matches!(
+        pair.as_rule(),
+        Rule::command_dbg_asrt
+            | Rule::command_gsub
+            | Rule::command_goto
+            | Rule::command_doif
+            | Rule::command_elif
+            | Rule::command_else
+            | Rule::command_subr
+            | Rule::command_reps
+            | Rule::command_loop
+            | Rule::command_econ
+            | Rule::command_enum
+            | Rule::command_etch
+            | Rule::command_esee
+            | Rule::command_epas
+            | Rule::command_untl
+            | Rule::command_endi
+            | Rule::command_retn
+            | Rule::command_ever
+            | Rule::command_next
+            | Rule::command_repe
+    ) || Command::parse_thunk(pair.clone()).is_some()
+}
+
+/// Returns the end of the source skipped over from `start`, which is that of
+/// the last pair in `remainder` before `next`.
+fn skipped_end<'i>(
+    start: &Pair<'i, Rule>,
+    remainder: Pairs<'i, Rule>,
+    next: Option<Pair<'i, Rule>>,
+) -> usize {
+    let next_start = next.map(|p| p.as_span().start());
+    remainder
+        .take_while(|p| Some(p.as_span().start()) != next_start)
+        .last()
+        .map(|p| p.as_span().end())
+        .unwrap_or(start.as_span().end())
 }
 
 fn find_command_match<'i>(
@@ -49,89 +156,106 @@ fn find_command_match<'i>(
     remainder: &mut Pairs<'i, Rule>,
 ) -> Result<CommandThunk<'i>, CaosError> {
     let rule = pair.as_rule();
+    let span = SpanNode::leaf(pair.as_span().into());
     match rule {
         Rule::command_dbg_asrt => {
-            let condition = parse_condition(remainder)?;
+            let (condition, condition_span) = parse_condition(remainder)?;
             Some(CommandThunk::Completed(
-                pair.clone(),
+                SpanNode::with_children(span.span, vec![condition_span]),
                 Command::DbgAsrt { condition },
             ))
         }
         Rule::command_gsub => {
-            let label_p = remainder
-                .next()
-                .ok_or_else(|| CaosError::new_end_of_stream())?;
-            let destination = parse_label(label_p)?;
+            let (destination, label_span) = parse_label_spanned(remainder)?;
             Some(CommandThunk::Completed(
-                pair.clone(),
+                SpanNode::with_children(span.span, vec![label_span]),
                 Command::Gsub { destination },
             ))
         }
         Rule::command_goto => {
-            let label_p = remainder
-                .next()
-                .ok_or_else(|| CaosError::new_end_of_stream())?;
-            let destination = parse_label(label_p)?;
+            let (destination, label_span) = parse_label_spanned(remainder)?;
             Some(CommandThunk::Completed(
-                pair.clone(),
+                SpanNode::with_children(span.span, vec![label_span]),
                 Command::Goto { destination },
             ))
         }
         Rule::command_doif => {
-            let condition = parse_condition(remainder)?;
-            Some(CommandThunk::Start(Control::DoIf(DoIf::empty(condition))))
+            let (condition, condition_span) = parse_condition(remainder)?;
+            Some(CommandThunk::Start(
+                SpanNode::with_children(span.span, vec![condition_span]),
+                Control::DoIf(DoIf::empty(condition)),
+            ))
         }
         Rule::command_elif => {
-            let condition = parse_condition(remainder)?;
-            Some(CommandThunk::StartElif(pair.clone(), condition))
+            let (condition, condition_span) = parse_condition(remainder)?;
+            Some(CommandThunk::StartElif(
+                pair.clone(),
+                condition,
+                condition_span,
+            ))
         }
         Rule::command_else => Some(CommandThunk::StartElse(pair.clone())),
         Rule::command_subr => {
-            let label_p = remainder
-                .next()
-                .ok_or_else(|| CaosError::new_end_of_stream())?;
-            let label = parse_label(label_p)?;
-            Some(CommandThunk::Start(Control::Subr {
-                label,
-                definition: ScriptDefinition::default(),
-            }))
+            let (label, label_span) = parse_label_spanned(remainder)?;
+            Some(CommandThunk::Start(
+                SpanNode::with_children(span.span, vec![label_span]),
+                Control::Subr {
+                    label,
+                    definition: ScriptDefinition::default(),
+                },
+            ))
         }
         Rule::command_reps => {
-            let count: IntArg = parse_expression(remainder)?.try_into()?;
-            Some(CommandThunk::Start(Control::Reps {
-                count: Box::new(count),
-                definition: ScriptDefinition::default(),
-            }))
+            let (count, count_span) = parse_expression(remainder)?;
+            let count: IntArg = cast_arg(count, &count_span)?;
+            Some(CommandThunk::Start(
+                SpanNode::with_children(span.span, vec![count_span]),
+                Control::Reps {
+                    count: Box::new(count),
+                    definition: ScriptDefinition::default(),
+                },
+            ))
         }
-        Rule::command_loop => Some(CommandThunk::Start(Control::Loop {
-            definition: ScriptDefinition::default(),
-        })),
-        Rule::command_econ => {
-            let agent: AgentArg = parse_expression(remainder)?.try_into()?;
-            Some(CommandThunk::Start(Control::Econ {
-                agent: Box::new(agent),
+        Rule::command_loop => Some(CommandThunk::Start(
+            span,
+            Control::Loop {
                 definition: ScriptDefinition::default(),
-            }))
+            },
+        )),
+        Rule::command_econ => {
+            let (agent, agent_span) = parse_expression(remainder)?;
+            let agent: AgentArg = cast_arg(agent, &agent_span)?;
+            Some(CommandThunk::Start(
+                SpanNode::with_children(span.span, vec![agent_span]),
+                Control::Econ {
+                    agent: Box::new(agent),
+                    definition: ScriptDefinition::default(),
+                },
+            ))
         }
         Rule::command_enum => {
-            let classifer = parse_enum_classifier(remainder)?;
-            Some(CommandThunk::Start(Control::Enum(classifer)))
+            let (classifer, span) = parse_enum_classifier(span, remainder)?;
+            Some(CommandThunk::Start(span, Control::Enum(classifer)))
         }
         Rule::command_etch => {
-            let classifer = parse_enum_classifier(remainder)?;
-            Some(CommandThunk::Start(Control::Etch(classifer)))
+            let (classifer, span) = parse_enum_classifier(span, remainder)?;
+            Some(CommandThunk::Start(span, Control::Etch(classifer)))
         }
         Rule::command_esee => {
-            let classifer = parse_enum_classifier(remainder)?;
-            Some(CommandThunk::Start(Control::Esee(classifer)))
+            let (classifer, span) = parse_enum_classifier(span, remainder)?;
+            Some(CommandThunk::Start(span, Control::Esee(classifer)))
         }
         Rule::command_epas => {
-            let classifer = parse_enum_classifier(remainder)?;
-            Some(CommandThunk::Start(Control::Epas(classifer)))
+            let (classifer, span) = parse_enum_classifier(span, remainder)?;
+            Some(CommandThunk::Start(span, Control::Epas(classifer)))
         }
         Rule::command_untl => {
-            let condition = parse_condition(remainder)?;
-            Some(CommandThunk::EndLoop(pair.clone(), condition))
+            let (condition, condition_span) = parse_condition(remainder)?;
+            Some(CommandThunk::EndLoop(
+                pair.clone(),
+                condition,
+                condition_span,
+            ))
         }
         Rule::command_endi
         | Rule::command_retn
@@ -141,17 +265,42 @@ fn find_command_match<'i>(
         _ => None,
     }
     .or_else(|| Command::parse_thunk(pair.clone()))
-    .ok_or_else(|| CaosError::new_parse_error(pair))
+    .ok_or_else(|| unexpected_token(pair, KeywordRole::Command))
+}
+
+fn parse_label_spanned(remainder: &mut Pairs<Rule>) -> Result<(Label, SpanNode), CaosError> {
+    let label_p = remainder.next().ok_or_else(|| {
+        CaosError::new(
+            ErrorType::UnexpectedEnd {
+                expected: Some(String::from("a label")),
+            },
+            None,
+        )
+    })?;
+    let span = SpanNode::leaf(label_p.as_span().into());
+    parse_label(label_p).map(|label| (label, span))
 }
 
-fn parse_enum_classifier(remainder: &mut Pairs<Rule>) -> Result<ClassifierEnum, CaosError> {
-    let family: Box<IntArg> = Box::new(parse_expression(remainder)?.try_into()?);
-    let genus: Box<IntArg> = Box::new(parse_expression(remainder)?.try_into()?);
-    let species: Box<IntArg> = Box::new(parse_expression(remainder)?.try_into()?);
-    Ok(ClassifierEnum {
-        family,
-        genus,
-        species,
-        definition: ScriptDefinition::default(),
-    })
+fn parse_enum_classifier(
+    mut span: SpanNode,
+    remainder: &mut Pairs<Rule>,
+) -> Result<(ClassifierEnum, SpanNode), CaosError> {
+    let mut parse_int_arg = || -> Result<Box<IntArg>, CaosError> {
+        let (arg, arg_span) = parse_expression(remainder)?;
+        let arg = cast_arg(arg, &arg_span)?;
+        span.push(arg_span);
+        Ok(Box::new(arg))
+    };
+    let family = parse_int_arg()?;
+    let genus = parse_int_arg()?;
+    let species = parse_int_arg()?;
+    Ok((
+        ClassifierEnum {
+            family,
+            genus,
+            species,
+            definition: ScriptDefinition::default(),
+        },
+        span,
+    ))
 }
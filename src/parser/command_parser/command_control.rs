@@ -1,4 +1,7 @@
-use crate::ast::{AgentArg, ClassifierEnum, Command, DoIf, IntArg, Label, ScriptDefinition};
+use crate::{
+    ast::{AgentArg, ClassifierEnum, Command, DoIf, IntArg, Label, ScriptDefinition},
+    Rule,
+};
 
 pub enum Control {
     Subr {
@@ -37,4 +40,71 @@ impl Control {
             Control::DoIf(do_if) => do_if.push(command),
         }
     }
+
+    /// Converts the block into its command, as if it had been closed.
+    ///
+    /// A `LOOP` is closed as though by `EVER`.
+    pub fn into_command(self) -> Command {
+        match self {
+            Control::Subr { label, definition } => Command::Subr { label, definition },
+            Control::Reps { count, definition } => Command::Reps { count, definition },
+            Control::Loop { definition } => Command::LoopEver { definition },
+            Control::Econ { agent, definition } => Command::Econ { agent, definition },
+            Control::Enum(e) => Command::Enum(e),
+            Control::Etch(e) => Command::Etch(e),
+            Control::Esee(e) => Command::Esee(e),
+            Control::Epas(e) => Command::Epas(e),
+            Control::DoIf(do_if) => Command::Doif(do_if),
+        }
+    }
+
+    /// The keyword which opens the block.
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            Control::Subr { .. } => "SUBR",
+            Control::Reps { .. } => "REPS",
+            Control::Loop { .. } => "LOOP",
+            Control::DoIf(_) => "DOIF",
+            Control::Econ { .. } => "ECON",
+            Control::Enum(_) => "ENUM",
+            Control::Etch(_) => "ETCH",
+            Control::Esee(_) => "ESEE",
+            Control::Epas(_) => "EPAS",
+        }
+    }
+
+    /// The keywords which close the block.
+    pub fn closers(&self) -> &'static [&'static str] {
+        match self {
+            Control::Subr { .. } => &["RETN"],
+            Control::Reps { .. } => &["REPE"],
+            Control::Loop { .. } => &["EVER", "UNTL"],
+            Control::DoIf(_) => &["ENDI"],
+            Control::Econ { .. }
+            | Control::Enum(_)
+            | Control::Etch(_)
+            | Control::Esee(_)
+            | Control::Epas(_) => &["NEXT"],
+        }
+    }
+
+    /// Returns `true` if the keyword `rule` closes the block. `UNTL`, which
+    /// also closes a `LOOP`, is handled separately as it takes a condition.
+    pub fn closed_by(&self, rule: Rule) -> bool {
+        matches!(
+            (self, rule),
+            (Control::Subr { .. }, Rule::command_retn)
+                | (Control::Reps { .. }, Rule::command_repe)
+                | (Control::Loop { .. }, Rule::command_ever)
+                | (Control::DoIf(_), Rule::command_endi)
+                | (
+                    Control::Econ { .. }
+                        | Control::Enum(_)
+                        | Control::Etch(_)
+                        | Control::Esee(_)
+                        | Control::Epas(_),
+                    Rule::command_next
+                )
+        )
+    }
 }
@@ -2,13 +2,18 @@ use super::{CommandThunk, Control};
 
 use crate::{
     ast::{Command, ScriptDefinition},
-    CaosError, Rule,
+    parser::keyword_name,
+    CaosError, ErrorType, OpenBlock, Rule, Span, SpanNode,
 };
+use pest::iterators::Pair;
 use std::vec::Vec;
 
 pub struct CommandStack {
     pub commands: Vec<Command>,
-    pub partials: Vec<Control>,
+    pub spans: Vec<SpanNode>,
+    /// The open blocks, innermost last, with the spans of their contents and
+    /// of the command which opened them.
+    pub partials: Vec<(Control, SpanNode, Span)>,
 }
 
 impl CommandStack {
@@ -16,137 +21,164 @@ impl CommandStack {
     pub fn new() -> Self {
         Self {
             commands: Vec::new(),
+            spans: Vec::new(),
             partials: Vec::new(),
         }
     }
 
-    pub fn to_commands(self) -> Result<Vec<Command>, CaosError> {
-        if let Some(_e) = self.partials.into_iter().last() {
-            return Err(CaosError::new_end_of_stream());
+    /// Returns the completed commands, alongside their spans.
+    pub fn into_commands(self) -> Result<(Vec<Command>, Vec<SpanNode>), CaosError> {
+        if let Some(e) = self.unclosed_error() {
+            return Err(e);
         }
-        Ok(self.commands)
+        Ok((self.commands, self.spans))
     }
 
-    fn push_command(&mut self, c: Command) {
+    /// Returns the completed commands, closing any blocks left open and
+    /// recording an error naming them.
+    pub fn into_commands_recovering(
+        mut self,
+        errors: &mut Vec<CaosError>,
+    ) -> (Vec<Command>, Vec<SpanNode>) {
+        errors.extend(self.unclosed_error());
+        while let Some((control, span, _)) = self.partials.pop() {
+            self.push_command(control.into_command(), span);
+        }
+        (self.commands, self.spans)
+    }
+
+    /// An error listing the blocks still open, if any, reported at the
+    /// innermost.
+    fn unclosed_error(&self) -> Option<CaosError> {
+        let open_blocks: Vec<_> = self
+            .partials
+            .iter()
+            .rev()
+            .map(|(control, _, opener)| open_block(control, opener))
+            .collect();
+        let span = open_blocks.first()?.span;
+        Some(CaosError::new(
+            ErrorType::UnclosedBlock { open_blocks },
+            Some(span),
+        ))
+    }
+
+    /// Pushes a [Command::Error] in place of source which failed to parse.
+    pub fn push_error(&mut self, text: String, span: SpanNode) {
+        self.push_command(Command::Error { text }, span);
+    }
+
+    fn push_command(&mut self, c: Command, span: SpanNode) {
         match self.partials.last_mut() {
-            None => self.commands.push(c),
-            Some(control) => control.push(c),
+            None => {
+                self.commands.push(c);
+                self.spans.push(span);
+            }
+            Some((control, control_span, _)) => {
+                control.push(c);
+                control_span.push(span);
+            }
         }
     }
 
+    /// Pops the innermost open block, closing its span at `closer`.
+    fn pop_control(&mut self, closer: &SpanNode) -> Option<(Control, SpanNode)> {
+        self.partials.pop().map(|(control, mut span, _)| {
+            span.extend(closer.span);
+            (control, span)
+        })
+    }
+
+    /// An error for the keyword `p` not matching the innermost open block.
+    fn unbalanced_error(&self, p: &Pair<'_, Rule>) -> CaosError {
+        CaosError::new(
+            ErrorType::UnbalancedBlock {
+                keyword: keyword_name(p),
+                open_block: self
+                    .partials
+                    .last()
+                    .map(|(control, _, opener)| open_block(control, opener)),
+            },
+            Some(p.as_span().into()),
+        )
+    }
+
     pub fn push(&mut self, thunk: CommandThunk<'_>) -> Result<(), CaosError> {
         match thunk {
             CommandThunk::Partial(p) => {
                 if p.is_ready() {
-                    let c = p.complete()?;
-                    self.push_command(c);
+                    let (c, span) = p.complete()?;
+                    self.push_command(c, span);
                     Ok(())
                 } else {
-                    Err(CaosError::new_parse_error(p.origin))
+                    Err(p.arity_error())
                 }
             }
-            CommandThunk::Completed(_, c) => {
-                self.push_command(c);
+            CommandThunk::Completed(span, c) => {
+                self.push_command(c, span);
                 Ok(())
             }
-            CommandThunk::Start(c) => {
-                self.partials.push(c);
+            CommandThunk::Start(span, c) => {
+                let opener = span.span;
+                self.partials.push((c, span, opener));
                 Ok(())
             }
-            CommandThunk::End(p) => match (p.as_rule(), self.partials.pop()) {
-                (
-                    Rule::command_retn,
-                    Some(Control::Subr {
-                        label, definition, ..
-                    }),
-                ) => {
-                    let c = Command::Subr { label, definition };
-                    self.push_command(c);
-                    Ok(())
-                }
-                (
-                    Rule::command_repe,
-                    Some(Control::Reps {
-                        count, definition, ..
-                    }),
-                ) => {
-                    let c = Command::Reps { count, definition };
-                    self.push_command(c);
-                    Ok(())
-                }
-                (Rule::command_ever, Some(Control::Loop { definition, .. })) => {
-                    let c = Command::LoopEver { definition };
-                    self.push_command(c);
-                    Ok(())
-                }
-                (
-                    Rule::command_next,
-                    Some(Control::Econ {
-                        agent, definition, ..
-                    }),
-                ) => {
-                    let c = Command::Econ { agent, definition };
-                    self.push_command(c);
-                    Ok(())
+            CommandThunk::End(p) => {
+                let closes = matches!(
+                    self.partials.last(),
+                    Some((control, ..)) if control.closed_by(p.as_rule())
+                );
+                if !closes {
+                    return Err(self.unbalanced_error(&p));
                 }
-                (Rule::command_next, Some(Control::Enum(e))) => {
-                    self.push_command(Command::Enum(e));
-                    Ok(())
-                }
-                (Rule::command_next, Some(Control::Etch(e))) => {
-                    self.push_command(Command::Etch(e));
-                    Ok(())
-                }
-
-                (Rule::command_next, Some(Control::Esee(e))) => {
-                    self.push_command(Command::Esee(e));
-                    Ok(())
-                }
-                (Rule::command_next, Some(Control::Epas(e))) => {
-                    self.push_command(Command::Epas(e));
-                    Ok(())
-                }
-                (Rule::command_endi, Some(Control::DoIf(do_if))) => {
-                    self.push_command(Command::Doif(do_if));
-                    Ok(())
+                let closer = SpanNode::leaf(p.as_span().into());
+                let (control, span) = self.pop_control(&closer).expect("Block is open");
+                self.push_command(control.into_command(), span);
+                Ok(())
+            }
+            CommandThunk::EndLoop(p, condition, condition_span) => {
+                if !matches!(self.partials.last(), Some((Control::Loop { .. }, ..))) {
+                    return Err(self.unbalanced_error(&p));
                 }
-                _ => Err(CaosError::new_parse_error(p)),
-            },
-            CommandThunk::EndLoop(p, condition) => {
-                if let Some(Control::Loop { definition }) = self.partials.pop() {
-                    self.push_command(Command::LoopUntl {
-                        definition,
-                        condition,
-                    });
-                    Ok(())
-                } else {
-                    Err(CaosError::new_parse_error(p))
+                if let Some((Control::Loop { definition }, mut span, _)) = self.partials.pop() {
+                    span.extend(p.as_span().into());
+                    span.push(condition_span);
+                    self.push_command(
+                        Command::LoopUntl {
+                            definition,
+                            condition,
+                        },
+                        span,
+                    );
                 }
+                Ok(())
             }
-            CommandThunk::StartElif(p, condition) => {
-                if let Some(Control::DoIf(do_if)) = self.partials.last_mut() {
-                    if do_if.else_definition.is_some() {
-                        Err(CaosError::new_parse_error(p))
-                    } else {
+            CommandThunk::StartElif(p, condition, condition_span) => {
+                match self.partials.last_mut() {
+                    Some((Control::DoIf(do_if), span, _)) if do_if.else_definition.is_none() => {
                         do_if.push_elif(condition, ScriptDefinition::default());
+                        span.push(condition_span);
                         Ok(())
                     }
-                } else {
-                    Err(CaosError::new_parse_error(p))
+                    _ => Err(self.unbalanced_error(&p)),
                 }
             }
-            CommandThunk::StartElse(p) => {
-                if let Some(Control::DoIf(do_if)) = self.partials.last_mut() {
-                    if do_if.else_definition.is_some() {
-                        Err(CaosError::new_parse_error(p))
-                    } else {
-                        do_if.set_else(ScriptDefinition::default());
-                        Ok(())
-                    }
-                } else {
-                    Err(CaosError::new_parse_error(p))
+            CommandThunk::StartElse(p) => match self.partials.last_mut() {
+                Some((Control::DoIf(do_if), span, _)) if do_if.else_definition.is_none() => {
+                    do_if.set_else(ScriptDefinition::default());
+                    span.extend(p.as_span().into());
+                    Ok(())
                 }
-            }
+                _ => Err(self.unbalanced_error(&p)),
+            },
         }
     }
 }
+
+fn open_block(control: &Control, opener: &Span) -> OpenBlock {
+    OpenBlock {
+        keyword: control.keyword().to_owned(),
+        span: *opener,
+        closers: control.closers(),
+    }
+}
@@ -1,7 +1,7 @@
 use super::Control;
 use crate::ast::{Command, Condition};
 use crate::parser::Partial;
-use crate::Rule;
+use crate::{Rule, SpanNode};
 use pest::iterators::Pair;
 
 /// A thunk for a partial-parse of some expression.
@@ -9,13 +9,13 @@ use pest::iterators::Pair;
 /// unwrapped into an [Command], or the [CommandThunk::Partial] state, where the expression
 /// requires further arguments to complete.
 pub(crate) enum CommandThunk<'i> {
-    Completed(Pair<'i, Rule>, Command),
+    Completed(SpanNode, Command),
     Partial(Partial<'i, Command>),
-    Start(Control),
-    StartElif(Pair<'i, Rule>, Condition),
+    Start(SpanNode, Control),
+    StartElif(Pair<'i, Rule>, Condition, SpanNode),
     StartElse(Pair<'i, Rule>),
     End(Pair<'i, Rule>),
-    EndLoop(Pair<'i, Rule>, Condition),
+    EndLoop(Pair<'i, Rule>, Condition, SpanNode),
 }
 
 impl<'i> CommandThunk<'i> {
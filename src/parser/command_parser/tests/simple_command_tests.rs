@@ -2,6 +2,17 @@ use crate::ast::{Agent, Condition, SString, Variable};
 
 use super::*;
 
+#[test]
+fn test_command_alph() {
+    assert_eq!(
+        parse_cmnd("ALPH 128 1"),
+        Command::Alph {
+            alpha_value: Box::new(128.into()),
+            yes_or_no: Box::new(1.into()),
+        }
+    );
+}
+
 #[test]
 fn test_command_anim() {
     assert_eq!(
@@ -298,6 +309,11 @@ fn test_command_ttar() {
     );
 }
 
+#[test]
+fn test_command_ucln() {
+    assert_eq!(parse_cmnd("UCLN"), Command::Ucln);
+}
+
 #[test]
 fn test_command_brn_dmpb() {
     assert_eq!(parse_cmnd("BRN: DMPB"), Command::BrnDmpb);
@@ -935,6 +951,24 @@ fn test_command_mvft() {
     );
 }
 
+#[test]
+fn test_command_new_crag() {
+    assert_eq!(
+        parse_cmnd(r#"NEW: CRAG 4 NULL 1 2 3 "grendel" 64 0 500"#),
+        Command::NewCrag {
+            family: Box::new(4.into()),
+            gene_agent: Box::new(Agent::Null.into()),
+            gene_slot: Box::new(1.into()),
+            sex: Box::new(2.into()),
+            variant: Box::new(3.into()),
+            sprite_file: Box::new(String::from("grendel").into()),
+            image_count: Box::new(64.into()),
+            first_image: Box::new(0.into()),
+            plane: Box::new(500.into()),
+        }
+    );
+}
+
 #[test]
 fn test_command_new_crea() {
     assert_eq!(
@@ -1014,6 +1048,17 @@ fn test_command_sayn() {
     assert_eq!(parse_cmnd("SAYN"), Command::Sayn);
 }
 
+#[test]
+fn test_command_soul() {
+    assert_eq!(
+        parse_cmnd("SOUL 2 0"),
+        Command::Soul {
+            faculty_id: Box::new(2.into()),
+            on: Box::new(0.into()),
+        }
+    );
+}
+
 #[test]
 fn test_command_spnl() {
     assert_eq!(
@@ -1026,6 +1071,16 @@ fn test_command_spnl() {
     );
 }
 
+#[test]
+fn test_command_step() {
+    assert_eq!(
+        parse_cmnd("STEP 5"),
+        Command::Step {
+            faculty_id: Box::new(5.into()),
+        }
+    );
+}
+
 #[test]
 fn test_command_stim_shou() {
     assert_eq!(
@@ -1456,6 +1511,16 @@ fn test_command_outx() {
     );
 }
 
+#[test]
+fn test_command_webb() {
+    assert_eq!(
+        parse_cmnd(r#"WEBB "http://www.gamewaredevelopment.co.uk""#),
+        Command::Webb {
+            http_url: Box::new(String::from("http://www.gamewaredevelopment.co.uk").into()),
+        }
+    );
+}
+
 #[test]
 fn test_command_goto() {
     assert_eq!(
@@ -1949,6 +2014,120 @@ fn test_command_velo() {
     );
 }
 
+#[test]
+fn test_command_net_head() {
+    assert_eq!(parse_cmnd("NET: HEAD"), Command::NetHead);
+}
+
+#[test]
+fn test_command_net_hear() {
+    assert_eq!(
+        parse_cmnd("NET: HEAR \"chat\""),
+        Command::NetHear {
+            channel: Box::new(String::from("chat").into()),
+        }
+    );
+}
+
+#[test]
+fn test_command_net_line() {
+    assert_eq!(
+        parse_cmnd("NET: LINE 1"),
+        Command::NetLine {
+            state: Box::new(1.into()),
+        }
+    );
+}
+
+#[test]
+fn test_command_net_pass() {
+    assert_eq!(
+        parse_cmnd("NET: PASS \"nick\" \"secret\""),
+        Command::NetPass {
+            nick_name: Box::new(String::from("nick").into()),
+            password: Box::new(String::from("secret").into()),
+        }
+    );
+}
+
+#[test]
+fn test_command_net_ruso() {
+    assert_eq!(
+        parse_cmnd("NET: RUSO VA00"),
+        Command::NetRuso {
+            store_result: Box::new(Variable::Vaxx(0)),
+        }
+    );
+}
+
+#[test]
+fn test_command_net_stat() {
+    assert_eq!(
+        parse_cmnd("NET: STAT VA00 VA01 VA02 VA03"),
+        Command::NetStat {
+            time_online: Box::new(Variable::Vaxx(0)),
+            users_online: Box::new(Variable::Vaxx(1)),
+            bytes_received: Box::new(Variable::Vaxx(2)),
+            bytes_sent: Box::new(Variable::Vaxx(3)),
+        }
+    );
+}
+
+#[test]
+fn test_command_net_unik() {
+    assert_eq!(
+        parse_cmnd("NET: UNIK NET: USER VA00"),
+        Command::NetUnik {
+            user_id: Box::new(SString::NetUser.into()),
+            store_result: Box::new(Variable::Vaxx(0)),
+        }
+    );
+}
+
+#[test]
+fn test_command_net_whod() {
+    assert_eq!(parse_cmnd("NET: WHOD"), Command::NetWhod);
+}
+
+#[test]
+fn test_command_net_whof() {
+    assert_eq!(
+        parse_cmnd("NET: WHOF \"dsuser\""),
+        Command::NetWhof {
+            user_id: Box::new(String::from("dsuser").into()),
+        }
+    );
+}
+
+#[test]
+fn test_command_net_whon() {
+    assert_eq!(
+        parse_cmnd("NET: WHON \"dsuser\""),
+        Command::NetWhon {
+            user_id: Box::new(String::from("dsuser").into()),
+        }
+    );
+}
+
+#[test]
+fn test_command_net_whoz() {
+    assert_eq!(parse_cmnd("NET: WHOZ"), Command::NetWhoz);
+}
+
+#[test]
+fn test_command_net_writ() {
+    assert_eq!(
+        parse_cmnd("NET: WRIT \"dsuser\" \"chat\" 1000 \"hello\" 2"),
+        Command::NetWrit {
+            user_id: Box::new(String::from("dsuser").into()),
+            channel: Box::new(String::from("chat").into()),
+            message_id: Box::new(1000.into()),
+            param_1: Box::new(SString::Literal(String::from("hello")).into()),
+            param_2: Box::new(2.into()),
+        }
+    );
+}
+
 #[test]
 fn test_command_prt_bang() {
     assert_eq!(
@@ -2097,11 +2276,34 @@ fn test_command_gids_spcs() {
     );
 }
 
+#[test]
+fn test_command_call() {
+    assert_eq!(
+        parse_cmnd(r#"CALL 1000 "a" OWNR"#),
+        Command::Call {
+            script_no: Box::new(1000.into()),
+            p1: Box::new(String::from("a").into()),
+            p2: Box::new(Agent::Ownr.into()),
+        }
+    );
+}
+
 #[test]
 fn test_command_inst() {
     assert_eq!(parse_cmnd("INST"), Command::Inst);
 }
 
+#[test]
+fn test_command_ject() {
+    assert_eq!(
+        parse_cmnd(r#"JECT "bootstrap.cos" 7"#),
+        Command::Ject {
+            macro_file: Box::new(String::from("bootstrap.cos").into()),
+            flags: Box::new(7.into()),
+        }
+    );
+}
+
 #[test]
 fn test_command_lock() {
     assert_eq!(parse_cmnd("LOCK"), Command::Lock);
@@ -2323,6 +2525,16 @@ fn test_command_volm() {
     );
 }
 
+#[test]
+fn test_command_buzz() {
+    assert_eq!(
+        parse_cmnd("BUZZ 50"),
+        Command::Buzz {
+            interval: Box::new(50.into())
+        },
+    );
+}
+
 #[test]
 fn test_command_wpau() {
     assert_eq!(
@@ -2388,6 +2600,16 @@ fn test_command_char() {
     );
 }
 
+#[test]
+fn test_command_dele() {
+    assert_eq!(
+        parse_cmnd(r#"DELE "score""#),
+        Command::Dele {
+            name: Box::new(String::from("score").into()),
+        }
+    );
+}
+
 #[test]
 fn test_command_delg() {
     assert_eq!(
@@ -2398,6 +2620,16 @@ fn test_command_delg() {
     );
 }
 
+#[test]
+fn test_command_deln() {
+    assert_eq!(
+        parse_cmnd("DELN 3"),
+        Command::Deln {
+            name: Box::new(3.into()),
+        }
+    );
+}
+
 #[test]
 fn test_command_divv() {
     assert_eq!(
@@ -2431,6 +2663,16 @@ fn test_command_mulv() {
     );
 }
 
+#[test]
+fn test_command_namn() {
+    assert_eq!(
+        parse_cmnd("NAMN VA00"),
+        Command::Namn {
+            previous: Box::new(Variable::Vaxx(0)),
+        }
+    );
+}
+
 #[test]
 fn test_command_negv() {
     assert_eq!(
@@ -2490,6 +2732,33 @@ fn test_command_setv() {
     );
 }
 
+#[test]
+fn test_command_setv_name() {
+    assert_eq!(
+        parse_cmnd(r#"SETV NAME "x" 1"#),
+        Command::Setv {
+            var: Box::new(Variable::Name {
+                name: Box::new(String::from("x").into()),
+            }),
+            value: Box::new(1.into()),
+        }
+    );
+    assert_eq!(
+        parse_cmnd(r#"SETV EAME "x" MAME 2"#),
+        Command::Setv {
+            var: Box::new(Variable::Eame {
+                name: Box::new(String::from("x").into()),
+            }),
+            value: Box::new(
+                Variable::Mame {
+                    name: Box::new(2.into()),
+                }
+                .into()
+            ),
+        }
+    );
+}
+
 #[test]
 fn test_command_subv() {
     assert_eq!(
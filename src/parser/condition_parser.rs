@@ -4,29 +4,36 @@ mod tests;
 use super::parse_expression;
 use crate::{
     ast::{Condition, ConditionType, JoinType},
-    CaosError, Rule,
+    CaosError, ErrorType, Rule, SpanNode,
 };
 use pest::iterators::{Pair, Pairs};
 
-pub fn parse_condition(pairs: &mut Pairs<Rule>) -> Result<Condition, CaosError> {
+type SpannedCondition = (Condition, SpanNode);
+
+pub fn parse_condition(pairs: &mut Pairs<Rule>) -> Result<SpannedCondition, CaosError> {
     let mut c = parse_condition_single(pairs)?;
-    let mut v = Vec::<(Condition, JoinType)>::new();
+    let mut v = Vec::<(SpannedCondition, JoinType)>::new();
     while let Some((j, new_c)) = try_parse_condition_join_part(pairs)? {
         v.push((c, j));
         c = new_c;
     }
     Ok(v.into_iter()
         .rev()
-        .fold(c, |c_rhs, (c_lhs, join_type)| Condition::Combination {
-            c_lhs: Box::new(c_lhs),
-            c_rhs: Box::new(c_rhs),
-            join_type,
+        .fold(c, |(c_rhs, rhs_span), ((c_lhs, lhs_span), join_type)| {
+            (
+                Condition::Combination {
+                    c_lhs: Box::new(c_lhs),
+                    c_rhs: Box::new(c_rhs),
+                    join_type,
+                },
+                SpanNode::with_children(lhs_span.span, vec![lhs_span, rhs_span]),
+            )
         }))
 }
 
 fn try_parse_condition_join_part(
     pairs: &mut Pairs<Rule>,
-) -> Result<Option<(JoinType, Condition)>, CaosError> {
+) -> Result<Option<(JoinType, SpannedCondition)>, CaosError> {
     if let Some(p) = pairs.peek() {
         if let Ok(join_type) = parse_condition_join(p) {
             _ = pairs.next();
@@ -45,7 +52,7 @@ fn parse_condition_operator(pair: Pair<Rule>) -> Result<ConditionType, CaosError
         Rule::condition_le => Ok(ConditionType::Le),
         Rule::condition_ne => Ok(ConditionType::Ne),
         Rule::condition_lt => Ok(ConditionType::Lt),
-        _ => Err(CaosError::new_parse_error(pair)),
+        _ => Err(CaosError::new_unexpected(pair, "a comparison operator")),
     }
 }
 
@@ -57,16 +64,26 @@ fn parse_condition_join(pair: Pair<Rule>) -> Result<JoinType, CaosError> {
     }
 }
 
-fn parse_condition_single(pairs: &mut Pairs<Rule>) -> Result<Condition, CaosError> {
-    let lhs = parse_expression(pairs)?;
+fn parse_condition_single(pairs: &mut Pairs<Rule>) -> Result<SpannedCondition, CaosError> {
+    let (lhs, lhs_span) = parse_expression(pairs)?;
     let cond_type = pairs
         .next()
-        .ok_or_else(|| CaosError::new_end_of_stream())
+        .ok_or_else(|| {
+            CaosError::new(
+                ErrorType::UnexpectedEnd {
+                    expected: Some(String::from("a comparison operator")),
+                },
+                None,
+            )
+        })
         .and_then(parse_condition_operator)?;
-    let rhs = parse_expression(pairs)?;
-    Ok(Condition::Simple {
-        cond_type,
-        lhs,
-        rhs,
-    })
+    let (rhs, rhs_span) = parse_expression(pairs)?;
+    Ok((
+        Condition::Simple {
+            cond_type,
+            lhs,
+            rhs,
+        },
+        SpanNode::with_children(lhs_span.span, vec![lhs_span, rhs_span]),
+    ))
 }
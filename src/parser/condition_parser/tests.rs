@@ -8,7 +8,7 @@ use super::*;
 
 fn parse_condition_input(input: &str) -> Condition {
     let mut p = CaosParser::parse(Rule::tokens, input).expect("Parsed");
-    parse_condition(&mut p).expect("Successful parse")
+    parse_condition(&mut p).expect("Successful parse").0
 }
 
 #[test]
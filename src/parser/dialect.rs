@@ -0,0 +1,71 @@
+use super::CaosParser;
+use crate::{
+    ast::CosFile,
+    parse_cos,
+    signature::{self, Signature},
+    CaosError, Dialect, ErrorType, RecoveredParse, Rule,
+};
+use pest::Parser;
+use std::collections::HashMap;
+
+/// Parses a CAOS file written in `dialect`.
+///
+/// As [parse_cos], but also fails at the first keyword which is not part of
+/// the dialect. See [check_dialect].
+pub fn parse_cos_with_dialect(cos_content: &str, dialect: Dialect) -> Result<CosFile, CaosError> {
+    if !dialect.is_supported() {
+        return Err(unsupported(dialect));
+    }
+    let cos_file = parse_cos(cos_content)?;
+    match dialect_errors(cos_content, dialect).into_iter().next() {
+        Some(error) => Err(error),
+        None => Ok(cos_file),
+    }
+}
+
+/// Finds every keyword in a file parsed by
+/// [parse_cos_recovering](crate::parse_cos_recovering) which is not part of
+/// `dialect`, such as `NET: LINE` in a script for Creatures 3.
+///
+/// Keywords are checked wherever they are, even within source the parse could
+/// not make sense of, so errors of both kinds are reported. Words which are not
+/// valid CAOS are left to the parse. A dialect the grammar does not cover gives
+/// a single error.
+pub fn check_dialect(parse: &RecoveredParse, dialect: Dialect) -> Vec<CaosError> {
+    if !dialect.is_supported() {
+        return vec![unsupported(dialect)];
+    }
+    dialect_errors(&parse.masked, dialect)
+}
+
+/// Finds every keyword in `tokens`, source which the grammar can tokenise
+/// throughout, which is not part of `dialect`.
+fn dialect_errors(tokens: &str, dialect: Dialect) -> Vec<CaosError> {
+    let Ok(pairs) = CaosParser::parse(Rule::recovery_prefix, tokens) else {
+        return Vec::new();
+    };
+
+    let mut by_rule: HashMap<&str, Vec<&Signature>> = HashMap::new();
+    for s in signature::signatures().filter(|s| !s.rule.is_empty()) {
+        by_rule.entry(s.rule).or_default().push(s);
+    }
+    let mut errors = Vec::new();
+    for pair in pairs.flatten() {
+        let rule = format!("{:?}", pair.as_rule());
+        let Some(signatures) = by_rule.get(rule.as_str()) else {
+            continue;
+        };
+        if !signatures.iter().any(|s| dialect.has(s)) {
+            let error_type = ErrorType::UnavailableKeyword {
+                keyword: signatures[0].keyword.to_owned(),
+                dialect,
+            };
+            errors.push(CaosError::new(error_type, Some(pair.as_span().into())));
+        }
+    }
+    errors
+}
+
+fn unsupported(dialect: Dialect) -> CaosError {
+    CaosError::new(ErrorType::UnsupportedDialect { dialect }, None)
+}
@@ -10,28 +10,56 @@ use crate::{
     parser::base::{
         parse_bytestring_literal, parse_float_literal, parse_int_literal, parse_string_literal,
     },
-    CaosError, Rule,
+    CaosError, ErrorType, KeywordRole, Rule, SpanNode,
 };
 use expression_stack::ExpressionStack;
 use pest::iterators::{Pair, Pairs};
 
-use super::Partial;
+use super::{suggest::unexpected_token, Partial};
 pub(crate) use expression_parser_trait::ExpressionParser;
 pub(crate) use expression_thunk::ExpressionThunk;
 
-pub fn parse_expression<'i>(pairs: &'_ mut Pairs<'i, Rule>) -> Result<Anything, CaosError> {
+pub fn parse_expression<'i>(
+    pairs: &'_ mut Pairs<'i, Rule>,
+) -> Result<(Anything, SpanNode), CaosError> {
     let mut expression_stack = ExpressionStack::new();
-    while let Some(pair) = pairs.next() {
-        let thunk: ExpressionThunk = find_expression_match(pair)?;
+    for pair in pairs.by_ref() {
+        let thunk: ExpressionThunk = match find_expression_match(pair) {
+            Ok(thunk) => thunk,
+            // An expression still waiting on arguments was cut short.
+            Err(e) if is_missing_expression(&e) => match expression_stack.root.last() {
+                Some(partial) => return Err(partial.arity_error()),
+                None => return Err(e),
+            },
+            Err(e) => return Err(e),
+        };
         let res = expression_stack.push(thunk)?;
         if let Some(res) = res {
             return Ok(res);
         }
     }
 
-    match expression_stack.root.into_iter().last() {
-        Some(e) => Err(CaosError::new_parse_error(e.origin)),
-        None => Err(CaosError::new_end_of_stream()),
+    match expression_stack.root.last() {
+        Some(e) => Err(e.arity_error()),
+        None => Err(CaosError::new(
+            ErrorType::UnexpectedEnd {
+                expected: Some(KeywordRole::Expression.to_string()),
+            },
+            None,
+        )),
+    }
+}
+
+/// Returns `true` if `e` is from an expression being expected, but either
+/// something else or nothing being found.
+pub(crate) fn is_missing_expression(e: &CaosError) -> bool {
+    let expression = KeywordRole::Expression.to_string();
+    match e.error_type() {
+        ErrorType::UnexpectedToken { expected, .. } | ErrorType::UnexpectedEnd { expected } => {
+            expected.as_deref() == Some(expression.as_str())
+        }
+        ErrorType::MisplacedKeyword { expected, .. } => *expected == KeywordRole::Expression,
+        _ => false,
     }
 }
 
@@ -41,22 +69,22 @@ fn find_expression_match(pair: Pair<Rule>) -> Result<ExpressionThunk, CaosError>
         Rule::literal_string => Some(
             parse_string_literal(pair.clone())
                 .map(Anything::from)
-                .map(|v| ExpressionThunk::Completed(pair.clone(), v))?,
+                .map(|v| ExpressionThunk::Completed(SpanNode::leaf(pair.as_span().into()), v))?,
         ),
         Rule::literal_byte_string => Some(
             parse_bytestring_literal(pair.clone())
                 .map(Anything::from)
-                .map(|v| ExpressionThunk::Completed(pair.clone(), v))?,
+                .map(|v| ExpressionThunk::Completed(SpanNode::leaf(pair.as_span().into()), v))?,
         ),
         Rule::literal_int => Some(
             parse_int_literal(pair.clone())
                 .map(Anything::from)
-                .map(|v| ExpressionThunk::Completed(pair.clone(), v))?,
+                .map(|v| ExpressionThunk::Completed(SpanNode::leaf(pair.as_span().into()), v))?,
         ),
         Rule::literal_float => Some(
             parse_float_literal(pair.clone())
                 .map(Anything::from)
-                .map(|v| ExpressionThunk::Completed(pair.clone(), v))?,
+                .map(|v| ExpressionThunk::Completed(SpanNode::leaf(pair.as_span().into()), v))?,
         ),
         Rule::variable_mvxx => Some(parse_variable(pair.clone(), Variable::Mvxx)?),
         Rule::variable_ovxx => Some(parse_variable(pair.clone(), Variable::Ovxx)?),
@@ -68,7 +96,7 @@ fn find_expression_match(pair: Pair<Rule>) -> Result<ExpressionThunk, CaosError>
     .or_else(|| Float::parse_thunk(pair.clone()))
     .or_else(|| Integer::parse_thunk(pair.clone()))
     .or_else(|| SString::parse_thunk(pair.clone()))
-    .ok_or_else(|| CaosError::new_parse_error(pair))
+    .ok_or_else(|| unexpected_token(pair, KeywordRole::Expression))
 }
 
 fn parse_variable<F>(pair: Pair<Rule>, f: F) -> Result<ExpressionThunk, CaosError>
@@ -83,7 +111,10 @@ where
         .and_then(parse_variable_digit)
         .map(f)
         .map(Anything::from)?;
-    Ok(ExpressionThunk::Completed(pair, v))
+    Ok(ExpressionThunk::Completed(
+        SpanNode::leaf(pair.as_span().into()),
+        v,
+    ))
 }
 
 fn parse_variable_digit(pair: Pair<Rule>) -> Result<u8, CaosError> {
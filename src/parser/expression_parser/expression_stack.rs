@@ -1,14 +1,14 @@
 use super::{ExpressionThunk, Partial};
 
-use crate::{ast::Anything, CaosError};
+use crate::{ast::Anything, CaosError, SpanNode};
 use std::vec::Vec;
 
 /// The [ExpressionStack] is used to take multiple [ExpressionThunk] objects and attempts to
 /// complete/unwrap them.
 ///
 /// [ExpressionThunk] objects are pushed onto the ExpressionStack, and [Anything]
-/// objects are returned. It may take several [ExpressionThunk] pushes before an [Anything]
-/// is produced.
+/// objects are returned along with their [SpanNode]. It may take several [ExpressionThunk]
+/// pushes before an [Anything] is produced.
 pub struct ExpressionStack<'i> {
     pub root: Vec<Partial<'i, Anything>>,
 }
@@ -22,24 +22,27 @@ impl<'i> ExpressionStack<'i> {
     /// Pushes an [Anything] into the argument list of the leaf node.
     /// If there is no leaf node (that is there are no partials to try and complete left),
     /// then returns the [Anything] argument back out.
-    fn try_push_partial(&mut self, a: Anything) -> Option<Anything> {
+    fn try_push_partial(&mut self, a: Anything, span: SpanNode) -> Option<(Anything, SpanNode)> {
         match self.root.last_mut() {
             Some(t) => {
-                t.arg_parts.push(a);
+                t.push_arg(a, span);
                 None
             }
-            None => Some(a),
+            None => Some((a, span)),
         }
     }
 
     /// Takes an [ExpressionThunk] and attempts to complete it, and then
     /// tries to complete any other expressions in the chain. If the chain has not completed
     /// then returns the result of that chain, otherwise returns nothing.
-    pub fn push(&mut self, thunk: ExpressionThunk<'i>) -> Result<Option<Anything>, CaosError> {
+    pub fn push(
+        &mut self,
+        thunk: ExpressionThunk<'i>,
+    ) -> Result<Option<(Anything, SpanNode)>, CaosError> {
         match thunk {
             // If pushed expression is ready then add to chain or immediately return it.
-            ExpressionThunk::Completed(_, a) => {
-                let res = self.try_push_partial(a);
+            ExpressionThunk::Completed(span, a) => {
+                let res = self.try_push_partial(a, span);
                 if res.is_some() {
                     return Ok(res);
                 }
@@ -48,8 +51,8 @@ impl<'i> ExpressionStack<'i> {
             // as a partial thunk.
             ExpressionThunk::Partial(p) => {
                 if p.is_ready() {
-                    let a = p.complete()?;
-                    let res = self.try_push_partial(a);
+                    let (a, span) = p.complete()?;
+                    let res = self.try_push_partial(a, span);
                     if res.is_some() {
                         return Ok(res);
                     }
@@ -65,8 +68,8 @@ impl<'i> ExpressionStack<'i> {
         loop {
             let is_ready = self.root.last().map(|p| p.is_ready()).unwrap_or(false);
             if is_ready {
-                let arg = self.root.pop().unwrap().complete()?;
-                let res = self.try_push_partial(arg);
+                let (arg, span) = self.root.pop().unwrap().complete()?;
+                let res = self.try_push_partial(arg, span);
                 if res.is_some() {
                     return Ok(res);
                 }
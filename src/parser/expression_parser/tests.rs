@@ -19,6 +19,6 @@ where
     T: TryFrom<Anything, Error = CaosError>,
 {
     let mut p = CaosParser::parse(Rule::tokens, content).expect("Successful pest parse");
-    let p = parse_expression(&mut p).expect("Successful expression parse");
+    let (p, _) = parse_expression(&mut p).expect("Successful expression parse");
     T::try_from(p).expect(concat!("Expected ", stringify!(T)))
 }
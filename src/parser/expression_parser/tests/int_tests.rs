@@ -25,6 +25,12 @@ fn test_int_arg() {
     assert_eq!(p, Variable::Mvxx(32).into());
 }
 
+#[test]
+fn test_int_abba() {
+    let p = parse_int("ABBA");
+    assert_eq!(p, Integer::Abba);
+}
+
 #[test]
 fn test_int_attr() {
     let p = parse_int("ATTR");
@@ -175,6 +181,20 @@ fn test_int_tick() {
     assert_eq!(p, Integer::Tick);
 }
 
+#[test]
+fn test_int_tcor() {
+    let p = parse_int("TCOR 0.5 10 0 20");
+    assert_eq!(
+        p,
+        Integer::Tcor {
+            top_y: Box::new(0.5f32.into()),
+            bottom_y: Box::new(10.into()),
+            left_x: Box::new(0.into()),
+            right_x: Box::new(20.into())
+        }
+    );
+}
+
 #[test]
 fn test_int_totl() {
     let p = parse_int("TOTL MOWS AERO BASE");
@@ -429,6 +449,17 @@ fn test_int_orgn() {
     assert_eq!(p, Integer::Orgn);
 }
 
+#[test]
+fn test_int_soul() {
+    let p = parse_int("SOUL 2");
+    assert_eq!(
+        p,
+        Integer::Soul {
+            faculty_id: Box::new(2.into())
+        }
+    );
+}
+
 #[test]
 fn test_int_tage() {
     let p = parse_int("TAGE");
@@ -965,6 +996,61 @@ fn test_int_wall() {
     assert_eq!(p, Integer::Wall);
 }
 
+#[test]
+fn test_int_net_erra() {
+    let p = parse_int("NET: ERRA");
+    assert_eq!(p, Integer::NetErra);
+}
+
+#[test]
+fn test_int_net_expo() {
+    let p = parse_int("NET: EXPO \"warp\" NET: USER");
+    assert_eq!(
+        p,
+        Integer::NetExpo {
+            chunk_type: Box::new(String::from("warp").into()),
+            dest_user_id: Box::new(SString::NetUser.into())
+        }
+    );
+}
+
+#[test]
+fn test_int_net_line() {
+    let p = parse_int("NET: LINE");
+    assert_eq!(p, Integer::NetLine);
+}
+
+#[test]
+fn test_int_net_make() {
+    let p = parse_int("NET: MAKE 0 \"journal\" \"dsuser\" VA00");
+    assert_eq!(
+        p,
+        Integer::NetMake {
+            which_journal_spool: Box::new(0.into()),
+            journal_name: Box::new(String::from("journal").into()),
+            user_id: Box::new(String::from("dsuser").into()),
+            report_destination: Box::new(Variable::Vaxx(0))
+        }
+    );
+}
+
+#[test]
+fn test_int_net_rawe() {
+    let p = parse_int("NET: RAWE");
+    assert_eq!(p, Integer::NetRawe);
+}
+
+#[test]
+fn test_int_net_ulin() {
+    let p = parse_int("NET: ULIN NET: USER");
+    assert_eq!(
+        p,
+        Integer::NetUlin {
+            user_id: Box::new(SString::NetUser.into())
+        }
+    );
+}
+
 #[test]
 fn test_int_pray_agti() {
     let p = parse_int("PRAY AGTI HAND WNAM 0");
@@ -1114,6 +1200,12 @@ fn test_int_mute() {
     );
 }
 
+#[test]
+fn test_int_buzz() {
+    let p = parse_int("BUZZ");
+    assert_eq!(p, Integer::Buzz);
+}
+
 #[test]
 fn test_int_date() {
     let p = parse_int("DATE");
@@ -1313,6 +1405,19 @@ fn test_int_reaq() {
     );
 }
 
+#[test]
+fn test_int_sins() {
+    let p = parse_int(r#"SINS "hello world" 1 "o""#);
+    assert_eq!(
+        p,
+        Integer::Sins {
+            value: Box::new(String::from("hello world").into()),
+            start: Box::new(1.into()),
+            search: Box::new(String::from("o").into())
+        }
+    );
+}
+
 #[test]
 fn test_int_stoi() {
     let p = parse_int("STOI HAND");
@@ -83,6 +83,21 @@ fn test_string_face() {
     assert_eq!(p, SString::Face);
 }
 
+#[test]
+fn test_string_limb() {
+    let p = parse_sstring("LIMB 0 1 2 ATTR AERO");
+    assert_eq!(
+        p,
+        SString::Limb {
+            body_part: Box::new(0.into()),
+            genus: Box::new(1.into()),
+            gender: Box::new(2.into()),
+            age: Box::new(Integer::Attr.into()),
+            variant: Box::new(Integer::Aero.into())
+        }
+    );
+}
+
 #[test]
 fn test_string_dbg() {
     let p = parse_sstring("DBG# ATTR");
@@ -288,6 +303,41 @@ fn test_string_rloc() {
     );
 }
 
+#[test]
+fn test_string_net_from() {
+    let p = parse_sstring("NET: FROM \"warped_creature\"");
+    assert_eq!(
+        p,
+        SString::NetFrom {
+            resource_name: Box::new(String::from("warped_creature").into())
+        }
+    );
+}
+
+#[test]
+fn test_string_net_host() {
+    let p = parse_sstring("NET: HOST");
+    assert_eq!(p, SString::NetHost);
+}
+
+#[test]
+fn test_string_net_pass() {
+    let p = parse_sstring("NET: PASS");
+    assert_eq!(p, SString::NetPass);
+}
+
+#[test]
+fn test_string_net_user() {
+    let p = parse_sstring("NET: USER");
+    assert_eq!(p, SString::NetUser);
+}
+
+#[test]
+fn test_string_net_what() {
+    let p = parse_sstring("NET: WHAT");
+    assert_eq!(p, SString::NetWhat);
+}
+
 #[test]
 fn test_string_pray_agts() {
     let p = parse_sstring("PRAY AGTS HAND EMID FACE");
@@ -338,7 +388,7 @@ fn test_string_caos() {
             commands: Box::new(SString::Hand.into()),
             throws: Box::new(Integer::Cage.into()),
             catches: Box::new(Integer::Attn.into()),
-            report: Box::new(Variable::Mvxx(0).into()),
+            report: Box::new(Variable::Mvxx(0)),
         }
     );
 }
@@ -373,6 +423,17 @@ fn test_string_rtif() {
     );
 }
 
+#[test]
+fn test_string_eamn() {
+    let p = parse_sstring("EAMN HAND");
+    assert_eq!(
+        p,
+        SString::Eamn {
+            previous: Box::new(SString::Hand.into())
+        }
+    );
+}
+
 #[test]
 fn test_string_gamn() {
     let p = parse_sstring("GAMN HAND");
@@ -390,6 +451,23 @@ fn test_string_gnam() {
     assert_eq!(p, SString::Gnam);
 }
 
+#[test]
+fn test_string_lowa() {
+    let p = parse_sstring("LOWA HAND");
+    assert_eq!(
+        p,
+        SString::Lowa {
+            value: Box::new(SString::Hand.into())
+        }
+    );
+}
+
+#[test]
+fn test_string_modu() {
+    let p = parse_sstring("MODU");
+    assert_eq!(p, SString::Modu);
+}
+
 #[test]
 fn test_string_read() {
     let p = parse_sstring("READ HAND ATTR");
@@ -415,6 +493,28 @@ fn test_string_subs() {
     );
 }
 
+#[test]
+fn test_string_ufos() {
+    let p = parse_sstring("UFOS");
+    assert_eq!(p, SString::Ufos);
+}
+
+#[test]
+fn test_string_uppa() {
+    let p = parse_sstring("UPPA LOWA HAND");
+    assert_eq!(
+        p,
+        SString::Uppa {
+            value: Box::new(
+                SString::Lowa {
+                    value: Box::new(SString::Hand.into())
+                }
+                .into()
+            )
+        }
+    );
+}
+
 #[test]
 fn test_string_vtos() {
     let p = parse_sstring("VTOS %1");
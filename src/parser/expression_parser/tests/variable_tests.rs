@@ -75,6 +75,52 @@ fn test_variable_game() {
     );
 }
 
+#[test]
+fn test_variable_name() {
+    let p = parse_variable(r#"NAME "Hello""#);
+    assert_eq!(
+        p,
+        Variable::Name {
+            name: Box::new(String::from("Hello").into())
+        }
+    );
+
+    let p = parse_variable("NAME VA00");
+    assert_eq!(
+        p,
+        Variable::Name {
+            name: Box::new(Variable::Vaxx(0).into())
+        }
+    );
+}
+
+#[test]
+fn test_variable_mame() {
+    let p = parse_variable("MAME 3");
+    assert_eq!(
+        p,
+        Variable::Mame {
+            name: Box::new(3.into())
+        }
+    );
+}
+
+#[test]
+fn test_variable_eame() {
+    let p = parse_variable(r#"EAME NAME "Hello""#);
+    assert_eq!(
+        p,
+        Variable::Eame {
+            name: Box::new(
+                Variable::Name {
+                    name: Box::new(String::from("Hello").into())
+                }
+                .into()
+            )
+        }
+    );
+}
+
 #[test]
 fn test_variable_avar() {
     let p = parse_variable("AVAR CARR 34");
@@ -0,0 +1,174 @@
+use super::{
+    end_span, locate_end, parse_commands, parse_condition, parse_expression, syntax_error,
+    CaosParser,
+};
+use crate::{
+    ast::{Anything, Command, Condition, ScriptDefinition},
+    CaosError, ErrorType, Rule, SpanNode,
+};
+use pest::{iterators::Pairs, Parser};
+
+/// Parses a single command, such as `setv va00 1` or a whole `reps 3 .. repe`
+/// block.
+pub fn parse_command_str(content: &str) -> Result<Command, CaosError> {
+    let (mut commands, spans) = parse_commands(&mut parse_fragment(content)?)?;
+    match spans.get(1) {
+        Some(extra) => Err(unexpected_after(content, extra, "the end of the command")),
+        None => commands
+            .pop()
+            .ok_or_else(|| end_error(content, "a command")),
+    }
+}
+
+/// Parses a single expression, such as `rand 1 10`.
+pub fn parse_expression_str(content: &str) -> Result<Anything, CaosError> {
+    let mut pairs = parse_fragment(content)?;
+    let end = end_span(&pairs);
+    let (expression, _) = parse_expression(&mut pairs).map_err(|e| locate_end(e, end))?;
+    expect_end(pairs, "the end of the expression")?;
+    Ok(expression)
+}
+
+/// Parses a condition, such as `va00 gt 1 and va01 lt 2`, as found after `DOIF`.
+pub fn parse_condition_str(content: &str) -> Result<Condition, CaosError> {
+    let mut pairs = parse_fragment(content)?;
+    let end = end_span(&pairs);
+    let (condition, _) = parse_condition(&mut pairs).map_err(|e| locate_end(e, end))?;
+    expect_end(pairs, "the end of the condition")?;
+    Ok(condition)
+}
+
+/// Parses any number of commands, as found in the body of a script.
+pub fn parse_script_body_str(content: &str) -> Result<ScriptDefinition, CaosError> {
+    let (commands, _) = parse_commands(&mut parse_fragment(content)?)?;
+    Ok(ScriptDefinition { commands })
+}
+
+/// Splits `content` into tokens.
+fn parse_fragment(content: &str) -> Result<Pairs<'_, Rule>, CaosError> {
+    let fragment = CaosParser::parse(Rule::fragment, content)
+        .map_err(|e| syntax_error(e, content))?
+        .next()
+        .ok_or_else(|| end_error(content, "CAOS"))?;
+    Ok(fragment.into_inner())
+}
+
+fn expect_end(mut pairs: Pairs<'_, Rule>, expected: &str) -> Result<(), CaosError> {
+    match pairs.next() {
+        Some(pair) => Err(CaosError::new_unexpected(pair, expected)),
+        None => Ok(()),
+    }
+}
+
+/// An error for the command at `extra` following the first in `content`.
+fn unexpected_after(content: &str, extra: &SpanNode, expected: &str) -> CaosError {
+    let span = extra.span;
+    CaosError::new(
+        ErrorType::UnexpectedToken {
+            text: content[span.start..span.end].to_owned(),
+            expected: Some(expected.to_owned()),
+        },
+        Some(span),
+    )
+}
+
+/// An error for `content` ending where `expected` was, placed at its end.
+fn end_error(content: &str, expected: &str) -> CaosError {
+    let end = content.len();
+    let span = pest::Span::new(content, end, end).expect("End is within the content");
+    CaosError::new(
+        ErrorType::UnexpectedEnd {
+            expected: Some(expected.to_owned()),
+        },
+        Some(span.into()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::{Agent, ConditionType, IntArg, Integer, JoinType, Variable};
+
+    #[test]
+    fn test_parse_command_str() {
+        assert_eq!(
+            parse_command_str("  kill targ * comment").expect("Parsed"),
+            Command::Kill {
+                agent: Box::new(Agent::Targ.into())
+            }
+        );
+        let reps = parse_command_str("reps 3 nohh over repe").expect("Parsed");
+        assert!(matches!(reps, Command::Reps { .. }));
+    }
+
+    #[test]
+    fn test_parse_command_str_errors() {
+        let e = parse_command_str("nohh over").unwrap_err();
+        assert_eq!(e.message(), "Expected the end of the command, found `over`");
+        assert_eq!(e.line_col(), Some((1, 6)));
+
+        let e = parse_command_str("   ").unwrap_err();
+        assert_eq!(
+            e.message(),
+            "Expected a command, found the end of the script"
+        );
+
+        let e = parse_command_str("setv va00").unwrap_err();
+        assert_eq!(e.code(), "E0102");
+    }
+
+    #[test]
+    fn test_parse_expression_str() {
+        assert_eq!(
+            parse_expression_str("rand 1 va00").expect("Parsed"),
+            Anything::from(Integer::Rand {
+                value1: Box::new(IntArg::from(1)),
+                value2: Box::new(Variable::Vaxx(0).into()),
+            })
+        );
+        let e = parse_expression_str("1 2").unwrap_err();
+        assert_eq!(e.message(), "Expected the end of the expression, found `2`");
+        let e = parse_expression_str("kill targ").unwrap_err();
+        assert_eq!(e.code(), "E0008");
+    }
+
+    #[test]
+    fn test_parse_condition_str() {
+        assert_eq!(
+            parse_condition_str("va00 gt 1 and va01 lt 2").expect("Parsed"),
+            Condition::Combination {
+                c_lhs: Box::new(Condition::Simple {
+                    cond_type: ConditionType::Gt,
+                    lhs: Variable::Vaxx(0).into(),
+                    rhs: 1.into(),
+                }),
+                c_rhs: Box::new(Condition::Simple {
+                    cond_type: ConditionType::Lt,
+                    lhs: Variable::Vaxx(1).into(),
+                    rhs: 2.into(),
+                }),
+                join_type: JoinType::And,
+            }
+        );
+        let e = parse_condition_str("va00 eq 1 va01").unwrap_err();
+        assert_eq!(
+            e.message(),
+            "Expected the end of the condition, found `va01`"
+        );
+    }
+
+    #[test]
+    fn test_parse_script_body_str() {
+        let body = parse_script_body_str("nohh\nover\n").expect("Parsed");
+        assert_eq!(body.commands, vec![Command::Nohh, Command::Over]);
+        assert!(parse_script_body_str("")
+            .expect("Parsed")
+            .commands
+            .is_empty());
+
+        let e = parse_script_body_str("nohh enmu 1 2 3").unwrap_err();
+        assert_eq!(e.suggestion(), Some("ENUM"));
+        let e = parse_script_body_str("reps 1 nohh").unwrap_err();
+        assert_eq!(e.code(), "E0202");
+    }
+}
@@ -1,12 +1,16 @@
 use crate::ast::Anything;
-use crate::{CaosError, Rule};
+use crate::{CaosError, Rule, SpanNode};
 use pest::iterators::Pair;
 
+type CompleteMethod<'i, T> =
+    Box<dyn Fn(Pair<'i, Rule>, Vec<Anything>, &[SpanNode]) -> Result<T, CaosError>>;
+
 pub(crate) struct Partial<'i, T> {
     pub origin: Pair<'i, Rule>,
     pub arg_parts: Vec<Anything>,
+    pub arg_spans: Vec<SpanNode>,
     pub target_args: usize,
-    pub complete_method: Box<dyn Fn(Pair<'i, Rule>, Vec<Anything>) -> Result<T, CaosError>>,
+    pub complete_method: CompleteMethod<'i, T>,
 }
 
 impl<'i, T> Partial<'i, T> {
@@ -15,8 +19,46 @@ impl<'i, T> Partial<'i, T> {
         self.arg_parts.len() == self.target_args
     }
 
-    /// Completes/unwraps the partial.
-    pub fn complete(self) -> Result<T, CaosError> {
-        (self.complete_method)(self.origin, self.arg_parts)
+    /// Pushes an argument, along with its source spans, onto the partial.
+    pub fn push_arg(&mut self, arg: Anything, span: SpanNode) {
+        self.arg_parts.push(arg);
+        self.arg_spans.push(span);
+    }
+
+    /// Completes/unwraps the partial, returning the spans of the completed node.
+    pub fn complete(self) -> Result<(T, SpanNode), CaosError> {
+        let t = (self.complete_method)(self.origin.clone(), self.arg_parts, &self.arg_spans)?;
+        let span = SpanNode::with_children(self.origin.as_span().into(), self.arg_spans);
+        Ok((t, span))
+    }
+
+    /// An error for the partial running out of arguments before it was ready.
+    pub fn arity_error(&self) -> CaosError {
+        CaosError::new_arg_count_error(
+            &keyword_name(&self.origin),
+            self.target_args,
+            self.arg_parts.len(),
+            self.origin.as_span().into(),
+        )
     }
 }
+
+/// The keyword `pair` was parsed from as it is conventionally written, in upper
+/// case with single spaces, such as `NEW: SIMP`.
+pub(crate) fn keyword_name(pair: &Pair<'_, Rule>) -> String {
+    pair.as_str()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_uppercase()
+}
+
+/// Converts a parsed argument into the type a command or expression takes,
+/// reporting a failure at the argument's span.
+pub(crate) fn cast_arg<T>(arg: Anything, span: &SpanNode) -> Result<T, CaosError>
+where
+    T: TryFrom<Anything>,
+    CaosError: From<T::Error>,
+{
+    T::try_from(arg).map_err(|e| CaosError::from(e).with_span(span.span))
+}
@@ -0,0 +1,476 @@
+use super::{
+    parse_commands_recovering, parse_script_with,
+    suggest::{suggest_at, word_at},
+    CaosParser,
+};
+use crate::{
+    ast::{CosFile, Script, ScriptDefinition},
+    parse_cos_with_spans, CaosError, ErrorType, Rule, Span, SpanNode,
+};
+use pest::{iterators::Pair, Parser};
+use std::collections::VecDeque;
+
+/// The result of [parse_cos_recovering].
+#[derive(Debug)]
+pub struct RecoveredParse {
+    /// The scripts that could be parsed, with [Command::Error](crate::ast::Command::Error)
+    /// nodes in place of any commands that could not.
+    pub cos_file: CosFile,
+    /// The spans of [RecoveredParse::cos_file], as returned by [parse_cos_with_spans].
+    pub spans: SpanNode,
+    /// Every error found, in the order they appear in the source.
+    pub errors: Vec<CaosError>,
+    /// The source, with each word which could not be tokenised blanked out.
+    pub(crate) masked: String,
+}
+
+impl RecoveredParse {
+    /// Returns `true` if the source parsed without any errors.
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// State shared by the recovering parsers.
+pub(crate) struct Recovery<'s> {
+    /// The original source, before any of it was skipped.
+    pub source: &'s str,
+    pub errors: Vec<CaosError>,
+    /// Source blanked out before parsing as it could not be tokenised, along
+    /// with its original text.
+    skipped: VecDeque<(Span, String)>,
+}
+
+impl Recovery<'_> {
+    /// Takes the next skipped piece of source if it starts before `offset`.
+    pub fn skipped_before(&mut self, offset: usize) -> Option<(Span, String)> {
+        match self.skipped.front() {
+            Some((span, _)) if span.start < offset => self.skipped.pop_front(),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a CAOS file, recovering from errors rather than stopping at the first.
+///
+/// Words which are not valid CAOS are skipped over. A command which fails to
+/// parse is skipped up to the next command keyword. A script whose header
+/// fails to parse is skipped up to its `ENDM`, and commands outside of any
+/// script are skipped up to the next script, each giving a single error. An
+/// event script missing its `ENDM` is kept, as if it ended before the next
+/// script. Each error is recorded in [RecoveredParse::errors], and source
+/// skipped within a script is kept in the AST as a
+/// [Command::Error](crate::ast::Command::Error).
+///
+/// Source without errors gives the same result as [parse_cos_with_spans].
+pub fn parse_cos_recovering(cos_content: &str) -> RecoveredParse {
+    if let Ok((cos_file, spans)) = parse_cos_with_spans(cos_content) {
+        return RecoveredParse {
+            cos_file,
+            spans,
+            errors: Vec::new(),
+            masked: cos_content.to_owned(),
+        };
+    }
+
+    let mut recovery = Recovery {
+        source: cos_content,
+        errors: Vec::new(),
+        skipped: VecDeque::new(),
+    };
+    let masked = mask_unknown_words(cos_content, &mut recovery);
+    let (cos_file, spans) = match CaosParser::parse(Rule::recovery, &masked) {
+        Ok(mut pairs) => {
+            let program = pairs.next().expect("A program is always produced");
+            recover_program(program, &mut recovery)
+        }
+        Err(e) => {
+            recovery.errors.push(CaosError::from_pest(e, &masked));
+            (CosFile::default(), SpanNode::default())
+        }
+    };
+    // Errors are found word by word and then script by script, so are out of order.
+    recovery
+        .errors
+        .sort_by_key(|e| e.span().map_or(usize::MAX, |span| span.start));
+    RecoveredParse {
+        cos_file,
+        spans,
+        errors: recovery.errors,
+        masked,
+    }
+}
+
+/// Blanks out each word the grammar cannot tokenise, reading the source once
+/// from start to end.
+///
+/// Words are replaced with spaces, so the byte offsets and line numbers of the
+/// rest of the source are unchanged.
+fn mask_unknown_words(cos_content: &str, recovery: &mut Recovery) -> String {
+    let mut masked = cos_content.to_owned();
+    let mut pos = 0;
+    loop {
+        let read = CaosParser::parse(Rule::recovery_prefix, &masked[pos..])
+            .ok()
+            .and_then(|mut pairs| pairs.next())
+            .map_or(0, |prefix| prefix.as_span().end());
+        let start = next_token_start(&masked, pos + read);
+        if start >= masked.len() {
+            break;
+        }
+        let (start, end, error_type, suggestion) = match string_end(&masked, start) {
+            Some(None) => {
+                let end = line_end(&masked, start);
+                (start, end, ErrorType::UnterminatedString, None)
+            }
+            // A string which is closed, but holds an escape the grammar does not have.
+            Some(Some(end)) => {
+                let text = cos_content[start..end].to_owned();
+                (start, end, ErrorType::UnknownToken { text }, None)
+            }
+            None => {
+                let (start, end) = word_at(&masked, start).expect("There is a word at the start");
+                // A misspelt keyword may run on into the next word, as in `NEW: SMIP`.
+                let (suggestion, end) = match suggest_at(&masked, start, end) {
+                    Some((suggestion, end)) => (Some(suggestion), end),
+                    None => (None, end),
+                };
+                let text = cos_content[start..end].to_owned();
+                (start, end, ErrorType::UnknownToken { text }, suggestion)
+            }
+        };
+        let span = pest::Span::new(cos_content, start, end)
+            .expect("Word is within the source")
+            .into();
+        let error = CaosError::new(error_type, Some(span));
+        recovery.errors.push(match suggestion {
+            Some(suggestion) => error.with_suggestion(suggestion),
+            None => error,
+        });
+        recovery
+            .skipped
+            .push_back((span, cos_content[start..end].to_owned()));
+        masked.replace_range(start..end, &" ".repeat(end - start));
+        pos = end;
+    }
+    masked
+}
+
+/// If a string literal starts at `start`, returns the end of it, or `None`
+/// within the result if it is never closed.
+fn string_end(source: &str, start: usize) -> Option<Option<usize>> {
+    let bytes = source.as_bytes();
+    if bytes[start] != b'"' {
+        return None;
+    }
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return Some(Some(i + 1)),
+            _ => i += 1,
+        }
+    }
+    Some(None)
+}
+
+/// Returns the offset of the end of the line `pos` is on.
+fn line_end(source: &str, pos: usize) -> usize {
+    source[pos..]
+        .find(['\r', '\n'])
+        .map_or(source.len(), |i| pos + i)
+}
+
+fn recover_program(pair: Pair<'_, Rule>, recovery: &mut Recovery<'_>) -> (CosFile, SpanNode) {
+    let mut scripts = Vec::new();
+    let mut spans = Vec::new();
+    let mut script_errors = Vec::new();
+    let mut parts = pair
+        .clone()
+        .into_inner()
+        .filter(|p| p.as_rule() != Rule::EOI)
+        .peekable();
+
+    // The commands at the start of the file are an install script, which may
+    // be closed with `ENDM`.
+    if let Some(install) = parts.next_if(|p| p.as_rule() == Rule::recovery_contents) {
+        let mut span: Span = install.as_span().into();
+        if let Some(end) = parts.next_if(|p| p.as_rule() == Rule::recovery_end) {
+            span = span.join(&end.as_span().into());
+        }
+        let (definition, children) = recover_script_contents(install, recovery);
+        scripts.push(Script::Install(definition));
+        spans.push(SpanNode::with_children(span, children));
+    } else {
+        parts.next_if(|p| p.as_rule() == Rule::recovery_end);
+    }
+
+    let mut outside: Option<Span> = None;
+    while let Some(part) = parts.next() {
+        let span: Span = part.as_span().into();
+        // Words skipped since the last script are already reported.
+        while recovery.skipped_before(span.start).is_some() {}
+        if matches!(part.as_rule(), Rule::recovery_end | Rule::recovery_contents) {
+            outside = Some(outside.map_or(span, |outside| outside.join(&span)));
+            continue;
+        }
+        if let Some(outside) = outside.take() {
+            script_errors.push(CaosError::new(ErrorType::OutsideScript, Some(outside)));
+        }
+        if part.as_rule() == Rule::bad_event_script {
+            let header = part.into_inner().next().expect("The script has a header");
+            // The header's span runs on over the whitespace after it.
+            let start = header.as_span().start();
+            let end = header
+                .clone()
+                .into_inner()
+                .last()
+                .map_or(start + "SCRP".len(), |int| int.as_span().end());
+            let span = pest::Span::new(header.get_input(), start, end)
+                .expect("Header is within the source")
+                .into();
+            script_errors.push(CaosError::new(ErrorType::InvalidScriptHeader, Some(span)));
+            continue;
+        }
+        let unclosed = part.as_rule() == Rule::unclosed_event_script;
+        let mut contents = |pair| Ok(recover_script_contents(pair, recovery));
+        match parse_script_with(part, &mut contents) {
+            Ok((script, span)) => {
+                scripts.push(script);
+                spans.push(span);
+            }
+            Err(e) => script_errors.push(e),
+        }
+        if unclosed {
+            script_errors.push(missing_end(span, parts.peek()));
+        }
+    }
+    if let Some(outside) = outside {
+        script_errors.push(CaosError::new(ErrorType::OutsideScript, Some(outside)));
+    }
+
+    recovery.errors.extend(script_errors);
+    (
+        CosFile { scripts },
+        SpanNode::with_children(pair.as_span().into(), spans),
+    )
+}
+
+/// The error for an event script with no `ENDM` before `next`, the start of
+/// the next script, or the end of the file.
+fn missing_end(script: Span, next: Option<&Pair<'_, Rule>>) -> CaosError {
+    let expected = Some(String::from("`ENDM`"));
+    match next {
+        Some(next) => {
+            let (start, end) = word_at(next.get_input(), next.as_span().start())
+                .expect("A script starts with a word");
+            let text = next.get_input()[start..end].to_owned();
+            let span = pest::Span::new(next.get_input(), start, end)
+                .expect("Word is within the source")
+                .into();
+            CaosError::new(ErrorType::UnexpectedToken { text, expected }, Some(span))
+        }
+        None => {
+            let end = Span {
+                start: script.end,
+                start_line_col: script.end_line_col,
+                ..script
+            };
+            CaosError::new(ErrorType::UnexpectedEnd { expected }, Some(end))
+        }
+    }
+}
+
+fn recover_script_contents(
+    pair: Pair<'_, Rule>,
+    recovery: &mut Recovery<'_>,
+) -> (ScriptDefinition, Vec<SpanNode>) {
+    // Skipped source after the last command, up to the end tag, belongs to the script.
+    let end = next_token_start(pair.get_input(), pair.as_span().end());
+    let (commands, spans) = parse_commands_recovering(&mut pair.into_inner(), end, recovery);
+    (ScriptDefinition { commands }, spans)
+}
+
+/// Returns the offset of the first character after `pos` which is not whitespace
+/// or part of a comment.
+fn next_token_start(source: &str, mut pos: usize) -> usize {
+    let bytes = source.as_bytes();
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'*' => {
+                while pos < bytes.len() && bytes[pos] != b'\n' {
+                    pos += 1;
+                }
+            }
+            b if b.is_ascii_whitespace() => pos += 1,
+            _ => break,
+        }
+    }
+    pos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ast::{Command, IntArg, ScriptDefinition},
+        parse_cos,
+    };
+
+    fn error(text: &str) -> Command {
+        Command::Error {
+            text: text.to_owned(),
+        }
+    }
+
+    fn commands(parse: &RecoveredParse, script: usize) -> &[Command] {
+        match &parse.cos_file.scripts[script] {
+            Script::Install(d) | Script::Removal(d) => &d.commands,
+            Script::Event(e) => &e.definition.commands,
+        }
+    }
+
+    #[test]
+    fn test_recover_valid_source() {
+        let content = "inst setv va00 1 endm scrp 1 2 3 4 nohh endm";
+        let parse = parse_cos_recovering(content);
+        assert!(parse.is_ok());
+        assert_eq!(parse.cos_file, parse_cos(content).expect("Parsed"));
+    }
+
+    #[test]
+    fn test_recover_unknown_word() {
+        let parse = parse_cos_recovering("inst\nsetv va00 1\nblah\nkill targ\nendm");
+        assert_eq!(parse.errors.len(), 1);
+        assert_eq!(commands(&parse, 0).len(), 4);
+        assert_eq!(commands(&parse, 0)[2], error("blah"));
+        assert_eq!(
+            parse.spans.children[0].children[2].span.start_line_col,
+            (3, 1)
+        );
+    }
+
+    #[test]
+    fn test_recover_bad_arguments() {
+        let parse = parse_cos_recovering("setv va00 \"string\" kill targ nohh");
+        assert_eq!(parse.errors.len(), 1);
+        assert_eq!(
+            commands(&parse, 0),
+            &[
+                error("setv va00 \"string\""),
+                Command::Kill {
+                    agent: Box::new(crate::ast::Agent::Targ.into())
+                },
+                Command::Nohh
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recover_errors_in_many_scripts() {
+        let parse = parse_cos_recovering(
+            "scrp 1 2 3 4 foo endm\nscrp 1 2 3 5 nohh bar endm\nscrp 1 2 3 6 over endm",
+        );
+        assert_eq!(parse.errors.len(), 2);
+        assert_eq!(parse.cos_file.scripts.len(), 3);
+        assert_eq!(commands(&parse, 0), &[error("foo")]);
+        assert_eq!(commands(&parse, 1), &[Command::Nohh, error("bar")]);
+        assert_eq!(commands(&parse, 2), &[Command::Over]);
+    }
+
+    #[test]
+    fn test_recover_unclosed_block() {
+        let parse = parse_cos_recovering("scrp 1 2 3 4 reps 3 nohh endm");
+        assert_eq!(parse.errors.len(), 1);
+        assert_eq!(
+            commands(&parse, 0),
+            &[Command::Reps {
+                count: Box::new(IntArg::from(3)),
+                definition: ScriptDefinition {
+                    commands: vec![Command::Nohh]
+                }
+            }]
+        );
+    }
+
+    #[test]
+    fn test_recover_stray_closer() {
+        let parse = parse_cos_recovering("nohh next over");
+        assert_eq!(parse.errors.len(), 1);
+        assert_eq!(
+            commands(&parse, 0),
+            &[Command::Nohh, error("next"), Command::Over]
+        );
+    }
+
+    #[test]
+    fn test_recover_trailing_junk() {
+        let parse = parse_cos_recovering("scrp 1 2 3 4 nohh endm junk");
+        assert_eq!(parse.errors.len(), 1);
+        assert_eq!(parse.cos_file.scripts.len(), 1);
+        assert_eq!(commands(&parse, 0), &[Command::Nohh]);
+    }
+
+    #[test]
+    fn test_recover_errors_in_source_order() {
+        let starts = |content: &str| -> Vec<usize> {
+            parse_cos_recovering(content)
+                .errors
+                .iter()
+                .map(|e| e.span().expect("Error has a span").start)
+                .collect()
+        };
+        assert_eq!(starts("[1 2"), [0, 3]);
+        assert_eq!(starts("scrp 1 2"), [0]);
+        assert_eq!(starts("foo scrp 1 2 3 4 bar endm baz"), [0, 17, 26]);
+    }
+
+    #[test]
+    fn test_recover_unterminated_string() {
+        let parse = parse_cos_recovering("sets va00 \"no end\nnohh");
+        assert_eq!(parse.errors.len(), 2);
+        assert_eq!(
+            commands(&parse, 0),
+            &[error("sets va00 \"no end"), Command::Nohh]
+        );
+    }
+
+    #[test]
+    fn test_recover_bad_script_header() {
+        let parse = parse_cos_recovering("scrp 1 2\nsetv va00 1\nendm\nrscr nohh");
+        assert_eq!(parse.errors.len(), 1);
+        assert_eq!(
+            parse.errors[0].code(),
+            ErrorType::InvalidScriptHeader.code()
+        );
+        assert_eq!(
+            parse.errors[0].span().expect("Error has a span").end,
+            "scrp 1 2".len()
+        );
+        assert_eq!(parse.cos_file.scripts.len(), 1);
+        assert_eq!(commands(&parse, 0), &[Command::Nohh]);
+    }
+
+    #[test]
+    fn test_recover_outside_script() {
+        let content = format!(
+            "scrp 1 2 3 4 nohh endm\n{}scrp 1 2 3 5 over endm",
+            "setv va00 1\n".repeat(2000)
+        );
+        let parse = parse_cos_recovering(&content);
+        assert_eq!(parse.errors.len(), 1);
+        assert_eq!(parse.errors[0].code(), ErrorType::OutsideScript.code());
+        assert_eq!(parse.cos_file.scripts.len(), 2);
+        assert_eq!(commands(&parse, 1), &[Command::Over]);
+    }
+
+    #[test]
+    fn test_recover_unclosed_script() {
+        let parse = parse_cos_recovering("scrp 1 2 3 4 nohh\nrscr over");
+        assert_eq!(parse.errors.len(), 1);
+        assert_eq!(parse.errors[0].span().expect("Error has a span").start, 18);
+        assert_eq!(parse.cos_file.scripts.len(), 2);
+        assert_eq!(commands(&parse, 0), &[Command::Nohh]);
+        assert_eq!(commands(&parse, 1), &[Command::Over]);
+    }
+}
@@ -1,47 +1,87 @@
 use super::{base::parse_int_literal, parse_commands};
 use crate::{
     ast::{Command, EventScriptDefinition, Script, ScriptDefinition},
-    CaosError, Rule,
+    CaosError, Rule, SpanNode,
 };
 use pest::iterators::Pair;
 
-pub fn parse_script(pair: Pair<Rule>) -> Result<Script, CaosError> {
+pub fn parse_script(pair: Pair<Rule>) -> Result<(Script, SpanNode), CaosError> {
+    parse_script_with(pair, &mut parse_script_contents)
+}
+
+/// Parses a script, using `contents` to parse its body.
+pub fn parse_script_with<'i, F>(
+    pair: Pair<'i, Rule>,
+    contents: &mut F,
+) -> Result<(Script, SpanNode), CaosError>
+where
+    F: FnMut(Pair<'i, Rule>) -> Result<(ScriptDefinition, Vec<SpanNode>), CaosError>,
+{
     match pair.as_rule() {
-        Rule::install_script => parse_install_script(pair),
-        Rule::remove_script => parse_remove_script(pair),
-        Rule::event_script => parse_event_script(pair),
+        Rule::install_script => parse_install_script(pair, contents),
+        Rule::remove_script => parse_remove_script(pair, contents),
+        Rule::event_script | Rule::unclosed_event_script => parse_event_script(pair, contents),
         _ => Err(CaosError::new_parse_error(pair)),
     }
 }
 
-fn parse_install_script(pair: Pair<Rule>) -> Result<Script, CaosError> {
+fn parse_install_script<'i, F>(
+    pair: Pair<'i, Rule>,
+    contents: &mut F,
+) -> Result<(Script, SpanNode), CaosError>
+where
+    F: FnMut(Pair<'i, Rule>) -> Result<(ScriptDefinition, Vec<SpanNode>), CaosError>,
+{
     if pair.as_rule() != Rule::install_script {
         return Err(CaosError::new_parse_error(pair));
     }
 
-    pair.clone()
+    let (definition, spans) = pair
+        .clone()
         .into_inner()
         .next()
         .ok_or(CaosError::new_parse_error(pair.clone()))
-        .and_then(parse_script_contents)
-        .map(Script::Install)
+        .and_then(contents)?;
+    Ok((
+        Script::Install(definition),
+        SpanNode::with_children(pair.as_span().into(), spans),
+    ))
 }
 
-fn parse_remove_script(pair: Pair<Rule>) -> Result<Script, CaosError> {
+fn parse_remove_script<'i, F>(
+    pair: Pair<'i, Rule>,
+    contents: &mut F,
+) -> Result<(Script, SpanNode), CaosError>
+where
+    F: FnMut(Pair<'i, Rule>) -> Result<(ScriptDefinition, Vec<SpanNode>), CaosError>,
+{
     if pair.as_rule() != Rule::remove_script {
         return Err(CaosError::new_parse_error(pair));
     }
 
-    pair.clone()
+    let (definition, spans) = pair
+        .clone()
         .into_inner()
         .next()
         .ok_or(CaosError::new_parse_error(pair.clone()))
-        .and_then(parse_script_contents)
-        .map(Script::Removal)
+        .and_then(contents)?;
+    Ok((
+        Script::Removal(definition),
+        SpanNode::with_children(pair.as_span().into(), spans),
+    ))
 }
 
-fn parse_event_script(pair: Pair<Rule>) -> Result<Script, CaosError> {
-    if pair.as_rule() != Rule::event_script {
+fn parse_event_script<'i, F>(
+    pair: Pair<'i, Rule>,
+    contents: &mut F,
+) -> Result<(Script, SpanNode), CaosError>
+where
+    F: FnMut(Pair<'i, Rule>) -> Result<(ScriptDefinition, Vec<SpanNode>), CaosError>,
+{
+    if !matches!(
+        pair.as_rule(),
+        Rule::event_script | Rule::unclosed_event_script
+    ) {
         return Err(CaosError::new_parse_error(pair));
     }
 
@@ -52,17 +92,20 @@ fn parse_event_script(pair: Pair<Rule>) -> Result<Script, CaosError> {
         .ok_or(CaosError::new_parse_error(pair.clone()))
         .and_then(parse_event_header)?;
 
-    let definition = it
+    let (definition, spans) = it
         .next()
         .ok_or(CaosError::new_parse_error(pair.clone()))
-        .and_then(parse_script_contents)?;
-    Ok(Script::Event(EventScriptDefinition {
-        family,
-        genus,
-        species,
-        script_number,
-        definition,
-    }))
+        .and_then(contents)?;
+    Ok((
+        Script::Event(EventScriptDefinition {
+            family,
+            genus,
+            species,
+            script_number,
+            definition,
+        }),
+        SpanNode::with_children(pair.as_span().into(), spans),
+    ))
 }
 
 fn parse_event_header(pair: Pair<Rule>) -> Result<(i32, i32, i32, i32), CaosError> {
@@ -95,13 +138,16 @@ fn parse_event_header(pair: Pair<Rule>) -> Result<(i32, i32, i32, i32), CaosErro
     Ok((family, genus, species, script_number))
 }
 
-pub fn parse_script_contents(pair: Pair<Rule>) -> Result<ScriptDefinition, CaosError> {
+pub fn parse_script_contents(
+    pair: Pair<Rule>,
+) -> Result<(ScriptDefinition, Vec<SpanNode>), CaosError> {
     if pair.as_rule() != Rule::script_contents {
         return Err(CaosError::new_parse_error(pair));
     }
 
-    let commands: Result<Vec<Command>, CaosError> = parse_commands(&mut pair.into_inner());
-    commands.map(|commands| ScriptDefinition { commands })
+    let commands: Result<(Vec<Command>, Vec<SpanNode>), CaosError> =
+        parse_commands(&mut pair.into_inner());
+    commands.map(|(commands, spans)| (ScriptDefinition { commands }, spans))
 }
 
 #[cfg(test)]
@@ -117,7 +163,7 @@ mod tests {
     fn test_script_definition_empty() {
         for p in CaosParser::parse(Rule::script_contents, "").expect("Parsed") {
             assert_eq!(
-                parse_script_contents(p).expect("Parsed script contents"),
+                parse_script_contents(p).expect("Parsed script contents").0,
                 ScriptDefinition { commands: vec![] }
             );
         }
@@ -129,7 +175,7 @@ mod tests {
             CaosParser::parse(Rule::script_contents, "PRAY REFR\nGIDS ROOT INST").expect("Parsed")
         {
             assert_eq!(
-                parse_script_contents(p).expect("Parsed script contents"),
+                parse_script_contents(p).expect("Parsed script contents").0,
                 ScriptDefinition {
                     commands: vec![Command::PrayRefr, Command::GidsRoot, Command::Inst]
                 }
@@ -141,7 +187,7 @@ mod tests {
     fn test_script_install_empty_no_end_tag() {
         for p in CaosParser::parse(Rule::install_script, "ISCR").expect("Parsed") {
             assert_eq!(
-                parse_script(p).expect("Parsed command"),
+                parse_script(p).expect("Parsed command").0,
                 Script::Install(ScriptDefinition::default()),
             );
         }
@@ -151,7 +197,7 @@ mod tests {
     fn test_script_install_empty() {
         for p in CaosParser::parse(Rule::install_script, "iscr endm").expect("Parsed") {
             assert_eq!(
-                parse_script(p).expect("Parsed command"),
+                parse_script(p).expect("Parsed command").0,
                 Script::Install(ScriptDefinition::default()),
             );
         }
@@ -160,7 +206,7 @@ mod tests {
     fn test_script_install_no_end_tags() {
         for p in CaosParser::parse(Rule::install_script, "ISCR BRN: DMPB").expect("Parsed") {
             assert_eq!(
-                parse_script(p).expect("Parsed command"),
+                parse_script(p).expect("Parsed command").0,
                 Script::Install(ScriptDefinition {
                     commands: vec![Command::BrnDmpb]
                 }),
@@ -172,7 +218,7 @@ mod tests {
     fn test_script_install() {
         for p in CaosParser::parse(Rule::install_script, "ISCR BRN: DMPB ENDM").expect("Parsed") {
             assert_eq!(
-                parse_script(p).expect("Parsed command"),
+                parse_script(p).expect("Parsed command").0,
                 Script::Install(ScriptDefinition {
                     commands: vec![Command::BrnDmpb]
                 }),
@@ -184,7 +230,7 @@ mod tests {
     fn test_script_removal_empty_no_end_tag() {
         for p in CaosParser::parse(Rule::remove_script, "RSCR").expect("Parsed") {
             assert_eq!(
-                parse_script(p).expect("Parsed command"),
+                parse_script(p).expect("Parsed command").0,
                 Script::Removal(ScriptDefinition::default()),
             );
         }
@@ -194,7 +240,7 @@ mod tests {
     fn test_script_removal_empty() {
         for p in CaosParser::parse(Rule::remove_script, "RSCR ENDM").expect("Parsed") {
             assert_eq!(
-                parse_script(p).expect("Parsed command"),
+                parse_script(p).expect("Parsed command").0,
                 Script::Removal(ScriptDefinition::default()),
             );
         }
@@ -204,7 +250,7 @@ mod tests {
     fn test_script_removal_no_end_tag() {
         for p in CaosParser::parse(Rule::remove_script, "RSCR BRN: DMPB").expect("Parsed") {
             assert_eq!(
-                parse_script(p).expect("Parsed command"),
+                parse_script(p).expect("Parsed command").0,
                 Script::Removal(ScriptDefinition {
                     commands: vec![Command::BrnDmpb]
                 }),
@@ -216,7 +262,7 @@ mod tests {
     fn test_script_removal() {
         for p in CaosParser::parse(Rule::remove_script, "RSCR BRN: DMPB ENDM").expect("Parsed") {
             assert_eq!(
-                parse_script(p).expect("Parsed command"),
+                parse_script(p).expect("Parsed command").0,
                 Script::Removal(ScriptDefinition {
                     commands: vec![Command::BrnDmpb]
                 }),
@@ -228,7 +274,7 @@ mod tests {
     fn test_script_event_empty() {
         for p in CaosParser::parse(Rule::event_script, "SCRP 0 1 2 3 ENDM").expect("Parsed") {
             assert_eq!(
-                parse_script(p).expect("Parsed command"),
+                parse_script(p).expect("Parsed command").0,
                 Script::Event(EventScriptDefinition {
                     definition: ScriptDefinition::default(),
                     family: 0,
@@ -246,7 +292,7 @@ mod tests {
             CaosParser::parse(Rule::event_script, "SCRP 0 1 2 3 BRN: DMPB ENDM").expect("Parsed")
         {
             assert_eq!(
-                parse_script(p).expect("Parsed command"),
+                parse_script(p).expect("Parsed command").0,
                 Script::Event(EventScriptDefinition {
                     definition: ScriptDefinition {
                         commands: vec![Command::BrnDmpb]
@@ -0,0 +1,229 @@
+use super::keyword_name;
+use crate::{CaosError, ErrorType, KeywordRole, Rule};
+use pest::iterators::Pair;
+
+/// Every keyword in the grammar, as `(rule, keyword)` pairs sorted by rule.
+const KEYWORDS: &[(&str, &str)] = caos_macros::grammar_keywords!();
+
+/// Returns the role of a keyword rule, or `None` for rules which are not
+/// keywords or which may be either a command or an expression, such as `ATTR`.
+pub(crate) fn keyword_role(rule: Rule) -> Option<KeywordRole> {
+    let name = format!("{:?}", rule);
+    let prefix = name.split('_').next().unwrap_or_default();
+    match prefix {
+        "command" => Some(KeywordRole::Command),
+        "agent" | "float" | "int" | "string" | "variable" | "literal" => {
+            Some(KeywordRole::Expression)
+        }
+        _ => None,
+    }
+}
+
+/// An error for `pair` being found where a token playing the `expected` role
+/// should have been.
+pub(crate) fn unexpected_token(pair: Pair<'_, Rule>, expected: KeywordRole) -> CaosError {
+    match keyword_role(pair.as_rule()) {
+        Some(role) if role != expected => CaosError::new(
+            ErrorType::MisplacedKeyword {
+                keyword: keyword_name(&pair),
+                role,
+                expected,
+            },
+            Some(pair.as_span().into()),
+        ),
+        _ => CaosError::new_unexpected(pair, &expected.to_string()),
+    }
+}
+
+/// Returns the keyword `text` is most likely a misspelling of, if any is
+/// close enough.
+///
+/// Variables written without their leading zero, such as `VA5`, are corrected
+/// to `VA05`.
+pub fn suggest_keyword(text: &str) -> Option<String> {
+    best_match(text).map(|(keyword, _)| keyword)
+}
+
+/// Suggests a keyword for the unknown word in `source` at `start..end`.
+///
+/// As keywords such as `NEW: SIMP` are made of more than one word, the word is
+/// also tried along with the word following it on the same line. Returns the
+/// suggestion and the end of the source it replaces.
+pub(crate) fn suggest_at(source: &str, start: usize, end: usize) -> Option<(String, usize)> {
+    let single = best_match(&source[start..end]).map(|(s, d)| (s, end, d));
+    let double = next_word(source, end).and_then(|(next_start, next_end)| {
+        let text = format!("{} {}", &source[start..end], &source[next_start..next_end]);
+        best_match(&text).map(|(s, d)| (s, next_end, d))
+    });
+    match (single, double) {
+        (Some(single), Some(double)) if single.2 < double.2 => Some(single),
+        (_, Some(double)) => Some(double),
+        (single, None) => single,
+    }
+    .map(|(suggestion, end, _)| (suggestion, end))
+}
+
+/// Adds a suggestion to `e` for the word at `pos`, if one can be made.
+pub(crate) fn with_suggestion_at(e: CaosError, source: &str, pos: usize) -> CaosError {
+    match word_at(source, pos).and_then(|(start, end)| suggest_at(source, start, end)) {
+        Some((suggestion, _)) => e.with_suggestion(suggestion),
+        None => e,
+    }
+}
+
+/// Finds the byte range of the word at `pos`, or failing that the nearest word
+/// after or before it.
+pub(crate) fn word_at(source: &str, pos: usize) -> Option<(usize, usize)> {
+    let bytes = source.as_bytes();
+    let is_space = |b: u8| b.is_ascii_whitespace();
+    let mut pos = pos.min(bytes.len());
+    if bytes.get(pos).copied().map(is_space).unwrap_or(true) {
+        pos = match (pos..bytes.len()).find(|&i| !is_space(bytes[i])) {
+            Some(next) => next,
+            None => (0..pos).rev().find(|&i| !is_space(bytes[i]))?,
+        };
+    }
+
+    let start = (0..pos)
+        .rev()
+        .find(|&i| is_space(bytes[i]))
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    let end = (pos..bytes.len())
+        .find(|&i| is_space(bytes[i]))
+        .unwrap_or(bytes.len());
+    Some((start, end))
+}
+
+/// Finds the byte range of the word after `end`, if it is on the same line.
+fn next_word(source: &str, end: usize) -> Option<(usize, usize)> {
+    let bytes = source.as_bytes();
+    let start = (end..bytes.len()).find(|&i| !matches!(bytes[i], b' ' | b'\t'))?;
+    if bytes[start].is_ascii_whitespace() || start == end {
+        return None;
+    }
+    word_at(source, start)
+}
+
+/// Returns the closest keyword to `text`, along with its edit distance.
+fn best_match(text: &str) -> Option<(String, usize)> {
+    let text = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_uppercase();
+    if let Some(variable) = padded_variable(&text) {
+        return Some((variable, 1));
+    }
+
+    if KEYWORDS.iter().any(|(_, keyword)| *keyword == text) {
+        return None;
+    }
+
+    let words = text.split(' ').count();
+    KEYWORDS
+        .iter()
+        .map(|(_, keyword)| *keyword)
+        .filter(|keyword| keyword.split(' ').count() == words)
+        .map(|keyword| (keyword, edit_distance(&text, keyword)))
+        .filter(|(keyword, distance)| *distance <= max_distance(keyword))
+        .min_by_key(|(keyword, distance)| (*distance, *keyword))
+        .map(|(keyword, distance)| (keyword.to_owned(), distance))
+}
+
+/// The most edits a word may be from `keyword` to be suggested as it.
+fn max_distance(keyword: &str) -> usize {
+    match keyword.chars().count() {
+        0..=2 => 0,
+        3..=5 => 1,
+        _ => 2,
+    }
+}
+
+/// Corrects `VA5`, `OV5` and `MV5` to `VA05`, `OV05` and `MV05`.
+fn padded_variable(text: &str) -> Option<String> {
+    let digit = ["VA", "OV", "MV"]
+        .iter()
+        .find_map(|prefix| text.strip_prefix(prefix).map(|d| (prefix, d)));
+    match digit {
+        Some((prefix, d)) if d.len() == 1 && d.chars().all(|c| c.is_ascii_digit()) => {
+            Some(format!("{}0{}", prefix, d))
+        }
+        _ => None,
+    }
+}
+
+/// The optimal string alignment distance between `a` and `b`: the number of
+/// insertions, deletions, substitutions and swaps of adjacent characters
+/// needed to turn one into the other.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("ENUM", "ENUM"), 0);
+        assert_eq!(edit_distance("ENMU", "ENUM"), 1);
+        assert_eq!(edit_distance("KIL", "KILL"), 1);
+        assert_eq!(edit_distance("TRAG", "TARG"), 1);
+        assert_eq!(edit_distance("ABCD", "WXYZ"), 4);
+    }
+
+    #[test]
+    fn test_suggest_keyword() {
+        assert_eq!(suggest_keyword("ENMU").as_deref(), Some("ENUM"));
+        assert_eq!(suggest_keyword("enmu").as_deref(), Some("ENUM"));
+        assert_eq!(suggest_keyword("NEW: SMIP").as_deref(), Some("NEW: SIMP"));
+        assert_eq!(suggest_keyword("va5").as_deref(), Some("VA05"));
+        assert_eq!(suggest_keyword("ov9").as_deref(), Some("OV09"));
+        assert_eq!(suggest_keyword("ENUM"), None);
+        assert_eq!(suggest_keyword("XYZZY"), None);
+    }
+
+    #[test]
+    fn test_suggest_at_joins_words() {
+        let source = "new: smip 1 2 3";
+        assert_eq!(
+            suggest_at(source, 0, 4),
+            Some((String::from("NEW: SIMP"), 9))
+        );
+        let source = "enmu 1 2 3";
+        assert_eq!(suggest_at(source, 0, 4), Some((String::from("ENUM"), 4)));
+        let source = "new:\nsmip";
+        assert_eq!(suggest_at(source, 0, 4).map(|(_, end)| end), Some(4));
+    }
+
+    #[test]
+    fn test_keyword_role() {
+        assert_eq!(keyword_role(Rule::command_kill), Some(KeywordRole::Command));
+        assert_eq!(
+            keyword_role(Rule::agent_ownr),
+            Some(KeywordRole::Expression)
+        );
+        assert_eq!(keyword_role(Rule::overloaded_attr), None);
+    }
+}
@@ -0,0 +1,64 @@
+#[cfg(test)]
+mod tests;
+
+mod caos_writer;
+mod comments;
+mod format;
+mod write_caos;
+
+pub use caos_writer::CaosWriter;
+pub(crate) use comments::SourceComments;
+pub use format::{format_cos, FormatOptions, KeywordCase};
+pub use write_caos::WriteCaos;
+pub(crate) use write_caos::WriteCaosIgnored;
+
+use crate::ast::{
+    Agent, AgentArg, Anything, ByteString, Command, Condition, CosFile, Decimal, DecimalArg, Float,
+    FloatArg, IntArg, Integer, Label, SString, SStringArg, Script, ScriptDefinition, Variable,
+};
+use crate::{CaosError, Encoding};
+use std::fmt;
+
+macro_rules! impl_display {
+    ($($t:ty),* $(,)?) => {
+        $(
+            impl fmt::Display for $t {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    self.write_caos(&mut CaosWriter::new(f))
+                }
+            }
+        )*
+    };
+}
+
+// Displaying any AST node writes it out as canonical CAOS, such that parsing
+// the output gives back the same node.
+impl_display!(
+    CosFile,
+    Script,
+    ScriptDefinition,
+    Command,
+    Condition,
+    Anything,
+    Decimal,
+    DecimalArg,
+    IntArg,
+    FloatArg,
+    SStringArg,
+    AgentArg,
+    Integer,
+    Float,
+    SString,
+    Agent,
+    Variable,
+    ByteString,
+    Label,
+);
+
+/// Writes `cos_file` as CAOS in `encoding`, such that the engine reads back
+/// the same strings, failing at any character the encoding does not have.
+///
+/// Spans in errors are within the text given by [CosFile]'s `Display`.
+pub fn write_cos_bytes(cos_file: &CosFile, encoding: Encoding) -> Result<Vec<u8>, CaosError> {
+    encoding.encode(&cos_file.to_string())
+}
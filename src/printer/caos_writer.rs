@@ -0,0 +1,311 @@
+use super::{FormatOptions, KeywordCase, SourceComments};
+use crate::Span;
+use std::fmt::{self, Write};
+
+/// Writes a stream of CAOS tokens, handling the whitespace between them.
+///
+/// Tokens on the same line are separated by a single space. Each new line is
+/// indented according to the current block depth.
+///
+/// When created with [CaosWriter::with_source], the comments of the original
+/// source are written back out around the commands they were found next to.
+pub struct CaosWriter<'a> {
+    out: &'a mut dyn Write,
+    options: FormatOptions,
+    depth: usize,
+    column: usize,
+    at_line_start: bool,
+    at_blank_line: bool,
+    /// Whether the next line continues a command broken by a comment.
+    continued: bool,
+    comments: Option<SourceComments<'a>>,
+}
+
+impl<'a> CaosWriter<'a> {
+    /// Creates a writer using the default [FormatOptions].
+    pub fn new(out: &'a mut dyn Write) -> Self {
+        Self::with_options(out, FormatOptions::default())
+    }
+
+    /// Creates a writer using the given [FormatOptions].
+    pub fn with_options(out: &'a mut dyn Write, options: FormatOptions) -> Self {
+        Self {
+            out,
+            options,
+            depth: 0,
+            column: 0,
+            at_line_start: true,
+            at_blank_line: true,
+            continued: false,
+            comments: None,
+        }
+    }
+
+    /// Creates a writer which reproduces the `comments` found in `source`.
+    ///
+    /// `node_spans` are the spans of every script and command in the AST about
+    /// to be written, in source order, and `words` the spans of the source
+    /// text each token is written from. See [SourceComments] for how comments
+    /// are placed relative to them.
+    pub(crate) fn with_source(
+        out: &'a mut dyn Write,
+        options: FormatOptions,
+        source: &'a str,
+        comments: Vec<Span>,
+        node_spans: Vec<Span>,
+        words: Vec<Span>,
+    ) -> Self {
+        let mut w = Self::with_options(out, options);
+        w.comments = Some(SourceComments::new(source, comments, node_spans, words));
+        w
+    }
+
+    fn write_indent(&mut self, depth: usize) -> fmt::Result {
+        for _ in 0..depth {
+            if self.options.use_tabs {
+                self.out.write_char('\t')?;
+                self.column += self.options.indent_width;
+            } else {
+                for _ in 0..self.options.indent_width {
+                    self.out.write_char(' ')?;
+                }
+                self.column += self.options.indent_width;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the whitespace needed before a token of `len` characters, wrapping
+    /// onto a continuation line if the token would overrun the maximum line length.
+    fn separate(&mut self, len: usize) -> fmt::Result {
+        self.at_blank_line = false;
+        if self.at_line_start {
+            self.at_line_start = false;
+            let depth = self.depth + usize::from(std::mem::take(&mut self.continued));
+            return self.write_indent(depth);
+        }
+
+        let overruns = self
+            .options
+            .max_line_length
+            .map(|max| self.column + 1 + len > max)
+            .unwrap_or(false);
+        if overruns {
+            self.out.write_char('\n')?;
+            self.column = 0;
+            self.write_indent(self.depth + 1)
+        } else {
+            self.column += 1;
+            self.out.write_char(' ')
+        }
+    }
+
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.column += s.chars().count();
+        self.out.write_str(s)
+    }
+
+    /// Writes a CAOS keyword, such as `NEW: SIMP`, in the configured case.
+    pub fn keyword(&mut self, keyword: &str) -> fmt::Result {
+        self.begin_word()?;
+        self.separate(keyword.len())?;
+        match self.options.keyword_case {
+            KeywordCase::Upper => self.write_str(&keyword.to_uppercase()),
+            KeywordCase::Lower => self.write_str(&keyword.to_lowercase()),
+        }
+    }
+
+    /// Writes a non-keyword token, such as a literal or label, verbatim.
+    pub fn token(&mut self, token: &str) -> fmt::Result {
+        self.begin_word()?;
+        self.separate(token.chars().count())?;
+        self.write_str(token)
+    }
+
+    /// Ends the current line. Does nothing if the line is empty.
+    pub fn line(&mut self) -> fmt::Result {
+        if self.at_line_start {
+            return Ok(());
+        }
+        self.at_line_start = true;
+        self.column = 0;
+        self.out.write_char('\n')
+    }
+
+    /// Writes an empty line, unless the output is empty or already ends in one.
+    pub fn blank_line(&mut self) -> fmt::Result {
+        self.line()?;
+        if self.at_blank_line {
+            return Ok(());
+        }
+        self.at_blank_line = true;
+        self.out.write_char('\n')
+    }
+
+    /// Increases the indentation of subsequent lines by one level.
+    pub fn indent(&mut self) {
+        self.depth += 1;
+        if let Some(c) = self.comments.as_mut() {
+            c.blank_allowed = false;
+        }
+    }
+
+    /// Decreases the indentation of subsequent lines by one level.
+    pub fn dedent(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+    }
+
+    /// Writes a comment on its own line.
+    fn comment_line(&mut self, comment: &str) -> fmt::Result {
+        self.line()?;
+        self.token(comment)?;
+        self.line()
+    }
+
+    /// Called before a script or command is written. Writes out any comments
+    /// that came before it in the source.
+    pub(crate) fn begin_node(&mut self) -> fmt::Result {
+        let Some(mut c) = self.comments.take() else {
+            return Ok(());
+        };
+        let res = c.begin_node(self);
+        self.comments = Some(c);
+        res
+    }
+
+    /// Called before a token is written. Writes out any comments that came
+    /// before it within the command being written.
+    fn begin_word(&mut self) -> fmt::Result {
+        let Some(mut c) = self.comments.take() else {
+            return Ok(());
+        };
+        let res = c.begin_word(self);
+        self.comments = Some(c);
+        res
+    }
+
+    /// Called after the opening line of a block is written, before its body.
+    /// Writes out any comment which trails that line in the source.
+    pub(crate) fn begin_body(&mut self) -> fmt::Result {
+        let Some(mut c) = self.comments.take() else {
+            return Ok(());
+        };
+        let res = c.begin_body(self);
+        self.comments = Some(c);
+        res
+    }
+
+    /// Called after a script or command is written. Writes out any comments
+    /// that were inside it or which trail it on the same line.
+    pub(crate) fn end_node(&mut self) -> fmt::Result {
+        let Some(mut c) = self.comments.take() else {
+            return Ok(());
+        };
+        let res = c.end_node(self);
+        self.comments = Some(c);
+        res
+    }
+
+    /// Called after the body of a block is written, before its closing keyword.
+    /// Writes out any comments that came before that keyword in the source.
+    pub(crate) fn end_body(&mut self) -> fmt::Result {
+        let Some(mut c) = self.comments.take() else {
+            return Ok(());
+        };
+        let res = c.end_body(self);
+        self.comments = Some(c);
+        res
+    }
+
+    /// Writes out any comments not yet written.
+    pub(crate) fn finish(&mut self) -> fmt::Result {
+        let Some(mut c) = self.comments.take() else {
+            return Ok(());
+        };
+        let res = c.finish(self);
+        self.comments = Some(c);
+        res
+    }
+}
+
+impl SourceComments<'_> {
+    fn write_blank_line(&mut self, w: &mut CaosWriter<'_>, blank_before: bool) -> fmt::Result {
+        if self.blank_allowed && blank_before {
+            w.blank_line()?;
+        }
+        Ok(())
+    }
+
+    fn write_comment(
+        &mut self,
+        w: &mut CaosWriter<'_>,
+        (blank_before, comment): (bool, &str),
+    ) -> fmt::Result {
+        self.write_blank_line(w, blank_before)?;
+        w.comment_line(comment)?;
+        self.blank_allowed = true;
+        Ok(())
+    }
+
+    fn begin_node(&mut self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        let Some(span) = self.next_node() else {
+            return Ok(());
+        };
+        while let Some(comment) = self.next_comment_before(span.start) {
+            self.write_comment(w, comment)?;
+        }
+        let blank_before = self.has_blank_line_before(span.start);
+        self.write_blank_line(w, blank_before)?;
+        self.enter_node(span);
+        w.line()
+    }
+
+    fn begin_word(&mut self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        let Some(word) = self.next_word() else {
+            return Ok(());
+        };
+        while let Some((_, comment)) = self.next_comment_before(word.start) {
+            w.token(comment)?;
+            w.line()?;
+            w.continued = true;
+        }
+        self.write_word(word);
+        Ok(())
+    }
+
+    fn begin_body(&mut self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        if let Some(comment) = self.next_trailing_comment() {
+            w.token(comment)?;
+        }
+        Ok(())
+    }
+
+    fn end_node(&mut self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        let Some(span) = self.close_node() else {
+            return Ok(());
+        };
+        if let Some(comment) = self.next_trailing_comment() {
+            w.token(comment)?;
+        }
+        w.line()?;
+        while let Some((_, comment)) = self.next_comment_before(span.end) {
+            self.write_comment(w, (false, comment))?;
+        }
+        self.blank_allowed = true;
+        Ok(())
+    }
+
+    fn end_body(&mut self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        while let Some(comment) = self.next_leading_comment() {
+            self.write_comment(w, comment)?;
+        }
+        w.line()
+    }
+
+    fn finish(&mut self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        while let Some(comment) = self.next_comment_before(usize::MAX) {
+            self.write_comment(w, comment)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,155 @@
+use crate::Span;
+use std::collections::VecDeque;
+
+/// Tracks the comments of a CAOS source while its AST is written back out.
+///
+/// Comments are placed relative to the scripts and commands (the "nodes") they
+/// sit between in the source:
+/// * Comments before a node are written on their own lines before it.
+/// * A comment on the same line as the end of a node is kept trailing it.
+/// * A comment after the opening line of a block, such as `DOIF` and its
+///   condition, is kept trailing that line.
+/// * Comments at the end of a block body stay in the body, before the closing keyword.
+/// * A comment in the middle of a command ends the line there, and the rest of
+///   the command is written on a continuation line.
+///
+/// To place comments within a command, each token written is matched with the
+/// next of the source's `words`, the spans of its non-trivia tokens.
+///
+/// A single blank line between nodes or comments in the source is also kept.
+pub(crate) struct SourceComments<'s> {
+    source: &'s str,
+    comments: Vec<Span>,
+    next: usize,
+    pos: usize,
+    nodes: VecDeque<Span>,
+    open: Vec<Span>,
+    words: Vec<Span>,
+    word: usize,
+    pub blank_allowed: bool,
+}
+
+impl<'s> SourceComments<'s> {
+    pub fn new(
+        source: &'s str,
+        comments: Vec<Span>,
+        node_spans: Vec<Span>,
+        words: Vec<Span>,
+    ) -> Self {
+        Self {
+            source,
+            comments,
+            next: 0,
+            pos: 0,
+            nodes: node_spans.into(),
+            open: Vec::new(),
+            words,
+            word: 0,
+            blank_allowed: false,
+        }
+    }
+
+    fn advance(&mut self, offset: usize) {
+        self.pos = self.pos.max(offset);
+    }
+
+    /// Moves on to the first word at or after `offset`, so that a token
+    /// written without a word of its own cannot misplace the comments of
+    /// later nodes.
+    fn seek_word(&mut self, offset: usize) {
+        self.word = self.words.partition_point(|word| word.start < offset);
+    }
+
+    /// The start of the next word to be written, or the end of the source.
+    fn next_word_start(&self) -> usize {
+        self.words
+            .get(self.word)
+            .map_or(self.source.len(), |word| word.start)
+    }
+
+    /// Takes the span of the word for the next token to be written.
+    pub fn next_word(&mut self) -> Option<Span> {
+        let word = *self.words.get(self.word)?;
+        self.word += 1;
+        Some(word)
+    }
+
+    /// Marks the word for a token as written, once comments before it are written.
+    pub fn write_word(&mut self, word: Span) {
+        self.advance(word.end);
+    }
+
+    fn comment_str(&self, comment: Span) -> &'s str {
+        comment.as_str(self.source).trim_end()
+    }
+
+    /// Takes the span of the next node to be written.
+    pub fn next_node(&mut self) -> Option<Span> {
+        let span = self.nodes.pop_front()?;
+        self.open.push(span);
+        Some(span)
+    }
+
+    /// Marks the position of the node being written, once comments before it are written.
+    pub fn enter_node(&mut self, span: Span) {
+        self.advance(span.start);
+        self.seek_word(span.start);
+    }
+
+    /// Finishes the innermost node being written.
+    pub fn close_node(&mut self) -> Option<Span> {
+        let span = self.open.pop()?;
+        self.advance(span.end);
+        self.seek_word(span.end);
+        Some(span)
+    }
+
+    /// Takes the next comment if `f` accepts it, returning whether it was
+    /// preceded by a blank line along with its text.
+    fn take_comment_if<F>(&mut self, f: F) -> Option<(bool, &'s str)>
+    where
+        F: FnOnce(&Self, Span) -> bool,
+    {
+        let comment = *self.comments.get(self.next)?;
+        if !f(self, comment) {
+            return None;
+        }
+        let blank_before = self.has_blank_line_before(comment.start);
+        self.next += 1;
+        self.advance(comment.end);
+        Some((blank_before, self.comment_str(comment)))
+    }
+
+    /// Takes the next comment if it starts before `offset`.
+    pub fn next_comment_before(&mut self, offset: usize) -> Option<(bool, &'s str)> {
+        self.take_comment_if(|_, comment| comment.start < offset)
+    }
+
+    /// Takes the next comment if it is on the same line as the last written
+    /// position, and before the next word to be written.
+    pub fn next_trailing_comment(&mut self) -> Option<&'s str> {
+        self.take_comment_if(|c, comment| {
+            comment.start >= c.pos
+                && comment.start < c.next_word_start()
+                && !c.source[c.pos..comment.start].contains('\n')
+        })
+        .map(|(_, comment)| comment)
+    }
+
+    /// Takes the next comment if only whitespace separates it from the last written position.
+    pub fn next_leading_comment(&mut self) -> Option<(bool, &'s str)> {
+        self.take_comment_if(|c, comment| {
+            comment.start >= c.pos && c.source[c.pos..comment.start].trim().is_empty()
+        })
+    }
+
+    /// Returns `true` if the source has an empty line just before `offset`.
+    pub fn has_blank_line_before(&self, offset: usize) -> bool {
+        if offset <= self.pos {
+            return false;
+        }
+        let gap = &self.source[self.pos..offset];
+        let whitespace = &gap[gap.trim_end().len()..];
+        whitespace.matches('\n').count() >= 2
+    }
+}
@@ -0,0 +1,187 @@
+use super::{CaosWriter, WriteCaos};
+use crate::{
+    ast::{Command, Script, ScriptDefinition},
+    parse_cst, CaosError, Cst, Span, SpanNode, TokenKind,
+};
+use std::fmt;
+
+/// The case keywords are written in. CAOS keywords are case insensitive.
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Default)]
+pub enum KeywordCase {
+    #[default]
+    Upper,
+    Lower,
+}
+
+/// Options controlling the layout of printed CAOS.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct FormatOptions {
+    /// Columns per level of indentation. Also used as the width of a tab when
+    /// measuring line length.
+    pub indent_width: usize,
+    /// Indent with tabs rather than spaces.
+    pub use_tabs: bool,
+    pub keyword_case: KeywordCase,
+    /// Commands longer than this are wrapped onto indented continuation lines.
+    pub max_line_length: Option<usize>,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent_width: 4,
+            use_tabs: false,
+            keyword_case: KeywordCase::Upper,
+            max_line_length: None,
+        }
+    }
+}
+
+/// Reformats CAOS source.
+///
+/// Blocks are re-indented, keywords written in a consistent case and each command
+/// placed on its own line. Comments, and single blank lines between commands, are
+/// kept. An install script without an `ISCR` tag in the source is left implicit.
+pub fn format_cos(source: &str, options: &FormatOptions) -> Result<String, CaosError> {
+    let cst = parse_cst(source)?;
+
+    let mut node_spans = Vec::new();
+    for (script, span) in cst.ast().scripts.iter().zip(&cst.spans().children) {
+        node_spans.push(span.span);
+        collect_body_spans(script_definition(script), &span.children, &mut node_spans);
+    }
+    let comments = cst
+        .tokens()
+        .filter(|t| t.kind == TokenKind::Comment)
+        .map(|t| t.span)
+        .collect();
+
+    let mut out = String::new();
+    let mut w = CaosWriter::with_source(
+        &mut out,
+        options.clone(),
+        source,
+        comments,
+        node_spans,
+        words(&cst),
+    );
+    write_scripts(&mut w, &cst).expect("Writing to a String cannot fail");
+    Ok(out)
+}
+
+fn write_scripts(w: &mut CaosWriter<'_>, cst: &Cst) -> fmt::Result {
+    let scripts = &cst.ast().scripts;
+    for (i, (script, span)) in scripts.iter().zip(&cst.spans().children).enumerate() {
+        if i > 0 {
+            w.blank_line()?;
+        }
+        w.begin_node()?;
+        match script {
+            Script::Install(definition) if i == 0 && is_implicit(cst.source(), span) => {
+                definition.write_caos(w)?;
+                if has_end_tag(cst, span) {
+                    w.line()?;
+                    w.keyword("ENDM")?;
+                }
+            }
+            _ => script.write_caos(w)?,
+        }
+        w.end_node()?;
+    }
+    w.finish()?;
+    w.line()
+}
+
+/// The spans of the source text each token is written from: the non-trivia
+/// tokens, with the brackets and bytes of a byte string kept together.
+fn words(cst: &Cst) -> Vec<Span> {
+    let mut words: Vec<Span> = Vec::new();
+    let mut in_byte_string = false;
+    for token in cst.tokens() {
+        if token.is_trivia() || token.span.start == token.span.end {
+            continue;
+        }
+        let text = cst.text(token);
+        match words.last_mut() {
+            Some(word) if in_byte_string => *word = word.join(&token.span),
+            _ => words.push(token.span),
+        }
+        if token.kind == TokenKind::Unnamed {
+            match text {
+                "[" => in_byte_string = true,
+                "]" => in_byte_string = false,
+                _ => {}
+            }
+        }
+    }
+    words
+}
+
+fn script_definition(script: &Script) -> &ScriptDefinition {
+    match script {
+        Script::Install(definition) | Script::Removal(definition) => definition,
+        Script::Event(e) => &e.definition,
+    }
+}
+
+/// Returns `true` if an install script was written without its `ISCR` tag.
+fn is_implicit(source: &str, span: &SpanNode) -> bool {
+    !span
+        .span
+        .as_str(source)
+        .get(..4)
+        .map(|tag| tag.eq_ignore_ascii_case("iscr"))
+        .unwrap_or(false)
+}
+
+/// Returns `true` if the source of an implicit install script ends in `ENDM`.
+fn has_end_tag(cst: &Cst, span: &SpanNode) -> bool {
+    let body_end = span
+        .children
+        .last()
+        .map(|c| c.span.end)
+        .unwrap_or(span.span.start);
+    cst.tokens()
+        .filter(|t| !t.is_trivia() && t.span.start >= body_end && t.span.end <= span.span.end)
+        .any(|t| cst.text(t).eq_ignore_ascii_case("endm"))
+}
+
+/// Collects the spans of each command in a body, and of the commands nested in
+/// their blocks, in source order.
+fn collect_body_spans(definition: &ScriptDefinition, spans: &[SpanNode], out: &mut Vec<Span>) {
+    for (command, span) in definition.commands.iter().zip(spans) {
+        collect_command_spans(command, span, out);
+    }
+}
+
+fn collect_command_spans(command: &Command, span: &SpanNode, out: &mut Vec<Span>) {
+    out.push(span.span);
+    let children = &span.children[..];
+    match command {
+        Command::Doif(do_if) => {
+            let mut offset = 1;
+            collect_body_spans(&do_if.definition, &children[offset..], out);
+            offset += do_if.definition.commands.len();
+            for (_, definition) in &do_if.elif_definitions {
+                offset += 1;
+                collect_body_spans(definition, &children[offset..], out);
+                offset += definition.commands.len();
+            }
+            if let Some(definition) = &do_if.else_definition {
+                collect_body_spans(definition, &children[offset..], out);
+            }
+        }
+        Command::Subr { definition, .. }
+        | Command::Reps { definition, .. }
+        | Command::Econ { definition, .. } => {
+            collect_body_spans(definition, &children[1..], out);
+        }
+        Command::LoopEver { definition } | Command::LoopUntl { definition, .. } => {
+            collect_body_spans(definition, children, out);
+        }
+        Command::Enum(e) | Command::Etch(e) | Command::Esee(e) | Command::Epas(e) => {
+            collect_body_spans(&e.definition, &children[3..], out);
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,271 @@
+use crate::{
+    ast::{Command, CosFile, IntArg, SString, Variable},
+    format_cos, parse_cos, FormatOptions, KeywordCase,
+};
+
+fn round_trip(content: &str) -> CosFile {
+    let ast = parse_cos(content).expect("Parsed original");
+    let printed = ast.to_string();
+    let reparsed = parse_cos(&printed).expect("Parsed printed output");
+    assert_eq!(ast, reparsed, "Printed output:\n{}", printed);
+    ast
+}
+
+#[test]
+fn test_print_simple_command() {
+    let c = Command::Setv {
+        var: Box::new(Variable::Vaxx(5)),
+        value: Box::new(1.5f32.into()),
+    };
+    assert_eq!(c.to_string(), "SETV VA05 1.5");
+}
+
+#[test]
+fn test_print_keywords_with_spaces() {
+    let ast = parse_cos("new: simp 1 2 3 \"x\" 1 0 mv01").expect("Parsed");
+    assert_eq!(
+        ast.scripts[0].to_string(),
+        "ISCR\n    NEW: SIMP 1 2 3 \"x\" 1 0 MV01\nENDM"
+    );
+}
+
+#[test]
+fn test_print_float_literals() {
+    assert_eq!(IntArg::from(3f32).to_string(), "3.0");
+    assert_eq!(IntArg::from(-0.25f32).to_string(), "-0.25");
+    assert_eq!(IntArg::from(0.0000001f32).to_string(), "0.0000001");
+}
+
+#[test]
+fn test_print_escaped_string() {
+    let s = SString::Literal(String::from("a \"quoted\" \\ line\nbreak"));
+    assert_eq!(s.to_string(), r#""a \"quoted\" \\ line\nbreak""#);
+}
+
+#[test]
+fn test_print_blocks() {
+    let ast = parse_cos(
+        "SCRP 2 3 4 9 DOIF VA00 EQ 1 AND OV01 <> 2 OVER ELIF 1 = 2 ELSE REPS 3 NOHH REPE ENDI ENDM",
+    )
+    .expect("Parsed");
+    assert_eq!(
+        ast.to_string(),
+        "SCRP 2 3 4 9
+    DOIF VA00 EQ 1 AND OV01 NE 2
+        OVER
+    ELIF 1 EQ 2
+    ELSE
+        REPS 3
+            NOHH
+        REPE
+    ENDI
+ENDM
+"
+    );
+}
+
+#[test]
+fn test_round_trip_scripts() {
+    let ast = round_trip("BRN: DMPB RSCR OVER SCRP 1 2 3 4 NOHH ENDM SCRP 1 2 3 5 ENDM");
+    assert_eq!(ast.scripts.len(), 4);
+}
+
+#[test]
+fn test_round_trip_commands() {
+    round_trip(
+        r#"
+        ISCR
+            SETV VA00 RAND 1 MUTE 2 3
+            SETS VA01 "escaped \"string\"\n"
+            SETA VA02 NCLS NULL 1 2 3
+            ANIM [0 1 2 255]
+            MESG WRT+ TARG 1000 "p1" 2.5 0
+            DBG: ASRT VA00 >= 1 OR VA00 < -1
+            DBG: OUTS VTOS SIN_ -1.5
+            GSUB foo
+            GOTO bar
+            SUBR foo
+                ENUM 1 2 0
+                    KILL TARG
+                NEXT
+                ESEE 0 0 0 NEXT
+                ETCH 0 0 0 NEXT
+                EPAS 0 0 0 NEXT
+                ECON OWNR NEXT
+            RETN
+            LOOP
+                ADDV VA00 1
+            UNTL VA00 GT 10
+            LOOP EVER
+            SETV GAME "score" 'a'
+            SETV NAME "x" MAME 3
+            NAMN VA00
+            DELN EAME "x"
+            SETV AVAR TARG 3 %101
+            SETV _P1_ .5
+        ENDM
+        "#,
+    );
+}
+
+fn format(source: &str, options: &FormatOptions) -> String {
+    let formatted = format_cos(source, options).expect("Formatted");
+    let again = format_cos(&formatted, options).expect("Formatted output");
+    assert_eq!(formatted, again, "Formatting is not idempotent");
+    formatted
+}
+
+#[test]
+fn test_format_comments() {
+    let formatted = format(
+        "* Header comment\n\ninst\nnew: simp 1 2 3 \"x\" 1 0 0 * trailing\n\n  * lone\n\
+         doif va00 eq 1\n setv va00 2\n\n * before endi\nendi\n\n\n\
+         SCRP 1 2 3 4\n* inside\nenum 1 2 3 kill targ next\nENDM\n* footer\n",
+        &FormatOptions::default(),
+    );
+    assert_eq!(
+        formatted,
+        "* Header comment
+
+INST
+NEW: SIMP 1 2 3 \"x\" 1 0 0 * trailing
+
+* lone
+DOIF VA00 EQ 1
+    SETV VA00 2
+
+    * before endi
+ENDI
+
+SCRP 1 2 3 4
+    * inside
+    ENUM 1 2 3
+        KILL TARG
+    NEXT
+ENDM
+* footer
+"
+    );
+}
+
+/// Formats `source`, checking its comments are written in the same order.
+fn format_keeping_comments(source: &str) -> String {
+    let formatted = format(source, &FormatOptions::default());
+    let comments = |text: &str| -> Vec<String> {
+        text.lines()
+            .filter_map(|line| line.find('*').map(|i| line[i..].trim_end().to_owned()))
+            .collect()
+    };
+    assert_eq!(
+        comments(&formatted),
+        comments(source),
+        "Formatted:\n{}",
+        formatted
+    );
+    formatted
+}
+
+#[test]
+fn test_format_block_opener_comments() {
+    assert_eq!(
+        format_keeping_comments("doif va00 eq 1 * c1\nendi * c2\n"),
+        "DOIF VA00 EQ 1 * c1\nENDI * c2\n"
+    );
+    assert_eq!(
+        format_keeping_comments(
+            "scrp 1 2 3 4 * c1\ndoif 1 eq 1 setv va00 1 * c2\nelif 2 eq 2 * c3\n\
+             * c4\nelse * c5\nendi\nreps 3 * c6\n* c7\nrepe\nendm\n"
+        ),
+        "SCRP 1 2 3 4 * c1
+    DOIF 1 EQ 1
+        SETV VA00 1 * c2
+    ELIF 2 EQ 2 * c3
+        * c4
+    ELSE * c5
+    ENDI
+    REPS 3 * c6
+        * c7
+    REPE
+ENDM
+"
+    );
+}
+
+#[test]
+fn test_format_comments_within_commands() {
+    assert_eq!(
+        format_keeping_comments(
+            "iscr\nsetv va00 * c1\n  1 * c2\nnew: simp * c3\n1 2 3 \"x\" 1 0 0\nendm\n"
+        ),
+        "ISCR
+    SETV VA00 * c1
+        1 * c2
+    NEW: SIMP * c3
+        1 2 3 \"x\" 1 0 0
+ENDM
+"
+    );
+}
+
+#[test]
+fn test_format_comment_like_strings() {
+    let formatted = format(
+        "sets va00 \"not * a comment\" * a comment\n",
+        &FormatOptions::default(),
+    );
+    assert_eq!(formatted, "SETS VA00 \"not * a comment\" * a comment\n");
+}
+
+#[test]
+fn test_format_keyword_case() {
+    let options = FormatOptions {
+        keyword_case: KeywordCase::Lower,
+        ..Default::default()
+    };
+    let formatted = format("ISCR SETS VA00 \"KEEP\" ENDM", &options);
+    assert_eq!(formatted, "iscr\n    sets va00 \"KEEP\"\nendm\n");
+}
+
+#[test]
+fn test_format_indent() {
+    let source = "SCRP 1 2 3 4 REPS 2 OVER REPE ENDM";
+
+    let options = FormatOptions {
+        indent_width: 2,
+        ..Default::default()
+    };
+    assert_eq!(
+        format(source, &options),
+        "SCRP 1 2 3 4\n  REPS 2\n    OVER\n  REPE\nENDM\n"
+    );
+
+    let options = FormatOptions {
+        use_tabs: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        format(source, &options),
+        "SCRP 1 2 3 4\n\tREPS 2\n\t\tOVER\n\tREPE\nENDM\n"
+    );
+}
+
+#[test]
+fn test_format_max_line_length() {
+    let options = FormatOptions {
+        max_line_length: Some(20),
+        ..Default::default()
+    };
+    let formatted = format("ISCR NEW: SIMP 1 2 3 \"long_name\" 1 0 0 ENDM", &options);
+    assert_eq!(
+        formatted,
+        "ISCR\n    NEW: SIMP 1 2 3\n        \"long_name\"\n        1 0 0\nENDM\n"
+    );
+}
+
+#[test]
+fn test_format_implicit_install() {
+    let options = FormatOptions::default();
+    assert_eq!(format("inst  over", &options), "INST\nOVER\n");
+    assert_eq!(format("over endm", &options), "OVER\nENDM\n");
+    assert_eq!(format("iscr over endm", &options), "ISCR\n    OVER\nENDM\n");
+}
@@ -0,0 +1,387 @@
+use super::CaosWriter;
+use crate::ast::{
+    AgentArg, Anything, ByteString, ClassifierEnum, Command, Condition, ConditionType, CosFile,
+    Decimal, DecimalArg, DoIf, EventScriptDefinition, Float, FloatArg, IntArg, Integer, JoinType,
+    Label, SString, SStringArg, Script, ScriptDefinition, Variable,
+};
+use std::fmt;
+
+/// Writes an AST node out as CAOS source.
+pub trait WriteCaos {
+    fn write_caos(&self, w: &mut CaosWriter<'_>) -> fmt::Result;
+}
+
+/// Writes the variants of a derived [WriteCaos] type which are marked `#[parse(ignore)]`,
+/// and so have no single keyword to print.
+pub(crate) trait WriteCaosIgnored {
+    fn write_caos_ignored(&self, w: &mut CaosWriter<'_>) -> fmt::Result;
+}
+
+impl<T: WriteCaos + ?Sized> WriteCaos for Box<T> {
+    fn write_caos(&self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        self.as_ref().write_caos(w)
+    }
+}
+
+impl WriteCaos for CosFile {
+    fn write_caos(&self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        for (i, script) in self.scripts.iter().enumerate() {
+            if i > 0 {
+                w.blank_line()?;
+            }
+            w.begin_node()?;
+            script.write_caos(w)?;
+            w.end_node()?;
+        }
+        w.finish()?;
+        w.line()
+    }
+}
+
+impl WriteCaos for Script {
+    fn write_caos(&self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        match self {
+            Script::Install(definition) => {
+                w.keyword("ISCR")?;
+                write_body(definition, w)?;
+            }
+            Script::Removal(definition) => {
+                w.keyword("RSCR")?;
+                write_body(definition, w)?;
+            }
+            Script::Event(EventScriptDefinition {
+                definition,
+                family,
+                genus,
+                species,
+                script_number,
+            }) => {
+                w.keyword("SCRP")?;
+                family.write_caos(w)?;
+                genus.write_caos(w)?;
+                species.write_caos(w)?;
+                script_number.write_caos(w)?;
+                write_body(definition, w)?;
+            }
+        }
+        w.keyword("ENDM")
+    }
+}
+
+impl WriteCaos for ScriptDefinition {
+    fn write_caos(&self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        for (i, command) in self.commands.iter().enumerate() {
+            if i > 0 {
+                w.line()?;
+            }
+            w.begin_node()?;
+            command.write_caos(w)?;
+            w.end_node()?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the body of a block, one command per line indented by one level.
+fn write_body(definition: &ScriptDefinition, w: &mut CaosWriter<'_>) -> fmt::Result {
+    w.begin_body()?;
+    w.indent();
+    for command in &definition.commands {
+        w.line()?;
+        w.begin_node()?;
+        command.write_caos(w)?;
+        w.end_node()?;
+    }
+    w.end_body()?;
+    w.dedent();
+    w.line()
+}
+
+impl WriteCaosIgnored for Command {
+    fn write_caos_ignored(&self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        match self {
+            Command::Gsub { destination } => {
+                w.keyword("GSUB")?;
+                destination.write_caos(w)
+            }
+            Command::DbgAsrt { condition } => {
+                w.keyword("DBG: ASRT")?;
+                condition.write_caos(w)
+            }
+            Command::Goto { destination } => {
+                w.keyword("GOTO")?;
+                destination.write_caos(w)
+            }
+            Command::Doif(do_if) => do_if.write_caos(w),
+            Command::Subr { label, definition } => {
+                w.keyword("SUBR")?;
+                label.write_caos(w)?;
+                write_body(definition, w)?;
+                w.keyword("RETN")
+            }
+            Command::Reps { count, definition } => {
+                w.keyword("REPS")?;
+                count.write_caos(w)?;
+                write_body(definition, w)?;
+                w.keyword("REPE")
+            }
+            Command::LoopEver { definition } => {
+                w.keyword("LOOP")?;
+                write_body(definition, w)?;
+                w.keyword("EVER")
+            }
+            Command::LoopUntl {
+                definition,
+                condition,
+            } => {
+                w.keyword("LOOP")?;
+                write_body(definition, w)?;
+                w.keyword("UNTL")?;
+                condition.write_caos(w)
+            }
+            Command::Econ { agent, definition } => {
+                w.keyword("ECON")?;
+                agent.write_caos(w)?;
+                write_body(definition, w)?;
+                w.keyword("NEXT")
+            }
+            Command::Enum(e) => write_classifier_enum("ENUM", e, w),
+            Command::Etch(e) => write_classifier_enum("ETCH", e, w),
+            Command::Esee(e) => write_classifier_enum("ESEE", e, w),
+            Command::Epas(e) => write_classifier_enum("EPAS", e, w),
+            Command::Error { text } => w.token(text),
+            _ => unreachable!("Command is not an ignored variant"),
+        }
+    }
+}
+
+fn write_classifier_enum(
+    keyword: &str,
+    classifier: &ClassifierEnum,
+    w: &mut CaosWriter<'_>,
+) -> fmt::Result {
+    w.keyword(keyword)?;
+    classifier.family.write_caos(w)?;
+    classifier.genus.write_caos(w)?;
+    classifier.species.write_caos(w)?;
+    write_body(&classifier.definition, w)?;
+    w.keyword("NEXT")
+}
+
+impl WriteCaos for DoIf {
+    fn write_caos(&self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        w.keyword("DOIF")?;
+        self.condition.write_caos(w)?;
+        write_body(&self.definition, w)?;
+        for (condition, definition) in &self.elif_definitions {
+            w.keyword("ELIF")?;
+            condition.write_caos(w)?;
+            write_body(definition, w)?;
+        }
+        if let Some(definition) = &self.else_definition {
+            w.keyword("ELSE")?;
+            write_body(definition, w)?;
+        }
+        w.keyword("ENDI")
+    }
+}
+
+impl WriteCaos for Condition {
+    fn write_caos(&self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        match self {
+            Condition::Simple {
+                cond_type,
+                lhs,
+                rhs,
+            } => {
+                lhs.write_caos(w)?;
+                cond_type.write_caos(w)?;
+                rhs.write_caos(w)
+            }
+            Condition::Combination {
+                c_lhs,
+                c_rhs,
+                join_type,
+            } => {
+                c_lhs.write_caos(w)?;
+                join_type.write_caos(w)?;
+                c_rhs.write_caos(w)
+            }
+        }
+    }
+}
+
+impl WriteCaos for ConditionType {
+    fn write_caos(&self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        w.keyword(match self {
+            ConditionType::Eq => "EQ",
+            ConditionType::Ne => "NE",
+            ConditionType::Ge => "GE",
+            ConditionType::Gt => "GT",
+            ConditionType::Le => "LE",
+            ConditionType::Lt => "LT",
+        })
+    }
+}
+
+impl WriteCaos for JoinType {
+    fn write_caos(&self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        w.keyword(match self {
+            JoinType::And => "AND",
+            JoinType::Or => "OR",
+        })
+    }
+}
+
+impl WriteCaos for Label {
+    fn write_caos(&self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        w.token(self.as_ref())
+    }
+}
+
+impl WriteCaos for Anything {
+    fn write_caos(&self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        match self {
+            Anything::Variable(v) => v.write_caos(w),
+            Anything::String(s) => s.write_caos(w),
+            Anything::Decimal(d) => d.write_caos(w),
+            Anything::ByteString(b) => b.write_caos(w),
+            Anything::Agent(a) => a.write_caos(w),
+        }
+    }
+}
+
+impl WriteCaos for Decimal {
+    fn write_caos(&self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        match self {
+            Decimal::Integer(i) => i.write_caos(w),
+            Decimal::Float(f) => f.write_caos(w),
+        }
+    }
+}
+
+impl WriteCaos for DecimalArg {
+    fn write_caos(&self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        match self {
+            DecimalArg::Decimal(d) => d.write_caos(w),
+            DecimalArg::Variable(v) => v.write_caos(w),
+        }
+    }
+}
+
+impl WriteCaos for IntArg {
+    fn write_caos(&self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        match self {
+            IntArg::Primary(i) => i.write_caos(w),
+            IntArg::Castable(f) => f.write_caos(w),
+            IntArg::Variable(v) => v.write_caos(w),
+        }
+    }
+}
+
+impl WriteCaos for FloatArg {
+    fn write_caos(&self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        match self {
+            FloatArg::Primary(f) => f.write_caos(w),
+            FloatArg::Castable(i) => i.write_caos(w),
+            FloatArg::Variable(v) => v.write_caos(w),
+        }
+    }
+}
+
+impl WriteCaos for SStringArg {
+    fn write_caos(&self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        match self {
+            SStringArg::String(s) => s.write_caos(w),
+            SStringArg::Variable(v) => v.write_caos(w),
+        }
+    }
+}
+
+impl WriteCaos for AgentArg {
+    fn write_caos(&self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        match self {
+            AgentArg::Agent(a) => a.write_caos(w),
+            AgentArg::Variable(v) => v.write_caos(w),
+        }
+    }
+}
+
+impl WriteCaos for i32 {
+    fn write_caos(&self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        w.token(&self.to_string())
+    }
+}
+
+impl WriteCaos for f32 {
+    fn write_caos(&self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        // Display never uses exponent notation, but drops the fractional part of
+        // whole numbers, which CAOS needs to tell a float from an integer.
+        let mut s = self.to_string();
+        if !s.contains('.') {
+            s.push_str(".0");
+        }
+        w.token(&s)
+    }
+}
+
+impl WriteCaos for ByteString {
+    fn write_caos(&self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        let bytes: Vec<String> = self.as_ref().iter().map(u8::to_string).collect();
+        w.token(&format!("[{}]", bytes.join(" ")))
+    }
+}
+
+impl WriteCaosIgnored for Integer {
+    fn write_caos_ignored(&self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        match self {
+            Integer::Literal(i) => i.write_caos(w),
+            _ => unreachable!("Integer is not an ignored variant"),
+        }
+    }
+}
+
+impl WriteCaosIgnored for Float {
+    fn write_caos_ignored(&self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        match self {
+            Float::Literal(f) => f32::from(f.clone()).write_caos(w),
+            _ => unreachable!("Float is not an ignored variant"),
+        }
+    }
+}
+
+impl WriteCaosIgnored for SString {
+    fn write_caos_ignored(&self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        match self {
+            SString::Literal(s) => w.token(&escape_string(s)),
+            _ => unreachable!("SString is not an ignored variant"),
+        }
+    }
+}
+
+/// Quotes a string literal, escaping characters as the grammar expects.
+fn escape_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+impl WriteCaosIgnored for Variable {
+    fn write_caos_ignored(&self, w: &mut CaosWriter<'_>) -> fmt::Result {
+        match self {
+            Variable::Mvxx(x) => w.keyword(&format!("MV{:02}", x)),
+            Variable::Ovxx(x) => w.keyword(&format!("OV{:02}", x)),
+            Variable::Vaxx(x) => w.keyword(&format!("VA{:02}", x)),
+            _ => unreachable!("Variable is not an ignored variant"),
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! A catalog of every CAOS command and expression the AST can represent.
+//!
+//! Each [Signature] gives a keyword's arguments, what it returns and the
+//! category it is documented under. Signatures are looked up by keyword with
+//! [lookup], by AST variant with [by_variant], or from a node with
+//! [Signatures::signature].
+//!
+//! ```
+//! use caos2::signature::{self, ArgKind, ReturnType};
+//!
+//! let new_simp = &signature::lookup("new: simp")[0];
+//! assert_eq!(new_simp.returns, ReturnType::Command);
+//! assert_eq!(new_simp.args[0].name, "family");
+//! assert_eq!(new_simp.args[3].kind, ArgKind::SStringArg);
+//! ```
+
+#[cfg(test)]
+mod tests;
+
+use crate::ast::{Agent, Command, Float, Integer, SString, Variable};
+use std::fmt;
+
+pub use caos_macros::Signatures;
+
+/// What a keyword is and the arguments it takes.
+#[derive(Debug, Eq, PartialEq)]
+pub struct Signature {
+    /// The keyword, such as `NEW: SIMP`.
+    pub keyword: &'static str,
+    /// The grammar rule which matches the keyword, or the empty string for
+    /// keywords the grammar handles specially, such as `DOIF`.
+    pub rule: &'static str,
+    /// The name of the AST variant the keyword is parsed to.
+    pub variant: &'static str,
+    /// The AST type the keyword is parsed to.
+    pub returns: ReturnType,
+    /// The group the keyword is documented under, such as `Camera`.
+    pub category: &'static str,
+    pub args: &'static [Argument],
+}
+
+/// A single argument of a [Signature].
+#[derive(Debug, Eq, PartialEq)]
+pub struct Argument {
+    pub name: &'static str,
+    pub kind: ArgKind,
+}
+
+/// The kind of value an argument accepts, named after its AST type.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum ArgKind {
+    IntArg,
+    FloatArg,
+    DecimalArg,
+    SStringArg,
+    AgentArg,
+    Anything,
+    ByteString,
+    Variable,
+    Label,
+    Condition,
+}
+
+/// The AST type a keyword is parsed to.
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
+pub enum ReturnType {
+    Command,
+    Integer,
+    Float,
+    SString,
+    Agent,
+    Variable,
+}
+
+/// Describes the variants of an AST type.
+///
+/// Derived with `#[derive(Signatures)]` for the variants with a grammar rule.
+pub trait Signatures {
+    /// The [ReturnType] of every variant.
+    const RETURNS: ReturnType;
+    /// The signatures of the variants with a grammar rule.
+    const SIGNATURES: &'static [Signature];
+
+    /// The name of the variant `self` is.
+    fn variant_name(&self) -> &'static str;
+
+    /// The signature of the variant `self` is, if it has a keyword.
+    fn signature(&self) -> Option<&'static Signature> {
+        by_variant(Self::RETURNS, self.variant_name())
+    }
+}
+
+impl fmt::Display for Signature {
+    /// Writes the signature as its keyword followed by its arguments, such as
+    /// `ADDV var (Variable) value (DecimalArg)`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.keyword)?;
+        for arg in self.args {
+            write!(f, " {} ({:?})", arg.name, arg.kind)?;
+        }
+        if self.returns != ReturnType::Command {
+            write!(f, " -> {:?}", self.returns)?;
+        }
+        Ok(())
+    }
+}
+
+/// Every signature in the catalog, commands first.
+pub fn signatures() -> impl Iterator<Item = &'static Signature> {
+    [
+        Command::SIGNATURES,
+        IGNORED_COMMANDS,
+        Integer::SIGNATURES,
+        Float::SIGNATURES,
+        SString::SIGNATURES,
+        Agent::SIGNATURES,
+        Variable::SIGNATURES,
+        IGNORED_VARIABLES,
+    ]
+    .into_iter()
+    .flatten()
+}
+
+/// The signatures for `keyword`, ignoring case and spacing.
+///
+/// Keywords such as `TARG` are both a command and an expression, and so have
+/// more than one signature.
+pub fn lookup(keyword: &str) -> Vec<&'static Signature> {
+    let keyword = keyword
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_uppercase();
+    signatures().filter(|s| s.keyword == keyword).collect()
+}
+
+/// The signature of the variant named `variant` of the AST type `returns`.
+pub fn by_variant(returns: ReturnType, variant: &str) -> Option<&'static Signature> {
+    signatures().find(|s| s.returns == returns && s.variant == variant)
+}
+
+const fn arg(name: &'static str, kind: ArgKind) -> Argument {
+    Argument { name, kind }
+}
+
+/// A signature for a variant marked `#[parse(ignore)]`, which has no rule.
+const fn ignored(
+    keyword: &'static str,
+    variant: &'static str,
+    returns: ReturnType,
+    category: &'static str,
+    args: &'static [Argument],
+) -> Signature {
+    Signature {
+        keyword,
+        rule: "",
+        variant,
+        returns,
+        category,
+        args,
+    }
+}
+
+const fn command(
+    keyword: &'static str,
+    variant: &'static str,
+    category: &'static str,
+    args: &'static [Argument],
+) -> Signature {
+    ignored(keyword, variant, ReturnType::Command, category, args)
+}
+
+const CONDITION: &[Argument] = &[arg("condition", ArgKind::Condition)];
+const DESTINATION: &[Argument] = &[arg("destination", ArgKind::Label)];
+const CLASSIFIER: &[Argument] = &[
+    arg("family", ArgKind::IntArg),
+    arg("genus", ArgKind::IntArg),
+    arg("species", ArgKind::IntArg),
+];
+
+/// The [Command] variants marked `#[parse(ignore)]` which have a keyword.
+const IGNORED_COMMANDS: &[Signature] = &[
+    command("DBG: ASRT", "DbgAsrt", "Debug", CONDITION),
+    command("DOIF", "Doif", "Flow", CONDITION),
+    command("ECON", "Econ", "Agents", &[arg("agent", ArgKind::AgentArg)]),
+    command("ENUM", "Enum", "Agents", CLASSIFIER),
+    command("EPAS", "Epas", "Vehicles", CLASSIFIER),
+    command("ESEE", "Esee", "Agents", CLASSIFIER),
+    command("ETCH", "Etch", "Agents", CLASSIFIER),
+    command("GOTO", "Goto", "Flow", DESTINATION),
+    command("GSUB", "Gsub", "Flow", DESTINATION),
+    command("LOOP", "LoopEver", "Flow", &[]),
+    command("LOOP", "LoopUntl", "Flow", CONDITION),
+    command("REPS", "Reps", "Flow", &[arg("count", ArgKind::IntArg)]),
+    command("SUBR", "Subr", "Flow", &[arg("label", ArgKind::Label)]),
+];
+
+/// The [Variable] variants marked `#[parse(ignore)]`, each standing for a
+/// hundred numbered keywords.
+const IGNORED_VARIABLES: &[Signature] = &[
+    ignored("MVXX", "Mvxx", ReturnType::Variable, "Variables", &[]),
+    ignored("OVXX", "Ovxx", ReturnType::Variable, "Variables", &[]),
+    ignored("VAXX", "Vaxx", ReturnType::Variable, "Variables", &[]),
+];
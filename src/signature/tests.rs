@@ -0,0 +1,159 @@
+use super::*;
+use crate::ast::{AgentArg, Anything, Decimal};
+use crate::{parse_command_str, parse_expression_str};
+use std::collections::HashSet;
+
+#[test]
+fn test_lookup_command() {
+    let signatures = lookup("NEW:   simp");
+    assert_eq!(signatures.len(), 1);
+    let new_simp = signatures[0];
+    assert_eq!(new_simp.keyword, "NEW: SIMP");
+    assert_eq!(new_simp.rule, "command_new_simp");
+    assert_eq!(new_simp.variant, "NewSimp");
+    assert_eq!(new_simp.returns, ReturnType::Command);
+    assert_eq!(new_simp.category, "Agents");
+    let args: Vec<_> = new_simp.args.iter().map(|a| (a.name, a.kind)).collect();
+    assert_eq!(
+        args,
+        [
+            ("family", ArgKind::IntArg),
+            ("genus", ArgKind::IntArg),
+            ("species", ArgKind::IntArg),
+            ("sprite_file", ArgKind::SStringArg),
+            ("image_count", ArgKind::IntArg),
+            ("first_image", ArgKind::IntArg),
+            ("plane", ArgKind::IntArg),
+        ]
+    );
+}
+
+#[test]
+fn test_lookup_overloaded() {
+    let mut returns: Vec<_> = lookup("targ").iter().map(|s| s.returns).collect();
+    returns.sort_by_key(|r| format!("{:?}", r));
+    assert_eq!(returns, [ReturnType::Agent, ReturnType::Command]);
+    assert!(lookup("XYZZ").is_empty());
+}
+
+#[test]
+fn test_by_variant() {
+    let rand = by_variant(ReturnType::Integer, "Rand").unwrap();
+    assert_eq!(rand.keyword, "RAND");
+    assert_eq!(rand.category, "Variables");
+    assert_eq!(
+        rand.to_string(),
+        "RAND value1 (IntArg) value2 (IntArg) -> Integer"
+    );
+    assert!(by_variant(ReturnType::Command, "Rand").is_none());
+}
+
+#[test]
+fn test_signature_of_node() {
+    let kill = Command::Kill {
+        agent: Box::new(AgentArg::Agent(Agent::Ownr)),
+    };
+    assert_eq!(
+        kill.signature().unwrap().to_string(),
+        "KILL agent (AgentArg)"
+    );
+
+    let reps = Command::Reps {
+        count: Box::new(Integer::Literal(3).into()),
+        definition: Default::default(),
+    };
+    assert_eq!(reps.signature().unwrap().category, "Flow");
+
+    assert_eq!(Variable::Vaxx(3).signature().unwrap().keyword, "VAXX");
+    assert!(Integer::Literal(3).signature().is_none());
+}
+
+#[test]
+fn test_every_rule_has_one_signature_per_type() {
+    let mut seen = HashSet::new();
+    for s in signatures() {
+        assert!(
+            seen.insert((s.returns, s.variant)),
+            "{:?} {} appears twice",
+            s.returns,
+            s.variant
+        );
+        assert!(!s.category.is_empty(), "{} has no category", s.keyword);
+    }
+    assert!(seen.len() > 500);
+}
+
+#[test]
+fn test_raw_argument_names() {
+    let modv = &lookup("MODV")[0];
+    assert_eq!(modv.args[1].name, "mod");
+}
+
+/// Source for a keyword of `signature` with a value for each argument.
+fn example(signature: &Signature) -> String {
+    let mut source = signature.keyword.to_owned();
+    for arg in signature.args {
+        source.push(' ');
+        source.push_str(match arg.kind {
+            ArgKind::IntArg | ArgKind::DecimalArg | ArgKind::Anything => "1",
+            ArgKind::FloatArg => "1.5",
+            ArgKind::SStringArg => "\"a\"",
+            ArgKind::AgentArg => "targ",
+            ArgKind::ByteString => "[1 2]",
+            ArgKind::Variable => "va00",
+            ArgKind::Label => "label",
+            ArgKind::Condition => "va00 eq 1",
+        });
+    }
+    source
+}
+
+/// The signature of the node an expression parses to.
+fn expression_signature(expression: &Anything) -> Option<&'static Signature> {
+    match expression {
+        Anything::Variable(v) => v.signature(),
+        Anything::String(s) => s.signature(),
+        Anything::Decimal(Decimal::Integer(i)) => i.signature(),
+        Anything::Decimal(Decimal::Float(f)) => f.signature(),
+        Anything::Agent(a) => a.signature(),
+        Anything::ByteString(_) => None,
+    }
+}
+
+#[test]
+fn test_every_signature_parses() {
+    // Keywords without a rule are only parsed as part of a block.
+    for signature in signatures().filter(|s| !s.rule.is_empty()) {
+        let source = example(signature);
+        let parsed = match signature.returns {
+            ReturnType::Command => parse_command_str(&source).map(|c| c.signature()),
+            _ => parse_expression_str(&source).map(|e| expression_signature(&e)),
+        };
+        let parsed = parsed.unwrap_or_else(|e| panic!("`{}` failed to parse: {:?}", source, e));
+        // Expressions such as `FACE` are both an integer and a string, and
+        // parse to whichever comes first without a type to expect.
+        assert_eq!(
+            parsed.map(|s| s.keyword),
+            Some(signature.keyword),
+            "{}",
+            source
+        );
+    }
+}
+
+#[test]
+fn test_catalog_covers_engine_keywords() {
+    let engine: HashSet<_> = include_str!("engine_keywords.txt")
+        .lines()
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .collect();
+    let missing: Vec<_> = engine.iter().filter(|k| lookup(k).is_empty()).collect();
+    assert!(missing.is_empty(), "Not in the catalog: {:?}", missing);
+    let unknown: Vec<_> = signatures()
+        .map(|s| s.keyword)
+        .filter(|k| !engine.contains(k))
+        .collect();
+    assert!(unknown.is_empty(), "Not an engine keyword: {:?}", unknown);
+    // A Creatures 2 command, left out of the list on purpose.
+    assert!(lookup("SNDV").is_empty());
+}
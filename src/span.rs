@@ -0,0 +1,163 @@
+use crate::LineCol;
+
+/// A region of CAOS source text.
+///
+/// Holds the byte range of the region within the source, along with the
+/// line and column of its first and last characters.
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub start_line_col: LineCol,
+    pub end_line_col: LineCol,
+}
+
+impl Span {
+    /// Returns the smallest span covering both `self` and `other`.
+    pub fn join(&self, other: &Span) -> Span {
+        let (start, start_line_col) = if self.start <= other.start {
+            (self.start, self.start_line_col)
+        } else {
+            (other.start, other.start_line_col)
+        };
+        let (end, end_line_col) = if self.end >= other.end {
+            (self.end, self.end_line_col)
+        } else {
+            (other.end, other.end_line_col)
+        };
+        Span {
+            start,
+            end,
+            start_line_col,
+            end_line_col,
+        }
+    }
+
+    /// Returns `true` if the byte `offset` falls within the span.
+    pub fn contains(&self, offset: usize) -> bool {
+        self.start <= offset && offset < self.end
+    }
+
+    /// The slice of `source` this span covers.
+    pub fn as_str<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.start..self.end]
+    }
+}
+
+impl From<pest::Span<'_>> for Span {
+    fn from(s: pest::Span<'_>) -> Self {
+        Span {
+            start: s.start(),
+            end: s.end(),
+            start_line_col: s.start_pos().line_col(),
+            end_line_col: s.end_pos().line_col(),
+        }
+    }
+}
+
+/// The source span of a single AST node, along with the spans of its child nodes.
+///
+/// A [SpanNode] tree mirrors the shape of the AST it was parsed alongside, so a
+/// tool walking both together can recover where any node came from. Nodes are
+/// recorded for each script, command, condition, label and expression
+/// (including literals and variables). Argument wrappers such as `IntArg` or
+/// `Anything`, and `ScriptDefinition` bodies, are transparent: their contents
+/// appear directly as children of the enclosing node.
+///
+/// Children are always in source order. For block commands this means the
+/// header arguments come first, followed by the body commands; for example
+/// `DOIF` is `[condition, commands.., elif condition, commands.., else commands..]`
+/// and `LOOP .. UNTL` is `[commands.., condition]`.
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct SpanNode {
+    pub span: Span,
+    pub children: Vec<SpanNode>,
+}
+
+impl SpanNode {
+    /// A span node with no children.
+    pub fn leaf(span: Span) -> Self {
+        Self {
+            span,
+            children: Vec::new(),
+        }
+    }
+
+    /// A span node covering `span` and all of the given `children`.
+    pub fn with_children(span: Span, children: Vec<SpanNode>) -> Self {
+        let mut node = Self::leaf(span);
+        for c in children {
+            node.push(c);
+        }
+        node
+    }
+
+    /// Appends a child node, widening this node's span to cover it.
+    pub fn push(&mut self, child: SpanNode) {
+        self.span = self.span.join(&child.span);
+        self.children.push(child);
+    }
+
+    /// Widens this node's span to also cover `span`.
+    pub fn extend(&mut self, span: Span) {
+        self.span = self.span.join(&span);
+    }
+
+    /// Returns the chain of nodes containing the byte `offset`, from this node
+    /// down to the innermost node.
+    pub fn path_to(&self, offset: usize) -> Vec<&SpanNode> {
+        let mut path = Vec::new();
+        let mut node = self;
+        if !node.span.contains(offset) {
+            return path;
+        }
+        loop {
+            path.push(node);
+            match node.children.iter().find(|c| c.span.contains(offset)) {
+                Some(c) => node = c,
+                None => return path,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(start: usize, end: usize) -> Span {
+        Span {
+            start,
+            end,
+            start_line_col: (1, start + 1),
+            end_line_col: (1, end + 1),
+        }
+    }
+
+    #[test]
+    fn test_span_join() {
+        assert_eq!(span(2, 5).join(&span(4, 9)), span(2, 9));
+        assert_eq!(span(4, 9).join(&span(2, 5)), span(2, 9));
+        assert_eq!(span(0, 9).join(&span(2, 5)), span(0, 9));
+    }
+
+    #[test]
+    fn test_span_node_push_widens() {
+        let mut n = SpanNode::leaf(span(0, 4));
+        n.push(SpanNode::leaf(span(5, 7)));
+        assert_eq!(n.span, span(0, 7));
+        assert_eq!(n.children, vec![SpanNode::leaf(span(5, 7))]);
+    }
+
+    #[test]
+    fn test_span_node_path_to() {
+        let n = SpanNode::with_children(
+            span(0, 4),
+            vec![SpanNode::leaf(span(5, 7)), SpanNode::leaf(span(8, 10))],
+        );
+        let path = n.path_to(9);
+        assert_eq!(path.len(), 2);
+        assert_eq!(path[1].span, span(8, 10));
+        assert!(n.path_to(12).is_empty());
+    }
+}
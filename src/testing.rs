@@ -0,0 +1,422 @@
+//! Runs unit tests written in CAOS.
+//!
+//! A test is an `ISCR` or `SCRP` script with a `* test: <name>` comment
+//! directly above it. Each test runs in its own [Interpreter] once the rest
+//! of the file is loaded, so the file's event scripts are available and its
+//! install scripts have set up any agents the test needs. An install script
+//! runs with no owner, and an event script is run by the first agent with its
+//! family, genus and species, which is made if there is none.
+//!
+//! A test fails if a `DBG: ASRT` condition is false, or if the script stops
+//! with any other error. `* expect: <line>` comments after `* test:` list the
+//! lines the test must write with `DBG: OUTS` and `DBG: OUTV`, in order.
+//!
+//! ```
+//! use caos2::testing::run_tests;
+//!
+//! let source = "
+//! * test: adds
+//! * expect: 3
+//! iscr setv va00 1 addv va00 2 dbg: outv va00 dbg: asrt va00 eq 3 endm
+//! ";
+//! let suite = run_tests("example.cos", source).unwrap();
+//! assert!(suite.passed());
+//! assert!(caos2::testing::tap(&[suite]).contains("ok 1 - example.cos: adds"));
+//! ```
+
+#[cfg(test)]
+mod tests;
+
+use crate::ast::{CosFile, Script};
+use crate::interpreter::{AgentState, Interpreter, Program, Thread};
+use crate::{parse_cst, CaosError, Cst, LineCol, Span, SpanNode, TokenKind};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// A script marked as a test.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct TestCase {
+    pub name: String,
+    /// The index of the script in the file.
+    pub script: usize,
+    pub span: Span,
+    /// The lines the test must write to the debug log, if given.
+    pub expected_output: Option<Vec<String>>,
+}
+
+/// The result of running a [TestCase].
+#[derive(Debug)]
+pub struct TestResult {
+    pub name: String,
+    pub span: Span,
+    /// The lines written by `DBG: OUTS` and `DBG: OUTV`.
+    pub output: Vec<String>,
+    pub failure: Option<Failure>,
+}
+
+/// Why a test failed.
+#[derive(Debug)]
+pub enum Failure {
+    /// The script stopped with an error, such as a failed `DBG: ASRT`.
+    Error(CaosError),
+    /// The test wrote other lines than its `* expect:` comments.
+    Output {
+        expected: Vec<String>,
+        found: Vec<String>,
+    },
+}
+
+/// The results of the tests in a file.
+#[derive(Debug)]
+pub struct TestSuite {
+    /// The name of the file, used in reports.
+    pub name: String,
+    pub results: Vec<TestResult>,
+    /// Why the file could not be parsed, in which case no tests were run.
+    pub error: Option<CaosError>,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+
+    /// Where the test failed, or where it starts if it passed or failed
+    /// without pointing at a command.
+    pub fn location(&self) -> LineCol {
+        match &self.failure {
+            Some(Failure::Error(e)) => e.line_col().unwrap_or(self.span.start_line_col),
+            _ => self.span.start_line_col,
+        }
+    }
+}
+
+impl TestSuite {
+    /// A suite for a file which could not be parsed, which reports show as a
+    /// single error in place of its tests.
+    pub fn unparsed(name: &str, error: CaosError) -> TestSuite {
+        TestSuite {
+            name: name.to_owned(),
+            results: Vec::new(),
+            error: Some(error),
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.error.is_none() && self.results.iter().all(TestResult::passed)
+    }
+
+    pub fn failures(&self) -> usize {
+        self.results.iter().filter(|r| !r.passed()).count()
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Failure::Error(e) => write!(f, "{}", e.message()),
+            Failure::Output { expected, found } => {
+                write!(f, "Expected output {:?}, found {:?}", expected, found)
+            }
+        }
+    }
+}
+
+/// Finds the scripts marked as tests in a file.
+pub fn discover(cst: &Cst) -> Vec<TestCase> {
+    let spans = cst.spans();
+    let starts: HashMap<usize, usize> = spans
+        .children
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (node.span.start, i))
+        .collect();
+
+    let mut cases = Vec::new();
+    let mut comments = Vec::new();
+    for token in cst.tokens() {
+        match token.kind {
+            TokenKind::Comment => comments.push(cst.text(token)),
+            TokenKind::Whitespace => {}
+            // Such as the contents of an empty implicit install script.
+            _ if token.span.start == token.span.end => {}
+            _ => {
+                let header = cst.text(token).to_ascii_lowercase();
+                if let Some(&script) = starts.get(&token.span.start) {
+                    if header == "iscr" || header == "scrp" {
+                        let span = spans.children[script].span;
+                        cases.extend(test_case(&comments, script, span));
+                    }
+                }
+                comments.clear();
+            }
+        }
+    }
+    cases
+}
+
+/// Reads a test from the comments above a script, if they mark one.
+fn test_case(comments: &[&str], script: usize, span: Span) -> Option<TestCase> {
+    let marker = comments
+        .iter()
+        .position(|c| directive(c, "test").is_some())?;
+    let name = match directive(comments[marker], "test") {
+        Some("") | None => format!("script {}", script + 1),
+        Some(name) => name.to_owned(),
+    };
+    let expected: Vec<_> = comments[marker + 1..]
+        .iter()
+        .filter_map(|c| directive(c, "expect"))
+        .map(str::to_owned)
+        .collect();
+    Some(TestCase {
+        name,
+        script,
+        span,
+        expected_output: (!expected.is_empty()).then_some(expected),
+    })
+}
+
+/// The text after `* <name>:` in a comment.
+fn directive<'a>(comment: &'a str, name: &str) -> Option<&'a str> {
+    let text = comment.trim_start_matches('*').trim_start();
+    let (directive, rest) = text.split_once(':')?;
+    directive.eq_ignore_ascii_case(name).then(|| rest.trim())
+}
+
+/// Runs the tests in a file, failing only if the file cannot be parsed.
+pub fn run_tests(name: &str, source: &str) -> Result<TestSuite, CaosError> {
+    let cst = parse_cst(source)?;
+    let cases = discover(&cst);
+
+    let (scripts, spans) = cst
+        .ast()
+        .scripts
+        .iter()
+        .zip(&cst.spans().children)
+        .enumerate()
+        .filter(|(i, _)| !cases.iter().any(|c| c.script == *i))
+        .map(|(_, (script, node))| (script.clone(), node.clone()))
+        .unzip();
+    let setup = CosFile { scripts };
+    let setup_spans = SpanNode::with_children(cst.spans().span, spans);
+
+    let results = cases
+        .into_iter()
+        .map(|case| run_case(&cst, &setup, &setup_spans, case))
+        .collect();
+    Ok(TestSuite {
+        name: name.to_owned(),
+        results,
+        error: None,
+    })
+}
+
+fn run_case(cst: &Cst, setup: &CosFile, setup_spans: &SpanNode, case: TestCase) -> TestResult {
+    let mut interpreter = Interpreter::new();
+    let script = &cst.ast().scripts[case.script];
+    let node = &cst.spans().children[case.script];
+    let result = interpreter
+        .load_cos_file(setup, setup_spans)
+        .and_then(|()| test_thread(&mut interpreter, script, node))
+        .and_then(|mut thread| interpreter.run_thread(&mut thread));
+
+    let output = interpreter.debug_log().to_vec();
+    let failure = match (result, case.expected_output) {
+        (Err(e), _) => Some(Failure::Error(e)),
+        (Ok(()), Some(expected)) if expected != output => Some(Failure::Output {
+            expected,
+            found: output.clone(),
+        }),
+        (Ok(()), _) => None,
+    };
+    TestResult {
+        name: case.name,
+        span: case.span,
+        output,
+        failure,
+    }
+}
+
+fn test_thread(
+    interpreter: &mut Interpreter,
+    script: &Script,
+    node: &SpanNode,
+) -> Result<Thread, CaosError> {
+    Ok(match script {
+        Script::Event(event) => {
+            let program = Program::compile_with_spans(&event.definition, &node.children)?;
+            let (f, g, s) = (event.family, event.genus, event.species);
+            let world = &mut interpreter.world;
+            let existing = world
+                .agents()
+                .find(|(_, a)| (a.family, a.genus, a.species) == (f, g, s))
+                .map(|(id, _)| id);
+            let owner = existing.unwrap_or_else(|| world.add_agent(AgentState::new(f, g, s)));
+            Thread::new(Arc::new(program), Some(owner))
+        }
+        Script::Install(definition) | Script::Removal(definition) => {
+            let program = Program::compile_with_spans(definition, &node.children)?;
+            Thread::new(Arc::new(program), None)
+        }
+    })
+}
+
+/// Reports the results of some tests in the Test Anything Protocol. A file
+/// which could not be parsed is a single failing test named after the file.
+pub fn tap(suites: &[TestSuite]) -> String {
+    let mut count = 0;
+    let mut out = String::new();
+    for suite in suites {
+        if let Some(error) = &suite.error {
+            count += 1;
+            out += &format!("not ok {} - {}\n", count, suite.name);
+            let (line, col) = error.line_col().unwrap_or((1, 1));
+            out += &tap_diagnostic(&error.message(), &suite.name, (line, col), &[]);
+        }
+        for result in &suite.results {
+            count += 1;
+            let status = if result.passed() { "ok" } else { "not ok" };
+            out += &format!("{} {} - {}: {}\n", status, count, suite.name, result.name);
+            if let Some(failure) = &result.failure {
+                let message = failure.to_string();
+                out += &tap_diagnostic(&message, &suite.name, result.location(), &result.output);
+            }
+        }
+    }
+    format!("TAP version 13\n1..{}\n{}", count, out)
+}
+
+/// The YAML block describing a failing test in a TAP report.
+fn tap_diagnostic(message: &str, file: &str, (line, col): LineCol, output: &[String]) -> String {
+    let mut out = String::from("  ---\n");
+    out += &format!("  message: {}\n", yaml_string(message));
+    out += &format!(
+        "  at: {}\n",
+        yaml_string(&format!("{}:{}:{}", file, line, col))
+    );
+    if !output.is_empty() {
+        out += "  output:\n";
+        for line in output {
+            out += &format!("    - {}\n", yaml_string(line));
+        }
+    }
+    out += "  ...\n";
+    out
+}
+
+/// Reports the results of some tests as JUnit XML, with a `testsuite` for
+/// each file. A file which could not be parsed has a single `testcase`, named
+/// after the file, holding an `error`.
+pub fn junit(suites: &[TestSuite]) -> String {
+    let errors = suites.iter().filter(|s| s.error.is_some()).count();
+    let tests = errors + suites.iter().map(|s| s.results.len()).sum::<usize>();
+    let failures: usize = suites.iter().map(TestSuite::failures).sum();
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out += &format!(
+        "<testsuites tests=\"{}\" failures=\"{}\" errors=\"{}\">\n",
+        tests, failures, errors
+    );
+    for suite in suites {
+        let name = xml_escape(&suite.name);
+        let errors = usize::from(suite.error.is_some());
+        out += &format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\">\n",
+            name,
+            errors + suite.results.len(),
+            suite.failures(),
+            errors
+        );
+        if let Some(error) = &suite.error {
+            let (line, col) = error.line_col().unwrap_or((1, 1));
+            let message = xml_escape(&error.message());
+            out += &format!(
+                "    <testcase name=\"{}\" classname=\"{}\" file=\"{}\" line=\"{}\">\n",
+                name, name, name, line
+            );
+            out += &format!(
+                "      <error message=\"{}\" type=\"{}\">{}:{}:{}: {}</error>\n",
+                message,
+                error.code(),
+                name,
+                line,
+                col,
+                message
+            );
+            out += "    </testcase>\n";
+        }
+        for result in &suite.results {
+            out += &format!(
+                "    <testcase name=\"{}\" classname=\"{}\" file=\"{}\" line=\"{}\"",
+                xml_escape(&result.name),
+                name,
+                name,
+                result.span.start_line_col.0
+            );
+            let Some(failure) = &result.failure else {
+                out += "/>\n";
+                continue;
+            };
+            let kind = match failure {
+                Failure::Error(e) => e.code(),
+                Failure::Output { .. } => "output",
+            };
+            let (line, col) = result.location();
+            let message = xml_escape(&failure.to_string());
+            out += ">\n";
+            out += &format!(
+                "      <failure message=\"{}\" type=\"{}\">{}:{}:{}: {}</failure>\n",
+                message, kind, name, line, col, message
+            );
+            if !result.output.is_empty() {
+                let output = xml_escape(&result.output.join("\n"));
+                out += &format!("      <system-out>{}</system-out>\n", output);
+            }
+            out += "    </testcase>\n";
+        }
+        out += "  </testsuite>\n";
+    }
+    out += "</testsuites>\n";
+    out
+}
+
+/// Writes a YAML double-quoted string, so that output with line breaks or
+/// other control characters stays on one line.
+fn yaml_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '\\' => out += "\\\\",
+            '"' => out += "\\\"",
+            '\n' => out += "\\n",
+            '\r' => out += "\\r",
+            '\t' => out += "\\t",
+            c if c.is_control() => out += &format!("\\x{:02x}", u32::from(c)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Escapes text for an XML attribute or element. Line breaks and tabs are
+/// written as character references, so attributes keep them, and the other
+/// C0 control characters, which XML 1.0 does not allow at all, as U+FFFD.
+fn xml_escape(s: &str) -> String {
+    let mut out = String::new();
+    for c in s.chars() {
+        match c {
+            '&' => out += "&amp;",
+            '<' => out += "&lt;",
+            '>' => out += "&gt;",
+            '"' => out += "&quot;",
+            '\'' => out += "&apos;",
+            '\n' | '\r' | '\t' => out += &format!("&#{};", u32::from(c)),
+            c if c < ' ' => out.push(char::REPLACEMENT_CHARACTER),
+            c if c.is_control() => out += &format!("&#{};", u32::from(c)),
+            c => out.push(c),
+        }
+    }
+    out
+}
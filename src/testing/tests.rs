@@ -0,0 +1,217 @@
+use super::*;
+
+const SOURCE: &str = "\
+* Setup, which is not a test.
+new: simp 2 11 100 \"a\" 1 0 0 setv ov00 5
+
+* test: passes
+iscr
+    dbg: asrt totl 2 11 100 eq 1
+endm
+
+* A description of the test.
+* TEST: fails
+* expect: one
+iscr
+    dbg: outs \"one\"
+    setv va00 1
+    dbg: asrt va00 eq 2
+endm
+
+* test: uses the owner
+scrp 2 11 100 1000
+    dbg: asrt ov00 eq 5
+endm
+
+* test: writes
+* expect: 1
+* expect: two
+iscr dbg: outv 1 dbg: outs \"too\" endm
+
+* test:
+rscr dbg: asrt 1 eq 2 endm
+
+* Not a test.
+scrp 2 11 100 1 dbg: asrt 1 eq 2 endm
+";
+
+#[test]
+fn test_discover() {
+    let cst = parse_cst(SOURCE).unwrap();
+    let cases = discover(&cst);
+    let names: Vec<_> = cases.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["passes", "fails", "uses the owner", "writes"]);
+    assert_eq!(cases[0].script, 1);
+    assert_eq!(cases[0].span.start_line_col, (5, 1));
+    assert_eq!(cases[0].expected_output, None);
+    assert_eq!(cases[1].expected_output, Some(vec!["one".to_owned()]));
+    assert_eq!(
+        cases[3].expected_output,
+        Some(vec!["1".to_owned(), "two".to_owned()])
+    );
+}
+
+#[test]
+fn test_unnamed_test() {
+    let cst = parse_cst("* test:\nscrp 1 2 3 4 endm").unwrap();
+    assert_eq!(discover(&cst)[0].name, "script 1");
+}
+
+#[test]
+fn test_run_tests() {
+    let suite = run_tests("tests.cos", SOURCE).unwrap();
+    let passed: Vec<_> = suite.results.iter().map(TestResult::passed).collect();
+    assert_eq!(passed, [true, false, true, false]);
+    assert!(!suite.passed());
+    assert_eq!(suite.failures(), 2);
+
+    let fails = &suite.results[1];
+    let Some(Failure::Error(error)) = &fails.failure else {
+        panic!("Expected an error, found {:?}", fails.failure);
+    };
+    assert_eq!(*error.error_type(), crate::ErrorType::AssertionFailed);
+    assert_eq!(fails.location(), (15, 5));
+    assert_eq!(fails.output, ["one"]);
+
+    let writes = &suite.results[3];
+    let Some(Failure::Output { expected, found }) = &writes.failure else {
+        panic!("Expected wrong output, found {:?}", writes.failure);
+    };
+    assert_eq!(*expected, ["1", "two"]);
+    assert_eq!(*found, ["1", "too"]);
+    assert_eq!(writes.location(), (26, 1));
+}
+
+#[test]
+fn test_unsupported_commands_fail() {
+    let suite = run_tests("a.cos", "* test: camera\niscr cmra 0 0 0 endm").unwrap();
+    let failure = suite.results[0].failure.as_ref().unwrap();
+    assert_eq!(
+        failure.to_string(),
+        "`CMRA` is not supported by the interpreter"
+    );
+    assert_eq!(suite.results[0].location(), (2, 6));
+}
+
+#[test]
+fn test_tap() {
+    let suite = run_tests("tests.cos", SOURCE).unwrap();
+    assert_eq!(
+        tap(&[suite]),
+        "\
+TAP version 13
+1..4
+ok 1 - tests.cos: passes
+not ok 2 - tests.cos: fails
+  ---
+  message: \"Assertion failed\"
+  at: \"tests.cos:15:5\"
+  output:
+    - \"one\"
+  ...
+ok 3 - tests.cos: uses the owner
+not ok 4 - tests.cos: writes
+  ---
+  message: \"Expected output [\\\"1\\\", \\\"two\\\"], found [\\\"1\\\", \\\"too\\\"]\"
+  at: \"tests.cos:26:1\"
+  output:
+    - \"1\"
+    - \"too\"
+  ...
+"
+    );
+}
+
+#[test]
+fn test_junit() {
+    let suites = [
+        run_tests("tests.cos", SOURCE).unwrap(),
+        run_tests("<other>", "* test: empty\niscr endm").unwrap(),
+    ];
+    assert_eq!(
+        junit(&suites),
+        "\
+<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<testsuites tests=\"5\" failures=\"2\" errors=\"0\">
+  <testsuite name=\"tests.cos\" tests=\"4\" failures=\"2\" errors=\"0\">
+    <testcase name=\"passes\" classname=\"tests.cos\" file=\"tests.cos\" line=\"5\"/>
+    <testcase name=\"fails\" classname=\"tests.cos\" file=\"tests.cos\" line=\"12\">
+      <failure message=\"Assertion failed\" type=\"E0409\">tests.cos:15:5: Assertion failed</failure>
+      <system-out>one</system-out>
+    </testcase>
+    <testcase name=\"uses the owner\" classname=\"tests.cos\" file=\"tests.cos\" line=\"19\"/>
+    <testcase name=\"writes\" classname=\"tests.cos\" file=\"tests.cos\" line=\"26\">
+      <failure message=\"Expected output [&quot;1&quot;, &quot;two&quot;], found [&quot;1&quot;, &quot;too&quot;]\" type=\"output\">tests.cos:26:1: Expected output [&quot;1&quot;, &quot;two&quot;], found [&quot;1&quot;, &quot;too&quot;]</failure>
+      <system-out>1&#10;too</system-out>
+    </testcase>
+  </testsuite>
+  <testsuite name=\"&lt;other&gt;\" tests=\"1\" failures=\"0\" errors=\"0\">
+    <testcase name=\"empty\" classname=\"&lt;other&gt;\" file=\"&lt;other&gt;\" line=\"2\"/>
+  </testsuite>
+</testsuites>
+"
+    );
+}
+
+#[test]
+fn test_reports_unparsed_files() {
+    let source = "* test: broken\niscr setv va00 endm";
+    let error = run_tests("broken.cos", source).unwrap_err();
+    let suites = [
+        TestSuite::unparsed("broken.cos", error),
+        run_tests("<other>", "* test: empty\niscr endm").unwrap(),
+    ];
+    assert!(!suites[0].passed());
+
+    assert_eq!(
+        tap(&suites),
+        "\
+TAP version 13
+1..2
+not ok 1 - broken.cos
+  ---
+  message: \"`SETV` expects 2 arguments, found 1\"
+  at: \"broken.cos:2:6\"
+  ...
+ok 2 - <other>: empty
+"
+    );
+
+    let junit = junit(&suites);
+    assert!(
+        junit.contains("<testsuites tests=\"2\" failures=\"0\" errors=\"1\">"),
+        "{}",
+        junit
+    );
+    assert!(
+        junit.contains(
+            "    <testcase name=\"broken.cos\" classname=\"broken.cos\" file=\"broken.cos\" line=\"2\">\n      \
+             <error message=\"`SETV` expects 2 arguments, found 1\" type=\"E0102\">broken.cos:2:6: \
+             `SETV` expects 2 arguments, found 1</error>\n    </testcase>\n"
+        ),
+        "{}",
+        junit
+    );
+}
+
+#[test]
+fn test_reports_escape_control_characters() {
+    let source = "* test: lines\n* expect: one\niscr dbg: outs \"one\\ntwo\tthree\u{1}\" endm";
+    let suites = [run_tests("tests.cos", source).unwrap()];
+
+    let tap = tap(&suites);
+    assert!(
+        tap.contains("    - \"one\\ntwo\\tthree\\x01\"\n"),
+        "{}",
+        tap
+    );
+    assert!(tap.lines().all(|line| !line.contains('\t')));
+
+    let junit = junit(&suites);
+    assert!(
+        junit.contains("<system-out>one&#10;two&#9;three\u{fffd}</system-out>"),
+        "{}",
+        junit
+    );
+    assert_eq!(junit.lines().count(), 9);
+}
@@ -0,0 +1,211 @@
+//! Traversal of the AST.
+//!
+//! [Visit] walks a tree by reference, [VisitMut] walks it by mutable reference
+//! and [Fold] rebuilds it by value. Each has a method per node type whose
+//! default implementation walks the node's children, so an implementation
+//! only needs to override the nodes it cares about. An overriding method can
+//! carry on into the node's children by calling [Walk::walk],
+//! [Walk::walk_mut] or [Walk::walk_fold] on the node.
+//!
+//! ```
+//! use caos2::ast::Variable;
+//! use caos2::visit::{Node, Visit, Walk};
+//!
+//! #[derive(Default)]
+//! struct CountVariables(usize);
+//!
+//! impl Visit for CountVariables {
+//!     fn visit_variable(&mut self, node: &Variable) {
+//!         self.0 += 1;
+//!         node.walk(self);
+//!     }
+//! }
+//!
+//! let cos = caos2::parse_cos("doif va00 eq 1 setv va01 avar targ 0 endi").unwrap();
+//! let mut count = CountVariables::default();
+//! cos.accept(&mut count);
+//! assert_eq!(count.0, 3);
+//! ```
+
+#[cfg(test)]
+mod tests;
+
+use crate::ast::{
+    Agent, AgentArg, Anything, ByteString, ClassifierEnum, Command, Condition, ConditionType,
+    CosFile, Decimal, DecimalArg, DoIf, EventScriptDefinition, Float, FloatArg, IntArg, Integer,
+    JoinType, Label, LitF32, SString, SStringArg, Script, ScriptDefinition, Variable,
+};
+
+pub use caos_macros::Walk;
+
+/// Walks the children of a node, passing each to the matching method of a
+/// visitor.
+///
+/// Derived with `#[derive(Walk)]`, which walks every field of every variant
+/// in order.
+pub trait Walk: Sized {
+    fn walk<V: Visit + ?Sized>(&self, visitor: &mut V);
+    fn walk_mut<V: VisitMut + ?Sized>(&mut self, visitor: &mut V);
+    fn walk_fold<F: Fold + ?Sized>(self, folder: &mut F) -> Self;
+}
+
+/// Anything which can be handed to a visitor: AST nodes, which are passed to
+/// their visitor method, and the containers and literals found between them.
+pub trait Node: Sized {
+    fn accept<V: Visit + ?Sized>(&self, visitor: &mut V);
+    fn accept_mut<V: VisitMut + ?Sized>(&mut self, visitor: &mut V);
+    fn fold_with<F: Fold + ?Sized>(self, folder: &mut F) -> Self;
+}
+
+macro_rules! visitors {
+    ($($t:ty => $visit:ident, $visit_mut:ident, $fold:ident;)*) => {
+        /// Visits an AST by reference.
+        pub trait Visit {
+            $(
+                fn $visit(&mut self, node: &$t) {
+                    node.walk(self)
+                }
+            )*
+        }
+
+        /// Visits an AST by mutable reference.
+        pub trait VisitMut {
+            $(
+                fn $visit_mut(&mut self, node: &mut $t) {
+                    node.walk_mut(self)
+                }
+            )*
+        }
+
+        /// Rebuilds an AST, replacing each node with the one returned.
+        pub trait Fold {
+            $(
+                fn $fold(&mut self, node: $t) -> $t {
+                    node.walk_fold(self)
+                }
+            )*
+        }
+
+        $(
+            impl Node for $t {
+                fn accept<V: Visit + ?Sized>(&self, visitor: &mut V) {
+                    visitor.$visit(self)
+                }
+
+                fn accept_mut<V: VisitMut + ?Sized>(&mut self, visitor: &mut V) {
+                    visitor.$visit_mut(self)
+                }
+
+                fn fold_with<F: Fold + ?Sized>(self, folder: &mut F) -> Self {
+                    folder.$fold(self)
+                }
+            }
+        )*
+    };
+}
+
+visitors! {
+    CosFile => visit_cos_file, visit_cos_file_mut, fold_cos_file;
+    Script => visit_script, visit_script_mut, fold_script;
+    EventScriptDefinition => visit_event_script_definition, visit_event_script_definition_mut, fold_event_script_definition;
+    ScriptDefinition => visit_script_definition, visit_script_definition_mut, fold_script_definition;
+    Command => visit_command, visit_command_mut, fold_command;
+    DoIf => visit_do_if, visit_do_if_mut, fold_do_if;
+    ClassifierEnum => visit_classifier_enum, visit_classifier_enum_mut, fold_classifier_enum;
+    Condition => visit_condition, visit_condition_mut, fold_condition;
+    Anything => visit_anything, visit_anything_mut, fold_anything;
+    Decimal => visit_decimal, visit_decimal_mut, fold_decimal;
+    DecimalArg => visit_decimal_arg, visit_decimal_arg_mut, fold_decimal_arg;
+    IntArg => visit_int_arg, visit_int_arg_mut, fold_int_arg;
+    FloatArg => visit_float_arg, visit_float_arg_mut, fold_float_arg;
+    SStringArg => visit_sstring_arg, visit_sstring_arg_mut, fold_sstring_arg;
+    AgentArg => visit_agent_arg, visit_agent_arg_mut, fold_agent_arg;
+    Integer => visit_integer, visit_integer_mut, fold_integer;
+    Float => visit_float, visit_float_mut, fold_float;
+    SString => visit_sstring, visit_sstring_mut, fold_sstring;
+    Agent => visit_agent, visit_agent_mut, fold_agent;
+    Variable => visit_variable, visit_variable_mut, fold_variable;
+    ByteString => visit_byte_string, visit_byte_string_mut, fold_byte_string;
+    Label => visit_label, visit_label_mut, fold_label;
+}
+
+macro_rules! leaf_nodes {
+    ($($t:ty),* $(,)?) => {
+        $(
+            impl Node for $t {
+                fn accept<V: Visit + ?Sized>(&self, _visitor: &mut V) {}
+
+                fn accept_mut<V: VisitMut + ?Sized>(&mut self, _visitor: &mut V) {}
+
+                fn fold_with<F: Fold + ?Sized>(self, _folder: &mut F) -> Self {
+                    self
+                }
+            }
+        )*
+    };
+}
+
+// Literal values and operators hold no further nodes.
+leaf_nodes!(i32, u8, String, LitF32, ConditionType, JoinType);
+
+impl<T: Node> Node for Box<T> {
+    fn accept<V: Visit + ?Sized>(&self, visitor: &mut V) {
+        self.as_ref().accept(visitor)
+    }
+
+    fn accept_mut<V: VisitMut + ?Sized>(&mut self, visitor: &mut V) {
+        self.as_mut().accept_mut(visitor)
+    }
+
+    fn fold_with<F: Fold + ?Sized>(self, folder: &mut F) -> Self {
+        Box::new((*self).fold_with(folder))
+    }
+}
+
+impl<T: Node> Node for Vec<T> {
+    fn accept<V: Visit + ?Sized>(&self, visitor: &mut V) {
+        self.iter().for_each(|n| n.accept(visitor))
+    }
+
+    fn accept_mut<V: VisitMut + ?Sized>(&mut self, visitor: &mut V) {
+        self.iter_mut().for_each(|n| n.accept_mut(visitor))
+    }
+
+    fn fold_with<F: Fold + ?Sized>(self, folder: &mut F) -> Self {
+        self.into_iter().map(|n| n.fold_with(folder)).collect()
+    }
+}
+
+impl<T: Node> Node for Option<T> {
+    fn accept<V: Visit + ?Sized>(&self, visitor: &mut V) {
+        if let Some(n) = self {
+            n.accept(visitor)
+        }
+    }
+
+    fn accept_mut<V: VisitMut + ?Sized>(&mut self, visitor: &mut V) {
+        if let Some(n) = self {
+            n.accept_mut(visitor)
+        }
+    }
+
+    fn fold_with<F: Fold + ?Sized>(self, folder: &mut F) -> Self {
+        self.map(|n| n.fold_with(folder))
+    }
+}
+
+impl<A: Node, B: Node> Node for (A, B) {
+    fn accept<V: Visit + ?Sized>(&self, visitor: &mut V) {
+        self.0.accept(visitor);
+        self.1.accept(visitor);
+    }
+
+    fn accept_mut<V: VisitMut + ?Sized>(&mut self, visitor: &mut V) {
+        self.0.accept_mut(visitor);
+        self.1.accept_mut(visitor);
+    }
+
+    fn fold_with<F: Fold + ?Sized>(self, folder: &mut F) -> Self {
+        (self.0.fold_with(folder), self.1.fold_with(folder))
+    }
+}
@@ -0,0 +1,146 @@
+use super::*;
+use crate::parse_cos;
+
+#[derive(Default)]
+struct CommandNames(Vec<String>);
+
+impl Visit for CommandNames {
+    fn visit_command(&mut self, node: &Command) {
+        let name = format!("{:?}", node);
+        let name = name.split([' ', '(', '{']).next().unwrap_or_default();
+        self.0.push(name.to_owned());
+        node.walk(self);
+    }
+}
+
+#[test]
+fn test_visit_reaches_nested_commands() {
+    let cos = parse_cos(
+        "doif va00 eq 1
+  enum 1 2 3
+    reps 2
+      kill targ
+    repe
+  next
+else
+  loop
+    nohh
+  untl va00 eq 2
+endi",
+    )
+    .unwrap();
+    let mut names = CommandNames::default();
+    cos.accept(&mut names);
+    assert_eq!(
+        names.0,
+        ["Doif", "Enum", "Reps", "Kill", "LoopUntl", "Nohh"]
+    );
+}
+
+#[derive(Default)]
+struct Integers(Vec<i32>);
+
+impl Visit for Integers {
+    fn visit_integer(&mut self, node: &Integer) {
+        if let Integer::Literal(i) = node {
+            self.0.push(*i);
+        }
+        node.walk(self);
+    }
+}
+
+#[test]
+fn test_visit_reaches_arguments_in_order() {
+    let cos = parse_cos("scrp 1 2 3 4 setv va00 rand 5 6 endm").unwrap();
+    let mut integers = Integers::default();
+    cos.accept(&mut integers);
+    // The classifier of an event script is not an AST node.
+    assert_eq!(integers.0, [5, 6]);
+
+    let cos = parse_cos("doif va00 eq 7 and va01 ne 8 endi").unwrap();
+    let mut integers = Integers::default();
+    cos.accept(&mut integers);
+    assert_eq!(integers.0, [7, 8]);
+}
+
+#[derive(Default)]
+struct SkipDoIf(Integers);
+
+impl Visit for SkipDoIf {
+    fn visit_do_if(&mut self, _node: &DoIf) {}
+
+    fn visit_integer(&mut self, node: &Integer) {
+        self.0.visit_integer(node)
+    }
+}
+
+#[test]
+fn test_visit_override_can_skip_children() {
+    let cos = parse_cos("doif va00 eq 1 setv va00 2 endi setv va00 3").unwrap();
+    let mut skip = SkipDoIf::default();
+    cos.accept(&mut skip);
+    assert_eq!(skip.0 .0, [3]);
+}
+
+struct RenameVariable;
+
+impl VisitMut for RenameVariable {
+    fn visit_variable_mut(&mut self, node: &mut Variable) {
+        if *node == Variable::Vaxx(0) {
+            *node = Variable::Vaxx(1);
+        }
+        node.walk_mut(self);
+    }
+}
+
+#[test]
+fn test_visit_mut_rewrites_in_place() {
+    let mut cos = parse_cos("reps 2 setv va00 avar va00 0 repe").unwrap();
+    cos.accept_mut(&mut RenameVariable);
+    assert_eq!(cos, parse_cos("reps 2 setv va01 avar va01 0 repe").unwrap());
+}
+
+struct DoubleLiterals;
+
+impl Fold for DoubleLiterals {
+    fn fold_integer(&mut self, node: Integer) -> Integer {
+        match node {
+            Integer::Literal(i) => Integer::Literal(i * 2),
+            node => node.walk_fold(self),
+        }
+    }
+}
+
+#[test]
+fn test_fold_rebuilds_tree() {
+    let cos = parse_cos("iscr doif va00 eq 1 setv va00 rand 2 3 elif va00 eq 4 endi endm").unwrap();
+    let folded = cos.fold_with(&mut DoubleLiterals);
+    assert_eq!(
+        folded,
+        parse_cos("iscr doif va00 eq 2 setv va00 rand 4 6 elif va00 eq 8 endi endm").unwrap()
+    );
+}
+
+struct UnwrapDoIf;
+
+impl Fold for UnwrapDoIf {
+    fn fold_script_definition(&mut self, node: ScriptDefinition) -> ScriptDefinition {
+        let mut definition = ScriptDefinition::default();
+        for command in node.walk_fold(self).commands {
+            match command {
+                Command::Doif(do_if) => definition.commands.extend(do_if.definition.commands),
+                command => definition.push(command),
+            }
+        }
+        definition
+    }
+}
+
+#[test]
+fn test_fold_can_replace_commands() {
+    let cos = parse_cos("iscr doif va00 eq 1 doif va01 eq 1 nohh endi endi endm").unwrap();
+    assert_eq!(
+        cos.fold_with(&mut UnwrapDoIf),
+        parse_cos("iscr nohh endm").unwrap()
+    );
+}